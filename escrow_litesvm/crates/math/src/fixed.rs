@@ -0,0 +1,80 @@
+use crate::{to_u64, Rounding};
+
+const FRACTION_BITS: u32 = 64;
+const FRACTION_MASK: u128 = u64::MAX as u128;
+
+/// Unsigned fixed-point number with 64 integer and 64 fractional bits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Fixed(u128);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(1 << FRACTION_BITS);
+
+    pub const fn from_raw(raw: u128) -> Fixed {
+        Fixed(raw)
+    }
+
+    pub const fn raw(self) -> u128 {
+        self.0
+    }
+
+    pub const fn from_int(value: u64) -> Fixed {
+        Fixed((value as u128) << FRACTION_BITS)
+    }
+
+    /// `numerator / denominator`, e.g. a price of `receive / deposit` per token.
+    pub fn from_ratio(numerator: u64, denominator: u64, rounding: Rounding) -> Option<Fixed> {
+        if denominator == 0 {
+            return None;
+        }
+        let scaled = (numerator as u128) << FRACTION_BITS;
+        let denominator = denominator as u128;
+        Some(Fixed(match rounding {
+            Rounding::Down => scaled / denominator,
+            Rounding::Up => scaled.div_ceil(denominator),
+        }))
+    }
+
+    pub fn checked_add(self, other: Fixed) -> Option<Fixed> {
+        self.0.checked_add(other.0).map(Fixed)
+    }
+
+    pub fn checked_sub(self, other: Fixed) -> Option<Fixed> {
+        self.0.checked_sub(other.0).map(Fixed)
+    }
+
+    /// Product of two fixed-point numbers, dropping fractional bits below the last place.
+    pub fn checked_mul(self, other: Fixed) -> Option<Fixed> {
+        let (a_hi, a_lo) = (self.0 >> FRACTION_BITS, self.0 & FRACTION_MASK);
+        let (b_hi, b_lo) = (other.0 >> FRACTION_BITS, other.0 & FRACTION_MASK);
+
+        (a_hi * b_hi)
+            .checked_shl(FRACTION_BITS)
+            .filter(|shifted| shifted >> FRACTION_BITS == a_hi * b_hi)?
+            .checked_add(a_hi * b_lo)?
+            .checked_add(a_lo * b_hi)?
+            .checked_add((a_lo * b_lo) >> FRACTION_BITS)
+            .map(Fixed)
+    }
+
+    /// Applies the number to an integer amount, e.g. tokens times a price.
+    pub fn mul_int(self, value: u64, rounding: Rounding) -> Option<u64> {
+        let value = value as u128;
+        let (hi, lo) = (self.0 >> FRACTION_BITS, self.0 & FRACTION_MASK);
+        let fraction = lo * value;
+        let mut result = (hi * value).checked_add(fraction >> FRACTION_BITS)?;
+        if rounding == Rounding::Up && fraction & FRACTION_MASK != 0 {
+            result = result.checked_add(1)?;
+        }
+        to_u64(result)
+    }
+
+    pub fn to_int(self, rounding: Rounding) -> Option<u64> {
+        let mut result = self.0 >> FRACTION_BITS;
+        if rounding == Rounding::Up && self.0 & FRACTION_MASK != 0 {
+            result += 1;
+        }
+        to_u64(result)
+    }
+}
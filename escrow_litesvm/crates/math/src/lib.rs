@@ -0,0 +1,48 @@
+//! Integer math shared by the workspace programs.
+//!
+//! Every operation is checked and returns `None` on overflow or division by
+//! zero, so callers can map it onto their own error with `ok_or`. Anything
+//! that divides takes an explicit [`Rounding`] so the direction of the
+//! remainder is a visible decision at the call site.
+
+mod fixed;
+#[cfg(test)]
+mod tests;
+
+pub use fixed::Fixed;
+
+/// Basis points in one whole.
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// `value * numerator / denominator` with a u128 intermediate.
+pub fn mul_div(value: u64, numerator: u64, denominator: u64, rounding: Rounding) -> Option<u64> {
+    if denominator == 0 {
+        return None;
+    }
+    let product = value as u128 * numerator as u128;
+    let denominator = denominator as u128;
+    let quotient = match rounding {
+        Rounding::Down => product / denominator,
+        Rounding::Up => product.div_ceil(denominator),
+    };
+    to_u64(quotient)
+}
+
+/// `amount * bps / 10_000`.
+pub fn apply_bps(amount: u64, bps: u16, rounding: Rounding) -> Option<u64> {
+    mul_div(amount, bps as u64, BPS_DENOMINATOR, rounding)
+}
+
+pub fn to_u64(value: u128) -> Option<u64> {
+    u64::try_from(value).ok()
+}
+
+pub fn to_i64(value: u64) -> Option<i64> {
+    i64::try_from(value).ok()
+}
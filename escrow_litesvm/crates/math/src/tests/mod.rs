@@ -0,0 +1,165 @@
+use proptest::prelude::*;
+
+use crate::{apply_bps, mul_div, to_i64, to_u64, Fixed, Rounding, BPS_DENOMINATOR};
+
+#[test]
+fn mul_div_rounds_in_the_requested_direction() {
+    assert_eq!(mul_div(10, 1, 3, Rounding::Down), Some(3));
+    assert_eq!(mul_div(10, 1, 3, Rounding::Up), Some(4));
+    assert_eq!(mul_div(9, 1, 3, Rounding::Up), Some(3));
+    assert_eq!(mul_div(1, 1, 0, Rounding::Down), None);
+    assert_eq!(
+        mul_div(u64::MAX, u64::MAX, u64::MAX, Rounding::Down),
+        Some(u64::MAX)
+    );
+    assert_eq!(mul_div(u64::MAX, 2, 1, Rounding::Down), None);
+}
+
+#[test]
+fn apply_bps_matches_percentages() {
+    assert_eq!(apply_bps(1_000, 250, Rounding::Down), Some(25));
+    assert_eq!(apply_bps(999, 5_000, Rounding::Down), Some(499));
+    assert_eq!(apply_bps(999, 5_000, Rounding::Up), Some(500));
+    assert_eq!(
+        apply_bps(u64::MAX, BPS_DENOMINATOR as u16, Rounding::Down),
+        Some(u64::MAX)
+    );
+}
+
+#[test]
+fn conversions_reject_out_of_range_values() {
+    assert_eq!(to_u64(u64::MAX as u128), Some(u64::MAX));
+    assert_eq!(to_u64(u64::MAX as u128 + 1), None);
+    assert_eq!(to_i64(i64::MAX as u64), Some(i64::MAX));
+    assert_eq!(to_i64(i64::MAX as u64 + 1), None);
+}
+
+#[test]
+fn fixed_basics() {
+    let third = Fixed::from_ratio(1, 3, Rounding::Down).unwrap();
+    assert_eq!(third.mul_int(3, Rounding::Down), Some(0));
+    assert_eq!(third.mul_int(3, Rounding::Up), Some(1));
+    assert_eq!(Fixed::ONE.checked_mul(Fixed::ONE), Some(Fixed::ONE));
+    assert_eq!(
+        Fixed::from_int(u64::MAX).checked_mul(Fixed::from_int(2)),
+        None
+    );
+    assert_eq!(Fixed::from_ratio(1, 0, Rounding::Down), None);
+    assert_eq!(Fixed::ZERO.checked_sub(Fixed::ONE), None);
+}
+
+proptest! {
+    #[test]
+    fn mul_div_brackets_the_exact_quotient(value: u64, numerator: u64, denominator in 1u64..) {
+        let exact = value as u128 * numerator as u128;
+        let down = mul_div(value, numerator, denominator, Rounding::Down);
+        let up = mul_div(value, numerator, denominator, Rounding::Up);
+
+        if let Some(down) = down {
+            prop_assert!(down as u128 * denominator as u128 <= exact);
+            prop_assert!((down as u128 + 1) * denominator as u128 > exact);
+        } else {
+            prop_assert!(exact / denominator as u128 > u64::MAX as u128);
+        }
+        if let (Some(down), Some(up)) = (down, up) {
+            prop_assert!(up - down <= 1);
+            prop_assert_eq!(up == down, exact.is_multiple_of(denominator as u128));
+        }
+    }
+
+    #[test]
+    fn apply_bps_never_exceeds_the_amount(amount: u64, bps in 0u16..=10_000) {
+        let down = apply_bps(amount, bps, Rounding::Down).unwrap();
+        let up = apply_bps(amount, bps, Rounding::Up).unwrap();
+        prop_assert!(down <= up && up <= amount);
+    }
+
+    #[test]
+    fn complementary_bps_split_loses_at_most_one(amount: u64, bps in 0u16..=10_000) {
+        let share = apply_bps(amount, bps, Rounding::Down).unwrap();
+        let rest = apply_bps(amount, 10_000 - bps, Rounding::Down).unwrap();
+        let total = share as u128 + rest as u128;
+        prop_assert!(total <= amount as u128 && amount as u128 - total <= 1);
+    }
+
+    #[test]
+    fn unit_priced_fills_do_not_drift(
+        numerator in 1u64..=u16::MAX as u64,
+        denominator in 1u64..=u32::MAX as u64,
+        fills in prop::collection::vec(1u64..=u32::MAX as u64, 1..16),
+    ) {
+        // Costing each fill on its own rounds up at most once per fill, never below the whole
+        let total: u64 = fills.iter().sum();
+        let whole = mul_div(total, numerator, denominator, Rounding::Up).unwrap();
+        let paid: u64 = fills
+            .iter()
+            .map(|fill| mul_div(*fill, numerator, denominator, Rounding::Up).unwrap())
+            .sum();
+        prop_assert!(paid >= whole && paid - whole < fills.len() as u64);
+    }
+
+    #[test]
+    fn unit_price_cost_buys_back_the_units(
+        amount in 0u64..=u32::MAX as u64,
+        numerator in 1u64..=u16::MAX as u64,
+        denominator in 1u64..=u32::MAX as u64,
+    ) {
+        // A budget of the rounded-up cost always buys at least the units it was quoted for
+        let cost = mul_div(amount, numerator, denominator, Rounding::Up).unwrap();
+        let units = mul_div(cost, denominator, numerator, Rounding::Down).unwrap();
+        prop_assert!(units >= amount);
+    }
+
+    #[test]
+    fn fixed_int_round_trip(value: u64) {
+        let fixed = Fixed::from_int(value);
+        prop_assert_eq!(fixed.to_int(Rounding::Down), Some(value));
+        prop_assert_eq!(fixed.to_int(Rounding::Up), Some(value));
+        prop_assert_eq!(fixed.mul_int(1, Rounding::Down), Some(value));
+    }
+
+    #[test]
+    fn fixed_ratio_applied_back_brackets_numerator(numerator: u64, denominator in 1u64..) {
+        let down = Fixed::from_ratio(numerator, denominator, Rounding::Down).unwrap();
+        let up = Fixed::from_ratio(numerator, denominator, Rounding::Up).unwrap();
+        prop_assert!(down.mul_int(denominator, Rounding::Down).unwrap() <= numerator);
+        if let Some(back) = up.mul_int(denominator, Rounding::Up) {
+            prop_assert!(back >= numerator);
+        }
+    }
+
+    #[test]
+    fn fixed_mul_int_matches_mul_div(numerator: u64, denominator in 1u64..=u32::MAX as u64, value in 0u64..=u32::MAX as u64) {
+        // A ratio applied through Fixed rounds down at most one unit below the exact mul_div
+        let ratio = Fixed::from_ratio(numerator, denominator, Rounding::Down).unwrap();
+        let exact = mul_div(value, numerator, denominator, Rounding::Down);
+        let via_fixed = ratio.mul_int(value, Rounding::Down);
+        if let (Some(exact), Some(via_fixed)) = (exact, via_fixed) {
+            prop_assert!(via_fixed <= exact && exact - via_fixed <= 1);
+        }
+    }
+
+    #[test]
+    fn fixed_mul_matches_integer_mul(a: u32, b: u32) {
+        let product = Fixed::from_int(a as u64).checked_mul(Fixed::from_int(b as u64)).unwrap();
+        prop_assert_eq!(product, Fixed::from_int(a as u64 * b as u64));
+    }
+
+    #[test]
+    fn fixed_mul_is_commutative(a: u128, b: u128) {
+        prop_assert_eq!(Fixed::from_raw(a).checked_mul(Fixed::from_raw(b)), Fixed::from_raw(b).checked_mul(Fixed::from_raw(a)));
+    }
+
+    #[test]
+    fn fixed_mul_by_one_is_identity(raw: u128) {
+        prop_assert_eq!(Fixed::from_raw(raw).checked_mul(Fixed::ONE), Some(Fixed::from_raw(raw)));
+    }
+
+    #[test]
+    fn fixed_add_sub_round_trip(a: u128, b: u128) {
+        let (a, b) = (Fixed::from_raw(a), Fixed::from_raw(b));
+        if let Some(sum) = a.checked_add(b) {
+            prop_assert_eq!(sum.checked_sub(b), Some(a));
+        }
+    }
+}
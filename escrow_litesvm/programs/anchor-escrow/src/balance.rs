@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{
+            transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Mint,
+    },
+    token_interface::TokenAccount,
+};
+
+use crate::error::ErrorCode;
+
+/// Token balance of `account`, read straight from its data so it sees CPIs made
+/// since the account was deserialized.
+pub fn of(account: &AccountInfo) -> Result<u64> {
+    let data = account.try_borrow_data()?;
+    let account = TokenAccount::try_deserialize(&mut &data[..])?;
+    Ok(account.amount)
+}
+
+/// What a transfer of `amount` credits once the mint's Token-2022 transfer fee for
+/// the current epoch is withheld. The full amount for mints without one.
+pub fn after_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    let Ok(config) = mint.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+    let fee = config
+        .calculate_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(ErrorCode::Overflow)?;
+    Ok(amount - fee)
+}
+
+/// Fails unless `to` gained exactly what sending it `amount` of `mint` should credit
+/// since it held `before`. A transfer hook moving tokens on, or a fee the mint does
+/// not declare, would otherwise under-deliver without failing the transfer.
+pub fn check_credited(
+    from: &AccountInfo,
+    to: &AccountInfo,
+    mint: &AccountInfo,
+    before: u64,
+    amount: u64,
+) -> Result<()> {
+    // Nothing moves between an account and itself
+    let expected = if from.key() == to.key() {
+        0
+    } else {
+        after_fee(mint, amount)?
+    };
+    let credited = of(to)?.checked_sub(before);
+    require!(credited == Some(expected), ErrorCode::TransferShortfall);
+    Ok(())
+}
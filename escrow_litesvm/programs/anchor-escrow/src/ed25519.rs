@@ -0,0 +1,59 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{ed25519_program, sysvar::instructions::get_instruction_relative},
+};
+
+use crate::error::ErrorCode;
+
+// The ed25519 instruction data starts with the signature count and a padding byte,
+// then one set of seven u16 offsets per signature
+const OFFSETS_START: usize = 2;
+const OFFSETS_LEN: usize = 7 * 2;
+// Instruction index the offsets use to point into the ed25519 instruction itself
+const SELF_INSTRUCTION: u16 = u16::MAX;
+
+/// Fails unless the instruction right before this one has the ed25519 program check
+/// a single signature by `signer` over exactly `message`. The runtime rejects the
+/// whole transaction when that signature is invalid, so it is not checked again.
+pub fn verify(instructions: &AccountInfo, signer: &Pubkey, message: &[u8]) -> Result<()> {
+    let instruction =
+        get_instruction_relative(-1, instructions).map_err(|_| ErrorCode::InvalidSignature)?;
+    require_keys_eq!(
+        instruction.program_id,
+        ed25519_program::ID,
+        ErrorCode::InvalidSignature
+    );
+
+    let data = &instruction.data;
+    require!(
+        data.len() >= OFFSETS_START + OFFSETS_LEN && data[0] == 1,
+        ErrorCode::InvalidSignature
+    );
+    let offset = |index: usize| {
+        let start = OFFSETS_START + index * 2;
+        u16::from_le_bytes([data[start], data[start + 1]])
+    };
+    // signature, public key and message must all come from the same instruction,
+    // otherwise the checked key or message could differ from the ones read here
+    require!(
+        offset(1) == SELF_INSTRUCTION
+            && offset(3) == SELF_INSTRUCTION
+            && offset(6) == SELF_INSTRUCTION,
+        ErrorCode::InvalidSignature
+    );
+
+    let public_key_start = offset(2) as usize;
+    let message_start = offset(4) as usize;
+    let message_end = message_start + offset(5) as usize;
+    let public_key = data
+        .get(public_key_start..public_key_start + 32)
+        .ok_or(ErrorCode::InvalidSignature)?;
+    let signed = data
+        .get(message_start..message_end)
+        .ok_or(ErrorCode::InvalidSignature)?;
+    require!(
+        public_key == signer.as_ref() && signed == message,
+        ErrorCode::InvalidSignature
+    );
+    Ok(())
+}
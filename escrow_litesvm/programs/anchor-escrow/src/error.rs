@@ -0,0 +1,241 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Waiting time has not elapsed yet, cannot take before the unlock time.")]
+    WaitingTimeNotElapsed,
+    #[msg("Config bounds are inconsistent.")]
+    InvalidConfig,
+    #[msg("Deposit is below the configured minimum.")]
+    DepositTooSmall,
+    #[msg("Waiting time exceeds the configured maximum.")]
+    WaitingTimeTooLong,
+    #[msg("Mint decimals are outside the configured range.")]
+    DecimalsOutOfRange,
+    #[msg("Arithmetic overflow.")]
+    Overflow,
+    #[msg("Escrow is locked.")]
+    EscrowLocked,
+    #[msg("Escrow is under dispute.")]
+    EscrowDisputed,
+    #[msg("Escrow is closed.")]
+    EscrowClosed,
+    #[msg("Account is not an escrow of the expected layout.")]
+    InvalidEscrowAccount,
+    #[msg("Waiting time cannot be negative.")]
+    NegativeWaitingTime,
+    #[msg("Taker token account is frozen.")]
+    TakerAccountFrozen,
+    #[msg("Maker token account is frozen, pass the proceeds accounts to settle.")]
+    MakerAccountFrozen,
+    #[msg("Take amount must be non-zero and at most the remaining receive amount.")]
+    InvalidTakeAmount,
+    #[msg("Take amount is too small to receive any of the deposit.")]
+    FillTooSmall,
+    #[msg("Expiry must be in the future, after unlock and within the configured horizon.")]
+    InvalidExpiry,
+    #[msg("Escrow has expired.")]
+    EscrowExpired,
+    #[msg("Escrow has no expiry or has not reached it yet.")]
+    EscrowNotPastExpiry,
+    #[msg("Escrow has already been partially filled.")]
+    EscrowPartiallyFilled,
+    #[msg("Mint does not match the one recorded on the account.")]
+    InvalidMint,
+    #[msg("Escrow is reserved for a different taker.")]
+    UnauthorizedTaker,
+    #[msg("Taker is not in the escrow's allowlist or the proof is invalid.")]
+    TakerNotAllowlisted,
+    #[msg("Fee exceeds the maximum protocol fee.")]
+    FeeTooHigh,
+    #[msg("Treasury does not match the config.")]
+    InvalidTreasury,
+    #[msg("Maker token account is required unless mint_a is native SOL.")]
+    MakerTokenAccountRequired,
+    #[msg("Mint is not an NFT, expected 0 decimals and a supply of 1.")]
+    NotAnNft,
+    #[msg("Metadata account is not the mint's Token Metadata account.")]
+    InvalidMetadata,
+    #[msg("NFT is not a verified member of the expected collection.")]
+    CollectionMismatch,
+    #[msg("Royalty accounts must be a mint_b token account per creator, in metadata order.")]
+    InvalidCreatorAccount,
+    #[msg("Escrows with taker restrictions, enforced royalties or oracle pricing cannot be auctioned.")]
+    AuctionNotSupported,
+    #[msg("Auction must end in the future, after unlock and before expiry.")]
+    InvalidAuctionEnd,
+    #[msg("Auction has ended.")]
+    AuctionEnded,
+    #[msg("Auction has not ended yet.")]
+    AuctionNotEnded,
+    #[msg("Bid must meet the reserve and beat the highest bid.")]
+    BidTooLow,
+    #[msg("The highest bid cannot be withdrawn.")]
+    WinningBid,
+    #[msg("Winner accounts do not match the auction's highest bid.")]
+    InvalidWinnerAccount,
+    #[msg("Oracle pricing is not enabled in this build.")]
+    OracleDisabled,
+    #[msg("Price account is not a verified Pyth price update for this escrow.")]
+    InvalidPriceFeed,
+    #[msg("Oracle price is too old.")]
+    StalePrice,
+    #[msg("Oracle confidence interval is too wide.")]
+    PriceTooUncertain,
+    #[msg("Take price moved beyond the taker's max_pay or min_receive.")]
+    SlippageExceeded,
+    #[msg("Unit price needs a non-zero numerator and denominator and no oracle.")]
+    InvalidUnitPrice,
+    #[msg("Escrows enforcing royalties can only be filled through Take.")]
+    RoyaltiesEnforced,
+    #[msg("Counter offer must offer mint_b for part of the remaining deposit.")]
+    InvalidCounterOffer,
+    #[msg("Withdraw amount must be non-zero and below the remaining deposit, use Refund to withdraw it all.")]
+    InvalidWithdrawAmount,
+    #[msg("Waiting time cannot be shortened on an escrow reserved for a taker.")]
+    WaitingTimeShortened,
+    #[msg("Refund lock must be within the configured horizon and no later than expiry.")]
+    InvalidRefundLock,
+    #[msg("Maker committed to keep the escrow open until no_refund_before.")]
+    RefundLocked,
+    #[msg("Payment options need a fixed price and up to four distinct mints other than mint_b, each with a non-zero receive.")]
+    InvalidPaymentOption,
+    #[msg("Mints with confidential transfers are not supported.")]
+    ConfidentialTransferUnsupported,
+    #[msg("UI amount quotes need an interest-bearing mint_b and a fixed price in mint_b only.")]
+    InvalidUiAmountQuote,
+    #[msg("Memo is longer than 256 bytes.")]
+    MemoTooLong,
+    #[msg("A memo needs the SPL Memo program account.")]
+    InvalidMemoProgram,
+    #[msg("Label is longer than 64 bytes.")]
+    LabelTooLong,
+    #[msg("The program is paused, only refunds are allowed.")]
+    Paused,
+    #[msg("Escrow is paused by its maker.")]
+    EscrowPaused,
+    #[msg("Escrow already uses the current layout.")]
+    EscrowUpToDate,
+    #[msg("Vault is not the escrow's mint_a token account.")]
+    InvalidVault,
+    #[msg("Escrow is counted in its maker's registry, which must be passed.")]
+    MakerRegistryRequired,
+    #[msg("Escrow reports to its maker's stats account, which must be passed.")]
+    MakerStatsRequired,
+    #[msg("Seed is not the maker's next counter seed.")]
+    SeedNotNext,
+    #[msg("TakeMany needs an escrow, vault and rent payer for every take amount.")]
+    InvalidTakeManyAccounts,
+    #[msg("Escrow needs arguments or accounts only Take accepts, or does not deliver at Take, so it must be filled on its own.")]
+    TakeManyUnsupported,
+    #[msg("Both escrows of a cross belong to the same maker.")]
+    CrossSameMaker,
+    #[msg("The escrows' prices do not cross.")]
+    PricesDoNotCross,
+    #[msg("Only fixed-price escrows open to any taker and delivering at Take, with nothing else for Take to check, can be crossed.")]
+    CrossUnsupported,
+    #[msg("The preceding instruction is not an ed25519 check of the taker's signature over this order.")]
+    InvalidSignature,
+    #[msg("The signed order has expired.")]
+    OrderExpired,
+    #[msg("Order nonce is not the taker's next nonce.")]
+    NonceNotNext,
+    #[msg("Escrow needs arguments or accounts only Take accepts, or does not deliver at Take, so it cannot be taken with a signed order.")]
+    SignedTakeUnsupported,
+    #[msg("Escrow has a reveal window, take it with CommitTake and RevealTake.")]
+    CommitRequired,
+    #[msg("Salt does not match the taker's commitment.")]
+    CommitmentMismatch,
+    #[msg("Commitment must be revealed in a later slot within the escrow's reveal window.")]
+    RevealOutsideWindow,
+    #[msg("A hashlock needs a refund lock, so the taker has time to claim.")]
+    InvalidHashlock,
+    #[msg("Preimage does not hash to the escrow's hashlock.")]
+    InvalidPreimage,
+    #[msg("An arbiter needs a designated taker to rule between.")]
+    InvalidArbiter,
+    #[msg("Escrow has no arbiter to settle a dispute.")]
+    NoArbiter,
+    #[msg("Only the maker or the designated taker can raise a dispute.")]
+    NotDisputant,
+    #[msg("Signer is not the escrow's arbiter.")]
+    NotArbiter,
+    #[msg("Escrow is not under dispute.")]
+    EscrowNotDisputed,
+    #[msg("Ruling awards the taker more than the vault holds.")]
+    InvalidRuling,
+    #[msg("Review period cannot be negative.")]
+    InvalidReviewPeriod,
+    #[msg("Escrow is two-sided, pay into it with DepositPayment.")]
+    DepositRequired,
+    #[msg("Escrow is not two-sided, take it with Take.")]
+    NotTwoSided,
+    #[msg("Escrow needs arguments or accounts only Take accepts, so it cannot be paid into with DepositPayment.")]
+    TwoSidedUnsupported,
+    #[msg("Review period has not passed yet.")]
+    ReviewNotOver,
+    #[msg("Vesting duration cannot be negative or set on a two-sided escrow.")]
+    InvalidVestingDuration,
+    #[msg("Escrow vests its deposit, take it with TakeVested.")]
+    VestingRequired,
+    #[msg("Escrow does not vest its deposit, take it with Take.")]
+    NotVesting,
+    #[msg("Escrow needs arguments or accounts only Take accepts, so it cannot be taken with TakeVested.")]
+    VestedTakeUnsupported,
+    #[msg("Nothing has vested since the last claim.")]
+    NothingVested,
+    #[msg("A standing offer cannot be two-sided.")]
+    InvalidStandingOffer,
+    #[msg("The escrow has no deposit left, top it up with a new receive.")]
+    ReceiveRequired,
+    #[msg("Fill must be non-zero, at most the remaining deposit and cost at least one unit.")]
+    InvalidFillAmount,
+    #[msg("Escrows quoted in a UI amount can only be taken by the amount paid.")]
+    ExactOutUnsupported,
+    #[msg("A transfer credited its destination a different amount than it was sent.")]
+    TransferShortfall,
+    #[msg("A mint has a freeze authority and the maker asked for mints without one.")]
+    FreezeAuthoritySet,
+    #[msg("Minimum fill must be at most the deposit.")]
+    InvalidMinFill,
+    #[msg("Fill is below the escrow's minimum and does not take the rest of the deposit.")]
+    FillBelowMinimum,
+    #[msg("Maker already has the most open escrows the config allows.")]
+    TooManyOpenEscrows,
+    #[msg("Cancellation fee and window must both be set, ending before expiry.")]
+    InvalidCancelFee,
+    #[msg("Refunding within the cancellation window requires the config and treasury.")]
+    CancelFeeAccountsRequired,
+    #[msg("Fee tiers must be at most four, with ascending non-zero thresholds.")]
+    InvalidFeeTiers,
+    #[msg("Taker has no active entry in the whitelist program.")]
+    TakerNotWhitelisted,
+    #[msg("Signer is not approved as delegate of the maker's token account for the deposit.")]
+    InvalidDelegate,
+    #[msg("Vault still holds tokens and cannot be closed.")]
+    VaultNotEmpty,
+    #[msg("Token account receiving funds has a delegate.")]
+    UnexpectedDelegate,
+    #[msg("Token account receiving funds has a close authority.")]
+    UnexpectedCloseAuthority,
+    #[msg("Fill grace period must be positive, within the expiry horizon and set with an expiry.")]
+    InvalidFillGracePeriod,
+    #[msg("Split amount must be non-zero and below the remaining deposit.")]
+    InvalidSplitAmount,
+    #[msg("Two-sided escrows cannot be split.")]
+    SplitUnsupported,
+    #[msg("Escrows with payment options, two-sided escrows or a cancellation fee still at stake cannot be merged.")]
+    MergeUnsupported,
+    #[msg("Escrows must have the same terms and price to be merged.")]
+    TermsMismatch,
+    #[msg("New maker must differ from the current one.")]
+    InvalidNewMaker,
+    #[msg("Escrows receiving nothing are gifts, which need a designated taker and a fixed price.")]
+    InvalidGift,
+    #[msg("Maker mint_a account is required to return what is left in the vault.")]
+    ResidualRecipientRequired,
+    #[msg("Mints with a transfer fee cannot be vested.")]
+    VestingTransferFee,
+    #[msg("Cancellation fee is still at stake.")]
+    CancelFeeAtStake,
+}
@@ -0,0 +1,103 @@
+use anchor_lang::{
+    event::EVENT_IX_TAG_LE,
+    prelude::*,
+    solana_program::{instruction::Instruction, program::invoke_signed},
+    Event,
+};
+
+/// `emit_cpi!` for instruction methods, which have no `ctx` to take the event
+/// authority from. The event is a self-CPI, so indexers read it back from the inner
+/// instructions even when the logs were truncated.
+pub fn emit_cpi<E: Event>(event: &E, event_authority: &AccountInfo, bump: u8) -> Result<()> {
+    let data: Vec<u8> = EVENT_IX_TAG_LE
+        .iter()
+        .copied()
+        .chain(event.data())
+        .collect();
+    let instruction = Instruction::new_with_bytes(
+        crate::ID,
+        &data,
+        vec![AccountMeta::new_readonly(event_authority.key(), true)],
+    );
+    invoke_signed(
+        &instruction,
+        std::slice::from_ref(event_authority),
+        &[&[b"__event_authority", &[bump]]],
+    )?;
+    Ok(())
+}
+
+#[event]
+pub struct EscrowMade {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub deposit: u64,
+    pub receive: u64,
+    // Absolute unix timestamp after which the escrow can be taken
+    pub unlock_at: i64,
+}
+
+#[event]
+pub struct EscrowTaken {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    // The mint_b paid, which may be one of the escrow's payment options
+    pub mint_b: Pubkey,
+    pub payment: u64,
+    pub fill: u64,
+    // Zero once the fill closed the escrow
+    pub remaining_deposit: u64,
+}
+
+#[event]
+pub struct EscrowRefunded {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct EscrowExpired {
+    pub escrow: Pubkey,
+    pub cranker: Pubkey,
+    // Returned to the maker's mint_a ATA
+    pub amount: u64,
+}
+
+#[event]
+pub struct WaitingTimeSet {
+    pub escrow: Pubkey,
+    // New absolute unix timestamp after which the escrow can be taken
+    pub unlock_at: i64,
+}
+
+#[event]
+pub struct EscrowPauseSet {
+    pub escrow: Pubkey,
+    pub paused: bool,
+}
+
+#[event]
+pub struct MakerAuthorityTransferred {
+    pub escrow: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+}
+
+#[event]
+pub struct DisputeRaised {
+    pub escrow: Pubkey,
+    // The maker or the designated taker
+    pub raised_by: Pubkey,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub escrow: Pubkey,
+    pub arbiter: Pubkey,
+    // mint_a the ruling returned to the maker and awarded to the taker
+    pub maker_amount: u64,
+    pub taker_amount: u64,
+}
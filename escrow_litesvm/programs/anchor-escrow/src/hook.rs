@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{token_2022::spl_token_2022::onchain, token_interface::TransferChecked};
+
+/// `transfer_checked` that also hands a Token-2022 transfer hook the accounts it
+/// needs, picked by address out of `hook_accounts`. Mints without a hook ignore
+/// them, so handlers can pass their remaining accounts through as they are.
+pub fn transfer_checked<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    decimals: u8,
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let TransferChecked {
+        from,
+        mint,
+        to,
+        authority,
+    } = ctx.accounts;
+    onchain::invoke_transfer_checked(
+        ctx.program.key,
+        from,
+        mint,
+        to,
+        authority,
+        hook_accounts,
+        amount,
+        decimals,
+        ctx.signer_seeds,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,290 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use math::Rounding;
+
+use crate::{
+    error::ErrorCode,
+    hook, residual,
+    state::{Config, CounterOffer, Escrow, EscrowStatus, MakerRegistry, MakerStats},
+};
+
+#[derive(Accounts)]
+pub struct AcceptCounterOffer<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    // Gets the counter offer rent back
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+    #[account(mut, address = escrow.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    // Receives whatever the last fill leaves in the vault, see `residual::sweep`
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = maker,
+        token::token_program = token_program,
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: only the owner of treasury_ata_b, pinned to the config
+    #[account(address = config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_ata_b: InterfaceAccount<'info, TokenAccount>,
+    // Closed to rent_payer when the counter offer takes the last of the deposit
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        has_one = mint_b @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    // Required for escrows counted in the maker's registry, see `Escrow::registered`
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Account<'info, MakerRegistry>>,
+    // Required for escrows that report to the maker's stats, see `Escrow::stats`
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Account<'info, MakerStats>>,
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow,
+        has_one = taker,
+        seeds = [b"counter_offer", escrow.key().as_ref(), taker.key().as_ref()],
+        bump = counter_offer.bump,
+    )]
+    pub counter_offer: Account<'info, CounterOffer>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = counter_offer,
+        associated_token::token_program = token_program,
+    )]
+    pub counter_offer_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AcceptCounterOffer<'info> {
+    // Earlier fills may have left less in the vault than the taker asked for
+    pub fn check_acceptable(&self) -> Result<()> {
+        self.escrow.require_active()?;
+        require!(
+            self.counter_offer.request <= self.escrow.remaining_deposit,
+            ErrorCode::InvalidCounterOffer
+        );
+        self.escrow.check_min_fill(self.counter_offer.request)
+    }
+
+    /// Pays the offer to the maker, less the protocol fee, and closes the counter offer.
+    pub fn collect_offer(&mut self) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"counter_offer",
+            self.counter_offer.escrow.as_ref(),
+            self.counter_offer.taker.as_ref(),
+            &[self.counter_offer.bump],
+        ]];
+
+        let offer = self.counter_offer_vault.amount;
+        let fee = self.config.fee_for(offer).ok_or(ErrorCode::Overflow)?;
+        if fee > 0 {
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: self.counter_offer_vault.to_account_info(),
+                to: self.treasury_ata_b.to_account_info(),
+                authority: self.counter_offer.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+            transfer_checked(cpi_ctx, fee, self.mint_b.decimals)?;
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.counter_offer_vault.to_account_info(),
+            to: self.maker_ata_b.to_account_info(),
+            authority: self.counter_offer.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_ctx, offer - fee, self.mint_b.decimals)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.counter_offer_vault.to_account_info(),
+            destination: self.taker.to_account_info(),
+            authority: self.counter_offer.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_ctx)
+    }
+
+    /// Sends the requested mint_a to the taker as a fill, counted as Take counts one.
+    /// What is still owed for the rest of a fixed-price deposit shrinks in proportion,
+    /// rounded up in the maker's favour.
+    pub fn deliver_request(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let request = self.counter_offer.request;
+        let escrow = &mut self.escrow;
+        // Unit prices are recounted by `record_fill`, oracle prices quoted at Take
+        if escrow.unit_price.is_none() && escrow.price_feed.is_none() {
+            escrow.remaining_receive = math::mul_div(
+                escrow.remaining_receive,
+                escrow.remaining_deposit - request,
+                escrow.remaining_deposit,
+                Rounding::Up,
+            )
+            .ok_or(ErrorCode::Overflow)?;
+        }
+        let filled = escrow.record_fill(request)?;
+        self.report(self.counter_offer.offer, request)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.escrow.maker_seed(),
+            &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        hook::transfer_checked(cpi_context, request, self.mint_a.decimals, hook_accounts)?;
+
+        if !filled {
+            return Ok(());
+        }
+
+        residual::sweep(
+            &self.vault,
+            self.maker_ata_a.as_ref(),
+            self.escrow.to_account_info(),
+            &self.mint_a,
+            self.token_program.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.rent_payer.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        self.deregister()?;
+        self.escrow.close(self.rent_payer.to_account_info())
+    }
+
+    // Counts the accepted offer in the maker's stats, when the escrow reports to them
+    fn report(&mut self, payment: u64, fill: u64) -> Result<()> {
+        if self.escrow.stats.is_none() {
+            return Ok(());
+        }
+        let stats = self
+            .maker_stats
+            .as_mut()
+            .ok_or(ErrorCode::MakerStatsRequired)?;
+        stats.add_volume(self.mint_a.key(), fill);
+        stats.add_volume(self.mint_b.key(), payment);
+        if self.escrow.status == EscrowStatus::Closed {
+            stats.escrows_filled = stats.escrows_filled.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    // Drops the escrow from the maker's registry as it closes
+    fn deregister(&mut self) -> Result<()> {
+        if !self.escrow.registered {
+            return Ok(());
+        }
+        let registry = self
+            .maker_registry
+            .as_mut()
+            .ok_or(ErrorCode::MakerRegistryRequired)?;
+        registry.remove(&self.escrow.key());
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+    TransferChecked,
+};
+
+use crate::{
+    error::ErrorCode,
+    state::{Escrow, EscrowStatus, TakerDeposit},
+};
+
+// Both parties sign to call off a two-sided trade before it settles
+#[derive(Accounts)]
+pub struct CancelDeposit<'info> {
+    pub maker: Signer<'info>,
+    // Gets the payment back along with the deposit rent
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::authority = taker,
+        token::token_program = token_program,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_b @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow,
+        has_one = taker,
+        seeds = [b"taker_deposit", escrow.key().as_ref()],
+        bump = deposit.bump,
+    )]
+    pub deposit: Account<'info, TakerDeposit>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = deposit,
+        associated_token::token_program = token_program,
+    )]
+    pub deposit_vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CancelDeposit<'info> {
+    /// Returns the payment to the taker and opens the escrow again, so the maker can
+    /// refund it or wait for another taker.
+    pub fn cancel(&mut self) -> Result<()> {
+        let escrow_key = self.escrow.key();
+        let deposit_seeds: [&[&[u8]]; 1] =
+            [&[b"taker_deposit", escrow_key.as_ref(), &[self.deposit.bump]]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.deposit_vault.to_account_info(),
+            to: self.taker_ata_b.to_account_info(),
+            authority: self.deposit.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &deposit_seeds);
+
+        transfer_checked(cpi_ctx, self.deposit_vault.amount, self.mint_b.decimals)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.deposit_vault.to_account_info(),
+            destination: self.taker.to_account_info(),
+            authority: self.deposit.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &deposit_seeds);
+
+        close_account(cpi_ctx)?;
+
+        self.escrow.status = EscrowStatus::Open;
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::ErrorCode, state::Proceeds};
+
+#[derive(Accounts)]
+pub struct ClaimProceeds<'info> {
+    pub maker: Signer<'info>,
+    #[account(mut, address = proceeds.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    // Any mint_b token account owned by the maker, e.g. the one that is now thawed
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::authority = maker,
+        token::token_program = token_program,
+    )]
+    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = maker,
+        has_one = mint_b @ ErrorCode::InvalidMint,
+        seeds = [b"proceeds", proceeds.escrow.as_ref()],
+        bump = proceeds.bump,
+    )]
+    pub proceeds: Account<'info, Proceeds>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = proceeds,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ClaimProceeds<'info> {
+    pub fn claim_and_close_vault(&mut self) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"proceeds",
+            self.proceeds.escrow.as_ref(),
+            &[self.proceeds.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.proceeds_vault.to_account_info(),
+            to: self.maker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            authority: self.proceeds.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, self.proceeds_vault.amount, self.mint_b.decimals)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.proceeds_vault.to_account_info(),
+            destination: self.rent_payer.to_account_info(),
+            authority: self.proceeds.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)
+    }
+}
@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{error::ErrorCode, hook, state::Vesting};
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = taker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        seeds = [b"vesting", vesting.escrow.as_ref(), taker.key().as_ref()],
+        bump = vesting.bump,
+    )]
+    pub vesting: Account<'info, Vesting>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = vesting,
+        associated_token::token_program = token_program,
+    )]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimVested<'info> {
+    /// Sends the taker whatever vested since their last claim. The claim that ends
+    /// the schedule also closes the vesting account and its vault to the taker.
+    pub fn claim(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let vesting = &self.vesting;
+        let vested = vesting
+            .vested(Clock::get()?.unix_timestamp)
+            .ok_or(ErrorCode::Overflow)?;
+        let amount = vested - vesting.claimed;
+        require!(amount > 0, ErrorCode::NothingVested);
+
+        // Copied out so the seeds do not hold a borrow of the vesting account
+        let (escrow, taker, bump) = (vesting.escrow, vesting.taker, vesting.bump);
+        let signer_seeds: [&[&[u8]]; 1] = [&[b"vesting", escrow.as_ref(), taker.as_ref(), &[bump]]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vesting_vault.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: self.vesting.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        hook::transfer_checked(cpi_context, amount, self.mint_a.decimals, hook_accounts)?;
+
+        self.vesting.claimed = vested;
+        if vested < self.vesting.total {
+            return Ok(());
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vesting_vault.to_account_info(),
+            destination: self.taker.to_account_info(),
+            authority: self.vesting.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+        self.vesting.close(self.taker.to_account_info())
+    }
+}
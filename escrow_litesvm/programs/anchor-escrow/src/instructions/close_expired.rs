@@ -0,0 +1,199 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    error::ErrorCode,
+    events::{self, EscrowExpired},
+    hook,
+    state::{Escrow, EscrowStatus, MakerRegistry, MakerStats},
+};
+
+// Lamports taken out of the escrow rent for whoever cranks an expired escrow
+pub const CLOSE_EXPIRED_BOUNTY: u64 = 10_000;
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct CloseExpired<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    // Gets back the cancellation fee staked on the escrow, if any
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(mut, address = escrow.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    // The maker is not signing, so the deposit only ever goes to their canonical ATA
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    // Closed by hand so the bounty can be split off the rent first
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    // Required for escrows counted in the maker's registry, see `Escrow::registered`
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Account<'info, MakerRegistry>>,
+    // Required for escrows that report to the maker's stats, see `Escrow::stats`
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Account<'info, MakerStats>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CloseExpired<'info> {
+    pub fn check_expired(&self) -> Result<()> {
+        require!(
+            self.escrow.status != EscrowStatus::Locked,
+            ErrorCode::EscrowLocked
+        );
+        require!(
+            self.escrow.status != EscrowStatus::Disputed,
+            ErrorCode::EscrowDisputed
+        );
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            self.escrow.is_expired(current_time),
+            ErrorCode::EscrowNotPastExpiry
+        );
+        // Expiry is kept past the window, this only guards older escrows
+        require!(
+            !self.escrow.cancel_fee_at_stake(current_time),
+            ErrorCode::CancelFeeAtStake
+        );
+        Ok(())
+    }
+
+    pub fn refund_and_close(
+        &mut self,
+        hook_accounts: &[AccountInfo<'info>],
+        bumps: &CloseExpiredBumps,
+    ) -> Result<()> {
+        self.escrow.status = EscrowStatus::Closed;
+        self.deregister()?;
+        self.report()?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.escrow.maker_seed(),
+            &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        hook::transfer_checked(
+            cpi_context,
+            self.vault.amount,
+            self.mint_a.decimals,
+            hook_accounts,
+        )?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.rent_payer.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        // The staked cancellation fee is the maker's, whoever paid the rent
+        let cancel_fee = self.escrow.cancel_fee;
+        if cancel_fee > 0 {
+            self.escrow.sub_lamports(cancel_fee)?;
+            self.maker.add_lamports(cancel_fee)?;
+        }
+
+        self.escrow.sub_lamports(CLOSE_EXPIRED_BOUNTY)?;
+        self.cranker.add_lamports(CLOSE_EXPIRED_BOUNTY)?;
+
+        self.escrow.close(self.rent_payer.to_account_info())?;
+
+        events::emit_cpi(
+            &EscrowExpired {
+                escrow: self.escrow.key(),
+                cranker: self.cranker.key(),
+                amount: self.vault.amount,
+            },
+            &self.event_authority,
+            bumps.event_authority,
+        )
+    }
+
+    // Counts the expired escrow as refunded in the maker's stats, when it reports to
+    // them, since the deposit goes back to the maker as on Refund
+    fn report(&mut self) -> Result<()> {
+        if self.escrow.stats.is_none() {
+            return Ok(());
+        }
+        let stats = self
+            .maker_stats
+            .as_mut()
+            .ok_or(ErrorCode::MakerStatsRequired)?;
+        stats.escrows_refunded = stats.escrows_refunded.saturating_add(1);
+        Ok(())
+    }
+
+    // Drops the escrow from the maker's registry as it closes
+    fn deregister(&mut self) -> Result<()> {
+        if !self.escrow.registered {
+            return Ok(());
+        }
+        let registry = self
+            .maker_registry
+            .as_mut()
+            .ok_or(ErrorCode::MakerRegistryRequired)?;
+        registry.remove(&self.escrow.key());
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Escrow, TakeCommitment};
+
+#[derive(Accounts)]
+pub struct CommitTake<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub escrow: Account<'info, Escrow>,
+    // One open commitment per taker and escrow, closed by the reveal
+    #[account(
+        init,
+        payer = taker,
+        seeds = [b"take_commitment", escrow.key().as_ref(), taker.key().as_ref()],
+        bump,
+        space = 8 + TakeCommitment::INIT_SPACE,
+    )]
+    pub commitment: Account<'info, TakeCommitment>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CommitTake<'info> {
+    pub fn commit(&mut self, hash: [u8; 32], bumps: &CommitTakeBumps) -> Result<()> {
+        self.commitment.set_inner(TakeCommitment {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            hash,
+            slot: Clock::get()?.slot,
+            bump: bumps.commitment,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,460 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use math::Rounding;
+
+use crate::{
+    error::ErrorCode,
+    hook, residual,
+    state::{Config, Escrow, MakerRegistry, MakerStats},
+};
+
+// Settles escrow_x (mint_a for mint_b) against escrow_y (mint_b for mint_a) vault
+// to vault. Anyone may crank it and keeps whatever the prices leave over.
+#[derive(Accounts)]
+pub struct Cross<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+    pub maker_x: SystemAccount<'info>,
+    #[account(constraint = maker_y.key() != maker_x.key() @ ErrorCode::CrossSameMaker)]
+    pub maker_y: SystemAccount<'info>,
+    #[account(mut, address = escrow_x.rent_payer)]
+    pub rent_payer_x: SystemAccount<'info>,
+    #[account(mut, address = escrow_y.rent_payer)]
+    pub rent_payer_y: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        constraint = escrow_x.maker == maker_x.key() @ ErrorCode::InvalidEscrowAccount,
+        constraint = escrow_x.mint_a == mint_a.key() @ ErrorCode::InvalidMint,
+        constraint = escrow_x.mint_b == mint_b.key() @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow_x.maker_seed(),
+            escrow_x.seed.to_le_bytes().as_ref(),
+            escrow_x.mint_a_seed(),
+            escrow_x.mint_b_seed(),
+        ],
+        bump = escrow_x.bump,
+    )]
+    pub escrow_x: Box<Account<'info, Escrow>>,
+    #[account(
+        mut,
+        constraint = escrow_y.maker == maker_y.key() @ ErrorCode::InvalidEscrowAccount,
+        constraint = escrow_y.mint_a == mint_b.key() @ ErrorCode::InvalidMint,
+        constraint = escrow_y.mint_b == mint_a.key() @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow_y.maker_seed(),
+            escrow_y.seed.to_le_bytes().as_ref(),
+            escrow_y.mint_a_seed(),
+            escrow_y.mint_b_seed(),
+        ],
+        bump = escrow_y.bump,
+    )]
+    pub escrow_y: Box<Account<'info, Escrow>>,
+    #[account(
+        mut,
+        address = escrow_x.vault_address(&escrow_x.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault_x: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        address = escrow_y.vault_address(&escrow_y.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault_y: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker_x,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_x_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker_y,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_y_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Receive whatever the fill closing escrow_x or escrow_y leaves in its vault, see
+    // `residual::sweep`
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = maker_x,
+        token::token_program = token_program,
+    )]
+    pub maker_x_ata_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::authority = maker_y,
+        token::token_program = token_program,
+    )]
+    pub maker_y_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_a,
+        associated_token::authority = cranker,
+        associated_token::token_program = token_program,
+    )]
+    pub cranker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_b,
+        associated_token::authority = cranker,
+        associated_token::token_program = token_program,
+    )]
+    pub cranker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    /// CHECK: only the owner of the treasury token accounts, pinned to the config
+    #[account(address = config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_a,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = cranker,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Required when a filled escrow is counted in its maker's registry
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker_x.key().as_ref()],
+        bump = maker_registry_x.bump,
+    )]
+    pub maker_registry_x: Option<Box<Account<'info, MakerRegistry>>>,
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker_y.key().as_ref()],
+        bump = maker_registry_y.bump,
+    )]
+    pub maker_registry_y: Option<Box<Account<'info, MakerRegistry>>>,
+    // Required when an escrow reports to its maker's stats
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker_x.key().as_ref()],
+        bump = maker_stats_x.bump,
+    )]
+    pub maker_stats_x: Option<Box<Account<'info, MakerStats>>>,
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker_y.key().as_ref()],
+        bump = maker_stats_y.bump,
+    )]
+    pub maker_stats_y: Option<Box<Account<'info, MakerStats>>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Amounts moved by a cross, each side paid as if the other had taken it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CrossQuote {
+    // mint_b escrow_y pays maker_x, and the mint_a escrow_x releases for it
+    pub paid_x: u64,
+    pub fill_x: u64,
+    // mint_a escrow_x pays maker_y, and the mint_b escrow_y releases for it
+    pub paid_y: u64,
+    pub fill_y: u64,
+}
+
+impl CrossQuote {
+    /// Fills whichever escrow is smaller in mint_b terms and as much of the other as
+    /// that pays for. Fails when either side releases less than it is asked to pay.
+    pub fn new(x: &Escrow, y: &Escrow) -> Result<Self> {
+        let quote = if x.remaining_receive <= y.remaining_deposit {
+            let paid_x = x.remaining_receive;
+            // The least mint_a that has escrow_y release all of paid_x
+            let paid_y = math::mul_div(
+                paid_x,
+                y.remaining_receive,
+                y.remaining_deposit,
+                Rounding::Up,
+            )
+            .ok_or(ErrorCode::Overflow)?;
+            Self {
+                paid_x,
+                fill_x: x.fill_for(paid_x)?,
+                paid_y,
+                fill_y: y.fill_for(paid_y)?,
+            }
+        } else {
+            let paid_x = y.remaining_deposit;
+            let paid_y = y.remaining_receive;
+            Self {
+                paid_x,
+                fill_x: x.fill_for(paid_x)?,
+                paid_y,
+                fill_y: y.fill_for(paid_y)?,
+            }
+        };
+        require!(
+            quote.fill_x >= quote.paid_y && quote.fill_y >= quote.paid_x,
+            ErrorCode::PricesDoNotCross
+        );
+        Ok(quote)
+    }
+}
+
+impl<'info> Cross<'info> {
+    pub fn check_escrows(&self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        for escrow in [&self.escrow_x, &self.escrow_y] {
+            escrow.require_active()?;
+            escrow.require_not_paused()?;
+            require!(now >= escrow.unlock_at, ErrorCode::WaitingTimeNotElapsed);
+            require!(!escrow.is_expired(now), ErrorCode::EscrowExpired);
+            // Crossed at the stored remaining pair only, and open to any taker
+            require!(
+                escrow.taker.is_none()
+                    && escrow.unit_price.is_none()
+                    && !escrow.needs_take()
+                    && escrow.delivers_at_take(),
+                ErrorCode::CrossUnsupported
+            );
+        }
+        Ok(())
+    }
+
+    pub fn settle(
+        &mut self,
+        quote: &CrossQuote,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        // Saturating like Take, so no pricing mode can have a payment outrun the rest
+        self.escrow_x.remaining_receive =
+            self.escrow_x.remaining_receive.saturating_sub(quote.paid_x);
+        let filled_x = self.escrow_x.record_fill(quote.fill_x)?;
+        self.escrow_y.remaining_receive =
+            self.escrow_y.remaining_receive.saturating_sub(quote.paid_y);
+        let filled_y = self.escrow_y.record_fill(quote.fill_y)?;
+
+        self.release(
+            &self.escrow_x,
+            &self.vault_x,
+            &self.mint_a,
+            self.maker_y_ata_a.to_account_info(),
+            self.treasury_ata_a.to_account_info(),
+            self.cranker_ata_a.to_account_info(),
+            quote.paid_y,
+            quote.fill_x,
+            hook_accounts,
+        )?;
+        self.release(
+            &self.escrow_y,
+            &self.vault_y,
+            &self.mint_b,
+            self.maker_x_ata_b.to_account_info(),
+            self.treasury_ata_b.to_account_info(),
+            self.cranker_ata_b.to_account_info(),
+            quote.paid_x,
+            quote.fill_y,
+            hook_accounts,
+        )?;
+
+        report(
+            &self.escrow_x,
+            self.maker_stats_x.as_deref_mut(),
+            (self.mint_a.key(), quote.fill_x),
+            (self.mint_b.key(), quote.paid_x),
+            filled_x,
+        )?;
+        report(
+            &self.escrow_y,
+            self.maker_stats_y.as_deref_mut(),
+            (self.mint_b.key(), quote.fill_y),
+            (self.mint_a.key(), quote.paid_y),
+            filled_y,
+        )?;
+
+        if filled_x {
+            self.close_vault(
+                &self.escrow_x,
+                &self.vault_x,
+                &self.mint_a,
+                self.maker_x_ata_a.as_deref(),
+                self.rent_payer_x.to_account_info(),
+                hook_accounts,
+            )?;
+            deregister(&self.escrow_x, self.maker_registry_x.as_deref_mut())?;
+            self.escrow_x.close(self.rent_payer_x.to_account_info())?;
+        }
+        if filled_y {
+            self.close_vault(
+                &self.escrow_y,
+                &self.vault_y,
+                &self.mint_b,
+                self.maker_y_ata_b.as_deref(),
+                self.rent_payer_y.to_account_info(),
+                hook_accounts,
+            )?;
+            deregister(&self.escrow_y, self.maker_registry_y.as_deref_mut())?;
+            self.escrow_y.close(self.rent_payer_y.to_account_info())?;
+        }
+        Ok(())
+    }
+
+    // Pays `paid` out of the vault to the other maker, less the protocol fee, and
+    // the rest of `amount` to the cranker
+    #[allow(clippy::too_many_arguments)]
+    fn release(
+        &self,
+        escrow: &Account<'info, Escrow>,
+        vault: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        maker_ata: AccountInfo<'info>,
+        treasury_ata: AccountInfo<'info>,
+        cranker_ata: AccountInfo<'info>,
+        paid: u64,
+        amount: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let fee = self.config.fee_for(paid).ok_or(ErrorCode::Overflow)?;
+        let spread = amount.checked_sub(paid).ok_or(ErrorCode::Overflow)?;
+        for (to, amount) in [
+            (treasury_ata, fee),
+            (maker_ata, paid - fee),
+            (cranker_ata, spread),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            self.transfer_from_vault(escrow, vault, mint, to, amount, hook_accounts)?;
+        }
+        Ok(())
+    }
+
+    fn transfer_from_vault(
+        &self,
+        escrow: &Account<'info, Escrow>,
+        vault: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        to: AccountInfo<'info>,
+        amount: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            escrow.maker_seed(),
+            &escrow.seed.to_le_bytes()[..],
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+            &[escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: vault.to_account_info(),
+            to,
+            authority: escrow.to_account_info(),
+            mint: mint.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        hook::transfer_checked(cpi_context, amount, mint.decimals, hook_accounts)
+    }
+
+    // Returns any residual to the escrow's maker before closing its vault
+    fn close_vault(
+        &self,
+        escrow: &Account<'info, Escrow>,
+        vault: &InterfaceAccount<'info, TokenAccount>,
+        mint: &InterfaceAccount<'info, Mint>,
+        maker_ata: Option<&InterfaceAccount<'info, TokenAccount>>,
+        rent_payer: AccountInfo<'info>,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            escrow.maker_seed(),
+            &escrow.seed.to_le_bytes()[..],
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+            &[escrow.bump],
+        ]];
+
+        residual::sweep(
+            vault,
+            maker_ata,
+            escrow.to_account_info(),
+            mint,
+            self.token_program.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: vault.to_account_info(),
+            destination: rent_payer,
+            authority: escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)
+    }
+}
+
+// Adds one side of the cross to its maker's stats, when the escrow reports to them
+fn report(
+    escrow: &Escrow,
+    stats: Option<&mut Account<MakerStats>>,
+    sold: (Pubkey, u64),
+    bought: (Pubkey, u64),
+    filled: bool,
+) -> Result<()> {
+    if escrow.stats.is_none() {
+        return Ok(());
+    }
+    let stats = stats.ok_or(ErrorCode::MakerStatsRequired)?;
+    stats.add_volume(sold.0, sold.1);
+    stats.add_volume(bought.0, bought.1);
+    if filled {
+        stats.escrows_filled = stats.escrows_filled.saturating_add(1);
+    }
+    Ok(())
+}
+
+// Drops a filled escrow from its maker's registry
+fn deregister(
+    escrow: &Account<Escrow>,
+    registry: Option<&mut Account<MakerRegistry>>,
+) -> Result<()> {
+    if !escrow.registered {
+        return Ok(());
+    }
+    let registry = registry.ok_or(ErrorCode::MakerRegistryRequired)?;
+    registry.remove(&escrow.key());
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    error::ErrorCode,
+    state::{Escrow, EscrowStatus, TakerDeposit},
+};
+
+// Take for two-sided escrows: the taker's mint_b is vaulted next to the deposit
+// instead of going to the maker, see `SettleDeposit` and `CancelDeposit`
+#[derive(Accounts)]
+pub struct DepositPayment<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        has_one = mint_b @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        init,
+        payer = taker,
+        seeds = [b"taker_deposit", escrow.key().as_ref()],
+        bump,
+        space = 8 + TakerDeposit::INIT_SPACE,
+    )]
+    pub deposit: Account<'info, TakerDeposit>,
+    #[account(
+        init,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = deposit,
+        associated_token::token_program = token_program,
+    )]
+    pub deposit_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> DepositPayment<'info> {
+    // The checks Take runs, for the escrows a deposit can fill
+    pub fn check_escrow(&self) -> Result<()> {
+        let escrow = &self.escrow;
+        require!(escrow.is_two_sided(), ErrorCode::NotTwoSided);
+        escrow.require_active()?;
+        escrow.require_not_paused()?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= escrow.unlock_at, ErrorCode::WaitingTimeNotElapsed);
+        require!(!escrow.is_expired(now), ErrorCode::EscrowExpired);
+        if let Some(taker) = escrow.taker {
+            require_keys_eq!(self.taker.key(), taker, ErrorCode::UnauthorizedTaker);
+        }
+        require!(!escrow.needs_take(), ErrorCode::TwoSidedUnsupported);
+        Ok(())
+    }
+
+    /// Vaults the whole remaining receive and locks the escrow until it is settled
+    /// or cancelled.
+    pub fn deposit(&mut self, bumps: &DepositPaymentBumps) -> Result<()> {
+        let amount = self.escrow.remaining_receive;
+        let review_period = self.escrow.review_period.unwrap_or_default();
+        let settle_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(review_period)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            to: self.deposit_vault.to_account_info(),
+            authority: self.taker.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer_checked(cpi_ctx, amount, self.mint_b.decimals)?;
+
+        self.deposit.set_inner(TakerDeposit {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            amount,
+            settle_at,
+            bump: bumps.deposit,
+        });
+        self.escrow.status = EscrowStatus::Locked;
+        Ok(())
+    }
+}
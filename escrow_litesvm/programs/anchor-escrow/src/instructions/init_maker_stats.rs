@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::state::MakerStats;
+
+#[derive(Accounts)]
+pub struct InitMakerStats<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump,
+        space = 8 + MakerStats::INIT_SPACE,
+    )]
+    pub maker_stats: Account<'info, MakerStats>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitMakerStats<'info> {
+    pub fn init_stats(&mut self, bumps: &InitMakerStatsBumps) -> Result<()> {
+        self.maker_stats.set_inner(MakerStats {
+            maker: self.maker.key(),
+            escrows_created: 0,
+            escrows_filled: 0,
+            escrows_refunded: 0,
+            volume: vec![],
+            bump: bumps.maker_stats,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::Config};
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"config"],
+        bump,
+        space = 8 + Config::INIT_SPACE,
+    )]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeConfig<'info> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_config(
+        &mut self,
+        min_deposit: u64,
+        max_waiting_time: i64,
+        max_expiry_horizon: i64,
+        min_decimals: u8,
+        max_decimals: u8,
+        fee_bps: u16,
+        treasury: Pubkey,
+        bumps: &InitializeConfigBumps,
+    ) -> Result<()> {
+        require!(
+            max_waiting_time >= 0 && max_expiry_horizon >= 0 && min_decimals <= max_decimals,
+            ErrorCode::InvalidConfig
+        );
+
+        self.config.set_inner(Config {
+            admin: self.admin.key(),
+            min_deposit,
+            max_waiting_time,
+            max_expiry_horizon,
+            min_decimals,
+            max_decimals,
+            bump: bumps.config,
+            fee_bps: 0,
+            treasury: Pubkey::default(),
+            paused: false,
+            max_open_escrows_per_maker: 0,
+            referral_bps: 0,
+            fee_tiers: vec![],
+        });
+
+        self.config.set_fee(fee_bps, treasury)
+    }
+}
@@ -1,81 +1,683 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, system_program};
 use anchor_spl::{
-    associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    associated_token::{self, AssociatedToken},
+    token_2022::spl_token_2022::{
+        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        state::Mint as MintState,
+    },
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
 };
 
-use crate::state::Escrow;
+use math::Rounding;
 
+use crate::{
+    error::ErrorCode,
+    events::{self, EscrowMade},
+    hook, interest, memo, native,
+    state::{Config, Escrow, EscrowStatus, MakerRegistry, MakerStats, PaymentOption, UnitPrice},
+};
+
+/// Terms of a Make. Optional features are grouped so an escrow without them leaves
+/// the group None.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct MakeArgs {
+    pub deposit: u64,
+    pub receive: u64,
+    pub waiting_time: i64,
+    // 0 for none
+    pub expiry: i64,
+    pub taker: Option<Pubkey>,
+    pub taker_root: Option<[u8; 32]>,
+    pub whitelisted_takers: bool,
+    pub require_no_freeze_authority: bool,
+    pub rent_recipient: Option<Pubkey>,
+    pub payment_options: Vec<PaymentOption>,
+    pub receive_in_ui_amount: bool,
+    pub label: Option<String>,
+    pub memo: Option<String>,
+    pub oracle: Option<OraclePricing>,
+    pub unit_pricing: Option<UnitPricing>,
+    pub fill_terms: Option<FillTerms>,
+    pub refund_terms: Option<RefundTerms>,
+    pub settlement_terms: Option<SettlementTerms>,
+}
+
+/// Prices Take from a Pyth price account, see `Make::price_with_oracle`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct OraclePricing {
+    pub price_feed: Pubkey,
+    pub spread_bps: u16,
+}
+
+/// Prices each fill on its own, see `Make::price_per_unit`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct UnitPricing {
+    pub unit_price: UnitPrice,
+    // 0 keeps the price flat
+    pub curve_bps: u16,
+}
+
+/// How the escrow behaves across partial fills. The default keeps none of them.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
+pub struct FillTerms {
+    pub standing: bool,
+    pub min_fill: u64,
+    pub fill_grace_period: i64,
+}
+
+/// What the maker commits to before refunding. The default commits to nothing.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
+pub struct RefundTerms {
+    pub no_refund_before: i64,
+    pub cancel_fee: u64,
+    pub cancel_fee_window: i64,
+}
+
+/// How the taker's side is released. The default delivers at Take.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
+pub struct SettlementTerms {
+    pub hashlock: Option<[u8; 32]>,
+    pub arbiter: Option<Pubkey>,
+    pub review_period: Option<i64>,
+    pub vesting_duration: i64,
+}
+
+#[event_cpi]
 #[derive(Accounts)]
 #[instruction(seed: u64)]
 pub struct Make<'info> {
     #[account(mut)]
     pub maker: Signer<'info>,
+    // Funds the escrow and vault rent, may be a sponsor distinct from the maker
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mint::token_program = token_program)]
     pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
     pub mint_b: InterfaceAccount<'info, Mint>,
+    // Not needed when mint_a is native SOL, the deposit is wrapped from the maker's lamports
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
+        associated_token::token_program = token_program,
     )]
-    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init,
-        payer = maker,
-        seeds = [b"escrow", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        payer = payer,
+        seeds = [
+            b"escrow",
+            maker.key().as_ref(),
+            seed.to_le_bytes().as_ref(),
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+        ],
         bump,
         space = 8 + Escrow::INIT_SPACE,
     )]
     pub escrow: Account<'info, Escrow>,
     #[account(
         init,
-        payer = maker,
+        payer = payer,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
+        associated_token::token_program = token_program,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
+    // Created on the maker's first Make
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump,
+        space = 8 + MakerRegistry::INIT_SPACE,
+    )]
+    pub maker_registry: Account<'info, MakerRegistry>,
+    // Only for makers who opted into stats, the escrow then reports to it
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Account<'info, MakerStats>>,
+    /// CHECK: only needed with a memo, pinned to the SPL Memo program
+    #[account(address = memo::MEMO_PROGRAM_ID @ ErrorCode::InvalidMemoProgram)]
+    pub memo_program: Option<UncheckedAccount<'info>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Make<'info> {
+    pub fn check_bounds(&self, deposit: u64, waiting_time: i64) -> Result<()> {
+        require!(
+            deposit >= self.config.min_deposit,
+            ErrorCode::DepositTooSmall
+        );
+        require!(waiting_time >= 0, ErrorCode::NegativeWaitingTime);
+        require!(
+            waiting_time <= self.config.max_waiting_time,
+            ErrorCode::WaitingTimeTooLong
+        );
+
+        for mint in [&self.mint_a, &self.mint_b] {
+            require!(
+                (self.config.min_decimals..=self.config.max_decimals).contains(&mint.decimals),
+                ErrorCode::DecimalsOutOfRange
+            );
+            require!(
+                !has_confidential_transfers(mint)?,
+                ErrorCode::ConfidentialTransferUnsupported
+            );
+        }
+
+        Ok(())
+    }
+
+    /// An expiry of 0 leaves the escrow open until taken or refunded.
+    pub fn check_expiry(&self, waiting_time: i64, expiry: i64) -> Result<()> {
+        if expiry == 0 {
+            return Ok(());
+        }
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let unlock_at = current_time
+            .checked_add(waiting_time)
+            .ok_or(ErrorCode::Overflow)?;
+        let horizon = current_time
+            .checked_add(self.config.max_expiry_horizon)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            expiry > current_time && expiry > unlock_at && expiry <= horizon,
+            ErrorCode::InvalidExpiry
+        );
+
+        Ok(())
+    }
+
+    /// Opt-in for makers whose takers want no mint able to freeze the vault or the
+    /// proceeds mid-escrow. A mint without a freeze authority can never gain one.
+    pub fn check_freeze_authority(&self, require_no_freeze_authority: bool) -> Result<()> {
+        if !require_no_freeze_authority {
+            return Ok(());
+        }
+        require!(
+            self.mint_a.freeze_authority.is_none() && self.mint_b.freeze_authority.is_none(),
+            ErrorCode::FreezeAuthoritySet
+        );
+        Ok(())
+    }
+
     pub fn init_escrow(
         &mut self,
         seed: u64,
+        deposit: u64,
         receive: u64,
         waiting_time: i64,
+        expiry: i64,
         bumps: &MakeBumps,
     ) -> Result<()> {
         let current_time = Clock::get()?.unix_timestamp;
+        let unlock_at = current_time
+            .checked_add(waiting_time)
+            .ok_or(ErrorCode::Overflow)?;
 
         self.escrow.set_inner(Escrow {
             seed,
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
+            status: EscrowStatus::Open,
             receive,
-            waiting_time: current_time + waiting_time,
+            unlock_at,
             bump: bumps.escrow,
+            version: Escrow::CURRENT_VERSION,
+            expiry,
+            taker: None,
+            stats: self.maker_stats.as_ref().map(|stats| stats.key()),
+            rent_payer: self.payer.key(),
+            remaining_deposit: deposit,
+            remaining_receive: receive,
+            taker_root: None,
+            enforce_royalties: false,
+            price_feed: None,
+            spread_bps: 0,
+            unit_price: None,
+            no_refund_before: 0,
+            payment_options: vec![],
+            receive_in_ui_amount: false,
+            label: None,
+            paused: false,
+            vault_bump: self.vault_bump(),
+            mint_seeded: true,
+            registered: true,
+            reveal_window: 0,
+            hashlock: None,
+            arbiter: None,
+            review_period: None,
+            vesting_duration: 0,
+            standing: false,
+            total_filled: 0,
+            min_fill: 0,
+            cancel_fee: 0,
+            cancel_fee_until: 0,
+            whitelisted_takers: false,
+            fill_grace_period: 0,
+            made_by: None,
+            curve_bps: 0,
         });
 
+        self.register(seed, bumps)
+    }
+
+    // Lists the escrow in the maker's registry, filling in a newly created one, and
+    // counts it in their stats
+    fn register(&mut self, seed: u64, bumps: &MakeBumps) -> Result<()> {
+        let registry = &mut self.maker_registry;
+        let max_open = self.config.max_open_escrows_per_maker;
+        require!(
+            max_open == 0 || registry.open_escrows < max_open,
+            ErrorCode::TooManyOpenEscrows
+        );
+        if registry.maker == Pubkey::default() {
+            registry.maker = self.maker.key();
+            registry.bump = bumps.maker_registry;
+        }
+        registry.add(self.escrow.key(), seed)?;
+
+        if let Some(stats) = self.maker_stats.as_mut() {
+            stats.escrows_created = stats.escrows_created.saturating_add(1);
+        }
         Ok(())
     }
 
-    pub fn deposit(&mut self, deposit: u64) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
+    // The vault is created by the associated token program, which does not hand back
+    // its bump, so it is searched for once here and stored for Take
+    fn vault_bump(&self) -> u8 {
+        Pubkey::find_program_address(
+            &[
+                self.escrow.key().as_ref(),
+                self.token_program.key().as_ref(),
+                self.mint_a.key().as_ref(),
+            ],
+            &associated_token::ID,
+        )
+        .1
+    }
 
-        let cpi_accounts = TransferChecked {
-            from: self.maker_ata_a.to_account_info(),
-            to: self.vault.to_account_info(),
-            authority: self.maker.to_account_info(),
-            mint: self.mint_a.to_account_info(),
+    /// Restricts Take to a single counterparty and/or the takers under a merkle
+    /// root built with `crate::merkle`, None for both leaves it open to anyone.
+    pub fn restrict_takers(&mut self, taker: Option<Pubkey>, taker_root: Option<[u8; 32]>) {
+        self.escrow.taker = taker;
+        self.escrow.taker_root = taker_root;
+    }
+
+    /// Where the escrow and vault rent goes once they close, the payer when None. Lets
+    /// a platform paying rent for its makers reclaim it to a treasury instead.
+    pub fn send_rent_to(&mut self, rent_recipient: Option<Pubkey>) {
+        if let Some(rent_recipient) = rent_recipient {
+            self.escrow.rent_payer = rent_recipient;
+        }
+    }
+
+    /// Prices the escrow from a Pyth price account at Take instead of the fixed
+    /// `receive`, which is zeroed. None keeps the fixed price.
+    pub fn price_with_oracle(&mut self, oracle: Option<OraclePricing>) -> Result<()> {
+        let Some(oracle) = oracle else {
+            return Ok(());
+        };
+        require!(cfg!(feature = "oracle"), ErrorCode::OracleDisabled);
+
+        let escrow = &mut self.escrow;
+        escrow.price_feed = Some(oracle.price_feed);
+        escrow.spread_bps = oracle.spread_bps;
+        escrow.receive = 0;
+        escrow.remaining_receive = 0;
+        Ok(())
+    }
+
+    /// Prices each fill at `unit_price` on its own, with `receive` set to the cost
+    /// of the whole deposit. A `curve_bps` raises the unit price as the vault drains,
+    /// by up to that much for the last unit, so a single escrow can quote large
+    /// takers like a one-sided liquidity position. None keeps the deposit/receive
+    /// pair.
+    pub fn price_per_unit(
+        &mut self,
+        deposit: u64,
+        unit_pricing: Option<UnitPricing>,
+    ) -> Result<()> {
+        let Some(UnitPricing {
+            unit_price,
+            curve_bps,
+        }) = unit_pricing
+        else {
+            return Ok(());
         };
+        require!(
+            unit_price.is_valid() && self.escrow.price_feed.is_none(),
+            ErrorCode::InvalidUnitPrice
+        );
+
+        let receive = unit_price
+            .cost(deposit, Rounding::Up)
+            .ok_or(ErrorCode::Overflow)?;
+        let escrow = &mut self.escrow;
+        escrow.unit_price = Some(unit_price);
+        escrow.curve_bps = curve_bps;
+        escrow.receive = receive;
+        escrow.remaining_receive = receive;
+        Ok(())
+    }
+
+    /// Commits the maker to not refunding before `no_refund_before`, which cannot
+    /// outlast the expiry since anyone may close an expired escrow. 0 for none.
+    pub fn lock_refunds(&mut self, no_refund_before: i64) -> Result<()> {
+        if no_refund_before == 0 {
+            return Ok(());
+        }
+
+        let horizon = Clock::get()?
+            .unix_timestamp
+            .checked_add(self.config.max_expiry_horizon)
+            .ok_or(ErrorCode::Overflow)?;
+        let escrow = &mut self.escrow;
+        require!(
+            no_refund_before <= horizon
+                && (escrow.expiry == 0 || no_refund_before <= escrow.expiry),
+            ErrorCode::InvalidRefundLock
+        );
+        escrow.no_refund_before = no_refund_before;
+        Ok(())
+    }
+
+    /// Lets Take be paid in other mints, each option's `receive` standing in for the
+    /// escrow's. Oracle and unit prices are quoted in mint_b only.
+    pub fn accept_payment_options(&mut self, payment_options: Vec<PaymentOption>) -> Result<()> {
+        if payment_options.is_empty() {
+            return Ok(());
+        }
+
+        let escrow = &mut self.escrow;
+        require!(
+            payment_options.len() <= Escrow::MAX_PAYMENT_OPTIONS
+                && escrow.price_feed.is_none()
+                && escrow.unit_price.is_none(),
+            ErrorCode::InvalidPaymentOption
+        );
+        for (i, option) in payment_options.iter().enumerate() {
+            let duplicate = payment_options[..i]
+                .iter()
+                .any(|other| other.mint == option.mint);
+            require!(
+                option.receive > 0 && option.mint != escrow.mint_b && !duplicate,
+                ErrorCode::InvalidPaymentOption
+            );
+        }
+
+        escrow.payment_options = payment_options;
+        Ok(())
+    }
+
+    /// Counts Take payments at the UI amount an interest-bearing mint_b is worth at
+    /// the time, so `receive` keeps its value as interest accrues. Fixed prices only.
+    pub fn quote_in_ui_amount(&mut self, receive_in_ui_amount: bool) -> Result<()> {
+        if !receive_in_ui_amount {
+            return Ok(());
+        }
+
+        let escrow = &mut self.escrow;
+        require!(
+            escrow.price_feed.is_none()
+                && escrow.unit_price.is_none()
+                && escrow.payment_options.is_empty()
+                && interest::config(&self.mint_b.to_account_info())?.is_some(),
+            ErrorCode::InvalidUiAmountQuote
+        );
+        escrow.receive_in_ui_amount = true;
+        Ok(())
+    }
+
+    /// Take then has to present the hashlock's preimage. The refund lock gives the
+    /// taker a window to claim before the maker can take the deposit back.
+    pub fn set_hashlock(&mut self, hashlock: Option<[u8; 32]>) -> Result<()> {
+        if hashlock.is_some() {
+            require!(
+                self.escrow.no_refund_before != 0,
+                ErrorCode::InvalidHashlock
+            );
+        }
+        self.escrow.hashlock = hashlock;
+        Ok(())
+    }
+
+    /// Lets `arbiter` split the vault between the maker and the designated taker
+    /// if either raises a dispute. None leaves the escrow without disputes.
+    pub fn set_arbiter(&mut self, arbiter: Option<Pubkey>) -> Result<()> {
+        if arbiter.is_some() {
+            require!(self.escrow.taker.is_some(), ErrorCode::InvalidArbiter);
+        }
+        self.escrow.arbiter = arbiter;
+        Ok(())
+    }
+
+    /// Makes the escrow two-sided: the taker's payment is vaulted by DepositPayment
+    /// and both sides are released `review_period` seconds later. None for Take.
+    pub fn set_review_period(&mut self, review_period: Option<i64>) -> Result<()> {
+        if let Some(review_period) = review_period {
+            require!(review_period >= 0, ErrorCode::InvalidReviewPeriod);
+        }
+        self.escrow.review_period = review_period;
+        Ok(())
+    }
+
+    /// Streams each fill to its taker over `vesting_duration` seconds, see
+    /// `TakeVested`. 0 delivers at Take.
+    pub fn set_vesting_duration(&mut self, vesting_duration: i64) -> Result<()> {
+        require!(
+            vesting_duration >= 0 && (vesting_duration == 0 || !self.escrow.is_two_sided()),
+            ErrorCode::InvalidVestingDuration
+        );
+        require!(
+            vesting_duration == 0 || !has_transfer_fee(&self.mint_a)?,
+            ErrorCode::VestingTransferFee
+        );
+        self.escrow.vesting_duration = vesting_duration;
+        Ok(())
+    }
+
+    /// Keeps the escrow at its address once the deposit runs out, for the maker to
+    /// refill with TopUp. Two-sided escrows settle once, so cannot stand.
+    pub fn keep_standing(&mut self, standing: bool) -> Result<()> {
+        require!(
+            !standing || !self.escrow.is_two_sided(),
+            ErrorCode::InvalidStandingOffer
+        );
+        self.escrow.standing = standing;
+        Ok(())
+    }
+
+    /// Keeps takers from splitting the offer into dust fills. The fill taking the rest
+    /// of the deposit is always allowed, so the escrow can still be emptied.
+    pub fn set_min_fill(&mut self, min_fill: u64) -> Result<()> {
+        require!(
+            min_fill <= self.escrow.remaining_deposit,
+            ErrorCode::InvalidMinFill
+        );
+        self.escrow.min_fill = min_fill;
+        Ok(())
+    }
+
+    /// Restricts Take to takers with an active entry in the whitelist-transfer-hook
+    /// program, for permissioned OTC desks built on its allowlist.
+    pub fn gate_on_whitelist(&mut self, whitelisted_takers: bool) {
+        self.escrow.whitelisted_takers = whitelisted_takers;
+    }
+
+    /// Keeps a partially filled offer open for at least `fill_grace_period` seconds
+    /// after each fill, so it does not expire while takers are still working it.
+    pub fn extend_on_fill(&mut self, fill_grace_period: i64) -> Result<()> {
+        if fill_grace_period == 0 {
+            return Ok(());
+        }
+        require!(
+            fill_grace_period > 0
+                && fill_grace_period <= self.config.max_expiry_horizon
+                && self.escrow.expiry != 0,
+            ErrorCode::InvalidFillGracePeriod
+        );
+        self.escrow.fill_grace_period = fill_grace_period;
+        Ok(())
+    }
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    /// Stakes `cancel_fee` lamports from the maker on the offer, forfeited to the
+    /// treasury if they refund within `cancel_fee_window` seconds. Otherwise the fee
+    /// goes back to the maker when the escrow closes.
+    pub fn commit_cancel_fee(&mut self, cancel_fee: u64, cancel_fee_window: i64) -> Result<()> {
+        if cancel_fee == 0 && cancel_fee_window == 0 {
+            return Ok(());
+        }
 
-        transfer_checked(cpi_ctx, deposit, self.mint_a.decimals)?;
+        require!(
+            cancel_fee > 0
+                && cancel_fee_window > 0
+                && cancel_fee_window <= self.config.max_expiry_horizon,
+            ErrorCode::InvalidCancelFee
+        );
+        let cancel_fee_until = Clock::get()?
+            .unix_timestamp
+            .checked_add(cancel_fee_window)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            self.escrow.expiry == 0 || cancel_fee_until <= self.escrow.expiry,
+            ErrorCode::InvalidCancelFee
+        );
 
+        let cpi_program = self.system_program.to_account_info();
+        let cpi_accounts = system_program::Transfer {
+            from: self.maker.to_account_info(),
+            to: self.escrow.to_account_info(),
+        };
+        let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+        system_program::transfer(cpi_context, cancel_fee)?;
+
+        self.escrow.cancel_fee = cancel_fee;
+        self.escrow.cancel_fee_until = cancel_fee_until;
         Ok(())
     }
+
+    /// Short description or URI frontends show for the offer. None for no label.
+    pub fn set_label(&mut self, label: Option<String>) -> Result<()> {
+        let len = label.as_ref().map_or(0, String::len);
+        require!(len <= Escrow::MAX_LABEL_LEN, ErrorCode::LabelTooLong);
+        self.escrow.label = label;
+        Ok(())
+    }
+
+    pub fn deposit(
+        &mut self,
+        deposit: u64,
+        hook_accounts: &[AccountInfo<'info>],
+        bumps: &MakeBumps,
+    ) -> Result<()> {
+        if native::is_native_mint(&self.mint_a.key()) {
+            native::wrap(
+                self.maker.to_account_info(),
+                self.vault.to_account_info(),
+                deposit,
+                self.system_program.to_account_info(),
+                self.token_program.to_account_info(),
+            )?;
+        } else {
+            let maker_ata_a = self
+                .maker_ata_a
+                .as_ref()
+                .ok_or(ErrorCode::MakerTokenAccountRequired)?;
+
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: maker_ata_a.to_account_info(),
+                to: self.vault.to_account_info(),
+                authority: self.maker.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+            hook::transfer_checked(cpi_ctx, deposit, self.mint_a.decimals, hook_accounts)?;
+        }
+
+        let deposit = self.credit_deposit()?;
+
+        events::emit_cpi(
+            &EscrowMade {
+                escrow: self.escrow.key(),
+                maker: self.maker.key(),
+                mint_a: self.mint_a.key(),
+                mint_b: self.mint_b.key(),
+                deposit,
+                receive: self.escrow.receive,
+                unlock_at: self.escrow.unlock_at,
+            },
+            &self.event_authority,
+            bumps.event_authority,
+        )
+    }
+
+    /// Token-2022 transfer fees withhold part of the deposit, so the escrow is
+    /// reconciled to what actually reached the vault. Returns that amount.
+    fn credit_deposit(&mut self) -> Result<u64> {
+        self.vault.reload()?;
+        let received = self.vault.amount;
+        require!(received > 0, ErrorCode::DepositTooSmall);
+
+        let escrow = &mut self.escrow;
+        if received != escrow.remaining_deposit {
+            escrow.remaining_deposit = received;
+            if let Some(unit_price) = escrow.unit_price {
+                let receive = unit_price
+                    .cost(received, Rounding::Up)
+                    .ok_or(ErrorCode::Overflow)?;
+                escrow.receive = receive;
+                escrow.remaining_receive = receive;
+            }
+        }
+        Ok(received)
+    }
+
+    pub fn log_memo(&self, memo: Option<String>) -> Result<()> {
+        memo::log(
+            memo,
+            self.memo_program.as_ref(),
+            self.maker.to_account_info(),
+        )
+    }
+}
+
+// Vault transfers are plain transfer_checked CPIs, which cannot carry the proofs a
+// confidential balance needs, so such mints are turned away up front
+pub fn has_confidential_transfers(mint: &InterfaceAccount<Mint>) -> Result<bool> {
+    let info = mint.to_account_info();
+    let data = info.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    Ok(mint
+        .get_extension_types()?
+        .contains(&ExtensionType::ConfidentialTransferMint))
+}
+
+// A vesting fill pays the mint's transfer fee once into its vesting vault and again
+// on every claim, and the fee withheld in that vault keeps it from ever closing
+pub fn has_transfer_fee(mint: &InterfaceAccount<Mint>) -> Result<bool> {
+    let info = mint.to_account_info();
+    let data = info.try_borrow_data()?;
+    let mint = StateWithExtensions::<MintState>::unpack(&data)?;
+    Ok(mint
+        .get_extension_types()?
+        .contains(&ExtensionType::TransferFeeConfig))
 }
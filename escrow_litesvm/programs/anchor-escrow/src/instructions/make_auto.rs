@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use super::make::*;
+use crate::{error::ErrorCode, state::SeedCounter};
+
+// Make with the seed taken from the maker's counter instead of chosen by the client
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeAuto<'info> {
+    pub make: Make<'info>,
+    // Created on the maker's first MakeAuto, starting at seed 0
+    #[account(
+        init_if_needed,
+        payer = make.payer,
+        seeds = [b"seed_counter", make.maker.key().as_ref()],
+        bump,
+        space = 8 + SeedCounter::INIT_SPACE,
+    )]
+    pub seed_counter: Account<'info, SeedCounter>,
+    // Anchor's init checks look for it at this level, not inside `make`
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeAuto<'info> {
+    /// The escrow address has to be known up front, so the client still passes the
+    /// seed, but only the counter's next one is accepted and the counter moves on.
+    pub fn use_seed(&mut self, seed: u64, bumps: &MakeAutoBumps) -> Result<()> {
+        let counter = &mut self.seed_counter;
+        require!(seed == counter.next_seed, ErrorCode::SeedNotNext);
+        if counter.maker == Pubkey::default() {
+            counter.maker = self.make.maker.key();
+            counter.bump = bumps.seed_counter;
+        }
+        counter.next_seed = seed.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,255 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{self, AssociatedToken},
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use super::make::has_confidential_transfers;
+use crate::{
+    error::ErrorCode,
+    events::{self, EscrowMade},
+    hook,
+    state::{Config, Escrow, EscrowStatus, MakerRegistry, MakerStats},
+};
+
+// Make landed by a session key or bot the maker approved as delegate of maker_ata_a,
+// moving the deposit under that approval without the maker signing. Only the basic
+// terms are offered, the rest stay at their Make defaults.
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeDelegated<'info> {
+    // Pays the escrow and vault rent, which goes back to it on close
+    #[account(mut)]
+    pub delegate: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = delegate,
+        seeds = [
+            b"escrow",
+            maker.key().as_ref(),
+            seed.to_le_bytes().as_ref(),
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+        ],
+        bump,
+        space = 8 + Escrow::INIT_SPACE,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        init,
+        payer = delegate,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = delegate,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump,
+        space = 8 + MakerRegistry::INIT_SPACE,
+    )]
+    pub maker_registry: Account<'info, MakerRegistry>,
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Account<'info, MakerStats>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeDelegated<'info> {
+    /// The approval must name the signer and cover the deposit, so a delegate can
+    /// never lock up more than the maker let it move.
+    pub fn check_delegation(&self, deposit: u64) -> Result<()> {
+        require!(
+            self.maker_ata_a.delegate == Some(self.delegate.key()).into()
+                && self.maker_ata_a.delegated_amount >= deposit,
+            ErrorCode::InvalidDelegate
+        );
+        Ok(())
+    }
+
+    // The bounds `Make::check_bounds` and `Make::check_expiry` enforce
+    pub fn check_bounds(&self, deposit: u64, waiting_time: i64, expiry: i64) -> Result<()> {
+        let config = &self.config;
+        require!(deposit >= config.min_deposit, ErrorCode::DepositTooSmall);
+        require!(waiting_time >= 0, ErrorCode::NegativeWaitingTime);
+        require!(
+            waiting_time <= config.max_waiting_time,
+            ErrorCode::WaitingTimeTooLong
+        );
+        for mint in [&self.mint_a, &self.mint_b] {
+            require!(
+                (config.min_decimals..=config.max_decimals).contains(&mint.decimals),
+                ErrorCode::DecimalsOutOfRange
+            );
+            require!(
+                !has_confidential_transfers(mint)?,
+                ErrorCode::ConfidentialTransferUnsupported
+            );
+        }
+
+        if expiry != 0 {
+            let now = Clock::get()?.unix_timestamp;
+            let unlock_at = now.checked_add(waiting_time).ok_or(ErrorCode::Overflow)?;
+            let horizon = now
+                .checked_add(config.max_expiry_horizon)
+                .ok_or(ErrorCode::Overflow)?;
+            require!(
+                expiry > now && expiry > unlock_at && expiry <= horizon,
+                ErrorCode::InvalidExpiry
+            );
+        }
+        Ok(())
+    }
+
+    pub fn init_escrow(
+        &mut self,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+        waiting_time: i64,
+        expiry: i64,
+        bumps: &MakeDelegatedBumps,
+    ) -> Result<()> {
+        let unlock_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(waiting_time)
+            .ok_or(ErrorCode::Overflow)?;
+        let vault_bump = Pubkey::find_program_address(
+            &[
+                self.escrow.key().as_ref(),
+                self.token_program.key().as_ref(),
+                self.mint_a.key().as_ref(),
+            ],
+            &associated_token::ID,
+        )
+        .1;
+
+        self.escrow.set_inner(Escrow {
+            seed,
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            status: EscrowStatus::Open,
+            receive,
+            unlock_at,
+            bump: bumps.escrow,
+            version: Escrow::CURRENT_VERSION,
+            expiry,
+            taker: None,
+            stats: self.maker_stats.as_ref().map(|stats| stats.key()),
+            rent_payer: self.delegate.key(),
+            remaining_deposit: deposit,
+            remaining_receive: receive,
+            taker_root: None,
+            enforce_royalties: false,
+            price_feed: None,
+            spread_bps: 0,
+            unit_price: None,
+            no_refund_before: 0,
+            payment_options: vec![],
+            receive_in_ui_amount: false,
+            label: None,
+            paused: false,
+            vault_bump,
+            mint_seeded: true,
+            registered: true,
+            reveal_window: 0,
+            hashlock: None,
+            arbiter: None,
+            review_period: None,
+            vesting_duration: 0,
+            standing: false,
+            total_filled: 0,
+            min_fill: 0,
+            cancel_fee: 0,
+            cancel_fee_until: 0,
+            whitelisted_takers: false,
+            fill_grace_period: 0,
+            made_by: None,
+            curve_bps: 0,
+        });
+
+        // As `Make::register`
+        let registry = &mut self.maker_registry;
+        let max_open = self.config.max_open_escrows_per_maker;
+        require!(
+            max_open == 0 || registry.open_escrows < max_open,
+            ErrorCode::TooManyOpenEscrows
+        );
+        if registry.maker == Pubkey::default() {
+            registry.maker = self.maker.key();
+            registry.bump = bumps.maker_registry;
+        }
+        registry.add(self.escrow.key(), seed)?;
+        if let Some(stats) = self.maker_stats.as_mut() {
+            stats.escrows_created = stats.escrows_created.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    /// Moves the deposit as the maker's delegate, then reconciles the escrow to what
+    /// reached the vault like `Make::deposit`.
+    pub fn deposit(
+        &mut self,
+        deposit: u64,
+        hook_accounts: &[AccountInfo<'info>],
+        bumps: &MakeDelegatedBumps,
+    ) -> Result<()> {
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.maker_ata_a.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.delegate.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        hook::transfer_checked(cpi_ctx, deposit, self.mint_a.decimals, hook_accounts)?;
+
+        self.vault.reload()?;
+        let received = self.vault.amount;
+        require!(received > 0, ErrorCode::DepositTooSmall);
+        self.escrow.remaining_deposit = received;
+
+        events::emit_cpi(
+            &EscrowMade {
+                escrow: self.escrow.key(),
+                maker: self.maker.key(),
+                mint_a: self.mint_a.key(),
+                mint_b: self.mint_b.key(),
+                deposit: received,
+                receive: self.escrow.receive,
+                unlock_at: self.escrow.unlock_at,
+            },
+            &self.event_authority,
+            bumps.event_authority,
+        )
+    }
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use super::make::*;
+use crate::{error::ErrorCode, metadata::Metadata};
+
+// Make with the mint_a side restricted to a single Metaplex NFT
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeNft<'info> {
+    pub make: Make<'info>,
+    /// CHECK: Metaplex metadata PDA of mint_a, parsed in `check_nft`
+    #[account(address = Metadata::address(&make.mint_a.key()) @ ErrorCode::InvalidMetadata)]
+    pub metadata: UncheckedAccount<'info>,
+}
+
+impl<'info> MakeNft<'info> {
+    pub fn check_nft(&self, collection: Option<Pubkey>) -> Result<()> {
+        let mint_a = &self.make.mint_a;
+        require!(
+            mint_a.decimals == 0 && mint_a.supply == 1,
+            ErrorCode::NotAnNft
+        );
+
+        let metadata = Metadata::load(&self.metadata, &mint_a.key())?;
+        if let Some(collection) = collection {
+            metadata.require_collection(&collection)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_royalty_enforcement(&mut self, enforce_royalties: bool) {
+        self.make.escrow.enforce_royalties = enforce_royalties;
+    }
+}
@@ -0,0 +1,181 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use math::Rounding;
+
+use crate::{
+    error::ErrorCode,
+    hook,
+    state::{Escrow, EscrowStatus, MakerRegistry},
+};
+
+// Folds `other` into `escrow`, two of the maker's escrows on the same terms, moving
+// its deposit over and closing it and its vault to their rent payer.
+#[derive(Accounts)]
+pub struct Merge<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        constraint = other.key() != escrow.key() @ ErrorCode::InvalidEscrowAccount,
+        seeds = [
+            b"escrow",
+            other.maker_seed(),
+            other.seed.to_le_bytes().as_ref(),
+            other.mint_a_seed(),
+            other.mint_b_seed(),
+        ],
+        bump = other.bump,
+    )]
+    pub other: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = other.vault_address(&other.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub other_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut, address = other.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+    // Required when `other` is counted in the maker's registry
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Account<'info, MakerRegistry>>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Merge<'info> {
+    /// Payment options are priced against `receive` and two-sided escrows may hold a
+    /// taker's payment, so neither can be pooled. A cancellation fee still at stake
+    /// on `other` would go back with its rent, so it has to run out first.
+    pub fn check_escrows(&self) -> Result<()> {
+        for escrow in [&self.escrow, &self.other] {
+            escrow.require_active()?;
+            escrow.require_not_paused()?;
+            require!(
+                escrow.payment_options.is_empty() && !escrow.is_two_sided(),
+                ErrorCode::MergeUnsupported
+            );
+        }
+        require!(
+            self.escrow.same_terms(&self.other),
+            ErrorCode::TermsMismatch
+        );
+        let other = &self.other;
+        require!(
+            other.cancel_fee == 0 || Clock::get()?.unix_timestamp >= other.cancel_fee_until,
+            ErrorCode::MergeUnsupported
+        );
+        Ok(())
+    }
+
+    /// Moves `other`'s deposit into the vault and adds what reached it, and what
+    /// `other` was still owed, to the escrow. The later unlock and refund lock of
+    /// the two apply to the whole.
+    pub fn merge(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let amount = self.other_vault.amount;
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.other.maker_seed(),
+            &self.other.seed.to_le_bytes()[..],
+            self.other.mint_a_seed(),
+            self.other.mint_b_seed(),
+            &[self.other.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.other_vault.to_account_info(),
+            to: self.vault.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            authority: self.other.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        let before = self.vault.amount;
+        hook::transfer_checked(cpi_context, amount, self.mint_a.decimals, hook_accounts)?;
+        self.vault.reload()?;
+        let received = self.vault.amount - before;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.other_vault.to_account_info(),
+            destination: self.rent_payer.to_account_info(),
+            authority: self.other.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        let other = &self.other;
+        let escrow = &mut self.escrow;
+        escrow.remaining_deposit = escrow
+            .remaining_deposit
+            .checked_add(received)
+            .ok_or(ErrorCode::Overflow)?;
+        let remaining_receive = match escrow.unit_price {
+            Some(unit_price) => unit_price
+                .cost(escrow.remaining_deposit, Rounding::Up)
+                .ok_or(ErrorCode::Overflow)?,
+            None => escrow
+                .remaining_receive
+                .checked_add(other.remaining_receive)
+                .ok_or(ErrorCode::Overflow)?,
+        };
+        // `receive` keeps counting what was already paid on top of what is still owed
+        escrow.receive = escrow
+            .receive
+            .checked_add(remaining_receive.saturating_sub(escrow.remaining_receive))
+            .ok_or(ErrorCode::Overflow)?;
+        escrow.remaining_receive = remaining_receive;
+        escrow.unlock_at = escrow.unlock_at.max(other.unlock_at);
+        escrow.no_refund_before = escrow.no_refund_before.max(other.no_refund_before);
+        Ok(())
+    }
+
+    // Drops `other` from the maker's registry as it closes
+    pub fn deregister(&mut self) -> Result<()> {
+        self.other.status = EscrowStatus::Closed;
+        if !self.other.registered {
+            return Ok(());
+        }
+        let registry = self
+            .maker_registry
+            .as_mut()
+            .ok_or(ErrorCode::MakerRegistryRequired)?;
+        registry.remove(&self.other.key());
+        Ok(())
+    }
+}
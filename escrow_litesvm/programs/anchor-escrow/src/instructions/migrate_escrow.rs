@@ -0,0 +1,169 @@
+use anchor_lang::{prelude::*, system_program, Discriminator};
+use anchor_spl::{associated_token, token_interface::TokenAccount};
+
+use crate::{
+    error::ErrorCode,
+    state::{Escrow, EscrowStatus},
+};
+
+// Escrow layout of the originally deployed program
+#[derive(AnchorDeserialize)]
+struct EscrowBaseline {
+    seed: u64,
+    maker: Pubkey,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+    receive: u64,
+    bump: u8,
+    // Absolute unlock time despite the name
+    waiting_time: i64,
+}
+
+// Reallocs an escrow to the current layout. A baseline escrow is converted field by
+// field, a later one only has its tail zero-extended, see `Escrow::TAIL_VERSION`.
+#[derive(Accounts)]
+pub struct MigrateEscrow<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    /// CHECK: holds an older layout that Account<Escrow> cannot deserialize, owner,
+    /// discriminator and maker are checked in the handler
+    #[account(mut)]
+    pub escrow: UncheckedAccount<'info>,
+    // Seeds the remaining deposit of a baseline escrow, checked against the escrow in
+    // the handler
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateEscrow<'info> {
+    pub fn migrate(&mut self) -> Result<()> {
+        let account_info = self.escrow.to_account_info();
+        require_keys_eq!(
+            *account_info.owner,
+            crate::ID,
+            ErrorCode::InvalidEscrowAccount
+        );
+
+        let escrow = {
+            let data = account_info.try_borrow_data()?;
+            require!(
+                data.len() >= Escrow::BASELINE_LEN && data[..8] == *Escrow::DISCRIMINATOR,
+                ErrorCode::InvalidEscrowAccount
+            );
+            if data.len() == Escrow::BASELINE_LEN {
+                self.convert_baseline(EscrowBaseline::deserialize(&mut &data[8..])?)?
+            } else {
+                require!(
+                    data.len() > Escrow::VERSION_OFFSET,
+                    ErrorCode::InvalidEscrowAccount
+                );
+                let version = data[Escrow::VERSION_OFFSET];
+                require!(version < Escrow::CURRENT_VERSION, ErrorCode::EscrowUpToDate);
+                require!(
+                    version >= Escrow::TAIL_VERSION,
+                    ErrorCode::InvalidEscrowAccount
+                );
+                // Fields appended since `version` read as zero, which keeps them off
+                let mut padded = data.to_vec();
+                padded.resize(8 + Escrow::INIT_SPACE, 0);
+                let mut escrow = Escrow::try_deserialize(&mut padded.as_slice())?;
+                require_keys_eq!(
+                    self.vault.key(),
+                    escrow.vault_address(account_info.key, self.vault.to_account_info().owner),
+                    ErrorCode::InvalidEscrowAccount
+                );
+                escrow.version = Escrow::CURRENT_VERSION;
+                escrow
+            }
+        };
+        require_keys_eq!(
+            escrow.maker,
+            self.maker.key(),
+            ErrorCode::InvalidEscrowAccount
+        );
+
+        // Maker pays the rent for the appended fields
+        let new_account_size = 8 + Escrow::INIT_SPACE;
+        let lamports_required = (Rent::get()?).minimum_balance(new_account_size);
+        let rent_diff = lamports_required.saturating_sub(account_info.lamports());
+        if rent_diff > 0 {
+            let cpi_program = self.system_program.to_account_info();
+            let cpi_accounts = system_program::Transfer {
+                from: self.maker.to_account_info(),
+                to: account_info.clone(),
+            };
+            let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+            system_program::transfer(cpi_context, rent_diff)?;
+        }
+
+        account_info.resize(new_account_size)?;
+
+        escrow.try_serialize(&mut &mut account_info.try_borrow_mut_data()?[..])?;
+
+        msg!("Escrow migrated to version {}", Escrow::CURRENT_VERSION);
+        Ok(())
+    }
+
+    // Fields a baseline escrow lacks get the value that keeps it behaving as it did:
+    // filled whole at its fixed price, by anyone once unlocked, and refundable at any
+    // time
+    fn convert_baseline(&self, baseline: EscrowBaseline) -> Result<Escrow> {
+        let escrow = self.escrow.key();
+        let (vault, vault_bump) = Pubkey::find_program_address(
+            &[
+                escrow.as_ref(),
+                self.vault.to_account_info().owner.as_ref(),
+                baseline.mint_a.as_ref(),
+            ],
+            &associated_token::ID,
+        );
+        require_keys_eq!(self.vault.key(), vault, ErrorCode::InvalidEscrowAccount);
+
+        Ok(Escrow {
+            maker: baseline.maker,
+            mint_a: baseline.mint_a,
+            mint_b: baseline.mint_b,
+            status: EscrowStatus::Open,
+            seed: baseline.seed,
+            receive: baseline.receive,
+            unlock_at: baseline.waiting_time,
+            bump: baseline.bump,
+            version: Escrow::CURRENT_VERSION,
+            expiry: 0,
+            taker: None,
+            stats: None,
+            // Baseline escrows were always funded by the maker
+            rent_payer: baseline.maker,
+            remaining_deposit: self.vault.amount,
+            remaining_receive: baseline.receive,
+            taker_root: None,
+            enforce_royalties: false,
+            price_feed: None,
+            spread_bps: 0,
+            unit_price: None,
+            no_refund_before: 0,
+            payment_options: vec![],
+            receive_in_ui_amount: false,
+            label: None,
+            paused: false,
+            vault_bump,
+            // Baseline escrows were derived without the mints
+            mint_seeded: false,
+            registered: false,
+            reveal_window: 0,
+            hashlock: None,
+            arbiter: None,
+            review_period: None,
+            vesting_duration: 0,
+            standing: false,
+            total_filled: 0,
+            min_fill: 0,
+            cancel_fee: 0,
+            cancel_fee_until: 0,
+            whitelisted_takers: false,
+            fill_grace_period: 0,
+            made_by: None,
+            curve_bps: 0,
+        })
+    }
+}
@@ -1,7 +1,89 @@
+pub mod accept_counter_offer;
+pub mod cancel_deposit;
+pub mod claim_proceeds;
+pub mod claim_vested;
+pub mod close_expired;
+pub mod commit_take;
+pub mod cross;
+pub mod deposit_payment;
+pub mod init_maker_stats;
+pub mod initialize_config;
 pub mod make;
+pub mod make_auto;
+pub mod make_delegated;
+pub mod make_nft;
+pub mod merge;
+pub mod migrate_escrow;
+pub mod outbid_refund;
+pub mod place_bid;
+pub mod propose_counter_offer;
+pub mod quote;
+pub mod raise_dispute;
 pub mod refund;
+pub mod resolve;
+pub mod reveal_take;
+pub mod set_admin;
+pub mod set_escrow_paused;
+pub mod set_limits;
+pub mod set_paused;
+pub mod set_reveal_window;
+pub mod set_waiting_time;
+pub mod settle;
+pub mod settle_deposit;
+pub mod split;
+pub mod start_auction;
 pub mod take;
+pub mod take_many;
+pub mod take_vested;
+pub mod take_with_signature;
+pub mod top_up;
+pub mod transfer_maker_authority;
+pub mod update_escrow;
+pub mod update_fee;
+pub mod withdraw_counter_offer;
+pub mod withdraw_partial;
 
+pub use accept_counter_offer::*;
+pub use cancel_deposit::*;
+pub use claim_proceeds::*;
+pub use claim_vested::*;
+pub use close_expired::*;
+pub use commit_take::*;
+pub use cross::*;
+pub use deposit_payment::*;
+pub use init_maker_stats::*;
+pub use initialize_config::*;
 pub use make::*;
+pub use make_auto::*;
+pub use make_delegated::*;
+pub use make_nft::*;
+pub use merge::*;
+pub use migrate_escrow::*;
+pub use outbid_refund::*;
+pub use place_bid::*;
+pub use propose_counter_offer::*;
+pub use quote::*;
+pub use raise_dispute::*;
 pub use refund::*;
+pub use resolve::*;
+pub use reveal_take::*;
+pub use set_admin::*;
+pub use set_escrow_paused::*;
+pub use set_limits::*;
+pub use set_paused::*;
+pub use set_reveal_window::*;
+pub use set_waiting_time::*;
+pub use settle::*;
+pub use settle_deposit::*;
+pub use split::*;
+pub use start_auction::*;
 pub use take::*;
+pub use take_many::*;
+pub use take_vested::*;
+pub use take_with_signature::*;
+pub use top_up::*;
+pub use transfer_maker_authority::*;
+pub use update_escrow::*;
+pub use update_fee::*;
+pub use withdraw_counter_offer::*;
+pub use withdraw_partial::*;
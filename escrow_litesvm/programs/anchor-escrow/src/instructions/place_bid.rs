@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    error::ErrorCode,
+    state::{Auction, Bid, Escrow},
+};
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    // Created up front so Settle can deliver the deposit without the bidder's signature
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        associated_token::mint = mint_a,
+        associated_token::authority = bidder,
+        associated_token::token_program = token_program,
+    )]
+    pub bidder_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = bidder,
+        associated_token::token_program = token_program,
+    )]
+    pub bidder_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        has_one = mint_b @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        has_one = escrow,
+        seeds = [b"auction", escrow.key().as_ref()],
+        bump = auction.bump,
+    )]
+    pub auction: Account<'info, Auction>,
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        seeds = [b"bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump,
+        space = 8 + Bid::INIT_SPACE,
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        associated_token::mint = mint_b,
+        associated_token::authority = bid,
+        associated_token::token_program = token_program,
+    )]
+    pub bid_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PlaceBid<'info> {
+    /// Adds `amount` to the bidder's locked total, which must become the highest bid.
+    pub fn place_bid(&mut self, amount: u64, bumps: &PlaceBidBumps) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(current_time < self.auction.end_at, ErrorCode::AuctionEnded);
+
+        let total = self
+            .bid
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(
+            amount > 0 && total >= self.auction.reserve && total > self.auction.highest_bid,
+            ErrorCode::BidTooLow
+        );
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.bidder_ata_b.to_account_info(),
+            to: self.bid_vault.to_account_info(),
+            authority: self.bidder.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer_checked(cpi_ctx, amount, self.mint_b.decimals)?;
+
+        self.bid.set_inner(Bid {
+            auction: self.auction.key(),
+            bidder: self.bidder.key(),
+            amount: total,
+            bump: bumps.bid,
+        });
+        self.auction.highest_bid = total;
+        self.auction.highest_bidder = Some(self.bidder.key());
+        Ok(())
+    }
+}
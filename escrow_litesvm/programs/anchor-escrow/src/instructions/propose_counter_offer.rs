@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    error::ErrorCode,
+    state::{CounterOffer, Escrow},
+};
+
+#[derive(Accounts)]
+pub struct ProposeCounterOffer<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        has_one = mint_b @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        init,
+        payer = taker,
+        seeds = [b"counter_offer", escrow.key().as_ref(), taker.key().as_ref()],
+        bump,
+        space = 8 + CounterOffer::INIT_SPACE,
+    )]
+    pub counter_offer: Account<'info, CounterOffer>,
+    #[account(
+        init,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = counter_offer,
+        associated_token::token_program = token_program,
+    )]
+    pub counter_offer_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ProposeCounterOffer<'info> {
+    // Taker restrictions do not apply, the maker still has to accept the terms
+    pub fn check_terms(&self, offer: u64, request: u64) -> Result<()> {
+        let escrow = &self.escrow;
+        escrow.require_active()?;
+        require!(
+            !escrow.is_expired(Clock::get()?.unix_timestamp),
+            ErrorCode::EscrowExpired
+        );
+        require!(!escrow.enforce_royalties, ErrorCode::RoyaltiesEnforced);
+        require!(
+            offer > 0 && request > 0 && request <= escrow.remaining_deposit,
+            ErrorCode::InvalidCounterOffer
+        );
+        Ok(())
+    }
+
+    pub fn propose(
+        &mut self,
+        offer: u64,
+        request: u64,
+        bumps: &ProposeCounterOfferBumps,
+    ) -> Result<()> {
+        self.counter_offer.set_inner(CounterOffer {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            offer,
+            request,
+            bump: bumps.counter_offer,
+        });
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            to: self.counter_offer_vault.to_account_info(),
+            authority: self.taker.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer_checked(cpi_ctx, offer, self.mint_b.decimals)
+    }
+}
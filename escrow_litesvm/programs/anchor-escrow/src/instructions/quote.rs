@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use math::Rounding;
+
+use crate::{
+    error::ErrorCode,
+    interest,
+    state::{Config, Escrow},
+};
+
+#[cfg(feature = "oracle")]
+use crate::oracle;
+
+/// What a Take paying `take_amount` would get right now, returned by Quote.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TakeQuote {
+    // mint_a received, before any transfer fee on mint_a
+    pub fill: u64,
+    // mint_b paid, protocol fee included
+    pub payment: u64,
+    // Part of `payment` that goes to the treasury, or partly to a referrer
+    pub fee: u64,
+    // What is left of the escrow after the fill
+    pub remaining_deposit: u64,
+    pub remaining_receive: u64,
+}
+
+// Read-only, for clients to simulate ahead of Take. The quote is the instruction's
+// return data.
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        constraint = escrow.accepts(&mint_b.key()) @ ErrorCode::InvalidMint,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    /// CHECK: only needed for oracle-priced escrows, checked against the escrow's
+    /// price feed and parsed in `reprice`
+    pub price_update: Option<UncheckedAccount<'info>>,
+}
+
+impl<'info> Quote<'info> {
+    // The timing checks Take would fail on, so a quote is never given for a Take
+    // that cannot happen
+    pub fn check_escrow(&self) -> Result<()> {
+        let escrow = &self.escrow;
+        escrow.require_active()?;
+        escrow.require_not_paused()?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= escrow.unlock_at, ErrorCode::WaitingTimeNotElapsed);
+        require!(!escrow.is_expired(now), ErrorCode::EscrowExpired);
+        Ok(())
+    }
+
+    /// Prices an oracle-priced escrow as Take would, in memory only.
+    #[cfg(feature = "oracle")]
+    pub fn reprice(&mut self) -> Result<()> {
+        let Some(price_feed) = self.escrow.price_feed else {
+            return Ok(());
+        };
+        let price_update = self
+            .price_update
+            .as_ref()
+            .ok_or(ErrorCode::InvalidPriceFeed)?;
+        require_keys_eq!(price_update.key(), price_feed, ErrorCode::InvalidPriceFeed);
+
+        let update = oracle::PriceUpdateV2::load(price_update)?;
+        let price = update.checked_price(Clock::get()?.unix_timestamp)?;
+        let escrow = &mut self.escrow;
+        escrow.remaining_receive = oracle::quote(
+            escrow.remaining_deposit,
+            price,
+            self.mint_a.decimals,
+            self.mint_b.decimals,
+            escrow.spread_bps,
+        )
+        .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "oracle"))]
+    pub fn reprice(&mut self) -> Result<()> {
+        require!(self.escrow.price_feed.is_none(), ErrorCode::OracleDisabled);
+        Ok(())
+    }
+
+    /// Runs Take's pricing for `take_amount` of the chosen mint_b without moving funds.
+    pub fn quote(&self, take_amount: u64) -> Result<TakeQuote> {
+        let escrow = &self.escrow;
+        let owed = if escrow.receive_in_ui_amount {
+            self.owed_in_ui_amount(take_amount)?
+        } else {
+            escrow
+                .owed_for(&self.mint_b.key(), take_amount)
+                .ok_or(ErrorCode::Overflow)?
+        };
+        let fill = escrow.fill_for(owed)?;
+        let payment = escrow.payment_for(take_amount, fill)?;
+        let fee = self.config.fee_for(payment).ok_or(ErrorCode::Overflow)?;
+
+        let remaining_deposit = escrow.remaining_deposit - fill;
+        let remaining_receive = match escrow.unit_price {
+            Some(unit_price) => unit_price
+                .cost(remaining_deposit, Rounding::Up)
+                .ok_or(ErrorCode::Overflow)?,
+            None => escrow.remaining_receive.saturating_sub(owed),
+        };
+
+        Ok(TakeQuote {
+            fill,
+            payment,
+            fee,
+            remaining_deposit,
+            remaining_receive,
+        })
+    }
+
+    // See `Take::owed_in_ui_amount`
+    fn owed_in_ui_amount(&self, take_amount: u64) -> Result<u64> {
+        let config =
+            interest::config(&self.mint_b.to_account_info())?.ok_or(ErrorCode::InvalidMint)?;
+        let owed = interest::ui_units(
+            &config,
+            take_amount,
+            self.mint_b.decimals,
+            Clock::get()?.unix_timestamp,
+        )?;
+        Ok(owed.min(self.escrow.remaining_receive))
+    }
+}
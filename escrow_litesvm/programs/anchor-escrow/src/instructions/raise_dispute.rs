@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    events::{self, DisputeRaised},
+    state::{Escrow, EscrowStatus},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct RaiseDispute<'info> {
+    // The maker or the escrow's designated taker
+    pub disputant: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> RaiseDispute<'info> {
+    /// Hands the escrow to its arbiter. Take, Refund and the maker's updates all
+    /// fail until Resolve splits the vault.
+    pub fn raise(&mut self, bumps: &RaiseDisputeBumps) -> Result<()> {
+        let escrow = &mut self.escrow;
+        require!(escrow.arbiter.is_some(), ErrorCode::NoArbiter);
+        let disputant = self.disputant.key();
+        require!(
+            disputant == escrow.maker || Some(disputant) == escrow.taker,
+            ErrorCode::NotDisputant
+        );
+        escrow.status = EscrowStatus::Disputed;
+
+        events::emit_cpi(
+            &DisputeRaised {
+                escrow: escrow.key(),
+                raised_by: disputant,
+            },
+            &self.event_authority,
+            bumps.event_authority,
+        )
+    }
+}
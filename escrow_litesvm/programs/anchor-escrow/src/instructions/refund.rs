@@ -2,52 +2,105 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{
-        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
-        TransferChecked,
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
     },
 };
 
-use crate::state::Escrow;
+use crate::{
+    balance,
+    error::ErrorCode,
+    events::{self, EscrowRefunded},
+    hook, memo, native,
+    state::{Config, Escrow, EscrowStatus, MakerRegistry, MakerStats},
+};
 
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Refund<'info> {
     #[account(mut)]
     maker: Signer<'info>,
+    #[account(mut, address = escrow.rent_payer)]
+    rent_payer: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
     mint_a: InterfaceAccount<'info, Mint>,
+    // Any mint_a token account owned by the maker, not only the canonical ATA
     #[account(
         mut,
-        associated_token::mint = mint_a,
-        associated_token::authority = maker,
-        associated_token::token_program = token_program,  
+        token::mint = mint_a,
+        token::authority = maker,
+        token::token_program = token_program,
     )]
     maker_ata_a: InterfaceAccount<'info, TokenAccount>,
     #[account(
         mut,
-        close = maker,
-        has_one = mint_a,
+        close = rent_payer,
+        has_one = mint_a @ ErrorCode::InvalidMint,
         has_one = maker,
-        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
         bump = escrow.bump,
     )]
     pub escrow: Account<'info, Escrow>,
+    // Checked from the stored bump, as in Take
     #[account(
         mut,
-        associated_token::mint = mint_a,
-        associated_token::authority = escrow,
-        associated_token::token_program = token_program,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
     )]
     vault: InterfaceAccount<'info, TokenAccount>,
+    // Required for escrows counted in the maker's registry, see `Escrow::registered`
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    maker_registry: Option<Account<'info, MakerRegistry>>,
+    // Required for escrows that report to the maker's stats, see `Escrow::stats`
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    maker_stats: Option<Account<'info, MakerStats>>,
+    // Required for refunds that forfeit the cancellation fee, see `Escrow::cancel_fee`
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    config: Option<Account<'info, Config>>,
+    /// CHECK: receives the forfeited cancellation fee, pinned to the config in
+    /// `settle_cancel_fee`
+    #[account(mut)]
+    treasury: Option<UncheckedAccount<'info>>,
+    /// CHECK: only needed with a memo, pinned to the SPL Memo program
+    #[account(address = memo::MEMO_PROGRAM_ID @ ErrorCode::InvalidMemoProgram)]
+    memo_program: Option<UncheckedAccount<'info>>,
     associated_token_program: Program<'info, AssociatedToken>,
     token_program: Interface<'info, TokenInterface>,
     system_program: Program<'info, System>,
 }
 
 impl<'info> Refund<'info> {
-    pub fn refund_and_close_vault(&mut self) -> Result<()> {
+    pub fn refund_and_close_vault(
+        &mut self,
+        hook_accounts: &[AccountInfo<'info>],
+        bumps: &RefundBumps,
+    ) -> Result<()> {
+        self.escrow.status = EscrowStatus::Closed;
+        self.deregister()?;
+        self.report()?;
+        self.settle_cancel_fee()?;
+
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
-            self.maker.key.as_ref(),
+            self.escrow.maker_seed(),
             &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
             &[self.escrow.bump],
         ]];
 
@@ -62,13 +115,26 @@ impl<'info> Refund<'info> {
 
         let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
 
-        transfer_checked(cpi_context, self.vault.amount, self.mint_a.decimals)?;
+        let before = balance::of(&self.maker_ata_a.to_account_info())?;
+        hook::transfer_checked(
+            cpi_context,
+            self.vault.amount,
+            self.mint_a.decimals,
+            hook_accounts,
+        )?;
+        balance::check_credited(
+            &self.vault.to_account_info(),
+            &self.maker_ata_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            before,
+            self.vault.amount,
+        )?;
 
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = CloseAccount {
             account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
+            destination: self.rent_payer.to_account_info(),
             authority: self.escrow.to_account_info(),
         };
 
@@ -76,6 +142,79 @@ impl<'info> Refund<'info> {
 
         close_account(cpi_context)?;
 
+        if native::is_native_mint(&self.mint_a.key()) {
+            native::unwrap(
+                self.maker_ata_a.to_account_info(),
+                self.maker.to_account_info(),
+                self.token_program.to_account_info(),
+            )?;
+        }
+
+        events::emit_cpi(
+            &EscrowRefunded {
+                escrow: self.escrow.key(),
+                maker: self.maker.key(),
+                amount: self.vault.amount,
+            },
+            &self.event_authority,
+            bumps.event_authority,
+        )
+    }
+
+    // Counts the refund in the maker's stats, when the escrow reports to them
+    fn report(&mut self) -> Result<()> {
+        if self.escrow.stats.is_none() {
+            return Ok(());
+        }
+        let stats = self
+            .maker_stats
+            .as_mut()
+            .ok_or(ErrorCode::MakerStatsRequired)?;
+        stats.escrows_refunded = stats.escrows_refunded.saturating_add(1);
+        Ok(())
+    }
+
+    // Sends the maker's cancellation fee to the treasury when refunding inside the
+    // window. Otherwise it goes back to the maker, who may not be the rent payer.
+    fn settle_cancel_fee(&mut self) -> Result<()> {
+        let cancel_fee = self.escrow.cancel_fee;
+        if cancel_fee == 0 {
+            return Ok(());
+        }
+        let now = Clock::get()?.unix_timestamp;
+        let recipient = if self.escrow.cancel_fee_at_stake(now) {
+            let (Some(config), Some(treasury)) = (&self.config, &self.treasury) else {
+                return err!(ErrorCode::CancelFeeAccountsRequired);
+            };
+            require_keys_eq!(treasury.key(), config.treasury, ErrorCode::InvalidTreasury);
+            treasury.to_account_info()
+        } else {
+            self.maker.to_account_info()
+        };
+
+        self.escrow.sub_lamports(cancel_fee)?;
+        recipient.add_lamports(cancel_fee)?;
+        Ok(())
+    }
+
+    // Drops the escrow from the maker's registry as it closes
+    fn deregister(&mut self) -> Result<()> {
+        if !self.escrow.registered {
+            return Ok(());
+        }
+        let registry = self
+            .maker_registry
+            .as_mut()
+            .ok_or(ErrorCode::MakerRegistryRequired)?;
+        registry.remove(&self.escrow.key());
         Ok(())
     }
+
+    pub fn log_memo(&self, memo: Option<String>) -> Result<()> {
+        memo::log(
+            memo,
+            self.memo_program.as_ref(),
+            self.maker.to_account_info(),
+        )
+    }
 }
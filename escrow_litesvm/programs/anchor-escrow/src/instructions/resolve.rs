@@ -0,0 +1,192 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    error::ErrorCode,
+    events::{self, DisputeResolved},
+    hook,
+    state::{Escrow, EscrowStatus, MakerRegistry},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct Resolve<'info> {
+    // Pays for whichever of the parties' token accounts do not exist yet
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    pub taker: SystemAccount<'info>,
+    #[account(mut, address = escrow.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        constraint = escrow.arbiter == Some(arbiter.key()) @ ErrorCode::NotArbiter,
+        constraint = escrow.taker == Some(taker.key()) @ ErrorCode::UnauthorizedTaker,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    // Required for escrows counted in the maker's registry, see `Escrow::registered`
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Account<'info, MakerRegistry>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Resolve<'info> {
+    /// Carries out the arbiter's ruling: `taker_amount` of the vault goes to the
+    /// designated taker, the rest back to the maker, and the escrow closes.
+    pub fn resolve(
+        &mut self,
+        taker_amount: u64,
+        hook_accounts: &[AccountInfo<'info>],
+        bumps: &ResolveBumps,
+    ) -> Result<()> {
+        require!(
+            self.escrow.status == EscrowStatus::Disputed,
+            ErrorCode::EscrowNotDisputed
+        );
+        let maker_amount = self
+            .vault
+            .amount
+            .checked_sub(taker_amount)
+            .ok_or(ErrorCode::InvalidRuling)?;
+
+        self.escrow.status = EscrowStatus::Closed;
+        self.deregister()?;
+
+        if taker_amount > 0 {
+            self.transfer_from_vault(
+                self.taker_ata_a.to_account_info(),
+                taker_amount,
+                hook_accounts,
+            )?;
+        }
+        if maker_amount > 0 {
+            self.transfer_from_vault(
+                self.maker_ata_a.to_account_info(),
+                maker_amount,
+                hook_accounts,
+            )?;
+        }
+        self.close_vault()?;
+
+        events::emit_cpi(
+            &DisputeResolved {
+                escrow: self.escrow.key(),
+                arbiter: self.arbiter.key(),
+                maker_amount,
+                taker_amount,
+            },
+            &self.event_authority,
+            bumps.event_authority,
+        )
+    }
+
+    fn transfer_from_vault(
+        &self,
+        to: AccountInfo<'info>,
+        amount: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.escrow.maker_seed(),
+            &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to,
+            authority: self.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        hook::transfer_checked(cpi_context, amount, self.mint_a.decimals, hook_accounts)
+    }
+
+    fn close_vault(&self) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.escrow.maker_seed(),
+            &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.rent_payer.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)
+    }
+
+    // Drops the escrow from the maker's registry as it closes
+    fn deregister(&mut self) -> Result<()> {
+        if !self.escrow.registered {
+            return Ok(());
+        }
+        let registry = self
+            .maker_registry
+            .as_mut()
+            .ok_or(ErrorCode::MakerRegistryRequired)?;
+        registry.remove(&self.escrow.key());
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use super::take::*;
+use crate::{error::ErrorCode, state::TakeCommitment};
+
+// Take against a commitment made in an earlier slot, see `Escrow::reveal_window`
+#[derive(Accounts)]
+pub struct RevealTake<'info> {
+    pub take: Take<'info>,
+    // Closed back to the taker once the take goes through
+    #[account(
+        mut,
+        seeds = [
+            b"take_commitment",
+            take.escrow.key().as_ref(),
+            take.taker.key().as_ref(),
+        ],
+        bump = commitment.bump,
+    )]
+    pub commitment: Account<'info, TakeCommitment>,
+}
+
+impl<'info> RevealTake<'info> {
+    pub fn check_reveal(&self, salt: &[u8; 32]) -> Result<()> {
+        let commitment = &self.commitment;
+        require!(
+            TakeCommitment::hash(self.take.taker.key, salt) == commitment.hash,
+            ErrorCode::CommitmentMismatch
+        );
+        // A later slot, so the commitment was public before the taker's terms were
+        let slot = Clock::get()?.slot;
+        require!(
+            slot > commitment.slot && slot - commitment.slot <= self.take.escrow.reveal_window,
+            ErrorCode::RevealOutsideWindow
+        );
+        Ok(())
+    }
+
+    pub fn close_commitment(&mut self) -> Result<()> {
+        self.commitment.close(self.take.taker.to_account_info())
+    }
+}
@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Config;
+
+// The new admin signs too, so the config cannot be handed to a mistyped key
+#[derive(Accounts)]
+pub struct SetAdmin<'info> {
+    pub admin: Signer<'info>,
+    pub new_admin: Signer<'info>,
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
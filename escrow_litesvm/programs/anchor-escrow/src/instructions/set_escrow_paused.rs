@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    events::{self, EscrowPauseSet},
+    state::Escrow,
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetEscrowPaused<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> SetEscrowPaused<'info> {
+    /// Stops Take while the maker reconsiders the terms, keeping the vault and its
+    /// rent in place. The maker can still update, top up or refund a paused escrow.
+    pub fn set_paused(&mut self, paused: bool, bumps: &SetEscrowPausedBumps) -> Result<()> {
+        let escrow = &mut self.escrow;
+        escrow.paused = paused;
+
+        events::emit_cpi(
+            &EscrowPauseSet {
+                escrow: escrow.key(),
+                paused,
+            },
+            &self.event_authority,
+            bumps.event_authority,
+        )
+    }
+}
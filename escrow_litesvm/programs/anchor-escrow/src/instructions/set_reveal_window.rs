@@ -0,0 +1,30 @@
+use anchor_lang::prelude::*;
+
+use crate::state::Escrow;
+
+#[derive(Accounts)]
+pub struct SetRevealWindow<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> SetRevealWindow<'info> {
+    /// With a window set, the escrow only fills through CommitTake then RevealTake,
+    /// so a pending take cannot be copied by whoever sees it first. 0 turns it off.
+    pub fn set_reveal_window(&mut self, reveal_window: u64) -> Result<()> {
+        self.escrow.reveal_window = reveal_window;
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    events::{self, WaitingTimeSet},
+    state::{Config, Escrow},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+pub struct SetWaitingTime<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> SetWaitingTime<'info> {
+    /// Moves the unlock time to `waiting_time` from now, within the same bounds as Make
+    /// and as `Escrow::set_unlock_at` allows.
+    pub fn set_waiting_time(
+        &mut self,
+        waiting_time: i64,
+        bumps: &SetWaitingTimeBumps,
+    ) -> Result<()> {
+        require!(waiting_time >= 0, ErrorCode::NegativeWaitingTime);
+        require!(
+            waiting_time <= self.config.max_waiting_time,
+            ErrorCode::WaitingTimeTooLong
+        );
+
+        let unlock_at = Clock::get()?
+            .unix_timestamp
+            .checked_add(waiting_time)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let escrow = &mut self.escrow;
+        require!(
+            escrow.expiry == 0 || escrow.expiry > unlock_at,
+            ErrorCode::InvalidExpiry
+        );
+        escrow.set_unlock_at(unlock_at)?;
+
+        events::emit_cpi(
+            &WaitingTimeSet {
+                escrow: escrow.key(),
+                unlock_at,
+            },
+            &self.event_authority,
+            bumps.event_authority,
+        )
+    }
+}
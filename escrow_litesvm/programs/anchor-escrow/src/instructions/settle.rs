@@ -0,0 +1,269 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::ErrorCode,
+    hook,
+    state::{Auction, Bid, Config, Escrow, EscrowStatus, MakerRegistry, MakerStats},
+};
+
+#[derive(Accounts)]
+pub struct Settle<'info> {
+    // Anyone can settle once the auction has ended
+    #[account(mut)]
+    pub settler: Signer<'info>,
+    // Receives the auction account's rent when nobody bid
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(mut, address = escrow.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: only the owner of treasury_ata_b, pinned to the config
+    #[account(address = config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        has_one = mint_b @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    // Required for escrows counted in the maker's registry, see `Escrow::registered`
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Account<'info, MakerRegistry>>,
+    // Required for escrows that report to the maker's stats, see `Escrow::stats`
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Account<'info, MakerStats>>,
+    #[account(
+        mut,
+        has_one = escrow,
+        seeds = [b"auction", escrow.key().as_ref()],
+        bump = auction.bump,
+    )]
+    pub auction: Account<'info, Auction>,
+    // The accounts below are only needed when there is a winning bid
+    #[account(mut)]
+    pub winner: Option<SystemAccount<'info>>,
+    #[account(mut)]
+    pub winner_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub bid: Option<Account<'info, Bid>>,
+    #[account(mut)]
+    pub bid_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Settle<'info> {
+    pub fn check_ended(&self) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= self.auction.end_at,
+            ErrorCode::AuctionNotEnded
+        );
+        Ok(())
+    }
+
+    /// Swaps the winning bid for the deposit and closes the escrow. Without bids the
+    /// escrow is unlocked again and the auction closed to the maker.
+    pub fn settle(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let Some(highest_bidder) = self.auction.highest_bidder else {
+            self.escrow.status = EscrowStatus::Open;
+            return self.auction.close(self.maker.to_account_info());
+        };
+
+        let (Some(winner), Some(winner_ata_a), Some(bid), Some(bid_vault)) =
+            (&self.winner, &self.winner_ata_a, &self.bid, &self.bid_vault)
+        else {
+            return err!(ErrorCode::InvalidWinnerAccount);
+        };
+        require!(
+            winner.key() == highest_bidder
+                && bid.bidder == highest_bidder
+                && bid.auction == self.auction.key()
+                && bid_vault.owner == bid.key()
+                && bid_vault.mint == self.mint_b.key()
+                && winner_ata_a.owner == highest_bidder
+                && winner_ata_a.mint == self.mint_a.key(),
+            ErrorCode::InvalidWinnerAccount
+        );
+
+        let bid_seeds: [&[&[u8]]; 1] = [&[
+            b"bid",
+            bid.auction.as_ref(),
+            bid.bidder.as_ref(),
+            &[bid.bump],
+        ]];
+        let (payment, fill) = (bid_vault.amount, self.vault.amount);
+
+        // The protocol fee is carved out of the winning bid, as on Take
+        let fee = self
+            .config
+            .fee_for(bid_vault.amount)
+            .ok_or(ErrorCode::Overflow)?;
+        if fee > 0 {
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: bid_vault.to_account_info(),
+                to: self.treasury_ata_b.to_account_info(),
+                authority: bid.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &bid_seeds);
+
+            transfer_checked(cpi_ctx, fee, self.mint_b.decimals)?;
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: bid_vault.to_account_info(),
+            to: self.maker_ata_b.to_account_info(),
+            authority: bid.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &bid_seeds);
+
+        transfer_checked(cpi_ctx, bid_vault.amount - fee, self.mint_b.decimals)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: bid_vault.to_account_info(),
+            destination: winner.to_account_info(),
+            authority: bid.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &bid_seeds);
+
+        close_account(cpi_ctx)?;
+        bid.close(winner.to_account_info())?;
+
+        let escrow_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.escrow.maker_seed(),
+            &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: winner_ata_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &escrow_seeds);
+
+        hook::transfer_checked(
+            cpi_ctx,
+            self.vault.amount,
+            self.mint_a.decimals,
+            hook_accounts,
+        )?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.rent_payer.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &escrow_seeds);
+
+        close_account(cpi_ctx)?;
+
+        self.escrow.status = EscrowStatus::Closed;
+        self.report(payment, fill)?;
+        self.deregister()?;
+        self.escrow.close(self.rent_payer.to_account_info())
+    }
+
+    // Counts the auction sale in the maker's stats, when the escrow reports to them
+    fn report(&mut self, payment: u64, fill: u64) -> Result<()> {
+        if self.escrow.stats.is_none() {
+            return Ok(());
+        }
+        let stats = self
+            .maker_stats
+            .as_mut()
+            .ok_or(ErrorCode::MakerStatsRequired)?;
+        stats.add_volume(self.mint_a.key(), fill);
+        stats.add_volume(self.mint_b.key(), payment);
+        stats.escrows_filled = stats.escrows_filled.saturating_add(1);
+        Ok(())
+    }
+
+    // Drops the escrow from the maker's registry as it closes
+    fn deregister(&mut self) -> Result<()> {
+        if !self.escrow.registered {
+            return Ok(());
+        }
+        let registry = self
+            .maker_registry
+            .as_mut()
+            .ok_or(ErrorCode::MakerRegistryRequired)?;
+        registry.remove(&self.escrow.key());
+        Ok(())
+    }
+}
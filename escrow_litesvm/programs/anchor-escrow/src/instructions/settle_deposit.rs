@@ -0,0 +1,255 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::ErrorCode,
+    hook,
+    state::{Config, Escrow, EscrowStatus, MakerRegistry, MakerStats, TakerDeposit},
+};
+
+#[derive(Accounts)]
+pub struct SettleDeposit<'info> {
+    // Anyone can settle once the review period has passed
+    #[account(mut)]
+    pub settler: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    // Funded the deposit and its vault, gets the rent back
+    #[account(mut, address = deposit.taker)]
+    pub taker: SystemAccount<'info>,
+    #[account(mut, address = escrow.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: only the owner of treasury_ata_b, pinned to the config
+    #[account(address = config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = settler,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = rent_payer,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        has_one = mint_b @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow,
+        seeds = [b"taker_deposit", escrow.key().as_ref()],
+        bump = deposit.bump,
+    )]
+    pub deposit: Account<'info, TakerDeposit>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = deposit,
+        associated_token::token_program = token_program,
+    )]
+    pub deposit_vault: InterfaceAccount<'info, TokenAccount>,
+    // Required for escrows counted in the maker's registry, see `Escrow::registered`
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Account<'info, MakerRegistry>>,
+    // Required for escrows that report to the maker's stats, see `Escrow::stats`
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Account<'info, MakerStats>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SettleDeposit<'info> {
+    pub fn check_review_over(&self) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            current_time >= self.deposit.settle_at,
+            ErrorCode::ReviewNotOver
+        );
+        Ok(())
+    }
+
+    /// Releases both sides at once: the vaulted payment to the maker, less the
+    /// protocol fee, and the deposit to the taker. Closes the escrow, the deposit
+    /// and both vaults.
+    pub fn settle(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let payment = self.deposit_vault.amount;
+        let fill = self.vault.amount;
+        self.pay(payment)?;
+        self.release(fill, hook_accounts)?;
+
+        self.escrow.remaining_deposit = 0;
+        self.escrow.remaining_receive = 0;
+        self.escrow.status = EscrowStatus::Closed;
+        self.report(payment, fill)?;
+        self.deregister()
+    }
+
+    fn pay(&self, payment: u64) -> Result<()> {
+        let escrow_key = self.escrow.key();
+        let deposit_seeds: [&[&[u8]]; 1] =
+            [&[b"taker_deposit", escrow_key.as_ref(), &[self.deposit.bump]]];
+
+        // The protocol fee is carved out of the payment, as on Take
+        let fee = self.config.fee_for(payment).ok_or(ErrorCode::Overflow)?;
+        if fee > 0 {
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: self.deposit_vault.to_account_info(),
+                to: self.treasury_ata_b.to_account_info(),
+                authority: self.deposit.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &deposit_seeds);
+
+            transfer_checked(cpi_ctx, fee, self.mint_b.decimals)?;
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.deposit_vault.to_account_info(),
+            to: self.maker_ata_b.to_account_info(),
+            authority: self.deposit.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &deposit_seeds);
+
+        transfer_checked(cpi_ctx, payment - fee, self.mint_b.decimals)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.deposit_vault.to_account_info(),
+            destination: self.taker.to_account_info(),
+            authority: self.deposit.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &deposit_seeds);
+
+        close_account(cpi_ctx)
+    }
+
+    fn release(&self, fill: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.escrow.maker_seed(),
+            &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &escrow_seeds);
+
+        hook::transfer_checked(cpi_ctx, fill, self.mint_a.decimals, hook_accounts)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.rent_payer.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &escrow_seeds);
+
+        close_account(cpi_ctx)
+    }
+
+    // Counts the settled trade in the maker's stats, when the escrow reports to them
+    fn report(&mut self, payment: u64, fill: u64) -> Result<()> {
+        if self.escrow.stats.is_none() {
+            return Ok(());
+        }
+        let stats = self
+            .maker_stats
+            .as_mut()
+            .ok_or(ErrorCode::MakerStatsRequired)?;
+        stats.add_volume(self.mint_a.key(), fill);
+        stats.add_volume(self.mint_b.key(), payment);
+        stats.escrows_filled = stats.escrows_filled.saturating_add(1);
+        Ok(())
+    }
+
+    // Drops the escrow from the maker's registry as it closes
+    fn deregister(&mut self) -> Result<()> {
+        if !self.escrow.registered {
+            return Ok(());
+        }
+        let registry = self
+            .maker_registry
+            .as_mut()
+            .ok_or(ErrorCode::MakerRegistryRequired)?;
+        registry.remove(&self.escrow.key());
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{self, AssociatedToken},
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use math::Rounding;
+
+use crate::{
+    error::ErrorCode,
+    hook,
+    state::{Config, Escrow, EscrowStatus, MakerRegistry, MakerStats},
+};
+
+// Peels `amount` of an escrow's remaining deposit off into a new escrow under
+// another seed, on the same terms, for instance reserved for one taker while the
+// rest stays public.
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct Split<'info> {
+    // Pays the new escrow and vault rent, which goes back to it on close
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        has_one = mint_b @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = maker,
+        seeds = [
+            b"escrow",
+            maker.key().as_ref(),
+            seed.to_le_bytes().as_ref(),
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+        ],
+        bump,
+        space = 8 + Escrow::INIT_SPACE,
+    )]
+    pub child: Account<'info, Escrow>,
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = child,
+        associated_token::token_program = token_program,
+    )]
+    pub child_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = maker,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump,
+        space = 8 + MakerRegistry::INIT_SPACE,
+    )]
+    pub maker_registry: Account<'info, MakerRegistry>,
+    // Required when the escrow reports to the maker's stats, as the new one will
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Account<'info, MakerStats>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Split<'info> {
+    // Two-sided escrows may hold a taker's payment for the whole remainder
+    pub fn check_escrow(&self, amount: u64) -> Result<()> {
+        let escrow = &self.escrow;
+        escrow.require_active()?;
+        escrow.require_not_paused()?;
+        require!(!escrow.is_two_sided(), ErrorCode::SplitUnsupported);
+        require!(
+            amount > 0 && amount < escrow.remaining_deposit,
+            ErrorCode::InvalidSplitAmount
+        );
+        require!(
+            amount >= self.config.min_deposit,
+            ErrorCode::DepositTooSmall
+        );
+        Ok(())
+    }
+
+    /// Opens the new escrow for `amount` on the parent's terms, owed in proportion
+    /// and rounded up in the maker's favour like `WithdrawPartial::update_terms`,
+    /// which shrinks the parent the same way. `taker` replaces the designated taker.
+    pub fn init_child(
+        &mut self,
+        seed: u64,
+        amount: u64,
+        taker: Option<Pubkey>,
+        bumps: &SplitBumps,
+    ) -> Result<()> {
+        let escrow = &self.escrow;
+        let remaining_deposit = escrow.remaining_deposit - amount;
+        let (receive, remaining_receive) = match escrow.unit_price {
+            Some(unit_price) => (
+                unit_price.cost(amount, Rounding::Up),
+                unit_price.cost(remaining_deposit, Rounding::Up),
+            ),
+            None => (
+                math::mul_div(
+                    escrow.remaining_receive,
+                    amount,
+                    escrow.remaining_deposit,
+                    Rounding::Up,
+                ),
+                math::mul_div(
+                    escrow.remaining_receive,
+                    remaining_deposit,
+                    escrow.remaining_deposit,
+                    Rounding::Up,
+                ),
+            ),
+        };
+        let receive = receive.ok_or(ErrorCode::Overflow)?;
+        let remaining_receive = remaining_receive.ok_or(ErrorCode::Overflow)?;
+        // Oracle-priced escrows keep a zero `receive`, see `Escrow::set_remaining_receive`
+        let child_receive = if escrow.price_feed.is_some() {
+            0
+        } else {
+            receive
+        };
+
+        let stats = escrow.stats;
+        if let Some(stats) = stats {
+            let maker_stats = self
+                .maker_stats
+                .as_mut()
+                .ok_or(ErrorCode::MakerStatsRequired)?;
+            require_keys_eq!(maker_stats.key(), stats, ErrorCode::MakerStatsRequired);
+            maker_stats.escrows_created = maker_stats.escrows_created.saturating_add(1);
+        }
+
+        let vault_bump = Pubkey::find_program_address(
+            &[
+                self.child.key().as_ref(),
+                self.token_program.key().as_ref(),
+                self.mint_a.key().as_ref(),
+            ],
+            &associated_token::ID,
+        )
+        .1;
+
+        // The cancellation fee stays staked on the parent
+        self.child.set_inner(Escrow {
+            seed,
+            status: EscrowStatus::Open,
+            receive: child_receive,
+            bump: bumps.child,
+            version: Escrow::CURRENT_VERSION,
+            taker,
+            stats,
+            rent_payer: self.maker.key(),
+            remaining_deposit: amount,
+            remaining_receive: receive,
+            paused: false,
+            vault_bump,
+            mint_seeded: true,
+            registered: true,
+            total_filled: 0,
+            cancel_fee: 0,
+            cancel_fee_until: 0,
+            made_by: None,
+            ..(*self.escrow).clone()
+        });
+
+        let escrow = &mut self.escrow;
+        escrow.set_remaining_receive(remaining_receive)?;
+        escrow.remaining_deposit = remaining_deposit;
+        Ok(())
+    }
+
+    // As `Make::register`
+    pub fn register(&mut self, seed: u64, bumps: &SplitBumps) -> Result<()> {
+        let registry = &mut self.maker_registry;
+        let max_open = self.config.max_open_escrows_per_maker;
+        require!(
+            max_open == 0 || registry.open_escrows < max_open,
+            ErrorCode::TooManyOpenEscrows
+        );
+        if registry.maker == Pubkey::default() {
+            registry.maker = self.maker.key();
+            registry.bump = bumps.maker_registry;
+        }
+        registry.add(self.child.key(), seed)
+    }
+
+    /// Moves `amount` into the new vault, then reconciles the new escrow to what
+    /// reached it like `Make::deposit`.
+    pub fn move_deposit(
+        &mut self,
+        amount: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.escrow.maker_seed(),
+            &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.child_vault.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        hook::transfer_checked(cpi_context, amount, self.mint_a.decimals, hook_accounts)?;
+
+        self.child_vault.reload()?;
+        let received = self.child_vault.amount;
+        require!(received > 0, ErrorCode::DepositTooSmall);
+
+        let child = &mut self.child;
+        if received != child.remaining_deposit {
+            child.remaining_deposit = received;
+            if let Some(unit_price) = child.unit_price {
+                let receive = unit_price
+                    .cost(received, Rounding::Up)
+                    .ok_or(ErrorCode::Overflow)?;
+                child.receive = receive;
+                child.remaining_receive = receive;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    state::{Auction, Escrow, EscrowStatus},
+};
+
+#[derive(Accounts)]
+pub struct StartAuction<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    // Locked for the auction so it can no longer be taken, updated or refunded
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"auction", escrow.key().as_ref()],
+        bump,
+        space = 8 + Auction::INIT_SPACE,
+    )]
+    pub auction: Account<'info, Auction>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> StartAuction<'info> {
+    // The whole deposit goes to a single winner, so nothing may have been taken yet
+    pub fn check_auctionable(&self) -> Result<()> {
+        let escrow = &self.escrow;
+        require!(
+            escrow.status == EscrowStatus::Open && escrow.remaining_receive == escrow.receive,
+            ErrorCode::EscrowPartiallyFilled
+        );
+        require!(
+            escrow.taker.is_none()
+                && escrow.taker_root.is_none()
+                && !escrow.enforce_royalties
+                && escrow.price_feed.is_none(),
+            ErrorCode::AuctionNotSupported
+        );
+        Ok(())
+    }
+
+    pub fn start_auction(
+        &mut self,
+        end_at: i64,
+        reserve: u64,
+        bumps: &StartAuctionBumps,
+    ) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        let escrow = &self.escrow;
+        require!(
+            end_at > current_time
+                && end_at >= escrow.unlock_at
+                && (escrow.expiry == 0 || end_at < escrow.expiry),
+            ErrorCode::InvalidAuctionEnd
+        );
+
+        self.auction.set_inner(Auction {
+            escrow: self.escrow.key(),
+            end_at,
+            reserve,
+            highest_bid: 0,
+            highest_bidder: None,
+            bump: bumps.auction,
+        });
+        self.escrow.status = EscrowStatus::Locked;
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{
@@ -7,97 +7,579 @@ use anchor_spl::{
     },
 };
 
-use crate::state::Escrow;
+use math::Rounding;
+
+use crate::{
+    balance,
+    error::ErrorCode,
+    events::{self, EscrowTaken},
+    hook, interest, memo, merkle,
+    metadata::Metadata,
+    native, residual,
+    state::{Config, Escrow, EscrowStatus, MakerRegistry, MakerStats, Proceeds},
+    whitelist,
+};
+
+#[cfg(feature = "oracle")]
+use crate::oracle;
 
 //Create context
+#[event_cpi]
 #[derive(Accounts)]
 pub struct Take<'info> {
     #[account(mut)]
     pub taker: Signer<'info>,
+    // Funds any accounts Take creates, may be a relayer distinct from the taker
     #[account(mut)]
+    pub payer: Signer<'info>,
     pub maker: SystemAccount<'info>,
+    #[account(mut, address = escrow.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
     pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
     pub mint_b: InterfaceAccount<'info, Mint>,
     #[account(
         init_if_needed,
-        payer = taker,
+        payer = payer,
         associated_token::mint = mint_a,
         associated_token::authority = taker,
+        associated_token::token_program = token_program,
     )]
     pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    // Created on the fly when mint_b is native SOL and the payment is wrapped from lamports
     #[account(
-        mut,
+        init_if_needed,
+        payer = payer,
         associated_token::mint = mint_b,
         associated_token::authority = taker,
+        associated_token::token_program = token_program,
     )]
     pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
     #[account(
         init_if_needed,
-        payer = taker,
+        payer = payer,
         associated_token::mint = mint_b,
         associated_token::authority = maker,
+        associated_token::token_program = token_program,
     )]
     pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    // Receives whatever the last fill leaves in the vault, see `residual::sweep`
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = maker,
+        token::token_program = token_program,
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: only the owner of treasury_ata_b, pinned to the config
+    #[account(address = config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_ata_b: InterfaceAccount<'info, TokenAccount>,
+    // Aggregator or frontend that routed the fill, paid `Config::referral_bps` of it
+    // out of the protocol fee
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::token_program = token_program,
+    )]
+    pub referrer_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+    // Closed to rent_payer by the fill that takes the last of the deposit
     #[account(
         mut,
-        close = maker,
         has_one = maker,
-        has_one = mint_a,
-        has_one = mint_b,
-        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        constraint = escrow.accepts(&mint_b.key()) @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
         bump = escrow.bump,
     )]
     pub escrow: Account<'info, Escrow>,
+    // The escrow's mint_a ATA, checked from the stored bump rather than searched for
     #[account(
         mut,
-        associated_token::mint = mint_a,
-        associated_token::authority = escrow,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
+    // Only needed when maker_ata_b is frozen: mint_b is parked here for ClaimProceeds
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"proceeds", escrow.key().as_ref()],
+        bump,
+        space = 8 + Proceeds::INIT_SPACE,
+    )]
+    pub proceeds: Option<Account<'info, Proceeds>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = proceeds,
+        associated_token::token_program = token_program,
+    )]
+    pub proceeds_vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    /// CHECK: only needed for oracle-priced escrows, checked against the escrow's
+    /// price feed and parsed in `reprice`
+    pub price_update: Option<UncheckedAccount<'info>>,
+    // Required for escrows counted in the maker's registry, see `Escrow::registered`
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Account<'info, MakerRegistry>>,
+    // Required for escrows that report to the maker's stats, see `Escrow::stats`
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Account<'info, MakerStats>>,
+    /// CHECK: only needed with a memo, pinned to the SPL Memo program
+    #[account(address = memo::MEMO_PROGRAM_ID @ ErrorCode::InvalidMemoProgram)]
+    pub memo_program: Option<UncheckedAccount<'info>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 //Deposit tokens from taker to maker
-//Transfer tokens from vault to taker
-//Close vault account
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Escrow has not waiting yet. Cannot take before waiting time.")]
-    EscrowNotExpired,
-}
-
+//Transfer the matching slice of the vault to taker
+//Close vault account once fully filled
 impl<'info> Take<'info> {
     pub fn check_waiting_time(&self) -> Result<()> {
         let current_time = Clock::get()?.unix_timestamp;
         require!(
-            current_time >= self.escrow.waiting_time,
-            ErrorCode::EscrowNotExpired
+            current_time >= self.escrow.unlock_at,
+            ErrorCode::WaitingTimeNotElapsed
+        );
+        Ok(())
+    }
+
+    pub fn check_expiry(&self) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        require!(
+            !self.escrow.is_expired(current_time),
+            ErrorCode::EscrowExpired
         );
         Ok(())
     }
 
-    pub fn deposit(&mut self) -> Result<()> {
+    // Escrows with a reveal window only fill through CommitTake and RevealTake
+    pub fn check_commit_free(&self) -> Result<()> {
+        require!(self.escrow.reveal_window == 0, ErrorCode::CommitRequired);
+        Ok(())
+    }
+
+    // Two-sided escrows fill through DepositPayment and vesting ones through TakeVested
+    pub fn check_delivers_at_take(&self) -> Result<()> {
+        require!(!self.escrow.is_two_sided(), ErrorCode::DepositRequired);
+        require!(!self.escrow.vests(), ErrorCode::VestingRequired);
+        Ok(())
+    }
+
+    pub fn check_taker_allowed(&self, proof: &[[u8; 32]]) -> Result<()> {
+        if let Some(taker) = self.escrow.taker {
+            require_keys_eq!(self.taker.key(), taker, ErrorCode::UnauthorizedTaker);
+        }
+        if let Some(root) = self.escrow.taker_root {
+            require!(
+                merkle::verify(proof, &root, self.taker.key),
+                ErrorCode::TakerNotAllowlisted
+            );
+        }
+        Ok(())
+    }
+
+    /// Finds the taker's whitelist entry by address among the remaining accounts,
+    /// so it can sit anywhere among the transfer hook accounts.
+    pub fn check_taker_whitelisted(&self, accounts: &[AccountInfo<'info>]) -> Result<()> {
+        if !self.escrow.whitelisted_takers {
+            return Ok(());
+        }
+        whitelist::require_whitelisted(self.taker.key, accounts)
+    }
+
+    pub fn check_preimage(&self, preimage: Option<[u8; 32]>) -> Result<()> {
+        let Some(hashlock) = self.escrow.hashlock else {
+            return Ok(());
+        };
+        let preimage = preimage.ok_or(ErrorCode::InvalidPreimage)?;
+        require!(
+            hashv(&[&preimage]).to_bytes() == hashlock,
+            ErrorCode::InvalidPreimage
+        );
+        Ok(())
+    }
+
+    // Fail before any transfer if the taker's accounts cannot move funds
+    pub fn check_taker_accounts(&self) -> Result<()> {
+        require!(
+            !self.taker_ata_a.is_frozen() && !self.taker_ata_b.is_frozen(),
+            ErrorCode::TakerAccountFrozen
+        );
+        Ok(())
+    }
+
+    /// Accounts Take pays into must answer to their owner alone, a delegate or close
+    /// authority left on one could move the funds out once they land.
+    pub fn check_recipient_accounts(&self) -> Result<()> {
+        let recipients = [Some(&self.taker_ata_a), Some(&self.maker_ata_b)]
+            .into_iter()
+            .chain([self.maker_ata_a.as_ref()])
+            .flatten();
+        for account in recipients {
+            require!(account.delegate.is_none(), ErrorCode::UnexpectedDelegate);
+            require!(
+                account.close_authority.is_none(),
+                ErrorCode::UnexpectedCloseAuthority
+            );
+        }
+        Ok(())
+    }
+
+    /// Oracle-priced escrows owe the current value of what is left in the vault.
+    #[cfg(feature = "oracle")]
+    pub fn reprice(&mut self) -> Result<()> {
+        let Some(price_feed) = self.escrow.price_feed else {
+            return Ok(());
+        };
+        let price_update = self
+            .price_update
+            .as_ref()
+            .ok_or(ErrorCode::InvalidPriceFeed)?;
+        require_keys_eq!(price_update.key(), price_feed, ErrorCode::InvalidPriceFeed);
+
+        let update = oracle::PriceUpdateV2::load(price_update)?;
+        let price = update.checked_price(Clock::get()?.unix_timestamp)?;
+        let escrow = &mut self.escrow;
+        escrow.remaining_receive = oracle::quote(
+            escrow.remaining_deposit,
+            price,
+            self.mint_a.decimals,
+            self.mint_b.decimals,
+            escrow.spread_bps,
+        )
+        .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "oracle"))]
+    pub fn reprice(&mut self) -> Result<()> {
+        require!(self.escrow.price_feed.is_none(), ErrorCode::OracleDisabled);
+        Ok(())
+    }
+
+    /// What paying `take_amount` of the chosen mint_b counts for against `remaining_receive`.
+    pub fn owed(&self, take_amount: u64) -> Result<u64> {
+        if self.escrow.receive_in_ui_amount {
+            return self.owed_in_ui_amount(take_amount);
+        }
+        let owed = self
+            .escrow
+            .owed_for(&self.mint_b.key(), take_amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(owed)
+    }
+
+    // The interest accrued on a payment counts towards the quote. Paying a little
+    // over what is left fills it, as the raw amount worth it exactly may not exist.
+    fn owed_in_ui_amount(&self, take_amount: u64) -> Result<u64> {
+        let config =
+            interest::config(&self.mint_b.to_account_info())?.ok_or(ErrorCode::InvalidMint)?;
+        let owed = interest::ui_units(
+            &config,
+            take_amount,
+            self.mint_b.decimals,
+            Clock::get()?.unix_timestamp,
+        )?;
+        Ok(owed.min(self.escrow.remaining_receive))
+    }
+
+    /// mint_a owed for paying `take_amount` of mint_b, rounded down in the maker's favour.
+    pub fn fill_amount(&self, take_amount: u64) -> Result<u64> {
+        self.escrow.fill_for(take_amount)
+    }
+
+    /// mint_b charged for `fill`, see `Escrow::payment_for`.
+    pub fn payment(&self, take_amount: u64, fill: u64) -> Result<u64> {
+        self.escrow.payment_for(take_amount, fill)
+    }
+
+    /// mint_b charged for exactly `fill` of mint_a, see `Escrow::cost_for`.
+    pub fn cost(&self, fill: u64) -> Result<u64> {
+        require!(
+            !self.escrow.receive_in_ui_amount,
+            ErrorCode::ExactOutUnsupported
+        );
+        self.escrow.cost_for(&self.mint_b.key(), fill)
+    }
+
+    // Fails when the taker would pay more mint_b or get less mint_a than they accepted
+    pub fn check_slippage(
+        &self,
+        take_amount: u64,
+        fill: u64,
+        max_pay: u64,
+        min_receive: u64,
+    ) -> Result<()> {
+        require!(
+            take_amount <= max_pay && fill >= min_receive,
+            ErrorCode::SlippageExceeded
+        );
+        Ok(())
+    }
+
+    pub fn deposit(
+        &mut self,
+        take_amount: u64,
+        royalty_accounts: &[AccountInfo<'info>],
+        bumps: &TakeBumps,
+    ) -> Result<()> {
+        // Claiming a gift pays nothing
+        if take_amount == 0 {
+            return Ok(());
+        }
+
+        if native::is_native_mint(&self.mint_b.key()) {
+            native::wrap(
+                self.taker.to_account_info(),
+                self.taker_ata_b.to_account_info(),
+                take_amount,
+                self.system_program.to_account_info(),
+                self.token_program.to_account_info(),
+            )?;
+        }
+
+        // A frozen maker account would block the fill, park the proceeds instead
+        let destination = if self.maker_ata_b.is_frozen() {
+            let (Some(proceeds), Some(proceeds_vault)) = (&mut self.proceeds, &self.proceeds_vault)
+            else {
+                return err!(ErrorCode::MakerAccountFrozen);
+            };
+
+            if proceeds.escrow == Pubkey::default() {
+                proceeds.set_inner(Proceeds {
+                    escrow: self.escrow.key(),
+                    maker: self.maker.key(),
+                    mint_b: self.mint_b.key(),
+                    rent_payer: self.payer.key(),
+                    bump: bumps.proceeds.unwrap(),
+                });
+            }
+            // Parked proceeds are claimed in a single mint
+            require_keys_eq!(proceeds.mint_b, self.mint_b.key(), ErrorCode::InvalidMint);
+            proceeds_vault.to_account_info()
+        } else {
+            self.maker_ata_b.to_account_info()
+        };
+
+        // The protocol fee is carved out of the payment, rounded down in the maker's favour
+        let fee = self
+            .config
+            .fee_for(take_amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let referral = self.pay_referral(take_amount, fee)?;
+        let treasury_fee = fee - referral;
+        if treasury_fee > 0 {
+            let before = balance::of(&self.treasury_ata_b.to_account_info())?;
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: self.taker_ata_b.to_account_info(),
+                to: self.treasury_ata_b.to_account_info(),
+                authority: self.taker.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+            transfer_checked(cpi_ctx, treasury_fee, self.mint_b.decimals)?;
+            balance::check_credited(
+                &self.taker_ata_b.to_account_info(),
+                &self.treasury_ata_b.to_account_info(),
+                &self.mint_b.to_account_info(),
+                before,
+                treasury_fee,
+            )?;
+        }
+
+        let royalties = self.pay_royalties(take_amount, royalty_accounts)?;
+        let proceeds = take_amount
+            .checked_sub(fee)
+            .and_then(|amount| amount.checked_sub(royalties))
+            .ok_or(ErrorCode::Overflow)?;
+
+        let before = balance::of(&destination)?;
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = TransferChecked {
             from: self.taker_ata_b.to_account_info(),
-            to: self.maker_ata_b.to_account_info(),
+            to: destination.clone(),
             authority: self.taker.to_account_info(),
             mint: self.mint_b.to_account_info(),
         };
 
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
 
-        transfer_checked(cpi_ctx, self.escrow.receive, self.mint_b.decimals)
+        transfer_checked(cpi_ctx, proceeds, self.mint_b.decimals)?;
+        balance::check_credited(
+            &self.taker_ata_b.to_account_info(),
+            &destination,
+            &self.mint_b.to_account_info(),
+            before,
+            proceeds,
+        )?;
+
+        // Counted as the taker paid it, a Token-2022 transfer fee on mint_b is borne
+        // by the maker like the protocol fee. An exact-out cost rounded up may count
+        // for a little more than is left
+        let owed = self.owed(take_amount)?;
+        self.escrow.remaining_receive = self.escrow.remaining_receive.saturating_sub(owed);
+        Ok(())
     }
 
-    pub fn withdraw_and_close_vault(&mut self) -> Result<()> {
+    /// Pays the referrer their cut of the payment and returns it. The cut comes out of
+    /// the protocol `fee`, never the maker's proceeds, so it is capped at the fee and
+    /// a taker naming themselves only shares in what the treasury would get.
+    fn pay_referral(&self, take_amount: u64, fee: u64) -> Result<u64> {
+        let Some(referrer_ata_b) = &self.referrer_ata_b else {
+            return Ok(0);
+        };
+        let referral = math::apply_bps(take_amount, self.config.referral_bps, Rounding::Down)
+            .ok_or(ErrorCode::Overflow)?
+            .min(fee);
+        if referral == 0 {
+            return Ok(0);
+        }
+
+        let before = balance::of(&referrer_ata_b.to_account_info())?;
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            to: referrer_ata_b.to_account_info(),
+            authority: self.taker.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer_checked(cpi_ctx, referral, self.mint_b.decimals)?;
+        balance::check_credited(
+            &self.taker_ata_b.to_account_info(),
+            &referrer_ata_b.to_account_info(),
+            &self.mint_b.to_account_info(),
+            before,
+            referral,
+        )?;
+        Ok(referral)
+    }
+
+    /// Splits the NFT's seller fee out of the payment between its creators by share,
+    /// returning the total paid. Each creator's cut is rounded down.
+    fn pay_royalties(&self, take_amount: u64, accounts: &[AccountInfo<'info>]) -> Result<u64> {
+        if !self.escrow.enforce_royalties {
+            return Ok(0);
+        }
+
+        let (metadata_info, creator_accounts) =
+            accounts.split_first().ok_or(ErrorCode::InvalidMetadata)?;
+        require_keys_eq!(
+            metadata_info.key(),
+            Metadata::address(&self.mint_a.key()),
+            ErrorCode::InvalidMetadata
+        );
+        let metadata = Metadata::load(metadata_info, &self.mint_a.key())?;
+
+        let royalty = math::apply_bps(
+            take_amount,
+            metadata.seller_fee_basis_points,
+            Rounding::Down,
+        )
+        .ok_or(ErrorCode::Overflow)?;
+        let creators: Vec<_> = metadata
+            .creators
+            .iter()
+            .flatten()
+            .filter(|creator| creator.share > 0)
+            .collect();
+        require!(
+            creators.len() == creator_accounts.len(),
+            ErrorCode::InvalidCreatorAccount
+        );
+
+        let mut paid = 0u64;
+        for (creator, account) in creators.into_iter().zip(creator_accounts) {
+            require_keys_eq!(
+                *account.owner,
+                self.token_program.key(),
+                ErrorCode::InvalidCreatorAccount
+            );
+            let token_account =
+                TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+            require!(
+                token_account.mint == self.mint_b.key() && token_account.owner == creator.address,
+                ErrorCode::InvalidCreatorAccount
+            );
+
+            let amount = math::mul_div(royalty, creator.share as u64, 100, Rounding::Down)
+                .ok_or(ErrorCode::Overflow)?;
+            if amount == 0 {
+                continue;
+            }
+
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: self.taker_ata_b.to_account_info(),
+                to: account.clone(),
+                authority: self.taker.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+            transfer_checked(cpi_ctx, amount, self.mint_b.decimals)?;
+            balance::check_credited(
+                &self.taker_ata_b.to_account_info(),
+                account,
+                &self.mint_b.to_account_info(),
+                token_account.amount,
+                amount,
+            )?;
+            paid += amount;
+        }
+
+        Ok(paid)
+    }
+
+    pub fn withdraw(&mut self, fill: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let filled = self.escrow.record_fill(fill)?;
+
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
-            self.maker.key.as_ref(),
+            self.escrow.maker_seed(),
             &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
             &[self.escrow.bump],
         ]];
 
@@ -112,18 +594,116 @@ impl<'info> Take<'info> {
 
         let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
 
-        transfer_checked(cpi_context, self.vault.amount, self.mint_a.decimals)?;
+        let before = balance::of(&self.taker_ata_a.to_account_info())?;
+        hook::transfer_checked(cpi_context, fill, self.mint_a.decimals, hook_accounts)?;
+        balance::check_credited(
+            &self.vault.to_account_info(),
+            &self.taker_ata_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            before,
+            fill,
+        )?;
+
+        if !filled {
+            return Ok(());
+        }
+
+        residual::sweep(
+            &self.vault,
+            self.maker_ata_a.as_ref(),
+            self.escrow.to_account_info(),
+            &self.mint_a,
+            self.token_program.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
 
         let cpi_program = self.token_program.to_account_info();
 
         let cpi_accounts = CloseAccount {
             account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
+            destination: self.rent_payer.to_account_info(),
             authority: self.escrow.to_account_info(),
         };
 
         let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
 
-        close_account(cpi_context)
+        close_account(cpi_context)?;
+
+        self.deregister()?;
+        self.escrow.close(self.rent_payer.to_account_info())
+    }
+
+    // The taker signs, so their native SOL accounts are unwrapped back to lamports.
+    // A native mint_b payment reaches the maker as wSOL since the maker cannot sign here.
+    pub fn unwrap_native(&mut self) -> Result<()> {
+        if native::is_native_mint(&self.mint_a.key()) {
+            native::unwrap(
+                self.taker_ata_a.to_account_info(),
+                self.taker.to_account_info(),
+                self.token_program.to_account_info(),
+            )?;
+        }
+        if native::is_native_mint(&self.mint_b.key()) {
+            native::unwrap(
+                self.taker_ata_b.to_account_info(),
+                self.taker.to_account_info(),
+                self.token_program.to_account_info(),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Adds the fill to the maker's stats, when the escrow reports to them.
+    pub fn report(&mut self, payment: u64, fill: u64) -> Result<()> {
+        if self.escrow.stats.is_none() {
+            return Ok(());
+        }
+        let stats = self
+            .maker_stats
+            .as_mut()
+            .ok_or(ErrorCode::MakerStatsRequired)?;
+        stats.add_volume(self.mint_a.key(), fill);
+        stats.add_volume(self.mint_b.key(), payment);
+        if self.escrow.status == EscrowStatus::Closed {
+            stats.escrows_filled = stats.escrows_filled.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    // Drops the escrow from the maker's registry as it closes
+    fn deregister(&mut self) -> Result<()> {
+        if !self.escrow.registered {
+            return Ok(());
+        }
+        let registry = self
+            .maker_registry
+            .as_mut()
+            .ok_or(ErrorCode::MakerRegistryRequired)?;
+        registry.remove(&self.escrow.key());
+        Ok(())
+    }
+
+    pub fn emit_taken(&self, payment: u64, fill: u64, bumps: &TakeBumps) -> Result<()> {
+        events::emit_cpi(
+            &EscrowTaken {
+                escrow: self.escrow.key(),
+                taker: self.taker.key(),
+                mint_b: self.mint_b.key(),
+                payment,
+                fill,
+                remaining_deposit: self.escrow.remaining_deposit,
+            },
+            &self.event_authority,
+            bumps.event_authority,
+        )
+    }
+
+    pub fn log_memo(&self, memo: Option<String>) -> Result<()> {
+        memo::log(
+            memo,
+            self.memo_program.as_ref(),
+            self.taker.to_account_info(),
+        )
     }
 }
@@ -0,0 +1,342 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::ErrorCode,
+    hook, residual,
+    state::{Config, Escrow, MakerRegistry, MakerStats},
+};
+
+// Take across several escrows of one maker and pair, all filled or none. The
+// escrows come in as remaining accounts, see `TakeMany::take_all`
+#[derive(Accounts)]
+pub struct TakeMany<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    // Receives whatever the last fill of an escrow leaves in its vault, see
+    // `residual::sweep`
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = maker,
+        token::token_program = token_program,
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: only the owner of treasury_ata_b, pinned to the config
+    #[account(address = config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_ata_b: InterfaceAccount<'info, TokenAccount>,
+    // Required when a filled escrow is counted in the maker's registry
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Account<'info, MakerRegistry>>,
+    // Required when an escrow reports to the maker's stats
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Account<'info, MakerStats>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeMany<'info> {
+    /// Pays `take_amounts[i]` of mint_b into the i-th escrow. `accounts` holds an
+    /// (escrow, vault, rent_payer) triple per take amount, followed by any accounts
+    /// mint_a's transfer hook needs. Returns the total paid and received.
+    pub fn take_all(
+        &mut self,
+        take_amounts: &[u64],
+        accounts: &'info [AccountInfo<'info>],
+    ) -> Result<(u64, u64)> {
+        require!(
+            !take_amounts.is_empty() && accounts.len() >= take_amounts.len() * 3,
+            ErrorCode::InvalidTakeManyAccounts
+        );
+        require!(
+            !self.taker_ata_a.is_frozen() && !self.taker_ata_b.is_frozen(),
+            ErrorCode::TakerAccountFrozen
+        );
+        require!(!self.maker_ata_b.is_frozen(), ErrorCode::MakerAccountFrozen);
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut paid = 0u64;
+        let mut received = 0u64;
+        for (take_amount, escrow_accounts) in take_amounts.iter().zip(accounts.chunks_exact(3)) {
+            let [escrow_info, vault_info, rent_payer] = escrow_accounts else {
+                unreachable!()
+            };
+            let mut escrow = Account::<Escrow>::try_from(escrow_info)?;
+            self.check_escrow(&escrow, vault_info, rent_payer, now)?;
+
+            let owed = escrow
+                .owed_for(&self.mint_b.key(), *take_amount)
+                .ok_or(ErrorCode::Overflow)?;
+            let fill = escrow.fill_for(owed)?;
+            let payment = escrow.payment_for(*take_amount, fill)?;
+            self.pay(payment)?;
+            // As on Take, a curve premium may count for more than is left
+            let owed = escrow
+                .owed_for(&self.mint_b.key(), payment)
+                .ok_or(ErrorCode::Overflow)?;
+            escrow.remaining_receive = escrow.remaining_receive.saturating_sub(owed);
+
+            let vault = InterfaceAccount::<TokenAccount>::try_from(vault_info)?;
+            let filled = escrow.record_fill(fill)?;
+            self.withdraw(&escrow, &vault, rent_payer, fill, filled, accounts)?;
+            self.report(&escrow, payment, fill, filled)?;
+
+            // Written back before the next triple, which may name the same escrow
+            if filled {
+                self.deregister(&escrow)?;
+                escrow.close(rent_payer.clone())?;
+            } else {
+                escrow.exit(&crate::ID)?;
+            }
+
+            paid = paid.checked_add(payment).ok_or(ErrorCode::Overflow)?;
+            received = received.checked_add(fill).ok_or(ErrorCode::Overflow)?;
+        }
+
+        Ok((paid, received))
+    }
+
+    // The checks Take runs through its account constraints and handler. Escrows
+    // needing more than the shared accounts can give are left to Take.
+    fn check_escrow(
+        &self,
+        escrow: &Account<'info, Escrow>,
+        vault: &AccountInfo<'info>,
+        rent_payer: &AccountInfo<'info>,
+        now: i64,
+    ) -> Result<()> {
+        let address = Pubkey::create_program_address(
+            &[
+                b"escrow",
+                escrow.maker_seed(),
+                &escrow.seed.to_le_bytes()[..],
+                escrow.mint_a_seed(),
+                escrow.mint_b_seed(),
+                &[escrow.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| ErrorCode::InvalidEscrowAccount)?;
+        require_keys_eq!(escrow.key(), address, ErrorCode::InvalidEscrowAccount);
+        require_keys_eq!(
+            escrow.maker,
+            self.maker.key(),
+            ErrorCode::InvalidEscrowAccount
+        );
+        require_keys_eq!(escrow.mint_a, self.mint_a.key(), ErrorCode::InvalidMint);
+        require!(escrow.accepts(&self.mint_b.key()), ErrorCode::InvalidMint);
+        require_keys_eq!(
+            vault.key(),
+            escrow.vault_address(&escrow.key(), &self.token_program.key()),
+            ErrorCode::InvalidVault
+        );
+        require_keys_eq!(
+            rent_payer.key(),
+            escrow.rent_payer,
+            ErrorCode::InvalidTakeManyAccounts
+        );
+        require!(rent_payer.is_writable, ErrorCode::InvalidTakeManyAccounts);
+
+        escrow.require_active()?;
+        escrow.require_not_paused()?;
+        require!(now >= escrow.unlock_at, ErrorCode::WaitingTimeNotElapsed);
+        require!(!escrow.is_expired(now), ErrorCode::EscrowExpired);
+        if let Some(taker) = escrow.taker {
+            require_keys_eq!(self.taker.key(), taker, ErrorCode::UnauthorizedTaker);
+        }
+        require!(
+            !escrow.needs_take() && escrow.delivers_at_take(),
+            ErrorCode::TakeManyUnsupported
+        );
+        Ok(())
+    }
+
+    // Taker pays the maker, less the protocol fee
+    fn pay(&self, payment: u64) -> Result<()> {
+        let fee = self.config.fee_for(payment).ok_or(ErrorCode::Overflow)?;
+        if fee > 0 {
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: self.taker_ata_b.to_account_info(),
+                to: self.treasury_ata_b.to_account_info(),
+                authority: self.taker.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+            transfer_checked(cpi_ctx, fee, self.mint_b.decimals)?;
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            to: self.maker_ata_b.to_account_info(),
+            authority: self.taker.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer_checked(cpi_ctx, payment - fee, self.mint_b.decimals)
+    }
+
+    fn withdraw(
+        &self,
+        escrow: &Account<'info, Escrow>,
+        vault: &InterfaceAccount<'info, TokenAccount>,
+        rent_payer: &AccountInfo<'info>,
+        fill: u64,
+        filled: bool,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            escrow.maker_seed(),
+            &escrow.seed.to_le_bytes()[..],
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+            &[escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: vault.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        hook::transfer_checked(cpi_context, fill, self.mint_a.decimals, hook_accounts)?;
+
+        if !filled {
+            return Ok(());
+        }
+
+        residual::sweep(
+            vault,
+            self.maker_ata_a.as_ref(),
+            escrow.to_account_info(),
+            &self.mint_a,
+            self.token_program.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: vault.to_account_info(),
+            destination: rent_payer.clone(),
+            authority: escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)
+    }
+
+    fn report(&mut self, escrow: &Escrow, payment: u64, fill: u64, filled: bool) -> Result<()> {
+        if escrow.stats.is_none() {
+            return Ok(());
+        }
+        let stats = self
+            .maker_stats
+            .as_mut()
+            .ok_or(ErrorCode::MakerStatsRequired)?;
+        stats.add_volume(self.mint_a.key(), fill);
+        stats.add_volume(self.mint_b.key(), payment);
+        if filled {
+            stats.escrows_filled = stats.escrows_filled.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    fn deregister(&mut self, escrow: &Account<'info, Escrow>) -> Result<()> {
+        if !escrow.registered {
+            return Ok(());
+        }
+        let registry = self
+            .maker_registry
+            .as_mut()
+            .ok_or(ErrorCode::MakerRegistryRequired)?;
+        registry.remove(&escrow.key());
+        Ok(())
+    }
+
+    // Fails when the taker would pay more mint_b or get less mint_a than they accepted
+    pub fn check_slippage(
+        &self,
+        paid: u64,
+        received: u64,
+        max_pay: u64,
+        min_receive: u64,
+    ) -> Result<()> {
+        require!(
+            paid <= max_pay && received >= min_receive,
+            ErrorCode::SlippageExceeded
+        );
+        Ok(())
+    }
+}
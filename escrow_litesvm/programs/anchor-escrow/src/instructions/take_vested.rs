@@ -0,0 +1,305 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    error::ErrorCode,
+    hook, residual,
+    state::{Config, Escrow, MakerRegistry, MakerStats, Vesting},
+};
+
+// Take for vesting escrows: the fill moves into a vault of its own that the taker
+// drains over the escrow's vesting duration with `ClaimVested`
+#[derive(Accounts)]
+pub struct TakeVested<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    #[account(mut, address = escrow.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    // Receives whatever the last fill leaves in the vault, see `residual::sweep`
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = maker,
+        token::token_program = token_program,
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: only the owner of treasury_ata_b, pinned to the config
+    #[account(address = config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_ata_b: InterfaceAccount<'info, TokenAccount>,
+    // Closed to rent_payer by the fill that takes the last of the deposit
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    // One vesting schedule per taker and escrow
+    #[account(
+        init,
+        payer = taker,
+        seeds = [b"vesting", escrow.key().as_ref(), taker.key().as_ref()],
+        bump,
+        space = 8 + Vesting::INIT_SPACE,
+    )]
+    pub vesting: Account<'info, Vesting>,
+    #[account(
+        init,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = vesting,
+        associated_token::token_program = token_program,
+    )]
+    pub vesting_vault: InterfaceAccount<'info, TokenAccount>,
+    // Required for escrows counted in the maker's registry, see `Escrow::registered`
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Account<'info, MakerRegistry>>,
+    // Required for escrows that report to the maker's stats, see `Escrow::stats`
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Account<'info, MakerStats>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeVested<'info> {
+    // The checks Take runs, for the escrows a vested take can fill
+    pub fn check_escrow(&self) -> Result<()> {
+        let escrow = &self.escrow;
+        require!(escrow.vests(), ErrorCode::NotVesting);
+        escrow.require_active()?;
+        escrow.require_not_paused()?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= escrow.unlock_at, ErrorCode::WaitingTimeNotElapsed);
+        require!(!escrow.is_expired(now), ErrorCode::EscrowExpired);
+        if let Some(taker) = escrow.taker {
+            require_keys_eq!(self.taker.key(), taker, ErrorCode::UnauthorizedTaker);
+        }
+        require!(!escrow.needs_take(), ErrorCode::VestedTakeUnsupported);
+        require!(!self.taker_ata_b.is_frozen(), ErrorCode::TakerAccountFrozen);
+        require!(!self.maker_ata_b.is_frozen(), ErrorCode::MakerAccountFrozen);
+        Ok(())
+    }
+
+    /// Pays for the fill like Take, returning the payment and the fill.
+    pub fn pay(&mut self, take_amount: u64) -> Result<(u64, u64)> {
+        let escrow = &self.escrow;
+        let owed = escrow
+            .owed_for(&self.mint_b.key(), take_amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let fill = escrow.fill_for(owed)?;
+        let payment = escrow.payment_for(take_amount, fill)?;
+
+        let fee = self.config.fee_for(payment).ok_or(ErrorCode::Overflow)?;
+        if fee > 0 {
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: self.taker_ata_b.to_account_info(),
+                to: self.treasury_ata_b.to_account_info(),
+                authority: self.taker.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+            transfer_checked(cpi_ctx, fee, self.mint_b.decimals)?;
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            to: self.maker_ata_b.to_account_info(),
+            authority: self.taker.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer_checked(cpi_ctx, payment - fee, self.mint_b.decimals)?;
+
+        // As on Take, a curve premium may count for more than is left
+        let owed = self
+            .escrow
+            .owed_for(&self.mint_b.key(), payment)
+            .ok_or(ErrorCode::Overflow)?;
+        self.escrow.remaining_receive = self.escrow.remaining_receive.saturating_sub(owed);
+        Ok((payment, fill))
+    }
+
+    /// Moves the fill into the vesting vault and starts the taker's schedule. The
+    /// last fill returns any residual to the maker and closes the escrow vault.
+    pub fn lock_fill(
+        &mut self,
+        fill: u64,
+        hook_accounts: &[AccountInfo<'info>],
+        bumps: &TakeVestedBumps,
+    ) -> Result<bool> {
+        let filled = self.escrow.record_fill(fill)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.escrow.maker_seed(),
+            &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.vesting_vault.to_account_info(),
+            authority: self.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        hook::transfer_checked(cpi_context, fill, self.mint_a.decimals, hook_accounts)?;
+
+        // Vests what actually landed in the vesting vault
+        self.vesting_vault.reload()?;
+        self.vesting.set_inner(Vesting {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            mint_a: self.mint_a.key(),
+            total: self.vesting_vault.amount,
+            claimed: 0,
+            start_at: Clock::get()?.unix_timestamp,
+            duration: self.escrow.vesting_duration,
+            bump: bumps.vesting,
+        });
+
+        if filled {
+            residual::sweep(
+                &self.vault,
+                self.maker_ata_a.as_ref(),
+                self.escrow.to_account_info(),
+                &self.mint_a,
+                self.token_program.to_account_info(),
+                &signer_seeds,
+                hook_accounts,
+            )?;
+
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = CloseAccount {
+                account: self.vault.to_account_info(),
+                destination: self.rent_payer.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+
+            let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+            close_account(cpi_context)?;
+        }
+
+        Ok(filled)
+    }
+
+    /// Adds the fill to the maker's stats, when the escrow reports to them.
+    pub fn report(&mut self, payment: u64, fill: u64, filled: bool) -> Result<()> {
+        if self.escrow.stats.is_none() {
+            return Ok(());
+        }
+        let stats = self
+            .maker_stats
+            .as_mut()
+            .ok_or(ErrorCode::MakerStatsRequired)?;
+        stats.add_volume(self.mint_a.key(), fill);
+        stats.add_volume(self.mint_b.key(), payment);
+        if filled {
+            stats.escrows_filled = stats.escrows_filled.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    /// Drops a filled escrow from the maker's registry and closes it.
+    pub fn close_filled(&mut self) -> Result<()> {
+        if self.escrow.registered {
+            let registry = self
+                .maker_registry
+                .as_mut()
+                .ok_or(ErrorCode::MakerRegistryRequired)?;
+            registry.remove(&self.escrow.key());
+        }
+        self.escrow.close(self.rent_payer.to_account_info())
+    }
+
+    // Fails when the taker would pay more mint_b or get less mint_a than they accepted
+    pub fn check_slippage(
+        &self,
+        payment: u64,
+        fill: u64,
+        max_pay: u64,
+        min_receive: u64,
+    ) -> Result<()> {
+        require!(
+            payment <= max_pay && fill >= min_receive,
+            ErrorCode::SlippageExceeded
+        );
+        Ok(())
+    }
+}
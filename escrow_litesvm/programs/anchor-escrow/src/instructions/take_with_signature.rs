@@ -0,0 +1,346 @@
+use anchor_lang::{prelude::*, solana_program::sysvar::instructions as instructions_sysvar};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{
+    ed25519,
+    error::ErrorCode,
+    hook, residual,
+    state::{Config, Escrow, EscrowStatus, MakerRegistry, MakerStats, TakerNonce},
+};
+
+/// The order a taker signs off-chain for TakeWithSignature. Borsh-encoded after
+/// `TakeOrder::DOMAIN` to form the signed message.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TakeOrder {
+    pub escrow: Pubkey,
+    pub take_amount: u64,
+    pub max_pay: u64,
+    pub min_receive: u64,
+    // Unix timestamp after which the order can no longer be landed
+    pub expiry: i64,
+    // Must be the taker's next nonce, see `TakerNonce`
+    pub nonce: u64,
+}
+
+impl TakeOrder {
+    // Keeps an order from passing as a signature over any other message
+    pub const DOMAIN: &'static [u8] = b"anchor_escrow:take_order:";
+
+    pub fn message(&self) -> Result<Vec<u8>> {
+        let mut message = Self::DOMAIN.to_vec();
+        self.serialize(&mut message)?;
+        Ok(message)
+    }
+}
+
+// Take landed by a relayer. The taker signs the order in an ed25519 instruction
+// placed right before this one and has approved taker_delegate on taker_ata_b.
+#[derive(Accounts)]
+#[instruction(order: TakeOrder)]
+pub struct TakeWithSignature<'info> {
+    // Pays the transaction and any accounts created
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    pub taker: SystemAccount<'info>,
+    /// CHECK: signs for taker_ata_b as its approved delegate, holds no data
+    #[account(
+        seeds = [b"taker_delegate", taker.key().as_ref()],
+        bump,
+    )]
+    pub taker_delegate: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        seeds = [b"taker_nonce", taker.key().as_ref()],
+        bump,
+        space = 8 + TakerNonce::INIT_SPACE,
+    )]
+    pub taker_nonce: Box<Account<'info, TakerNonce>>,
+    pub maker: SystemAccount<'info>,
+    #[account(mut, address = escrow.rent_payer)]
+    pub rent_payer: SystemAccount<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program = token_program)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program,
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Receives whatever the last fill leaves in the vault, see `residual::sweep`
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = maker,
+        token::token_program = token_program,
+    )]
+    pub maker_ata_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Box<Account<'info, Config>>,
+    /// CHECK: only the owner of treasury_ata_b, pinned to the config
+    #[account(address = config.treasury @ ErrorCode::InvalidTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program,
+    )]
+    pub treasury_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Closed to rent_payer by the fill that takes the last of the deposit
+    #[account(
+        mut,
+        address = order.escrow @ ErrorCode::InvalidEscrowAccount,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        constraint = escrow.accepts(&mint_b.key()) @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+    // Required for escrows counted in the maker's registry, see `Escrow::registered`
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Box<Account<'info, MakerRegistry>>>,
+    // Required for escrows that report to the maker's stats, see `Escrow::stats`
+    #[account(
+        mut,
+        seeds = [b"maker_stats", maker.key().as_ref()],
+        bump = maker_stats.bump,
+    )]
+    pub maker_stats: Option<Box<Account<'info, MakerStats>>>,
+    /// CHECK: the instructions sysvar, read for the taker's ed25519 signature
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeWithSignature<'info> {
+    pub fn check_order(&self, order: &TakeOrder) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < order.expiry, ErrorCode::OrderExpired);
+        ed25519::verify(&self.instructions, self.taker.key, &order.message()?)?;
+
+        let escrow = &self.escrow;
+        escrow.require_active()?;
+        escrow.require_not_paused()?;
+        require!(now >= escrow.unlock_at, ErrorCode::WaitingTimeNotElapsed);
+        require!(!escrow.is_expired(now), ErrorCode::EscrowExpired);
+        if let Some(taker) = escrow.taker {
+            require_keys_eq!(self.taker.key(), taker, ErrorCode::UnauthorizedTaker);
+        }
+        require!(
+            !escrow.needs_take() && escrow.delivers_at_take(),
+            ErrorCode::SignedTakeUnsupported
+        );
+        require!(
+            !self.taker_ata_a.is_frozen() && !self.taker_ata_b.is_frozen(),
+            ErrorCode::TakerAccountFrozen
+        );
+        require!(!self.maker_ata_b.is_frozen(), ErrorCode::MakerAccountFrozen);
+        Ok(())
+    }
+
+    pub fn use_nonce(&mut self, nonce: u64, bumps: &TakeWithSignatureBumps) -> Result<()> {
+        let taker_nonce = &mut self.taker_nonce;
+        require!(nonce == taker_nonce.next_nonce, ErrorCode::NonceNotNext);
+        if taker_nonce.taker == Pubkey::default() {
+            taker_nonce.taker = self.taker.key();
+            taker_nonce.bump = bumps.taker_nonce;
+        }
+        taker_nonce.next_nonce = nonce.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Fills the order like Take, returning the mint_b paid and the mint_a received.
+    pub fn fill(
+        &mut self,
+        order: &TakeOrder,
+        hook_accounts: &[AccountInfo<'info>],
+        bumps: &TakeWithSignatureBumps,
+    ) -> Result<(u64, u64)> {
+        let owed = self
+            .escrow
+            .owed_for(&self.mint_b.key(), order.take_amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let fill = self.escrow.fill_for(owed)?;
+        let payment = self.escrow.payment_for(order.take_amount, fill)?;
+        require!(
+            payment <= order.max_pay && fill >= order.min_receive,
+            ErrorCode::SlippageExceeded
+        );
+
+        self.deposit(payment, bumps)?;
+        self.withdraw(fill, hook_accounts)?;
+        Ok((payment, fill))
+    }
+
+    // The delegate moves the payment out of taker_ata_b, less the protocol fee
+    fn deposit(&mut self, payment: u64, bumps: &TakeWithSignatureBumps) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"taker_delegate",
+            self.taker.key.as_ref(),
+            &[bumps.taker_delegate],
+        ]];
+
+        let fee = self.config.fee_for(payment).ok_or(ErrorCode::Overflow)?;
+        if fee > 0 {
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: self.taker_ata_b.to_account_info(),
+                to: self.treasury_ata_b.to_account_info(),
+                authority: self.taker_delegate.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+            transfer_checked(cpi_ctx, fee, self.mint_b.decimals)?;
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            to: self.maker_ata_b.to_account_info(),
+            authority: self.taker_delegate.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_ctx, payment - fee, self.mint_b.decimals)?;
+
+        // As on Take, a curve premium may count for more than is left
+        let owed = self
+            .escrow
+            .owed_for(&self.mint_b.key(), payment)
+            .ok_or(ErrorCode::Overflow)?;
+        self.escrow.remaining_receive = self.escrow.remaining_receive.saturating_sub(owed);
+        Ok(())
+    }
+
+    fn withdraw(&mut self, fill: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let filled = self.escrow.record_fill(fill)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.escrow.maker_seed(),
+            &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.taker_ata_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        hook::transfer_checked(cpi_context, fill, self.mint_a.decimals, hook_accounts)?;
+
+        if !filled {
+            return Ok(());
+        }
+
+        residual::sweep(
+            &self.vault,
+            self.maker_ata_a.as_deref(),
+            self.escrow.to_account_info(),
+            &self.mint_a,
+            self.token_program.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.rent_payer.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        if self.escrow.registered {
+            let registry = self
+                .maker_registry
+                .as_mut()
+                .ok_or(ErrorCode::MakerRegistryRequired)?;
+            registry.remove(&self.escrow.key());
+        }
+        self.escrow.close(self.rent_payer.to_account_info())
+    }
+
+    /// Adds the fill to the maker's stats, when the escrow reports to them.
+    pub fn report(&mut self, payment: u64, fill: u64) -> Result<()> {
+        if self.escrow.stats.is_none() {
+            return Ok(());
+        }
+        let stats = self
+            .maker_stats
+            .as_mut()
+            .ok_or(ErrorCode::MakerStatsRequired)?;
+        stats.add_volume(self.mint_a.key(), fill);
+        stats.add_volume(self.mint_b.key(), payment);
+        if self.escrow.status == EscrowStatus::Closed {
+            stats.escrows_filled = stats.escrows_filled.saturating_add(1);
+        }
+        Ok(())
+    }
+}
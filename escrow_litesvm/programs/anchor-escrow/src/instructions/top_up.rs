@@ -0,0 +1,119 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use math::Rounding;
+
+use crate::{error::ErrorCode, hook, native, state::Escrow};
+
+#[derive(Accounts)]
+pub struct TopUp<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    // Not needed when mint_a is native SOL, the top-up is wrapped from the maker's lamports
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TopUp<'info> {
+    /// Adds `amount` to the remaining deposit. What is owed for it becomes `receive`
+    /// when given, otherwise it grows in proportion, rounded up in the maker's favour.
+    /// Unit-priced escrows always owe the unit cost.
+    pub fn update_terms(&mut self, amount: u64, receive: Option<u64>) -> Result<()> {
+        require!(amount > 0, ErrorCode::DepositTooSmall);
+
+        let escrow = &mut self.escrow;
+        let remaining_deposit = escrow
+            .remaining_deposit
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        let remaining_receive = match (escrow.unit_price, receive) {
+            (Some(unit_price), None) => unit_price.cost(remaining_deposit, Rounding::Up),
+            (Some(_), Some(_)) => return err!(ErrorCode::InvalidUnitPrice),
+            (None, Some(receive)) => Some(receive),
+            // A drained standing offer has no remaining pair to grow
+            (None, None) if escrow.remaining_deposit == 0 => {
+                return err!(ErrorCode::ReceiveRequired)
+            }
+            (None, None) => math::mul_div(
+                escrow.remaining_receive,
+                remaining_deposit,
+                escrow.remaining_deposit,
+                Rounding::Up,
+            ),
+        }
+        .ok_or(ErrorCode::Overflow)?;
+
+        escrow.set_remaining_receive(remaining_receive)?;
+        escrow.remaining_deposit = remaining_deposit;
+        Ok(())
+    }
+
+    /// Moves `amount` into the vault and returns what arrived, which is less for
+    /// Token-2022 mints with a transfer fee.
+    pub fn deposit(&mut self, amount: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<u64> {
+        let before = self.vault.amount;
+
+        if native::is_native_mint(&self.mint_a.key()) {
+            native::wrap(
+                self.maker.to_account_info(),
+                self.vault.to_account_info(),
+                amount,
+                self.system_program.to_account_info(),
+                self.token_program.to_account_info(),
+            )?;
+        } else {
+            let maker_ata_a = self
+                .maker_ata_a
+                .as_ref()
+                .ok_or(ErrorCode::MakerTokenAccountRequired)?;
+
+            let cpi_program = self.token_program.to_account_info();
+
+            let cpi_accounts = TransferChecked {
+                from: maker_ata_a.to_account_info(),
+                to: self.vault.to_account_info(),
+                authority: self.maker.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+            };
+
+            let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+            hook::transfer_checked(cpi_ctx, amount, self.mint_a.decimals, hook_accounts)?;
+        }
+
+        self.vault.reload()?;
+        Ok(self.vault.amount - before)
+    }
+}
@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    error::ErrorCode,
+    events::{self, MakerAuthorityTransferred},
+    state::{Config, Escrow, MakerRegistry},
+};
+
+#[event_cpi]
+#[derive(Accounts)]
+#[instruction(new_maker: Pubkey)]
+pub struct TransferMakerAuthority<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    // Required for escrows counted in the maker's registry, see `Escrow::registered`
+    #[account(
+        mut,
+        seeds = [b"maker_registry", maker.key().as_ref()],
+        bump = maker_registry.bump,
+    )]
+    pub maker_registry: Option<Account<'info, MakerRegistry>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    // The escrow counts towards the new maker's open escrows from now on
+    #[account(
+        init_if_needed,
+        payer = maker,
+        seeds = [b"maker_registry", new_maker.as_ref()],
+        bump,
+        space = 8 + MakerRegistry::INIT_SPACE,
+    )]
+    pub new_maker_registry: Account<'info, MakerRegistry>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TransferMakerAuthority<'info> {
+    /// Hands the escrow to `new_maker`, who then receives its proceeds and holds its
+    /// refund and update rights. The escrow keeps its address and rent payer, moves
+    /// from the current maker's registry to the new maker's and leaves their stats.
+    pub fn transfer(
+        &mut self,
+        new_maker: Pubkey,
+        bumps: &TransferMakerAuthorityBumps,
+    ) -> Result<()> {
+        let maker = self.maker.key();
+        require_keys_neq!(new_maker, maker, ErrorCode::InvalidNewMaker);
+
+        if self.escrow.registered {
+            let registry = self
+                .maker_registry
+                .as_mut()
+                .ok_or(ErrorCode::MakerRegistryRequired)?;
+            registry.remove(&self.escrow.key());
+        }
+
+        // As `Make::register`, against the new maker's limit
+        let registry = &mut self.new_maker_registry;
+        let max_open = self.config.max_open_escrows_per_maker;
+        require!(
+            max_open == 0 || registry.open_escrows < max_open,
+            ErrorCode::TooManyOpenEscrows
+        );
+        if registry.maker == Pubkey::default() {
+            registry.maker = new_maker;
+            registry.bump = bumps.new_maker_registry;
+        }
+        registry.add(self.escrow.key(), self.escrow.seed)?;
+
+        let escrow = &mut self.escrow;
+        escrow.made_by.get_or_insert(maker);
+        escrow.maker = new_maker;
+        escrow.registered = true;
+        escrow.stats = None;
+
+        events::emit_cpi(
+            &MakerAuthorityTransferred {
+                escrow: escrow.key(),
+                from: maker,
+                to: new_maker,
+            },
+            &self.event_authority,
+            bumps.event_authority,
+        )
+    }
+}
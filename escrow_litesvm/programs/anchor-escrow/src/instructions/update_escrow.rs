@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    error::ErrorCode,
+    state::{Config, Escrow, EscrowStatus},
+};
+
+#[derive(Accounts)]
+pub struct UpdateEscrow<'info> {
+    pub maker: Signer<'info>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> UpdateEscrow<'info> {
+    // Terms can only change while nothing has been taken from the vault
+    pub fn check_untouched(&self) -> Result<()> {
+        let escrow = &self.escrow;
+        require!(
+            escrow.status == EscrowStatus::Open
+                && escrow.remaining_receive == escrow.receive
+                && self.vault.amount == escrow.remaining_deposit,
+            ErrorCode::EscrowPartiallyFilled
+        );
+        Ok(())
+    }
+
+    /// Applies the same bounds as Make, with times measured from now.
+    pub fn update_terms(&mut self, receive: u64, waiting_time: i64, expiry: i64) -> Result<()> {
+        require!(waiting_time >= 0, ErrorCode::NegativeWaitingTime);
+        require!(
+            waiting_time <= self.config.max_waiting_time,
+            ErrorCode::WaitingTimeTooLong
+        );
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let unlock_at = current_time
+            .checked_add(waiting_time)
+            .ok_or(ErrorCode::Overflow)?;
+
+        if expiry != 0 {
+            let horizon = current_time
+                .checked_add(self.config.max_expiry_horizon)
+                .ok_or(ErrorCode::Overflow)?;
+            require!(
+                expiry > current_time && expiry > unlock_at && expiry <= horizon,
+                ErrorCode::InvalidExpiry
+            );
+        }
+
+        let escrow = &mut self.escrow;
+        // An earlier expiry would let anyone close the escrow while refunds are locked
+        require!(
+            expiry == 0 || expiry >= escrow.no_refund_before,
+            ErrorCode::RefundLocked
+        );
+        // As at Make, so an expiry never cuts the cancellation window short
+        require!(
+            escrow.cancel_fee == 0 || expiry == 0 || expiry >= escrow.cancel_fee_until,
+            ErrorCode::InvalidCancelFee
+        );
+        escrow.set_unlock_at(unlock_at)?;
+        // A fixed receive replaces any unit price and its curve
+        escrow.receive = receive;
+        escrow.remaining_receive = receive;
+        escrow.unit_price = None;
+        escrow.curve_bps = 0;
+        escrow.expiry = expiry;
+
+        Ok(())
+    }
+}
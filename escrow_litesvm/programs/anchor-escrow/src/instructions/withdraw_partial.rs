@@ -0,0 +1,115 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use math::Rounding;
+
+use crate::{error::ErrorCode, hook, native, state::Escrow};
+
+#[derive(Accounts)]
+pub struct WithdrawPartial<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(mint::token_program = token_program)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    // Any mint_a token account owned by the maker, not only the canonical ATA
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = maker,
+        token::token_program = token_program,
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a @ ErrorCode::InvalidMint,
+        seeds = [
+            b"escrow",
+            escrow.maker_seed(),
+            escrow.seed.to_le_bytes().as_ref(),
+            escrow.mint_a_seed(),
+            escrow.mint_b_seed(),
+        ],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        address = escrow.vault_address(&escrow.key(), &token_program.key()) @ ErrorCode::InvalidVault,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawPartial<'info> {
+    /// Takes `amount` off the remaining deposit, shrinking what is owed in proportion,
+    /// rounded up in the maker's favour. Unit-priced escrows owe the unit cost of what
+    /// is left. Withdrawing everything is a Refund.
+    pub fn update_terms(&mut self, amount: u64) -> Result<()> {
+        let escrow = &mut self.escrow;
+        require!(
+            amount > 0 && amount < escrow.remaining_deposit,
+            ErrorCode::InvalidWithdrawAmount
+        );
+        // Otherwise the maker could empty all but a unit without forfeiting the fee
+        require!(
+            !escrow.cancel_fee_at_stake(Clock::get()?.unix_timestamp),
+            ErrorCode::CancelFeeAtStake
+        );
+
+        let remaining_deposit = escrow.remaining_deposit - amount;
+        let remaining_receive = match escrow.unit_price {
+            Some(unit_price) => unit_price.cost(remaining_deposit, Rounding::Up),
+            None => math::mul_div(
+                escrow.remaining_receive,
+                remaining_deposit,
+                escrow.remaining_deposit,
+                Rounding::Up,
+            ),
+        }
+        .ok_or(ErrorCode::Overflow)?;
+
+        escrow.set_remaining_receive(remaining_receive)?;
+        escrow.remaining_deposit = remaining_deposit;
+        Ok(())
+    }
+
+    pub fn withdraw(&mut self, amount: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            self.escrow.maker_seed(),
+            &self.escrow.seed.to_le_bytes()[..],
+            self.escrow.mint_a_seed(),
+            self.escrow.mint_b_seed(),
+            &[self.escrow.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        hook::transfer_checked(cpi_context, amount, self.mint_a.decimals, hook_accounts)?;
+
+        if native::is_native_mint(&self.mint_a.key()) {
+            native::unwrap(
+                self.maker_ata_a.to_account_info(),
+                self.maker.to_account_info(),
+                self.token_program.to_account_info(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
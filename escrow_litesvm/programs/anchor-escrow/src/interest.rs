@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    extension::{
+        interest_bearing_mint::InterestBearingConfig, BaseStateWithExtensions, StateWithExtensions,
+    },
+    state::Mint,
+};
+
+use crate::error::ErrorCode;
+
+/// The mint's interest-bearing config, None for mints without the extension.
+pub fn config(mint: &AccountInfo) -> Result<Option<InterestBearingConfig>> {
+    let data = mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(mint.get_extension::<InterestBearingConfig>().ok().copied())
+}
+
+/// UI amount `amount` is worth at `now`, scaled back up by the decimals so it
+/// stays in base units. Rounded to the nearest unit like the extension's own
+/// conversions, which go through floating point.
+pub fn ui_units(
+    config: &InterestBearingConfig,
+    amount: u64,
+    decimals: u8,
+    now: i64,
+) -> Result<u64> {
+    let ui_amount = config
+        .amount_to_ui_amount(amount, decimals, now)
+        .and_then(|ui_amount| ui_amount.parse::<f64>().ok())
+        .ok_or(ErrorCode::Overflow)?;
+    let units = (ui_amount * 10f64.powi(decimals as i32)).round();
+    require!(units >= 0.0 && units < u64::MAX as f64, ErrorCode::Overflow);
+    Ok(units as u64)
+}
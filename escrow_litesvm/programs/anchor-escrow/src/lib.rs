@@ -3,9 +3,23 @@
 
 use anchor_lang::prelude::*;
 
+mod balance;
+mod ed25519;
+mod error;
+mod events;
+mod hook;
 mod instructions;
-mod state;
+mod interest;
+mod memo;
+pub mod merkle;
+pub mod metadata;
+mod native;
+#[cfg(feature = "oracle")]
+pub mod oracle;
+mod residual;
+pub mod state;
 mod tests;
+pub mod whitelist;
 
 use instructions::*;
 
@@ -15,25 +29,552 @@ declare_id!("FircrADQ2wgGuvpm8qneNCfKM7o5zoHTWnDQxngpTQ3J");
 pub mod anchor_escrow {
     use super::*;
 
-    pub fn make(
-        ctx: Context<Make>,
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        min_deposit: u64,
+        max_waiting_time: i64,
+        max_expiry_horizon: i64,
+        min_decimals: u8,
+        max_decimals: u8,
+        fee_bps: u16,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.init_config(
+            min_deposit,
+            max_waiting_time,
+            max_expiry_horizon,
+            min_decimals,
+            max_decimals,
+            fee_bps,
+            treasury,
+            &ctx.bumps,
+        )
+    }
+
+    pub fn update_fee(ctx: Context<UpdateFee>, fee_bps: u16, treasury: Pubkey) -> Result<()> {
+        ctx.accounts.config.set_fee(fee_bps, treasury)
+    }
+
+    pub fn set_fee_tiers(ctx: Context<UpdateFee>, fee_tiers: Vec<state::FeeTier>) -> Result<()> {
+        ctx.accounts.config.set_fee_tiers(fee_tiers)
+    }
+
+    pub fn set_referral_fee(ctx: Context<UpdateFee>, referral_bps: u16) -> Result<()> {
+        ctx.accounts.config.set_referral_fee(referral_bps)
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.config.paused = paused;
+        Ok(())
+    }
+
+    pub fn set_limits(
+        ctx: Context<SetLimits>,
+        min_deposit: u64,
+        max_open_escrows_per_maker: u32,
+    ) -> Result<()> {
+        ctx.accounts.config.set_limits(min_deposit, max_open_escrows_per_maker);
+        Ok(())
+    }
+
+    pub fn set_admin(ctx: Context<SetAdmin>) -> Result<()> {
+        ctx.accounts.config.admin = ctx.accounts.new_admin.key();
+        Ok(())
+    }
+
+    pub fn init_maker_stats(ctx: Context<InitMakerStats>) -> Result<()> {
+        ctx.accounts.init_stats(&ctx.bumps)
+    }
+
+    /// Remaining accounts are forwarded to mint_a's transfer hook, if it has one.
+    /// A `memo` is logged through the SPL Memo program, signed by the maker.
+    pub fn make<'info>(
+        ctx: Context<'_, '_, '_, 'info, Make<'info>>,
+        seed: u64,
+        args: MakeArgs,
+    ) -> Result<()> {
+        let fill_terms = args.fill_terms.unwrap_or_default();
+        let refund_terms = args.refund_terms.unwrap_or_default();
+        let settlement_terms = args.settlement_terms.unwrap_or_default();
+
+        ctx.accounts.config.require_not_paused()?;
+        ctx.accounts.check_bounds(args.deposit, args.waiting_time)?;
+        ctx.accounts.check_expiry(args.waiting_time, args.expiry)?;
+        ctx.accounts
+            .check_freeze_authority(args.require_no_freeze_authority)?;
+        ctx.accounts.init_escrow(
+            seed,
+            args.deposit,
+            args.receive,
+            args.waiting_time,
+            args.expiry,
+            &ctx.bumps,
+        )?;
+        ctx.accounts.restrict_takers(args.taker, args.taker_root);
+        ctx.accounts.send_rent_to(args.rent_recipient);
+        ctx.accounts.price_with_oracle(args.oracle)?;
+        ctx.accounts
+            .price_per_unit(args.deposit, args.unit_pricing)?;
+        ctx.accounts.lock_refunds(refund_terms.no_refund_before)?;
+        ctx.accounts.accept_payment_options(args.payment_options)?;
+        ctx.accounts.quote_in_ui_amount(args.receive_in_ui_amount)?;
+        ctx.accounts.set_label(args.label)?;
+        ctx.accounts.set_hashlock(settlement_terms.hashlock)?;
+        ctx.accounts.set_arbiter(settlement_terms.arbiter)?;
+        ctx.accounts
+            .set_review_period(settlement_terms.review_period)?;
+        ctx.accounts
+            .set_vesting_duration(settlement_terms.vesting_duration)?;
+        ctx.accounts.keep_standing(fill_terms.standing)?;
+        ctx.accounts.set_min_fill(fill_terms.min_fill)?;
+        ctx.accounts
+            .commit_cancel_fee(refund_terms.cancel_fee, refund_terms.cancel_fee_window)?;
+        ctx.accounts.gate_on_whitelist(args.whitelisted_takers);
+        ctx.accounts.extend_on_fill(fill_terms.fill_grace_period)?;
+        ctx.accounts.escrow.require_valid_gift()?;
+        ctx.accounts
+            .deposit(args.deposit, ctx.remaining_accounts, &ctx.bumps)?;
+        ctx.accounts.log_memo(args.memo)
+    }
+
+    pub fn make_nft<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeNft<'info>>,
+        seed: u64,
+        receive: u64,
+        waiting_time: i64,
+        expiry: i64,
+        collection: Option<Pubkey>,
+        enforce_royalties: bool,
+    ) -> Result<()> {
+        ctx.accounts.make.config.require_not_paused()?;
+        ctx.accounts.check_nft(collection)?;
+        let make = &mut ctx.accounts.make;
+        make.check_bounds(1, waiting_time)?;
+        make.check_expiry(waiting_time, expiry)?;
+        make.init_escrow(seed, 1, receive, waiting_time, expiry, &ctx.bumps.make)?;
+        make.escrow.require_valid_gift()?;
+        make.deposit(1, ctx.remaining_accounts, &ctx.bumps.make)?;
+        ctx.accounts.set_royalty_enforcement(enforce_royalties);
+        Ok(())
+    }
+
+    /// Make with `seed` required to be the maker's next SeedCounter value, 0 before
+    /// their first MakeAuto. Offers fired back to back use consecutive seeds.
+    pub fn make_auto<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeAuto<'info>>,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+        waiting_time: i64,
+        expiry: i64,
+    ) -> Result<()> {
+        ctx.accounts.make.config.require_not_paused()?;
+        ctx.accounts.use_seed(seed, &ctx.bumps)?;
+        let make = &mut ctx.accounts.make;
+        make.check_bounds(deposit, waiting_time)?;
+        make.check_expiry(waiting_time, expiry)?;
+        make.init_escrow(
+            seed,
+            deposit,
+            receive,
+            waiting_time,
+            expiry,
+            &ctx.bumps.make,
+        )?;
+        make.escrow.require_valid_gift()?;
+        make.deposit(deposit, ctx.remaining_accounts, &ctx.bumps.make)
+    }
+
+    pub fn make_delegated<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeDelegated<'info>>,
         seed: u64,
         deposit: u64,
         receive: u64,
         waiting_time: i64,
+        expiry: i64,
+    ) -> Result<()> {
+        ctx.accounts.config.require_not_paused()?;
+        ctx.accounts.check_delegation(deposit)?;
+        ctx.accounts.check_bounds(deposit, waiting_time, expiry)?;
+        ctx.accounts.init_escrow(
+            seed,
+            deposit,
+            receive,
+            waiting_time,
+            expiry,
+            &ctx.bumps,
+        )?;
+        ctx.accounts.escrow.require_valid_gift()?;
+        ctx.accounts.deposit(deposit, ctx.remaining_accounts, &ctx.bumps)
+    }
+
+    pub fn refund<'info>(
+        ctx: Context<'_, '_, '_, 'info, Refund<'info>>,
+        memo: Option<String>,
+    ) -> Result<()> {
+        // Once expired the maker can always get the deposit back, unless it is up for
+        // auction or under dispute
+        let escrow = &ctx.accounts.escrow;
+        let now = Clock::get()?.unix_timestamp;
+        let held = matches!(
+            escrow.status,
+            state::EscrowStatus::Locked | state::EscrowStatus::Disputed
+        );
+        if held || !escrow.is_expired(now) {
+            escrow.require_active()?;
+        }
+        escrow.require_refundable(now)?;
+        ctx.accounts.refund_and_close_vault(ctx.remaining_accounts, &ctx.bumps)?;
+        ctx.accounts.log_memo(memo)
+    }
+
+    /// With royalties enforced, remaining accounts are the NFT's metadata account
+    /// followed by a mint_b token account for each creator with a non-zero share, then
+    /// any accounts mint_a's transfer hook needs.
+    /// `max_pay` and `min_receive` bound the trade against price moves since simulation.
+    /// For unit-priced escrows `take_amount` is a budget, only the cost of the fill is paid.
+    pub fn take<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        take_amount: u64,
+        proof: Vec<[u8; 32]>,
+        max_pay: u64,
+        min_receive: u64,
+        memo: Option<String>,
+        preimage: Option<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.config.require_not_paused()?;
+        ctx.accounts.escrow.require_active()?;
+        ctx.accounts.escrow.require_not_paused()?;
+        ctx.accounts.check_waiting_time()?;
+        ctx.accounts.check_expiry()?;
+        ctx.accounts.check_commit_free()?;
+        ctx.accounts.check_delivers_at_take()?;
+        ctx.accounts.check_taker_allowed(&proof)?;
+        ctx.accounts.check_taker_whitelisted(ctx.remaining_accounts)?;
+        ctx.accounts.check_preimage(preimage)?;
+        ctx.accounts.check_taker_accounts()?;
+        ctx.accounts.check_recipient_accounts()?;
+        ctx.accounts.reprice()?;
+        let owed = ctx.accounts.owed(take_amount)?;
+        let fill = ctx.accounts.fill_amount(owed)?;
+        let payment = ctx.accounts.payment(take_amount, fill)?;
+        ctx.accounts.check_slippage(payment, fill, max_pay, min_receive)?;
+        ctx.accounts.deposit(payment, ctx.remaining_accounts, &ctx.bumps)?;
+        ctx.accounts.withdraw(fill, ctx.remaining_accounts)?;
+        ctx.accounts.report(payment, fill)?;
+        ctx.accounts.emit_taken(payment, fill, &ctx.bumps)?;
+        ctx.accounts.log_memo(memo)?;
+        ctx.accounts.unwrap_native()
+    }
+
+    /// Take that names the mint_a received instead of the mint_b paid, see `take` for
+    /// the other arguments. The cost of `fill` is rounded up and bounded by `max_pay`.
+    pub fn take_exact_out<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        fill: u64,
+        proof: Vec<[u8; 32]>,
+        max_pay: u64,
+        memo: Option<String>,
+        preimage: Option<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.config.require_not_paused()?;
+        ctx.accounts.escrow.require_active()?;
+        ctx.accounts.escrow.require_not_paused()?;
+        ctx.accounts.check_waiting_time()?;
+        ctx.accounts.check_expiry()?;
+        ctx.accounts.check_commit_free()?;
+        ctx.accounts.check_delivers_at_take()?;
+        ctx.accounts.check_taker_allowed(&proof)?;
+        ctx.accounts.check_taker_whitelisted(ctx.remaining_accounts)?;
+        ctx.accounts.check_preimage(preimage)?;
+        ctx.accounts.check_taker_accounts()?;
+        ctx.accounts.check_recipient_accounts()?;
+        ctx.accounts.reprice()?;
+        let payment = ctx.accounts.cost(fill)?;
+        ctx.accounts.check_slippage(payment, fill, max_pay, fill)?;
+        ctx.accounts.deposit(payment, ctx.remaining_accounts, &ctx.bumps)?;
+        ctx.accounts.withdraw(fill, ctx.remaining_accounts)?;
+        ctx.accounts.report(payment, fill)?;
+        ctx.accounts.emit_taken(payment, fill, &ctx.bumps)?;
+        ctx.accounts.log_memo(memo)?;
+        ctx.accounts.unwrap_native()
+    }
+
+    /// Read-only preview of `take` for clients to simulate: what paying `take_amount`
+    /// would fill and cost right now, returned as the instruction's return data.
+    pub fn quote(ctx: Context<Quote>, take_amount: u64) -> Result<TakeQuote> {
+        ctx.accounts.config.require_not_paused()?;
+        ctx.accounts.check_escrow()?;
+        ctx.accounts.reprice()?;
+        ctx.accounts.quote(take_amount)
+    }
+
+    pub fn commit_take(ctx: Context<CommitTake>, hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.escrow.require_active()?;
+        ctx.accounts.commit(hash, &ctx.bumps)
+    }
+
+    /// Take for escrows with a reveal window, see `take` for the arguments.
+    #[allow(clippy::too_many_arguments)]
+    pub fn reveal_take<'info>(
+        ctx: Context<'_, '_, '_, 'info, RevealTake<'info>>,
+        salt: [u8; 32],
+        take_amount: u64,
+        proof: Vec<[u8; 32]>,
+        max_pay: u64,
+        min_receive: u64,
+        memo: Option<String>,
+        preimage: Option<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.check_reveal(&salt)?;
+        let take = &mut ctx.accounts.take;
+        take.config.require_not_paused()?;
+        take.escrow.require_active()?;
+        take.escrow.require_not_paused()?;
+        take.check_waiting_time()?;
+        take.check_expiry()?;
+        take.check_delivers_at_take()?;
+        take.check_taker_allowed(&proof)?;
+        take.check_taker_whitelisted(ctx.remaining_accounts)?;
+        take.check_preimage(preimage)?;
+        take.check_taker_accounts()?;
+        take.check_recipient_accounts()?;
+        take.reprice()?;
+        let owed = take.owed(take_amount)?;
+        let fill = take.fill_amount(owed)?;
+        let payment = take.payment(take_amount, fill)?;
+        take.check_slippage(payment, fill, max_pay, min_receive)?;
+        take.deposit(payment, ctx.remaining_accounts, &ctx.bumps.take)?;
+        take.withdraw(fill, ctx.remaining_accounts)?;
+        take.report(payment, fill)?;
+        take.emit_taken(payment, fill, &ctx.bumps.take)?;
+        take.log_memo(memo)?;
+        take.unwrap_native()?;
+        ctx.accounts.close_commitment()
+    }
+
+    pub fn take_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TakeMany<'info>>,
+        take_amounts: Vec<u64>,
+        max_pay: u64,
+        min_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.config.require_not_paused()?;
+        let (paid, received) = ctx
+            .accounts
+            .take_all(&take_amounts, ctx.remaining_accounts)?;
+        ctx.accounts
+            .check_slippage(paid, received, max_pay, min_receive)
+    }
+
+    /// Remaining accounts are forwarded to mint_a's transfer hook, if it has one.
+    /// `max_pay` and `min_receive` bound the trade as on Take.
+    pub fn take_vested<'info>(
+        ctx: Context<'_, '_, '_, 'info, TakeVested<'info>>,
+        take_amount: u64,
+        max_pay: u64,
+        min_receive: u64,
     ) -> Result<()> {
+        ctx.accounts.config.require_not_paused()?;
+        ctx.accounts.check_escrow()?;
+        let (payment, fill) = ctx.accounts.pay(take_amount)?;
         ctx.accounts
-            .init_escrow(seed, receive, waiting_time, &ctx.bumps)?;
-        ctx.accounts.deposit(deposit)
+            .check_slippage(payment, fill, max_pay, min_receive)?;
+        let filled = ctx
+            .accounts
+            .lock_fill(fill, ctx.remaining_accounts, &ctx.bumps)?;
+        ctx.accounts.report(payment, fill, filled)?;
+        if filled {
+            ctx.accounts.close_filled()?;
+        }
+        Ok(())
+    }
+
+    /// Remaining accounts are forwarded to mint_a's transfer hook, if it has one.
+    pub fn claim_vested<'info>(ctx: Context<'_, '_, '_, 'info, ClaimVested<'info>>) -> Result<()> {
+        ctx.accounts.claim(ctx.remaining_accounts)
+    }
+
+    pub fn take_with_signature<'info>(
+        ctx: Context<'_, '_, '_, 'info, TakeWithSignature<'info>>,
+        order: TakeOrder,
+    ) -> Result<()> {
+        ctx.accounts.config.require_not_paused()?;
+        ctx.accounts.check_order(&order)?;
+        ctx.accounts.use_nonce(order.nonce, &ctx.bumps)?;
+        let (payment, fill) = ctx
+            .accounts
+            .fill(&order, ctx.remaining_accounts, &ctx.bumps)?;
+        ctx.accounts.report(payment, fill)
+    }
+
+    pub fn cross<'info>(ctx: Context<'_, '_, '_, 'info, Cross<'info>>) -> Result<()> {
+        ctx.accounts.config.require_not_paused()?;
+        ctx.accounts.check_escrows()?;
+        let quote = CrossQuote::new(&ctx.accounts.escrow_x, &ctx.accounts.escrow_y)?;
+        ctx.accounts.settle(&quote, ctx.remaining_accounts)
+    }
+
+    pub fn close_expired<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseExpired<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.check_expired()?;
+        ctx.accounts.refund_and_close(ctx.remaining_accounts, &ctx.bumps)
+    }
+
+    pub fn update_escrow(
+        ctx: Context<UpdateEscrow>,
+        receive: u64,
+        waiting_time: i64,
+        expiry: i64,
+    ) -> Result<()> {
+        ctx.accounts.escrow.require_active()?;
+        ctx.accounts.check_untouched()?;
+        ctx.accounts.update_terms(receive, waiting_time, expiry)?;
+        ctx.accounts.escrow.require_valid_gift()
+    }
+
+    pub fn set_escrow_paused(ctx: Context<SetEscrowPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.escrow.require_active()?;
+        ctx.accounts.set_paused(paused, &ctx.bumps)
+    }
+
+    pub fn transfer_maker_authority(
+        ctx: Context<TransferMakerAuthority>,
+        new_maker: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.escrow.require_active()?;
+        ctx.accounts.transfer(new_maker, &ctx.bumps)
+    }
+
+    pub fn set_reveal_window(ctx: Context<SetRevealWindow>, reveal_window: u64) -> Result<()> {
+        ctx.accounts.escrow.require_active()?;
+        ctx.accounts.set_reveal_window(reveal_window)
+    }
+
+    pub fn set_waiting_time(ctx: Context<SetWaitingTime>, waiting_time: i64) -> Result<()> {
+        ctx.accounts.escrow.require_active()?;
+        ctx.accounts.set_waiting_time(waiting_time, &ctx.bumps)
+    }
+
+    pub fn top_up<'info>(
+        ctx: Context<'_, '_, '_, 'info, TopUp<'info>>,
+        amount: u64,
+        receive: Option<u64>,
+    ) -> Result<()> {
+        ctx.accounts.escrow.require_active()?;
+        let received = ctx.accounts.deposit(amount, ctx.remaining_accounts)?;
+        ctx.accounts.update_terms(received, receive)
+    }
+
+    pub fn withdraw_partial<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawPartial<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        escrow.require_active()?;
+        escrow.require_refundable(Clock::get()?.unix_timestamp)?;
+        ctx.accounts.update_terms(amount)?;
+        ctx.accounts.withdraw(amount, ctx.remaining_accounts)
+    }
+
+    pub fn split<'info>(
+        ctx: Context<'_, '_, '_, 'info, Split<'info>>,
+        seed: u64,
+        amount: u64,
+        taker: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.config.require_not_paused()?;
+        ctx.accounts.check_escrow(amount)?;
+        ctx.accounts.init_child(seed, amount, taker, &ctx.bumps)?;
+        ctx.accounts.child.require_valid_gift()?;
+        ctx.accounts.register(seed, &ctx.bumps)?;
+        ctx.accounts.move_deposit(amount, ctx.remaining_accounts)
+    }
+
+    pub fn merge<'info>(ctx: Context<'_, '_, '_, 'info, Merge<'info>>) -> Result<()> {
+        ctx.accounts.check_escrows()?;
+        ctx.accounts.deregister()?;
+        ctx.accounts.merge(ctx.remaining_accounts)
+    }
+
+    pub fn raise_dispute(ctx: Context<RaiseDispute>) -> Result<()> {
+        ctx.accounts.escrow.require_active()?;
+        ctx.accounts.raise(&ctx.bumps)
+    }
+
+    /// `taker_amount` of the vault goes to the designated taker and the rest back to
+    /// the maker. Remaining accounts are forwarded to mint_a's transfer hook.
+    pub fn resolve<'info>(
+        ctx: Context<'_, '_, '_, 'info, Resolve<'info>>,
+        taker_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.resolve(taker_amount, ctx.remaining_accounts, &ctx.bumps)
     }
 
-    pub fn refund(ctx: Context<Refund>) -> Result<()> {
+    pub fn claim_proceeds(ctx: Context<ClaimProceeds>) -> Result<()> {
+        ctx.accounts.claim_and_close_vault()
+    }
+
+    pub fn start_auction(ctx: Context<StartAuction>, end_at: i64, reserve: u64) -> Result<()> {
+        ctx.accounts.check_auctionable()?;
+        ctx.accounts.start_auction(end_at, reserve, &ctx.bumps)
+    }
+
+    pub fn bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        ctx.accounts.place_bid(amount, &ctx.bumps)
+    }
+
+    pub fn outbid_refund(ctx: Context<OutbidRefund>) -> Result<()> {
+        ctx.accounts.check_outbid()?;
         ctx.accounts.refund_and_close_vault()
     }
 
-    pub fn take(ctx: Context<Take>) -> Result<()> {
-        ctx.accounts.check_waiting_time()?;
-        ctx.accounts.deposit()?;
+    pub fn settle<'info>(ctx: Context<'_, '_, '_, 'info, Settle<'info>>) -> Result<()> {
+        ctx.accounts.check_ended()?;
+        ctx.accounts.settle(ctx.remaining_accounts)
+    }
+
+    pub fn deposit_payment(ctx: Context<DepositPayment>) -> Result<()> {
+        ctx.accounts.check_escrow()?;
+        ctx.accounts.deposit(&ctx.bumps)
+    }
+
+    /// Remaining accounts are forwarded to mint_a's transfer hook, if it has one.
+    pub fn settle_deposit<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleDeposit<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.check_review_over()?;
+        ctx.accounts.settle(ctx.remaining_accounts)
+    }
+
+    pub fn cancel_deposit(ctx: Context<CancelDeposit>) -> Result<()> {
+        ctx.accounts.cancel()
+    }
+
+    pub fn propose_counter_offer(
+        ctx: Context<ProposeCounterOffer>,
+        offer: u64,
+        request: u64,
+    ) -> Result<()> {
+        ctx.accounts.check_terms(offer, request)?;
+        ctx.accounts.propose(offer, request, &ctx.bumps)
+    }
+
+    pub fn accept_counter_offer<'info>(
+        ctx: Context<'_, '_, '_, 'info, AcceptCounterOffer<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.check_acceptable()?;
+        ctx.accounts.collect_offer()?;
+        ctx.accounts.deliver_request(ctx.remaining_accounts)
+    }
+
+    pub fn withdraw_counter_offer(ctx: Context<WithdrawCounterOffer>) -> Result<()> {
         ctx.accounts.withdraw_and_close_vault()
     }
+
+    pub fn migrate_escrow(ctx: Context<MigrateEscrow>) -> Result<()> {
+        ctx.accounts.migrate()
+    }
 }
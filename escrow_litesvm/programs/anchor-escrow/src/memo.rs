@@ -0,0 +1,36 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::invoke,
+    },
+};
+
+use crate::error::ErrorCode;
+
+pub const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+
+// The memo program charges compute per byte, this keeps the CPI well inside budget
+pub const MAX_MEMO_LEN: usize = 256;
+
+/// Logs `memo` through the SPL Memo program, signed by `signer` so indexers can
+/// attribute it to the flow's transfers. Nothing is stored. None logs nothing.
+pub fn log<'info>(
+    memo: Option<String>,
+    memo_program: Option<&UncheckedAccount<'info>>,
+    signer: AccountInfo<'info>,
+) -> Result<()> {
+    let Some(memo) = memo else {
+        return Ok(());
+    };
+    require!(memo.len() <= MAX_MEMO_LEN, ErrorCode::MemoTooLong);
+    let memo_program = memo_program.ok_or(ErrorCode::InvalidMemoProgram)?;
+
+    let instruction = Instruction {
+        program_id: MEMO_PROGRAM_ID,
+        accounts: vec![AccountMeta::new_readonly(signer.key(), true)],
+        data: memo.into_bytes(),
+    };
+    invoke(&instruction, &[signer, memo_program.to_account_info()])?;
+    Ok(())
+}
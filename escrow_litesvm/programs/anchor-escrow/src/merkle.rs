@@ -0,0 +1,23 @@
+use anchor_lang::{prelude::Pubkey, solana_program::hash::hashv};
+
+// Leaves and inner nodes are hashed under different prefixes so an inner node
+// can never be passed off as a leaf
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+pub fn leaf(taker: &Pubkey) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, taker.as_ref()]).to_bytes()
+}
+
+/// Siblings are ordered before hashing, so proofs carry no left/right flags.
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, first, second]).to_bytes()
+}
+
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], taker: &Pubkey) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf(taker), |node, sibling| hash_pair(&node, sibling));
+    computed == *root
+}
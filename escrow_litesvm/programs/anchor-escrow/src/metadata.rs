@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+pub const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// Account key Metaplex writes as the first byte of a v1 metadata account
+const METADATA_V1_KEY: u8 = 4;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Debug)]
+pub struct Creator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Debug)]
+pub struct Collection {
+    pub verified: bool,
+    pub key: Pubkey,
+}
+
+/// Leading fields of a Token Metadata account, enough to validate an NFT
+/// without pulling in the Metaplex crate. Fields after `collection` are ignored.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct Metadata {
+    pub key: u8,
+    pub update_authority: Pubkey,
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub seller_fee_basis_points: u16,
+    pub creators: Option<Vec<Creator>>,
+    pub primary_sale_happened: bool,
+    pub is_mutable: bool,
+    pub edition_nonce: Option<u8>,
+    pub token_standard: Option<u8>,
+    pub collection: Option<Collection>,
+}
+
+impl Metadata {
+    pub fn address(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+            &METADATA_PROGRAM_ID,
+        )
+        .0
+    }
+
+    /// Reads the metadata of `mint`, the caller checks the account address.
+    pub fn load(account: &AccountInfo, mint: &Pubkey) -> Result<Self> {
+        require_keys_eq!(
+            *account.owner,
+            METADATA_PROGRAM_ID,
+            ErrorCode::InvalidMetadata
+        );
+        let data = account.try_borrow_data()?;
+        let metadata =
+            Self::deserialize(&mut &data[..]).map_err(|_| error!(ErrorCode::InvalidMetadata))?;
+        require!(
+            metadata.key == METADATA_V1_KEY && metadata.mint == *mint,
+            ErrorCode::InvalidMetadata
+        );
+        Ok(metadata)
+    }
+
+    pub fn require_collection(&self, collection: &Pubkey) -> Result<()> {
+        match &self.collection {
+            Some(Collection { verified: true, key }) if key == collection => Ok(()),
+            _ => err!(ErrorCode::CollectionMismatch),
+        }
+    }
+}
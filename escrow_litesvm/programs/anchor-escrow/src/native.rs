@@ -0,0 +1,43 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::{
+    token::spl_token,
+    token_interface::{close_account, sync_native, CloseAccount, SyncNative},
+};
+
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID
+}
+
+/// Moves `amount` lamports into a wSOL account and credits them as tokens.
+pub fn wrap<'info>(
+    from: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+    amount: u64,
+    system_program: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+) -> Result<()> {
+    let cpi_accounts = Transfer {
+        from,
+        to: account.clone(),
+    };
+    transfer(CpiContext::new(system_program, cpi_accounts), amount)?;
+
+    sync_native(CpiContext::new(token_program, SyncNative { account }))
+}
+
+/// Closes a wSOL account so its balance and rent come back to the owner as lamports.
+pub fn unwrap<'info>(
+    account: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+) -> Result<()> {
+    let cpi_accounts = CloseAccount {
+        account,
+        destination: owner.clone(),
+        authority: owner,
+    };
+    close_account(CpiContext::new(token_program, cpi_accounts))
+}
@@ -0,0 +1,111 @@
+//! Pyth pull-oracle prices for escrows priced at Take instead of by a fixed `receive`.
+//!
+//! `PriceUpdateV2` accounts are mirrored here, like Token Metadata in
+//! `crate::metadata`, so the program does not depend on the Pyth SDK. Only
+//! fully verified updates are accepted. Compiled with the `oracle` feature,
+//! without it Make rejects a price feed with `OracleDisabled`.
+
+use anchor_lang::prelude::*;
+
+use math::{Rounding, BPS_DENOMINATOR};
+
+use crate::error::ErrorCode;
+
+pub const PYTH_RECEIVER_PROGRAM_ID: Pubkey = pubkey!("rec5EKMGg6MxZYaMdyBfgwp4d5rB9T1VQH5pJv5LtFJ");
+
+// Anchor discriminator of the receiver's PriceUpdateV2 account
+pub const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+// Oldest publish time accepted at Take, in seconds
+pub const MAX_PRICE_AGE: i64 = 60;
+// Widest confidence interval accepted, relative to the price
+pub const MAX_CONFIDENCE_BPS: u16 = 200;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+impl PriceUpdateV2 {
+    /// Reads a price update, the caller checks the account address.
+    pub fn load(account: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(
+            *account.owner,
+            PYTH_RECEIVER_PROGRAM_ID,
+            ErrorCode::InvalidPriceFeed
+        );
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR,
+            ErrorCode::InvalidPriceFeed
+        );
+        let update =
+            Self::deserialize(&mut &data[8..]).map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+        require!(
+            update.verification_level == VerificationLevel::Full,
+            ErrorCode::InvalidPriceFeed
+        );
+        Ok(update)
+    }
+
+    /// The latest price, rejected when stale, non-positive or too uncertain.
+    pub fn checked_price(&self, now: i64) -> Result<&PriceFeedMessage> {
+        let message = &self.price_message;
+        require!(
+            now.saturating_sub(message.publish_time) <= MAX_PRICE_AGE,
+            ErrorCode::StalePrice
+        );
+        let price =
+            u64::try_from(message.price).map_err(|_| error!(ErrorCode::InvalidPriceFeed))?;
+        require!(price > 0, ErrorCode::InvalidPriceFeed);
+        let max_conf = math::apply_bps(price, MAX_CONFIDENCE_BPS, Rounding::Down)
+            .ok_or(ErrorCode::Overflow)?;
+        require!(message.conf <= max_conf, ErrorCode::PriceTooUncertain);
+        Ok(message)
+    }
+}
+
+/// mint_b owed for `amount` of mint_a at a price quoted in mint_b per mint_a,
+/// plus `spread_bps`. Rounded up in the maker's favour.
+pub fn quote(
+    amount: u64,
+    price: &PriceFeedMessage,
+    decimals_a: u8,
+    decimals_b: u8,
+    spread_bps: u16,
+) -> Option<u64> {
+    let exponent = price.exponent + decimals_b as i32 - decimals_a as i32;
+    let scale = 10u128.checked_pow(exponent.unsigned_abs())?;
+
+    let mut numerator = (amount as u128)
+        .checked_mul(u64::try_from(price.price).ok()? as u128)?
+        .checked_mul(BPS_DENOMINATOR as u128 + spread_bps as u128)?;
+    let mut denominator = BPS_DENOMINATOR as u128;
+    if exponent >= 0 {
+        numerator = numerator.checked_mul(scale)?;
+    } else {
+        denominator = denominator.checked_mul(scale)?;
+    }
+    math::to_u64(numerator.div_ceil(denominator))
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TransferChecked};
+
+use crate::{balance, error::ErrorCode, hook};
+
+/// Returns whatever the fill closing an escrow left in its `vault` to the maker, so
+/// the vault can be closed. Only tokens sent to the vault outside Make and TopUp are
+/// left over, and they need `maker_ata_a` rather than going to whoever filled it.
+pub fn sweep<'info>(
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    maker_ata_a: Option<&InterfaceAccount<'info, TokenAccount>>,
+    escrow: AccountInfo<'info>,
+    mint_a: &InterfaceAccount<'info, Mint>,
+    token_program: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let residual = balance::of(&vault.to_account_info())?;
+    if residual > 0 {
+        let maker_ata_a = maker_ata_a.ok_or(ErrorCode::ResidualRecipientRequired)?;
+
+        let cpi_accounts = TransferChecked {
+            from: vault.to_account_info(),
+            to: maker_ata_a.to_account_info(),
+            authority: escrow,
+            mint: mint_a.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(token_program, cpi_accounts, signer_seeds);
+
+        hook::transfer_checked(cpi_context, residual, mint_a.decimals, hook_accounts)?;
+    }
+    require!(
+        balance::of(&vault.to_account_info())? == 0,
+        ErrorCode::VaultNotEmpty
+    );
+    Ok(())
+}
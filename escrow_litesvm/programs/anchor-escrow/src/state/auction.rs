@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+/// English auction over an escrow's deposit, opened by the maker with StartAuction.
+/// Outlives the escrow so losing bidders can still reclaim their bids after Settle.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Auction {
+    pub escrow: Pubkey,
+    // Bids are accepted before this unix timestamp, Settle is allowed from it
+    pub end_at: i64,
+    // Lowest acceptable bid, in mint_b
+    pub reserve: u64,
+    pub highest_bid: u64,
+    pub highest_bidder: Option<Pubkey>,
+    pub bump: u8,
+}
@@ -0,0 +1,11 @@
+use anchor_lang::prelude::*;
+
+/// A bidder's running total on one auction, locked in the mint_b vault this account owns.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Bid {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
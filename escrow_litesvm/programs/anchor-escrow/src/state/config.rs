@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+
+use math::Rounding;
+
+use crate::error::ErrorCode;
+
+/// Deployment-wide bounds on the terms an escrow may be created with, and the
+/// protocol fee charged on takes.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub min_deposit: u64,
+    pub max_waiting_time: i64,
+    // Upper bound on how far in the future an escrow may expire
+    pub max_expiry_horizon: i64,
+    pub min_decimals: u8,
+    pub max_decimals: u8,
+    pub bump: u8,
+    // Share of every Take payment sent to the treasury, in basis points
+    pub fee_bps: u16,
+    // Owner of the token accounts the protocol fee is paid into
+    pub treasury: Pubkey,
+    // Halts Make and Take during an incident, refunds stay open
+    pub paused: bool,
+    // Most escrows a maker may have open at once, 0 for no limit
+    pub max_open_escrows_per_maker: u32,
+    // Share of a Take payment sent to the referrer the taker names, in basis points.
+    // Paid out of the protocol fee, so at most `fee_bps` of it is ever paid
+    pub referral_bps: u16,
+    // Lower rates for larger payments, by ascending `min_size`. Payments under the
+    // first tier pay `fee_bps`
+    #[max_len(4)]
+    pub fee_tiers: Vec<FeeTier>,
+}
+
+impl Config {
+    pub const MAX_FEE_BPS: u16 = 1_000;
+    // Matches the max_len of `fee_tiers`
+    pub const MAX_FEE_TIERS: usize = 4;
+
+    pub fn set_fee(&mut self, fee_bps: u16, treasury: Pubkey) -> Result<()> {
+        require!(fee_bps <= Self::MAX_FEE_BPS, ErrorCode::FeeTooHigh);
+        self.fee_bps = fee_bps;
+        self.treasury = treasury;
+        Ok(())
+    }
+
+    /// Replaces the fee tiers, which must be at most `MAX_FEE_TIERS` with strictly
+    /// ascending non-zero thresholds. An empty list charges `fee_bps` on every size.
+    pub fn set_fee_tiers(&mut self, fee_tiers: Vec<FeeTier>) -> Result<()> {
+        require!(
+            fee_tiers.len() <= Self::MAX_FEE_TIERS,
+            ErrorCode::InvalidFeeTiers
+        );
+        let mut floor = 0;
+        for tier in &fee_tiers {
+            require!(tier.min_size > floor, ErrorCode::InvalidFeeTiers);
+            require!(tier.fee_bps <= Self::MAX_FEE_BPS, ErrorCode::FeeTooHigh);
+            floor = tier.min_size;
+        }
+        self.fee_tiers = fee_tiers;
+        Ok(())
+    }
+
+    /// Protocol fee on a payment of `amount`, at the rate of the highest tier it
+    /// reaches and rounded down in the maker's favour.
+    pub fn fee_for(&self, amount: u64) -> Option<u64> {
+        let fee_bps = self
+            .fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| amount >= tier.min_size)
+            .map_or(self.fee_bps, |tier| tier.fee_bps);
+        math::apply_bps(amount, fee_bps, Rounding::Down)
+    }
+
+    /// Cut of each referred Take paid to the referrer instead of the treasury.
+    pub fn set_referral_fee(&mut self, referral_bps: u16) -> Result<()> {
+        require!(referral_bps <= Self::MAX_FEE_BPS, ErrorCode::FeeTooHigh);
+        self.referral_bps = referral_bps;
+        Ok(())
+    }
+
+    /// Levers against state spam on a shared deployment, enforced by Make.
+    pub fn set_limits(&mut self, min_deposit: u64, max_open_escrows_per_maker: u32) {
+        self.min_deposit = min_deposit;
+        self.max_open_escrows_per_maker = max_open_escrows_per_maker;
+    }
+
+    pub fn require_not_paused(&self) -> Result<()> {
+        require!(!self.paused, ErrorCode::Paused);
+        Ok(())
+    }
+}
+
+/// Fee rate for payments of at least `min_size`, in base units of the paying mint.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct FeeTier {
+    pub min_size: u64,
+    pub fee_bps: u16,
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+/// A taker's alternative terms for an escrow, with the offered mint_b locked in
+/// the vault this account owns until the maker accepts or the taker withdraws.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct CounterOffer {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    // mint_b locked in the counter offer vault
+    pub offer: u64,
+    // mint_a asked for in return, out of the escrow's remaining deposit
+    pub request: u64,
+    pub bump: u8,
+}
@@ -1,13 +1,556 @@
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token;
 
+use math::Rounding;
+
+use crate::error::ErrorCode;
+
+// Field order is part of the account's public interface: scanners filter
+// getProgramAccounts results with memcmp at the offsets below, so the leading
+// fields must never move and new fields are appended at the end.
+//
+// Deliberately Borsh rather than zero_copy: the Option, Vec and String fields have
+// no fixed POD form, a POD layout would move those scanner offsets and need every
+// live escrow migrated one way, and decoding this account is a small share of
+// Take next to its token CPIs. Take's ceiling is pinned by TAKE_CU_BUDGET in the
+// LiteSVM tests, revisit this if it no longer holds.
 #[account]
 #[derive(InitSpace, Debug)]
 pub struct Escrow {
-    pub seed: u64,
     pub maker: Pubkey,
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
+    pub status: EscrowStatus,
+    pub seed: u64,
     pub receive: u64,
+    // Absolute unix timestamp after which Take is allowed
+    pub unlock_at: i64,
     pub bump: u8,
-    pub waiting_time: i64,
+    // Fields below were added in v2, older escrows are migrated with `migrate_escrow`
+    pub version: u8,
+    // Unix timestamp after which the escrow can no longer be taken, 0 means none
+    pub expiry: i64,
+    // Only this taker may fill the escrow when set
+    pub taker: Option<Pubkey>,
+    // Maker statistics account this escrow reports to
+    pub stats: Option<Pubkey>,
+    // Receives the escrow and vault rent back on close: whoever paid it at Make,
+    // unless the maker named another recipient
+    pub rent_payer: Pubkey,
+    // Fields below were added in v3 for partial fills
+    // mint_a still in the vault for takers and mint_b still owed to the maker
+    pub remaining_deposit: u64,
+    pub remaining_receive: u64,
+    // Fields below were added in v4
+    // Merkle root of the takers allowed to fill the escrow, see `crate::merkle`
+    pub taker_root: Option<[u8; 32]>,
+    // Fields below were added in v5
+    // Take pays the NFT creators their Token Metadata royalties out of mint_b
+    pub enforce_royalties: bool,
+    // Fields below were added in v6
+    // Pyth price account that prices mint_a in mint_b at Take, replacing `receive`
+    pub price_feed: Option<Pubkey>,
+    // Premium over the oracle price the taker pays, in basis points
+    pub spread_bps: u16,
+    // Fields below were added in v7
+    // Prices every fill on its own instead of by the remaining deposit/receive pair
+    pub unit_price: Option<UnitPrice>,
+    // Fields below were added in v8
+    // Unix timestamp before which the maker cannot refund, 0 means none
+    pub no_refund_before: i64,
+    // Fields below were added in v9
+    // Other mints Take may be paid in, each priced in place of `receive`
+    #[max_len(4)]
+    pub payment_options: Vec<PaymentOption>,
+    // Fields below were added in v10
+    // `receive` is quoted in the UI amount of an interest-bearing mint_b, see `crate::interest`
+    pub receive_in_ui_amount: bool,
+    // Fields below were added in v11
+    // Short offer description or URI for frontends to display
+    #[max_len(64)]
+    pub label: Option<String>,
+    // Fields below were added in v12
+    // Set by the maker to stop Take without closing the escrow
+    pub paused: bool,
+    // Fields below were added in v13
+    // Bump of the vault ATA, so Take can check it without a find_program_address
+    pub vault_bump: u8,
+    // Fields below were added in v14
+    // The PDA is also seeded with mint_a and mint_b, false for escrows made before v14
+    pub mint_seeded: bool,
+    // Fields below were added in v15
+    // Counted in the maker's MakerRegistry, which Take and Refund then update
+    pub registered: bool,
+    // Fields below were added in v16
+    // Slots a CommitTake must be revealed within, 0 lets Take fill directly
+    pub reveal_window: u64,
+    // Fields below were added in v17
+    // SHA-256 hash of the secret Take must present, for HTLC-style atomic swaps
+    pub hashlock: Option<[u8; 32]>,
+    // Fields below were added in v18
+    // Splits the vault between maker and designated taker once either raises a dispute
+    pub arbiter: Option<Pubkey>,
+    // Fields below were added in v19
+    // Seconds between a DepositPayment and the SettleDeposit that releases both
+    // sides. Set for two-sided escrows only, which Take cannot fill
+    pub review_period: Option<i64>,
+    // Fields below were added in v20
+    // Seconds over which a TakeVested fill is released to the taker, 0 delivers at Take
+    pub vesting_duration: i64,
+    // Fields below were added in v21
+    // Stays open once the deposit runs out, for the maker to refill with TopUp
+    pub standing: bool,
+    // mint_a filled over the escrow's life, counted from v21. The mint_b paid for it
+    // is `receive - remaining_receive`, as TopUp keeps `receive` cumulative
+    pub total_filled: u64,
+    // Fields below were added in v22
+    // Smallest fill Take accepts, except for the one taking the rest of the deposit
+    pub min_fill: u64,
+    // Fields below were added in v23
+    // Lamports the maker staked on the offer at Make, held on top of the escrow rent
+    pub cancel_fee: u64,
+    // Unix timestamp before which a Refund forfeits `cancel_fee` to the treasury
+    pub cancel_fee_until: i64,
+    // Fields below were added in v24
+    // Take requires the taker's entry in the whitelist-transfer-hook program, see
+    // `crate::whitelist`
+    pub whitelisted_takers: bool,
+    // Fields below were added in v25
+    // Seconds a partial fill keeps the offer open for at least, pushing `expiry` out
+    // when it is closer. 0 leaves the expiry as set at Make
+    pub fill_grace_period: i64,
+    // Fields below were added in v26
+    // Maker the escrow's address was derived from, set once TransferMakerAuthority
+    // hands the escrow to a new `maker`
+    pub made_by: Option<Pubkey>,
+    // Fields below were added in v27
+    // Premium over `unit_price`, in basis points, that fills pay once the vault is
+    // drained. It grows linearly from 0 with the vault full, see `Escrow::unit_cost`,
+    // and is left out of `remaining_receive`
+    pub curve_bps: u16,
+}
+
+impl Escrow {
+    // Byte offsets into the account data, including the 8-byte discriminator
+    pub const MAKER_OFFSET: usize = 8;
+    pub const MINT_A_OFFSET: usize = Self::MAKER_OFFSET + 32;
+    pub const MINT_B_OFFSET: usize = Self::MINT_A_OFFSET + 32;
+    pub const STATUS_OFFSET: usize = Self::MINT_B_OFFSET + 32;
+
+    pub const CURRENT_VERSION: u8 = 27;
+    // Account size (with discriminator) of the originally deployed layout: seed, maker,
+    // mint_a, mint_b, receive, bump and waiting_time, with no status or version
+    pub const BASELINE_LEN: usize = 8 + 8 + 32 * 3 + 8 + 1 + 8;
+    // Offset of `version`, the first field after the fixed leading fields
+    pub const VERSION_OFFSET: usize = Self::STATUS_OFFSET + 1 + 8 + 8 + 8 + 1;
+    // First version that `MigrateEscrow` upgrades by zero-extending its tail. Fields
+    // appended from then on must read as zero (false, None, 0) on an escrow that
+    // predates them, for the behaviour it had without them
+    pub const TAIL_VERSION: u8 = 27;
+
+    // Matches the max_len of `payment_options`
+    pub const MAX_PAYMENT_OPTIONS: usize = 4;
+    // Matches the max_len of `label`, in bytes
+    pub const MAX_LABEL_LEN: usize = 64;
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expiry != 0 && now >= self.expiry
+    }
+
+    /// Fails unless the escrow can still be filled or cancelled.
+    pub fn require_active(&self) -> Result<()> {
+        match self.status {
+            EscrowStatus::Open | EscrowStatus::PartiallyFilled => Ok(()),
+            EscrowStatus::Locked => err!(ErrorCode::EscrowLocked),
+            EscrowStatus::Disputed => err!(ErrorCode::EscrowDisputed),
+            EscrowStatus::Closed => err!(ErrorCode::EscrowClosed),
+        }
+    }
+
+    /// True when filling needs more than a payment: an allowlist proof, a price
+    /// update, royalty accounts, an interest-bearing quote, a commitment, a
+    /// preimage or a whitelist entry. Only Take and RevealTake handle those.
+    pub fn needs_take(&self) -> bool {
+        self.taker_root.is_some()
+            || self.price_feed.is_some()
+            || self.enforce_royalties
+            || self.receive_in_ui_amount
+            || self.reveal_window != 0
+            || self.hashlock.is_some()
+            || self.whitelisted_takers
+    }
+
+    /// True when the taker's payment is vaulted by DepositPayment instead of going
+    /// straight to the maker.
+    pub fn is_two_sided(&self) -> bool {
+        self.review_period.is_some()
+    }
+
+    /// True when fills are streamed to the taker through TakeVested and ClaimVested.
+    pub fn vests(&self) -> bool {
+        self.vesting_duration != 0
+    }
+
+    /// True when Take hands the fill to the taker straight away, so the paths that
+    /// mirror Take can fill the escrow too.
+    pub fn delivers_at_take(&self) -> bool {
+        !self.is_two_sided() && !self.vests()
+    }
+
+    /// True for escrows asking nothing in return: a gift its designated taker claims
+    /// with a Take paying nothing, which the maker can still Refund until then.
+    /// Oracle-priced escrows also store a zero `receive` but are priced at Take.
+    pub fn is_gift(&self) -> bool {
+        self.receive == 0 && self.price_feed.is_none()
+    }
+
+    /// Fails for a gift anyone could claim, or with terms that only make sense for a
+    /// price.
+    pub fn require_valid_gift(&self) -> Result<()> {
+        if !self.is_gift() {
+            return Ok(());
+        }
+        require!(
+            self.taker.is_some()
+                && self.payment_options.is_empty()
+                && !self.receive_in_ui_amount
+                && !self.standing
+                && self.delivers_at_take(),
+            ErrorCode::InvalidGift
+        );
+        Ok(())
+    }
+
+    /// True when `other` fills on the same terms at the same price, so Merge can
+    /// pool the two deposits without changing what any taker gets. Oracle-priced
+    /// escrows are priced at Take and only need the same feed and spread.
+    pub fn same_terms(&self, other: &Escrow) -> bool {
+        let same_price = match (self.unit_price, other.unit_price) {
+            _ if self.price_feed.is_some() => true,
+            (Some(unit_price), Some(other_unit_price)) => unit_price == other_unit_price,
+            (None, None) => {
+                u128::from(self.remaining_receive) * u128::from(other.remaining_deposit)
+                    == u128::from(other.remaining_receive) * u128::from(self.remaining_deposit)
+            }
+            _ => false,
+        };
+        same_price
+            && self.mint_b == other.mint_b
+            && self.taker == other.taker
+            && self.taker_root == other.taker_root
+            && self.enforce_royalties == other.enforce_royalties
+            && self.price_feed == other.price_feed
+            && self.spread_bps == other.spread_bps
+            && self.curve_bps == other.curve_bps
+            && self.payment_options == other.payment_options
+            && self.receive_in_ui_amount == other.receive_in_ui_amount
+            && self.expiry == other.expiry
+            && self.reveal_window == other.reveal_window
+            && self.hashlock == other.hashlock
+            && self.arbiter == other.arbiter
+            && self.review_period == other.review_period
+            && self.vesting_duration == other.vesting_duration
+            && self.standing == other.standing
+            && self.min_fill == other.min_fill
+            && self.whitelisted_takers == other.whitelisted_takers
+            && self.fill_grace_period == other.fill_grace_period
+            && self.stats == other.stats
+    }
+
+    pub fn payment_option(&self, mint: &Pubkey) -> Option<&PaymentOption> {
+        self.payment_options
+            .iter()
+            .find(|option| option.mint == *mint)
+    }
+
+    /// True when Take may be paid in `mint`.
+    pub fn accepts(&self, mint: &Pubkey) -> bool {
+        self.mint_b == *mint || self.payment_option(mint).is_some()
+    }
+
+    /// Converts `amount` of `mint` into what it counts for against `remaining_receive`,
+    /// rounded down in the maker's favour.
+    pub fn owed_for(&self, mint: &Pubkey, amount: u64) -> Option<u64> {
+        if self.mint_b == *mint {
+            return Some(amount);
+        }
+        let option = self.payment_option(mint)?;
+        math::mul_div(amount, self.receive, option.receive, Rounding::Down)
+    }
+
+    /// Maker seed of the escrow PDA, the original maker once the escrow was handed
+    /// to a new one.
+    pub fn maker_seed(&self) -> &[u8] {
+        self.made_by.as_ref().unwrap_or(&self.maker).as_ref()
+    }
+
+    /// mint_a seed of the escrow PDA. Empty for escrows made before the mints were
+    /// seeds, which leaves their derivation unchanged.
+    pub fn mint_a_seed(&self) -> &[u8] {
+        if self.mint_seeded {
+            self.mint_a.as_ref()
+        } else {
+            &[]
+        }
+    }
+
+    /// mint_b seed of the escrow PDA, see `mint_a_seed`.
+    pub fn mint_b_seed(&self) -> &[u8] {
+        if self.mint_seeded {
+            self.mint_b.as_ref()
+        } else {
+            &[]
+        }
+    }
+
+    /// mint_a owed for `owed` counted against `remaining_receive`, rounded down in
+    /// the maker's favour. A gift is claimed whole for nothing.
+    pub fn fill_for(&self, owed: u64) -> Result<u64> {
+        if self.is_gift() {
+            require!(owed == 0, ErrorCode::InvalidTakeAmount);
+            return Ok(self.remaining_deposit);
+        }
+        if let Some(unit_price) = self.unit_price {
+            require!(owed > 0, ErrorCode::InvalidTakeAmount);
+            let fill = if self.curve_bps == 0 {
+                unit_price.units_for(owed, Rounding::Down)
+            } else {
+                Some(self.curve_fill_for(unit_price, owed))
+            }
+            .ok_or(ErrorCode::Overflow)?
+            .min(self.remaining_deposit);
+            require!(fill > 0, ErrorCode::FillTooSmall);
+            self.check_min_fill(fill)?;
+            return Ok(fill);
+        }
+
+        require!(
+            owed > 0 && owed <= self.remaining_receive,
+            ErrorCode::InvalidTakeAmount
+        );
+
+        let fill = math::mul_div(
+            self.remaining_deposit,
+            owed,
+            self.remaining_receive,
+            Rounding::Down,
+        )
+        .ok_or(ErrorCode::Overflow)?;
+        require!(fill > 0, ErrorCode::FillTooSmall);
+        self.check_min_fill(fill)?;
+
+        Ok(fill)
+    }
+
+    /// Fails for fills under `min_fill`, unless they take the rest of the deposit.
+    pub fn check_min_fill(&self, fill: u64) -> Result<()> {
+        require!(
+            fill >= self.min_fill || fill == self.remaining_deposit,
+            ErrorCode::FillBelowMinimum
+        );
+        Ok(())
+    }
+
+    /// mint_b charged for `fill`: the exact cost rounded up for unit-priced escrows,
+    /// so earlier fills never skew later ones, otherwise all of `take_amount`.
+    pub fn payment_for(&self, take_amount: u64, fill: u64) -> Result<u64> {
+        let Some(unit_price) = self.unit_price else {
+            return Ok(take_amount);
+        };
+        let payment = self
+            .unit_cost(unit_price, fill)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(payment)
+    }
+
+    /// mint_b owed for `fill` at `unit_price`, plus the price curve's premium when
+    /// there is one. The premium rises linearly as the vault drains, so a fill pays
+    /// it at the midpoint of the inventory it takes, rounded up in the maker's
+    /// favour, and larger fills pay more per unit.
+    pub fn unit_cost(&self, unit_price: UnitPrice, fill: u64) -> Option<u64> {
+        let cost = unit_price.cost(fill, Rounding::Up)?;
+        if self.curve_bps == 0 {
+            return Some(cost);
+        }
+        // The inventory is what has been filled so far plus what is left, so TopUp
+        // eases the price again and WithdrawPartial steepens it
+        let inventory = self.total_filled.checked_add(self.remaining_deposit)?;
+        let midpoint = self.total_filled.checked_mul(2)?.checked_add(fill)?;
+        let drained_premium = math::apply_bps(cost, self.curve_bps, Rounding::Up)?;
+        let premium = math::mul_div(
+            drained_premium,
+            midpoint,
+            inventory.checked_mul(2)?,
+            Rounding::Up,
+        )?;
+        cost.checked_add(premium)
+    }
+
+    // The largest fill `owed` covers on the price curve, bisected as `unit_cost`
+    // only grows with the fill
+    fn curve_fill_for(&self, unit_price: UnitPrice, owed: u64) -> u64 {
+        let (mut low, mut high) = (0, self.remaining_deposit);
+        while low < high {
+            let mid = high - (high - low) / 2;
+            match self.unit_cost(unit_price, mid) {
+                Some(cost) if cost <= owed => low = mid,
+                _ => high = mid - 1,
+            }
+        }
+        low
+    }
+
+    /// What taking exactly `fill` of mint_a costs in `mint`, rounded up in the maker's
+    /// favour. The exact-out counterpart of `fill_for`.
+    pub fn cost_for(&self, mint: &Pubkey, fill: u64) -> Result<u64> {
+        require!(
+            fill > 0 && fill <= self.remaining_deposit,
+            ErrorCode::InvalidFillAmount
+        );
+        self.check_min_fill(fill)?;
+        let owed = match self.unit_price {
+            Some(unit_price) => self.unit_cost(unit_price, fill),
+            None => math::mul_div(
+                fill,
+                self.remaining_receive,
+                self.remaining_deposit,
+                Rounding::Up,
+            ),
+        }
+        .ok_or(ErrorCode::Overflow)?;
+        require!(owed > 0, ErrorCode::InvalidFillAmount);
+
+        if self.mint_b == *mint {
+            return Ok(owed);
+        }
+        let option = self.payment_option(mint).ok_or(ErrorCode::InvalidMint)?;
+        let cost = math::mul_div(owed, option.receive, self.receive, Rounding::Up)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(cost)
+    }
+
+    /// Takes `fill` out of the remaining deposit once its payment is counted, and
+    /// returns whether that emptied the escrow. Standing offers are never filled, they
+    /// wait for the maker's next TopUp instead of closing. A partial fill extends the
+    /// expiry by the fill grace period.
+    pub fn record_fill(&mut self, fill: u64) -> Result<bool> {
+        self.remaining_deposit -= fill;
+        self.total_filled = self.total_filled.saturating_add(fill);
+        if let Some(unit_price) = self.unit_price {
+            // Whatever a fill rounded up is not carried over to the rest
+            self.remaining_receive = unit_price
+                .cost(self.remaining_deposit, Rounding::Up)
+                .ok_or(ErrorCode::Overflow)?;
+        }
+        let filled = self.remaining_deposit == 0 && !self.standing;
+        self.status = if filled {
+            EscrowStatus::Closed
+        } else {
+            EscrowStatus::PartiallyFilled
+        };
+        if !filled && self.expiry != 0 && self.fill_grace_period != 0 {
+            let grace_until = Clock::get()?
+                .unix_timestamp
+                .checked_add(self.fill_grace_period)
+                .ok_or(ErrorCode::Overflow)?;
+            self.expiry = self.expiry.max(grace_until);
+        }
+        Ok(filled)
+    }
+
+    /// Replaces what is still owed with `remaining_receive`. `receive` keeps counting
+    /// what was already paid on top of it, except on oracle-priced escrows: they are
+    /// quoted at Take, so `receive` stays zero and the rest is only a stale quote.
+    pub fn set_remaining_receive(&mut self, remaining_receive: u64) -> Result<()> {
+        if self.price_feed.is_none() {
+            self.receive = self
+                .receive
+                .checked_sub(self.remaining_receive)
+                .and_then(|paid| paid.checked_add(remaining_receive))
+                .ok_or(ErrorCode::Overflow)?;
+        }
+        self.remaining_receive = remaining_receive;
+        Ok(())
+    }
+
+    /// Address of the escrow's mint_a ATA, rebuilt from `vault_bump`. The default
+    /// key when the bump does not give a valid address, which no vault matches.
+    pub fn vault_address(&self, escrow: &Pubkey, token_program: &Pubkey) -> Pubkey {
+        Pubkey::create_program_address(
+            &[
+                escrow.as_ref(),
+                token_program.as_ref(),
+                self.mint_a.as_ref(),
+                &[self.vault_bump],
+            ],
+            &associated_token::ID,
+        )
+        .unwrap_or_default()
+    }
+
+    pub fn require_not_paused(&self) -> Result<()> {
+        require!(!self.paused, ErrorCode::EscrowPaused);
+        Ok(())
+    }
+
+    /// Moves the unlock time to `unlock_at`. A designated taker agreed to the current
+    /// one, so for them it can only be pushed back.
+    pub fn set_unlock_at(&mut self, unlock_at: i64) -> Result<()> {
+        require!(
+            self.taker.is_none() || unlock_at >= self.unlock_at,
+            ErrorCode::WaitingTimeShortened
+        );
+        self.unlock_at = unlock_at;
+        Ok(())
+    }
+
+    /// True while a Refund would forfeit `cancel_fee` to the treasury.
+    pub fn cancel_fee_at_stake(&self, now: i64) -> bool {
+        self.cancel_fee != 0 && now < self.cancel_fee_until
+    }
+
+    /// Fails while the maker is still committed to keeping the deposit in.
+    pub fn require_refundable(&self, now: i64) -> Result<()> {
+        require!(now >= self.no_refund_before, ErrorCode::RefundLocked);
+        Ok(())
+    }
+}
+
+/// `numerator` of mint_b for every `denominator` of mint_a, in base units.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct UnitPrice {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl UnitPrice {
+    pub fn is_valid(&self) -> bool {
+        self.numerator > 0 && self.denominator > 0
+    }
+
+    /// mint_b owed for `amount` of mint_a.
+    pub fn cost(&self, amount: u64, rounding: Rounding) -> Option<u64> {
+        math::mul_div(amount, self.numerator, self.denominator, rounding)
+    }
+
+    /// mint_a that `payment` of mint_b buys.
+    pub fn units_for(&self, payment: u64, rounding: Rounding) -> Option<u64> {
+        math::mul_div(payment, self.denominator, self.numerator, rounding)
+    }
+}
+
+/// `receive` of `mint` accepted in place of the escrow's `receive` of mint_b.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct PaymentOption {
+    pub mint: Pubkey,
+    pub receive: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum EscrowStatus {
+    Open,
+    PartiallyFilled,
+    Locked,
+    Disputed,
+    Closed,
 }
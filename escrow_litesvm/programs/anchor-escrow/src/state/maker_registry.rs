@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+/// Index of a maker's open escrows, so clients can list them without scanning
+/// every escrow account. Created on the maker's first Make.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct MakerRegistry {
+    pub maker: Pubkey,
+    // Escrows made and not yet filled or refunded
+    pub open_escrows: u32,
+    // The most recent of those, oldest first
+    #[max_len(8)]
+    pub recent: Vec<RegisteredEscrow>,
+    pub bump: u8,
+}
+
+impl MakerRegistry {
+    // Matches the max_len of `recent`
+    pub const MAX_RECENT: usize = 8;
+
+    /// Counts a new escrow, dropping the oldest recent entry once the list is full.
+    pub fn add(&mut self, escrow: Pubkey, seed: u64) -> Result<()> {
+        self.open_escrows = self
+            .open_escrows
+            .checked_add(1)
+            .ok_or(ErrorCode::Overflow)?;
+        if self.recent.len() == Self::MAX_RECENT {
+            self.recent.remove(0);
+        }
+        self.recent.push(RegisteredEscrow { escrow, seed });
+        Ok(())
+    }
+
+    pub fn remove(&mut self, escrow: &Pubkey) {
+        self.open_escrows = self.open_escrows.saturating_sub(1);
+        self.recent
+            .retain(|registered| registered.escrow != *escrow);
+    }
+}
+
+/// An escrow's address with its seed, since the mints are part of the derivation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct RegisteredEscrow {
+    pub escrow: Pubkey,
+    pub seed: u64,
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+/// Running totals of a maker's escrows for reputation displays. Opt-in: created
+/// with InitMakerStats, and escrows made with it passed report to it for life.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct MakerStats {
+    pub maker: Pubkey,
+    pub escrows_created: u64,
+    pub escrows_filled: u64,
+    pub escrows_refunded: u64,
+    // mint_a given and mint_b received across fills, for the first mints traded
+    #[max_len(16)]
+    pub volume: Vec<MintVolume>,
+    pub bump: u8,
+}
+
+impl MakerStats {
+    // Matches the max_len of `volume`
+    pub const MAX_MINTS: usize = 16;
+
+    /// Adds `amount` to the mint's volume. Mints beyond the first `MAX_MINTS` are
+    /// not tracked.
+    pub fn add_volume(&mut self, mint: Pubkey, amount: u64) {
+        if let Some(volume) = self.volume.iter_mut().find(|volume| volume.mint == mint) {
+            volume.amount = volume.amount.saturating_add(amount.into());
+        } else if self.volume.len() < Self::MAX_MINTS {
+            self.volume.push(MintVolume {
+                mint,
+                amount: amount.into(),
+            });
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct MintVolume {
+    pub mint: Pubkey,
+    pub amount: u128,
+}
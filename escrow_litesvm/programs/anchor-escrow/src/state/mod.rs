@@ -1,3 +1,27 @@
+pub mod auction;
+pub mod bid;
+pub mod config;
+pub mod counter_offer;
 pub mod escrow;
+pub mod maker_registry;
+pub mod maker_stats;
+pub mod proceeds;
+pub mod seed_counter;
+pub mod take_commitment;
+pub mod taker_deposit;
+pub mod taker_nonce;
+pub mod vesting;
 
+pub use auction::*;
+pub use bid::*;
+pub use config::*;
+pub use counter_offer::*;
 pub use escrow::*;
+pub use maker_registry::*;
+pub use maker_stats::*;
+pub use proceeds::*;
+pub use seed_counter::*;
+pub use take_commitment::*;
+pub use taker_deposit::*;
+pub use taker_nonce::*;
+pub use vesting::*;
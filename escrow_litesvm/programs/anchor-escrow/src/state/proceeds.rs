@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+/// Holds a maker's mint_b proceeds when their token account was frozen at Take.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Proceeds {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub mint_b: Pubkey,
+    // Taker funded this account and its vault, receives the rent back on claim
+    pub rent_payer: Pubkey,
+    pub bump: u8,
+}
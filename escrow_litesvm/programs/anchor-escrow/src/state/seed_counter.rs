@@ -0,0 +1,10 @@
+use anchor_lang::prelude::*;
+
+/// The next seed MakeAuto uses for a maker, so clients do not have to invent one.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct SeedCounter {
+    pub maker: Pubkey,
+    pub next_seed: u64,
+    pub bump: u8,
+}
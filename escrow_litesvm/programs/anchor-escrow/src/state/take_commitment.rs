@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+/// A taker's hidden intent to fill an escrow, see `CommitTake`. Closed back to the
+/// taker by the RevealTake that uses it.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct TakeCommitment {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    // SHA-256 of the taker's key followed by their salt
+    pub hash: [u8; 32],
+    // Slot of the commit, the reveal must land in a later one within the window
+    pub slot: u64,
+    pub bump: u8,
+}
+
+impl TakeCommitment {
+    pub fn hash(taker: &Pubkey, salt: &[u8; 32]) -> [u8; 32] {
+        anchor_lang::solana_program::hash::hashv(&[taker.as_ref(), salt]).to_bytes()
+    }
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+/// A taker's payment into a two-sided escrow, locked in the mint_b vault this account
+/// owns until SettleDeposit releases both sides or both parties cancel.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct TakerDeposit {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    // mint_b paid in, the escrow's whole remaining receive
+    pub amount: u64,
+    // Unix timestamp after which anyone can settle
+    pub settle_at: i64,
+    pub bump: u8,
+}
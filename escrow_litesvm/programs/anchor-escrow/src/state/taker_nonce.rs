@@ -0,0 +1,11 @@
+use anchor_lang::prelude::*;
+
+/// The next order nonce TakeWithSignature accepts from a taker, so a signed order
+/// fills at most once.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct TakerNonce {
+    pub taker: Pubkey,
+    pub next_nonce: u64,
+    pub bump: u8,
+}
@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use math::Rounding;
+
+/// A taker's fill of a vesting escrow, released linearly out of the mint_a vault this
+/// account owns. Closed to the taker by the claim that empties it.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Vesting {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub mint_a: Pubkey,
+    // mint_a filled at TakeVested and how much of it the taker has claimed
+    pub total: u64,
+    pub claimed: u64,
+    // Unix timestamp of the fill, vesting runs for `duration` seconds from there
+    pub start_at: i64,
+    pub duration: i64,
+    pub bump: u8,
+}
+
+impl Vesting {
+    /// mint_a released by `now`, rounded down until the schedule ends.
+    pub fn vested(&self, now: i64) -> Option<u64> {
+        let elapsed = now.saturating_sub(self.start_at).clamp(0, self.duration);
+        math::mul_div(
+            self.total,
+            elapsed as u64,
+            self.duration as u64,
+            Rounding::Down,
+        )
+    }
+}
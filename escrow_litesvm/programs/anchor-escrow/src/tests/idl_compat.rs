@@ -0,0 +1,193 @@
+//! IDL compatibility check against the committed baseline.
+//!
+//! The generated IDL is reduced to a signature (instruction accounts and args,
+//! type fields and variants) and compared with `idl-baselines/anchor_escrow.txt`.
+//! Deployed clients are built against the baseline, so the IDL may only grow past
+//! it. New instructions and types are free, struct fields and enum variants may
+//! only be appended, and existing instructions must match exactly. For an
+//! intentional breaking change, rerun the test with `UPDATE_IDL_BASELINE=1` and
+//! commit the rewritten baseline.
+
+use {
+    serde_json::Value,
+    std::{collections::BTreeMap, path::PathBuf},
+};
+
+type Signature = BTreeMap<String, Vec<String>>;
+
+fn render_type(ty: &Value) -> String {
+    match ty {
+        Value::String(name) => name.clone(),
+        Value::Object(map) => {
+            if let Some(inner) = map.get("option") {
+                format!("option<{}>", render_type(inner))
+            } else if let Some(inner) = map.get("vec") {
+                format!("vec<{}>", render_type(inner))
+            } else if let Some(Value::Array(array)) = map.get("array") {
+                format!("[{}; {}]", render_type(&array[0]), array[1])
+            } else if let Some(defined) = map.get("defined") {
+                defined
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .or_else(|| defined.as_str())
+                    .unwrap()
+                    .to_string()
+            } else if let Some(generic) = map.get("generic") {
+                generic.as_str().unwrap().to_string()
+            } else {
+                panic!("Unsupported IDL type: {ty}")
+            }
+        }
+        _ => panic!("Unsupported IDL type: {ty}"),
+    }
+}
+
+fn render_accounts(accounts: &[Value], prefix: &str, lines: &mut Vec<String>) {
+    for account in accounts {
+        let name = format!("{prefix}{}", account["name"].as_str().unwrap());
+        if let Some(Value::Array(nested)) = account.get("accounts") {
+            render_accounts(nested, &format!("{name}."), lines);
+            continue;
+        }
+
+        let mut line = format!("account {name}");
+        for flag in ["writable", "signer", "optional"] {
+            if account.get(flag).and_then(Value::as_bool).unwrap_or(false) {
+                line.push(' ');
+                line.push_str(if flag == "writable" { "mut" } else { flag });
+            }
+        }
+        lines.push(line);
+    }
+}
+
+fn signature(idl: &Value) -> Signature {
+    let mut signature = Signature::new();
+
+    for ix in idl["instructions"].as_array().unwrap() {
+        let mut lines = vec![];
+        render_accounts(ix["accounts"].as_array().unwrap(), "", &mut lines);
+        for arg in ix["args"].as_array().unwrap() {
+            lines.push(format!(
+                "arg {}: {}",
+                arg["name"].as_str().unwrap(),
+                render_type(&arg["type"])
+            ));
+        }
+        signature.insert(format!("instruction {}", ix["name"].as_str().unwrap()), lines);
+    }
+
+    for ty in idl["types"].as_array().into_iter().flatten() {
+        let def = &ty["type"];
+        let lines = match def["kind"].as_str().unwrap() {
+            "struct" => def["fields"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|field| match field.get("name") {
+                    Some(name) => {
+                        format!("field {}: {}", name.as_str().unwrap(), render_type(&field["type"]))
+                    }
+                    None => format!("field {}", render_type(field)),
+                })
+                .collect(),
+            "enum" => def["variants"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|variant| format!("variant {}", variant["name"].as_str().unwrap()))
+                .collect(),
+            kind => vec![format!("kind {kind}")],
+        };
+        signature.insert(format!("type {}", ty["name"].as_str().unwrap()), lines);
+    }
+
+    signature
+}
+
+fn parse_baseline(text: &str) -> Signature {
+    let mut signature = Signature::new();
+    let mut current = None;
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        if let Some(entry) = line.strip_prefix("  ") {
+            let header = current.as_ref().expect("Baseline entry before any header");
+            signature.get_mut(header).unwrap().push(entry.to_string());
+        } else {
+            signature.insert(line.to_string(), vec![]);
+            current = Some(line.to_string());
+        }
+    }
+    signature
+}
+
+fn format_baseline(signature: &Signature) -> String {
+    let mut text = String::new();
+    for (header, lines) in signature {
+        text.push_str(header);
+        text.push('\n');
+        for line in lines {
+            text.push_str("  ");
+            text.push_str(line);
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Returns a description of every way `current` breaks clients built against `baseline`
+fn breaking_changes(baseline: &Signature, current: &Signature) -> Vec<String> {
+    let mut changes = vec![];
+    for (header, old) in baseline {
+        let Some(new) = current.get(header) else {
+            changes.push(format!("{header} was removed"));
+            continue;
+        };
+
+        let compatible = if header.starts_with("type ") {
+            // Appending fields or variants keeps existing encodings readable
+            new.len() >= old.len() && new[..old.len()] == old[..]
+        } else {
+            new == old
+        };
+        if !compatible {
+            changes.push(format!("{header} changed:\n  was: {old:?}\n  now: {new:?}"));
+        }
+    }
+    changes
+}
+
+fn check_idl(idl_path: PathBuf, baseline_path: PathBuf) {
+    let idl_json = std::fs::read_to_string(&idl_path).unwrap_or_else(|_| {
+        panic!(
+            "Failed to read generated IDL at {}, run `anchor build` first",
+            idl_path.display()
+        )
+    });
+    let idl: Value = serde_json::from_str(&idl_json).expect("Failed to parse IDL JSON");
+    let current = signature(&idl);
+
+    if std::env::var("UPDATE_IDL_BASELINE").is_ok() {
+        std::fs::write(&baseline_path, format_baseline(&current))
+            .expect("Failed to write IDL baseline");
+        return;
+    }
+
+    let baseline_text =
+        std::fs::read_to_string(&baseline_path).expect("Failed to read IDL baseline");
+    let changes = breaking_changes(&parse_baseline(&baseline_text), &current);
+    assert!(
+        changes.is_empty(),
+        "IDL is incompatible with {}:\n{}\nRerun with UPDATE_IDL_BASELINE=1 if this is intentional",
+        baseline_path.display(),
+        changes.join("\n")
+    );
+}
+
+#[test]
+fn test_anchor_escrow_idl_compatibility() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    check_idl(
+        manifest_dir.join("../../target/idl/anchor_escrow.json"),
+        manifest_dir.join("idl-baselines/anchor_escrow.txt"),
+    );
+}
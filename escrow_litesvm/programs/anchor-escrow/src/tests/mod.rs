@@ -1,19 +1,24 @@
+#[cfg(test)]
+mod idl_compat;
+
 #[cfg(test)]
 mod tests {
 
     use {
+        crate::error::ErrorCode,
         anchor_lang::{
             prelude::{msg, Clock},
             solana_program::program_pack::Pack,
-            AccountDeserialize, InstructionData, ToAccountMetas,
+            AccountDeserialize, AccountSerialize, InstructionData, Space, ToAccountMetas,
         },
         anchor_spl::{
             associated_token::{self, spl_associated_token_account},
             token::spl_token,
         },
-        litesvm::LiteSVM,
+        litesvm::{types::FailedTransactionMetadata, LiteSVM},
         litesvm_token::{
-            spl_token::ID as TOKEN_PROGRAM_ID, CreateAssociatedTokenAccount, CreateMint, MintTo,
+            spl_token::ID as TOKEN_PROGRAM_ID, CreateAccount, CreateAssociatedTokenAccount,
+            CreateMint, MintTo,
         },
         solana_account::Account,
         solana_address::Address,
@@ -30,6 +35,50 @@ mod tests {
     };
 
     static PROGRAM_ID: Pubkey = crate::ID;
+    // Fee destination for every test deployment
+    static TREASURY: Pubkey = Pubkey::new_from_array([7; 32]);
+
+    // Bounds used by every test deployment
+    const MIN_DEPOSIT: u64 = 1;
+    const MAX_WAITING_TIME: i64 = 30 * 24 * 60 * 60;
+    const MAX_EXPIRY_HORIZON: i64 = 365 * 24 * 60 * 60;
+    const MIN_DECIMALS: u8 = 0;
+    const MAX_DECIMALS: u8 = 9;
+
+    // Take's compute ceiling, including creating the maker's and treasury's
+    // mint_b accounts. Well under the 200k default so clients need no budget ix
+    const TAKE_CU_BUDGET: u64 = 120_000;
+
+    fn config_pda() -> Pubkey {
+        Pubkey::find_program_address(&[b"config"], &PROGRAM_ID).0
+    }
+
+    fn event_authority_pda() -> Pubkey {
+        Pubkey::find_program_address(&[b"__event_authority"], &PROGRAM_ID).0
+    }
+
+    fn maker_registry_pda(maker: Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"maker_registry", maker.as_ref()], &PROGRAM_ID).0
+    }
+
+    fn read_maker_registry(program: &LiteSVM, maker: Pubkey) -> crate::state::MakerRegistry {
+        let account = program.get_account(&maker_registry_pda(maker)).unwrap();
+        crate::state::MakerRegistry::try_deserialize(&mut account.data.as_ref()).unwrap()
+    }
+
+    fn escrow_pda(maker: Pubkey, seed: u64, mint_a: Pubkey, mint_b: Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"escrow",
+                maker.as_ref(),
+                &seed.to_le_bytes(),
+                mint_a.as_ref(),
+                mint_b.as_ref(),
+            ],
+            &PROGRAM_ID,
+        )
+        .0
+    }
 
     /// Setup function to initialize LiteSVM, load program, create mints, and fund maker's ATA
     /// Returns: (LiteSVM instance, payer keypair, mint_a, mint_b, maker_ata_a)
@@ -80,6 +129,9 @@ mod tests {
 
         let maker = payer.pubkey();
 
+        // Initialize the global config with the test bounds
+        execute_initialize_config(&mut program, &payer);
+
         // Create two mints (Mint A and Mint B) with 6 decimal places and the maker as the authority
         let mint_a = CreateMint::new(&mut program, &payer)
             .decimals(6)
@@ -111,12 +163,40 @@ mod tests {
         (program, payer, mint_a, mint_b, maker_ata_a)
     }
 
-    /// Helper function to execute Make instruction
-    /// Creates escrow and vault, deposits tokens from maker
-    /// Returns: (escrow PDA, vault PDA)
-    fn execute_make(
-        program: &mut LiteSVM,
-        payer: &Keypair,
+    /// Helper function to execute InitializeConfig instruction
+    /// Creates the config PDA with the payer as admin
+    fn execute_initialize_config(program: &mut LiteSVM, payer: &Keypair) {
+        let init_config_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::InitializeConfig {
+                admin: payer.pubkey(),
+                config: config_pda(),
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::InitializeConfig {
+                min_deposit: MIN_DEPOSIT,
+                max_waiting_time: MAX_WAITING_TIME,
+                max_expiry_horizon: MAX_EXPIRY_HORIZON,
+                min_decimals: MIN_DECIMALS,
+                max_decimals: MAX_DECIMALS,
+                fee_bps: 0,
+                treasury: TREASURY,
+            }
+            .data(),
+        };
+
+        let message = Message::new(&[init_config_ix], Some(&payer.pubkey()));
+        let blockhash = program.latest_blockhash();
+        let transaction = Transaction::new(&[payer], message, blockhash);
+
+        program.send_transaction(transaction).unwrap();
+        msg!("InitializeConfig transaction successful");
+    }
+
+    /// Helper function to build the Make instruction without an expiry
+    /// Returns: (Make instruction, escrow PDA, vault PDA)
+    fn make_instruction(
         maker: Pubkey,
         mint_a: Pubkey,
         mint_b: Pubkey,
@@ -125,13 +205,35 @@ mod tests {
         deposit: u64,
         receive: u64,
         waiting_time: i64,
-    ) -> (Pubkey, Pubkey) {
-        // Derive the escrow PDA using maker's pubkey and seed
-        let escrow = Pubkey::find_program_address(
-            &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
-            &PROGRAM_ID,
+    ) -> (Instruction, Pubkey, Pubkey) {
+        make_instruction_with_expiry(
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            seed,
+            deposit,
+            receive,
+            waiting_time,
+            0,
         )
-        .0;
+    }
+
+    /// Helper function to build the Make instruction
+    /// Returns: (Make instruction, escrow PDA, vault PDA)
+    fn make_instruction_with_expiry(
+        maker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        maker_ata_a: Pubkey,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+        waiting_time: i64,
+        expiry: i64,
+    ) -> (Instruction, Pubkey, Pubkey) {
+        // Derive the escrow PDA from the maker, seed and mint pair
+        let escrow = escrow_pda(maker, seed, mint_a, mint_b);
 
         // Derive the vault PDA (associated token account owned by escrow)
         let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
@@ -141,25 +243,78 @@ mod tests {
             program_id: PROGRAM_ID,
             accounts: crate::accounts::Make {
                 maker: maker,
+                payer: maker,
+                config: config_pda(),
                 mint_a: mint_a,
                 mint_b: mint_b,
-                maker_ata_a: maker_ata_a,
+                maker_ata_a: Some(maker_ata_a),
                 escrow: escrow,
+                maker_registry: maker_registry_pda(maker),
+                maker_stats: None,
                 vault: vault,
+                memo_program: None,
                 associated_token_program: spl_associated_token_account::ID,
                 token_program: TOKEN_PROGRAM_ID,
                 system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
             }
             .to_account_metas(None),
             data: crate::instruction::Make {
-                deposit,
                 seed,
-                receive,
-                waiting_time,
+                args: crate::MakeArgs {
+                    deposit,
+                    receive,
+                    waiting_time,
+                    expiry,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
             }
             .data(),
         };
 
+        (make_ix, escrow, vault)
+    }
+
+    /// Helper function to execute Make instruction
+    /// Creates escrow and vault, deposits tokens from maker
+    /// Returns: (escrow PDA, vault PDA)
+    fn execute_make(
+        program: &mut LiteSVM,
+        payer: &Keypair,
+        maker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        maker_ata_a: Pubkey,
+        seed: u64,
+        deposit: u64,
+        receive: u64,
+        waiting_time: i64,
+    ) -> (Pubkey, Pubkey) {
+        let (make_ix, escrow, vault) = make_instruction(
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            seed,
+            deposit,
+            receive,
+            waiting_time,
+        );
+
         // Create and send transaction
         let message = Message::new(&[make_ix], Some(&payer.pubkey()));
         let blockhash = program.latest_blockhash();
@@ -204,6 +359,284 @@ mod tests {
         (program, payer, mint_a, mint_b, maker_ata_a, escrow, vault)
     }
 
+    /// Helper function to create and fund a taker
+    /// Creates the taker's ATAs for both mints and mints 1,000 tokens of Mint B to them
+    /// Returns: (taker keypair, taker ATA A, taker ATA B)
+    fn setup_taker(
+        program: &mut LiteSVM,
+        payer: &Keypair,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+    ) -> (Keypair, Pubkey, Pubkey) {
+        let taker = Keypair::new();
+        program
+            .airdrop(&taker.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        let taker_ata_a = CreateAssociatedTokenAccount::new(program, &taker, &mint_a)
+            .owner(&taker.pubkey())
+            .send()
+            .unwrap();
+        let taker_ata_b = CreateAssociatedTokenAccount::new(program, &taker, &mint_b)
+            .owner(&taker.pubkey())
+            .send()
+            .unwrap();
+        MintTo::new(program, payer, &mint_b, &taker_ata_b, 1000000000)
+            .send()
+            .unwrap();
+
+        (taker, taker_ata_a, taker_ata_b)
+    }
+
+    /// Helper function to build the Take instruction for a taker set up with `setup_taker`
+    fn take_instruction(
+        taker: Pubkey,
+        maker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        escrow: Pubkey,
+        vault: Pubkey,
+        take_amount: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker,
+                payer: taker,
+                maker,
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_a: associated_token::get_associated_token_address(&taker, &mint_a),
+                taker_ata_b: associated_token::get_associated_token_address(&taker, &mint_b),
+                maker_ata_b: associated_token::get_associated_token_address(&maker, &mint_b),
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                referrer_ata_b: None,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                proceeds: None,
+                proceeds_vault: None,
+                price_update: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take {
+                take_amount,
+                proof: vec![],
+                max_pay: take_amount,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data(),
+        }
+    }
+
+    /// Helper function to build TakeMany over `(escrow, vault)` pairs whose rent the
+    /// maker paid, for a taker set up with `setup_taker`
+    #[allow(clippy::too_many_arguments)]
+    fn take_many_instruction(
+        taker: Pubkey,
+        maker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        maker_ata_a: Option<Pubkey>,
+        escrows: &[(Pubkey, Pubkey)],
+        take_amounts: Vec<u64>,
+        max_pay: u64,
+        min_receive: u64,
+    ) -> Instruction {
+        let mut accounts = crate::accounts::TakeMany {
+            taker,
+            maker,
+            mint_a,
+            mint_b,
+            taker_ata_a: associated_token::get_associated_token_address(&taker, &mint_a),
+            taker_ata_b: associated_token::get_associated_token_address(&taker, &mint_b),
+            maker_ata_b: associated_token::get_associated_token_address(&maker, &mint_b),
+            maker_ata_a,
+            config: config_pda(),
+            treasury: TREASURY,
+            treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+            maker_registry: Some(maker_registry_pda(maker)),
+            maker_stats: None,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: TOKEN_PROGRAM_ID,
+            system_program: SYSTEM_PROGRAM_ID,
+        }
+        .to_account_metas(None);
+        for (escrow, vault) in escrows {
+            accounts.push(solana_instruction::AccountMeta::new(*escrow, false));
+            accounts.push(solana_instruction::AccountMeta::new(*vault, false));
+            accounts.push(solana_instruction::AccountMeta::new(maker, false));
+        }
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: crate::instruction::TakeMany {
+                take_amounts,
+                max_pay,
+                min_receive,
+            }
+            .data(),
+        }
+    }
+
+    fn vesting_pda(escrow: &Pubkey, taker: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"vesting", escrow.as_ref(), taker.as_ref()], &PROGRAM_ID).0
+    }
+
+    /// Helper function to build TakeVested for a taker set up with `setup_taker`
+    fn take_vested_instruction(
+        taker: Pubkey,
+        maker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        escrow: Pubkey,
+        vault: Pubkey,
+        take_amount: u64,
+    ) -> Instruction {
+        let vesting = vesting_pda(&escrow, &taker);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::TakeVested {
+                taker,
+                maker,
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_b: associated_token::get_associated_token_address(&taker, &mint_b),
+                maker_ata_b: associated_token::get_associated_token_address(&maker, &mint_b),
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                escrow,
+                vault,
+                vesting,
+                vesting_vault: associated_token::get_associated_token_address(&vesting, &mint_a),
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::TakeVested {
+                take_amount,
+                max_pay: take_amount,
+                min_receive: 0,
+            }
+            .data(),
+        }
+    }
+
+    fn taker_delegate_pda(taker: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"taker_delegate", taker.as_ref()], &PROGRAM_ID).0
+    }
+
+    /// Helper function to build the ed25519 instruction carrying `taker`'s signature
+    /// over `order` and the TakeWithSignature that lands it, in that order
+    fn take_with_signature_instructions(
+        relayer: Pubkey,
+        taker: &Keypair,
+        maker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        maker_ata_a: Option<Pubkey>,
+        vault: Pubkey,
+        order: crate::TakeOrder,
+    ) -> (Instruction, Instruction) {
+        // The ed25519 instruction carries the key, signature and message inline
+        let message = order.message().unwrap();
+        let signature = taker.sign_message(&message);
+        let header_len = 2 + 7 * 2;
+        let mut data = vec![1u8, 0];
+        for offset in [
+            header_len + 32,
+            u16::MAX,
+            header_len,
+            u16::MAX,
+            header_len + 32 + 64,
+            message.len() as u16,
+            u16::MAX,
+        ] {
+            data.extend_from_slice(&offset.to_le_bytes());
+        }
+        data.extend_from_slice(taker.pubkey().as_ref());
+        data.extend_from_slice(signature.as_ref());
+        data.extend_from_slice(&message);
+        let ed25519_ix = Instruction {
+            program_id: solana_sdk_ids::ed25519_program::ID,
+            accounts: vec![],
+            data,
+        };
+
+        let taker = taker.pubkey();
+        let take_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::TakeWithSignature {
+                relayer,
+                taker,
+                taker_delegate: taker_delegate_pda(&taker),
+                taker_nonce: Pubkey::find_program_address(
+                    &[b"taker_nonce", taker.as_ref()],
+                    &PROGRAM_ID,
+                )
+                .0,
+                maker,
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_a: associated_token::get_associated_token_address(&taker, &mint_a),
+                taker_ata_b: associated_token::get_associated_token_address(&taker, &mint_b),
+                maker_ata_b: associated_token::get_associated_token_address(&maker, &mint_b),
+                maker_ata_a,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                escrow: order.escrow,
+                vault,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                instructions: solana_sdk_ids::sysvar::instructions::ID,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::TakeWithSignature { order }.data(),
+        };
+        (ed25519_ix, take_ix)
+    }
+
+    /// True when the transaction failed with the given program error
+    fn failed_with<T>(result: Result<T, FailedTransactionMetadata>, error: ErrorCode) -> bool {
+        let expected = format!("Error Code: {}.", error.name());
+        result.is_err_and(|failed| failed.meta.logs.iter().any(|log| log.contains(&expected)))
+    }
+
+    /// Helper function to move the Clock sysvar by `seconds` (possibly backwards)
+    /// The slot is advanced as well so retried transactions get a fresh blockhash
+    fn warp_clock(program: &mut LiteSVM, seconds: i64) {
+        let mut clock: Clock = program.get_sysvar();
+        clock.unix_timestamp += seconds;
+        clock.slot += 100;
+        program.set_sysvar::<Clock>(&clock);
+        program.warp_to_slot(clock.slot);
+        program.expire_blockhash();
+    }
+
     #[test]
     fn test_make() {
         // Setup the test environment (mints and maker's ATA)
@@ -245,299 +678,8653 @@ mod tests {
             escrow_data.receive, 10,
             "Escrow receive amount should be 10"
         );
-
-        msg!("\nAll Make assertions passed!");
-    }
-
-    #[test]
-    fn test_refund() {
-        // Use helper function to setup and execute Make instruction
-        // This gives us an escrow with deposited tokens ready to be refunded
-        let (mut program, payer, mint_a, _mint_b, maker_ata_a, escrow, vault) =
-            setup_with_make(123u64, 10, 10, 0);
-
-        let maker = payer.pubkey();
-
-        // Check maker's ATA_A balance before refund
-        let maker_ata_before = program.get_account(&maker_ata_a).unwrap();
-        let maker_ata_data_before =
-            spl_token::state::Account::unpack(&maker_ata_before.data).unwrap();
-        msg!(
-            "Maker ATA balance before refund: {}",
-            maker_ata_data_before.amount
+        assert_eq!(
+            escrow_data.status,
+            crate::state::EscrowStatus::Open,
+            "New escrow should be open"
+        );
+        let clock: Clock = program.get_sysvar();
+        assert_eq!(
+            escrow_data.unlock_at, clock.unix_timestamp,
+            "Escrow with no waiting time should unlock immediately"
+        );
+        assert_eq!(
+            escrow_data.vault_address(&escrow, &TOKEN_PROGRAM_ID),
+            vault,
+            "Stored vault bump should derive the vault"
         );
 
-        // Execute Refund instruction
-        let refund_ix = Instruction {
-            program_id: PROGRAM_ID,
-            accounts: crate::accounts::Refund {
-                maker: maker,
-                mint_a: mint_a,
-                maker_ata_a: maker_ata_a,
-                escrow: escrow,
-                vault: vault,
-                associated_token_program: spl_associated_token_account::ID,
-                token_program: TOKEN_PROGRAM_ID,
-                system_program: SYSTEM_PROGRAM_ID,
-            }
-            .to_account_metas(None),
-            data: crate::instruction::Refund {}.data(),
-        };
-
-        let refund_message = Message::new(&[refund_ix], Some(&payer.pubkey()));
-        let refund_blockhash = program.latest_blockhash();
-        let refund_transaction = Transaction::new(&[&payer], refund_message, refund_blockhash);
-
-        let refund_tx = program.send_transaction(refund_transaction).unwrap();
-        msg!("\nRefund transaction successful");
-        msg!("CUs Consumed: {}", refund_tx.compute_units_consumed);
-        msg!("Tx Signature: {}", refund_tx.signature);
-
-        // Verify the results
-
-        // Check that tokens were returned to maker's ATA_A
-        let maker_ata_after = program.get_account(&maker_ata_a).unwrap();
-        let maker_ata_data_after =
-            spl_token::state::Account::unpack(&maker_ata_after.data).unwrap();
-        msg!(
-            "Maker ATA balance after refund: {}",
-            maker_ata_data_after.amount
+        // Signing and vault checks reuse the bumps stored at Make
+        let escrow_bump = Pubkey::find_program_address(
+            &[
+                b"escrow",
+                maker.as_ref(),
+                &123u64.to_le_bytes(),
+                mint_a.as_ref(),
+                mint_b.as_ref(),
+            ],
+            &PROGRAM_ID,
+        )
+        .1;
+        assert_eq!(
+            escrow_data.bump, escrow_bump,
+            "Escrow bump should be stored"
         );
+        let vault_bump = Pubkey::find_program_address(
+            &[escrow.as_ref(), TOKEN_PROGRAM_ID.as_ref(), mint_a.as_ref()],
+            &spl_associated_token_account::ID,
+        )
+        .1;
         assert_eq!(
-            maker_ata_data_after.amount,
-            maker_ata_data_before.amount + 10,
-            "Tokens should be returned to maker"
+            escrow_data.vault_bump, vault_bump,
+            "Vault bump should be stored"
         );
 
-        msg!("\nAll refund assertions passed!");
+        msg!("\nAll Make assertions passed!");
     }
 
     #[test]
-    fn test_take() {
+    fn test_make_reuses_seed_across_pairs() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        let (escrow, _vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            123u64,
+            10,
+            10,
+            0,
+        );
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert!(
+            escrow_data.mint_seeded,
+            "New escrows are seeded with their mints"
+        );
+
+        // The same seed is free again for a different pair
+        let other_mint_b = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&maker)
+            .send()
+            .unwrap();
+        let (other_escrow, _vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            other_mint_b,
+            maker_ata_a,
+            123u64,
+            10,
+            10,
+            0,
+        );
+        assert_ne!(escrow, other_escrow);
+
+        // Reusing it for the same pair still fails at Make
+        let (make_ix, _, _) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 10, 10, 0);
+        program.expire_blockhash();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            program.send_transaction(transaction).is_err(),
+            "Make should fail when the seed is already used for the pair"
+        );
+    }
+
+    #[test]
+    fn test_make_auto() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let seed_counter =
+            Pubkey::find_program_address(&[b"seed_counter", maker.as_ref()], &PROGRAM_ID).0;
+
+        let mut make_auto = |seed: u64| {
+            let (make_ix, escrow, _vault) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, seed, 10, 10, 0);
+            let mut accounts = make_ix.accounts;
+            accounts.push(solana_instruction::AccountMeta::new(seed_counter, false));
+            accounts.push(solana_instruction::AccountMeta::new_readonly(
+                SYSTEM_PROGRAM_ID,
+                false,
+            ));
+            let make_auto_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts,
+                data: crate::instruction::MakeAuto {
+                    seed,
+                    deposit: 10,
+                    receive: 10,
+                    waiting_time: 0,
+                    expiry: 0,
+                }
+                .data(),
+            };
+            let message = Message::new(&[make_auto_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            program.expire_blockhash();
+            (program.send_transaction(transaction), escrow)
+        };
+
+        // The counter starts at 0, any other seed is turned away
+        let (result, _) = make_auto(1);
+        assert!(
+            failed_with(result, ErrorCode::SeedNotNext),
+            "MakeAuto should only accept the counter's next seed"
+        );
+
+        let (result, first) = make_auto(0);
+        result.unwrap();
+        let (result, second) = make_auto(1);
+        result.unwrap();
+        assert_eq!(first, escrow_pda(maker, 0, mint_a, mint_b));
+        assert_eq!(second, escrow_pda(maker, 1, mint_a, mint_b));
+
+        // A used seed cannot be made again
+        let (result, _) = make_auto(0);
+        assert!(result.is_err());
+
+        let counter = crate::state::SeedCounter::try_deserialize(
+            &mut program.get_account(&seed_counter).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(counter.maker, maker);
+        assert_eq!(counter.next_seed, 2);
+    }
+
+    #[test]
+    fn test_make_delegated() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let bot = Keypair::new();
+        program
+            .airdrop(&bot.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        // The maker approves the bot once, for at most 10
+        let approve_ix = spl_token_2022::instruction::approve(
+            &TOKEN_PROGRAM_ID,
+            &maker_ata_a,
+            &bot.pubkey(),
+            &maker,
+            &[],
+            10,
+        )
+        .unwrap();
+        let message = Message::new(&[approve_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let make_delegated = |program: &mut LiteSVM, seed: u64, deposit: u64| {
+            let escrow = escrow_pda(maker, seed, mint_a, mint_b);
+            let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+            let make_delegated_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::MakeDelegated {
+                    delegate: bot.pubkey(),
+                    maker,
+                    config: config_pda(),
+                    mint_a,
+                    mint_b,
+                    maker_ata_a,
+                    escrow,
+                    vault,
+                    maker_registry: maker_registry_pda(maker),
+                    maker_stats: None,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                    event_authority: event_authority_pda(),
+                    program: PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::MakeDelegated {
+                    seed,
+                    deposit,
+                    receive: 10,
+                    waiting_time: 0,
+                    expiry: 0,
+                }
+                .data(),
+            };
+            let message = Message::new(&[make_delegated_ix], Some(&bot.pubkey()));
+            let transaction = Transaction::new(&[&bot], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result.map(|_| (escrow, vault))
+        };
+
+        // Nothing past the approved amount
+        assert!(failed_with(
+            make_delegated(&mut program, 1, 11),
+            ErrorCode::InvalidDelegate
+        ));
+        let (escrow, vault) = make_delegated(&mut program, 1, 10).unwrap();
+
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.maker, maker);
+        assert_eq!(escrow_data.rent_payer, bot.pubkey());
+        let vault_data =
+            spl_token::state::Account::unpack(&program.get_account(&vault).unwrap().data).unwrap();
+        assert_eq!(vault_data.amount, 10);
+    }
+
+    #[test]
+    fn test_escrow_memcmp_offsets() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        let (escrow, _vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            123u64,
+            10,
+            10,
+            0,
+        );
+
+        // Scanners rely on these fields sitting at fixed offsets
+        let data = program.get_account(&escrow).unwrap().data;
+        let field = |offset: usize| &data[offset..offset + 32];
+        assert_eq!(field(crate::state::Escrow::MAKER_OFFSET), maker.as_ref());
+        assert_eq!(field(crate::state::Escrow::MINT_A_OFFSET), mint_a.as_ref());
+        assert_eq!(field(crate::state::Escrow::MINT_B_OFFSET), mint_b.as_ref());
+        assert_eq!(
+            data[crate::state::Escrow::STATUS_OFFSET],
+            crate::state::EscrowStatus::Open as u8
+        );
+
+        msg!("\nAll offset assertions passed!");
+    }
+
+    #[test]
+    fn test_make_rejects_terms_outside_config_bounds() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        // Deposit below the configured minimum
+        let (make_ix, _, _) = make_instruction(maker, mint_a, mint_b, maker_ata_a, 1u64, 0, 10, 0);
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::DepositTooSmall
+            ),
+            "Make should fail with a deposit below min_deposit"
+        );
+
+        // Waiting time above the configured maximum (e.g. a 100-year wait)
+        let (make_ix, _, _) = make_instruction(
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            2u64,
+            10,
+            10,
+            100 * 365 * 24 * 60 * 60,
+        );
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::WaitingTimeTooLong
+            ),
+            "Make should fail with a waiting time above max_waiting_time"
+        );
+
+        // Mint with decimals above the configured range
+        let wide_mint = CreateMint::new(&mut program, &payer)
+            .decimals(MAX_DECIMALS + 1)
+            .authority(&maker)
+            .send()
+            .unwrap();
+        let (make_ix, _, _) =
+            make_instruction(maker, mint_a, wide_mint, maker_ata_a, 3u64, 10, 10, 0);
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::DecimalsOutOfRange
+            ),
+            "Make should fail when a mint's decimals are out of range"
+        );
+
+        // Terms inside the bounds still go through
+        execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            4u64,
+            10,
+            10,
+            MAX_WAITING_TIME,
+        );
+
+        msg!("\nAll config bounds assertions passed!");
+    }
+
+    #[test]
+    fn test_refund() {
         // Use helper function to setup and execute Make instruction
-        // This creates the escrow with maker's tokens deposited
+        // This gives us an escrow with deposited tokens ready to be refunded
+        let (mut program, payer, mint_a, _mint_b, maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+
+        let maker = payer.pubkey();
+
+        // Check maker's ATA_A balance before refund
+        let maker_ata_before = program.get_account(&maker_ata_a).unwrap();
+        let maker_ata_data_before =
+            spl_token::state::Account::unpack(&maker_ata_before.data).unwrap();
+        msg!(
+            "Maker ATA balance before refund: {}",
+            maker_ata_data_before.amount
+        );
+
+        // Execute Refund instruction
+        let refund_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker: maker,
+                rent_payer: maker,
+                mint_a: mint_a,
+                maker_ata_a: maker_ata_a,
+                escrow: escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault: vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+
+        // Refund checks the vault against the stored bump
+        let mut wrong_vault_ix = refund_ix.clone();
+        for meta in wrong_vault_ix.accounts.iter_mut() {
+            if meta.pubkey == vault {
+                meta.pubkey = maker_ata_a;
+            }
+        }
+        let message = Message::new(&[wrong_vault_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::InvalidVault
+            ),
+            "Refund should reject a vault other than the stored one"
+        );
+
+        let refund_message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+        let refund_blockhash = program.latest_blockhash();
+        let refund_transaction = Transaction::new(&[&payer], refund_message, refund_blockhash);
+
+        let refund_tx = program.send_transaction(refund_transaction).unwrap();
+        msg!("\nRefund transaction successful");
+        msg!("CUs Consumed: {}", refund_tx.compute_units_consumed);
+        msg!("Tx Signature: {}", refund_tx.signature);
+
+        // Verify the results
+
+        // Check that tokens were returned to maker's ATA_A
+        let maker_ata_after = program.get_account(&maker_ata_a).unwrap();
+        let maker_ata_data_after =
+            spl_token::state::Account::unpack(&maker_ata_after.data).unwrap();
+        msg!(
+            "Maker ATA balance after refund: {}",
+            maker_ata_data_after.amount
+        );
+        assert_eq!(
+            maker_ata_data_after.amount,
+            maker_ata_data_before.amount + 10,
+            "Tokens should be returned to maker"
+        );
+
+        msg!("\nAll refund assertions passed!");
+    }
+
+    #[test]
+    fn test_refund_with_memo() {
+        let (mut program, payer, mint_a, _mint_b, maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+        let maker = payer.pubkey();
+
+        let mut refund = |memo: Option<String>, memo_program: Option<Pubkey>| {
+            let refund_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::Refund {
+                    maker,
+                    rent_payer: maker,
+                    mint_a,
+                    maker_ata_a,
+                    escrow,
+                    maker_registry: Some(maker_registry_pda(maker)),
+                    maker_stats: None,
+                    vault,
+                    memo_program,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                    event_authority: event_authority_pda(),
+                    program: PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::Refund { memo }.data(),
+            };
+            let message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            program.expire_blockhash();
+            program.send_transaction(transaction)
+        };
+        let memo_program = Some(crate::memo::MEMO_PROGRAM_ID);
+
+        let long_memo = "x".repeat(crate::memo::MAX_MEMO_LEN + 1);
+        assert!(
+            failed_with(
+                refund(Some(long_memo), memo_program),
+                ErrorCode::MemoTooLong
+            ),
+            "Refund should reject a memo over the length limit"
+        );
+        assert!(
+            failed_with(
+                refund(Some("order-42".to_string()), None),
+                ErrorCode::InvalidMemoProgram
+            ),
+            "Refund should need the memo program to log a memo"
+        );
+
+        let result = refund(Some("order-42".to_string()), memo_program).unwrap();
+        assert!(
+            result.logs.iter().any(|log| log.contains("order-42")),
+            "Memo should be logged by the memo program"
+        );
+        let escrow_account = program.get_account(&escrow);
+        assert!(escrow_account.is_none() || escrow_account.unwrap().data.is_empty());
+
+        msg!("\nAll memo assertions passed!");
+    }
+
+    #[test]
+    fn test_maker_registry() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let registry = maker_registry_pda(maker);
+        let read_registry = |program: &LiteSVM| {
+            let account = program.get_account(&registry).unwrap();
+            crate::state::MakerRegistry::try_deserialize(&mut account.data.as_ref()).unwrap()
+        };
+
+        let (refunded, refunded_vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            1u64,
+            10,
+            10,
+            0,
+        );
+        let (taken, taken_vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            2u64,
+            10,
+            10,
+            0,
+        );
+
+        // Both escrows are listed, oldest first
+        let maker_registry = read_registry(&program);
+        assert_eq!(maker_registry.maker, maker);
+        assert_eq!(maker_registry.open_escrows, 2);
+        assert_eq!(
+            maker_registry.recent,
+            vec![
+                crate::state::RegisteredEscrow {
+                    escrow: refunded,
+                    seed: 1
+                },
+                crate::state::RegisteredEscrow {
+                    escrow: taken,
+                    seed: 2
+                },
+            ]
+        );
+
+        let refund_ix = |maker_registry: Option<Pubkey>| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker,
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                escrow: refunded,
+                maker_registry,
+                maker_stats: None,
+                vault: refunded_vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+
+        // A registered escrow cannot close without updating the registry
+        let message = Message::new(&[refund_ix(None)], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::MakerRegistryRequired
+            ),
+            "Refund should need the maker registry"
+        );
+
+        program.expire_blockhash();
+        let message = Message::new(&[refund_ix(Some(registry))], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let maker_registry = read_registry(&program);
+        assert_eq!(maker_registry.open_escrows, 1);
+        assert_eq!(maker_registry.recent.len(), 1);
+        assert_eq!(maker_registry.recent[0].escrow, taken);
+
+        // Filling the other escrow empties the registry
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let take_ix = take_instruction(
+            taker.pubkey(),
+            maker,
+            mint_a,
+            mint_b,
+            taken,
+            taken_vault,
+            10,
+        );
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let maker_registry = read_registry(&program);
+        assert_eq!(maker_registry.open_escrows, 0);
+        assert!(maker_registry.recent.is_empty());
+    }
+
+    #[test]
+    fn test_maker_stats() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let maker_stats =
+            Pubkey::find_program_address(&[b"maker_stats", maker.as_ref()], &PROGRAM_ID).0;
+        let read_stats = |program: &LiteSVM| {
+            let account = program.get_account(&maker_stats).unwrap();
+            crate::state::MakerStats::try_deserialize(&mut account.data.as_ref()).unwrap()
+        };
+
+        let init_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::InitMakerStats {
+                maker,
+                maker_stats,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::InitMakerStats {}.data(),
+        };
+        let message = Message::new(&[init_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let mut make = |seed: u64, receive: u64| {
+            let (mut make_ix, escrow, vault) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, seed, 10, receive, 0);
+            make_ix.accounts = crate::accounts::Make {
+                maker,
+                payer: maker,
+                config: config_pda(),
+                mint_a,
+                mint_b,
+                maker_ata_a: Some(maker_ata_a),
+                escrow,
+                maker_registry: maker_registry_pda(maker),
+                maker_stats: Some(maker_stats),
+                vault,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None);
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            program.send_transaction(transaction).unwrap();
+            (escrow, vault)
+        };
+        let (taken, taken_vault) = make(1, 40);
+        let (refunded, refunded_vault) = make(2, 10);
+
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&taken).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.stats, Some(maker_stats));
+        assert_eq!(read_stats(&program).escrows_created, 2);
+
+        // Two partial takes fill the first escrow
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        for _ in 0..2 {
+            let take_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::Take {
+                    taker: taker.pubkey(),
+                    payer: taker.pubkey(),
+                    maker,
+                    rent_payer: maker,
+                    mint_a,
+                    mint_b,
+                    taker_ata_a: associated_token::get_associated_token_address(
+                        &taker.pubkey(),
+                        &mint_a,
+                    ),
+                    taker_ata_b: associated_token::get_associated_token_address(
+                        &taker.pubkey(),
+                        &mint_b,
+                    ),
+                    maker_ata_b: associated_token::get_associated_token_address(&maker, &mint_b),
+                    maker_ata_a: None,
+                    config: config_pda(),
+                    treasury: TREASURY,
+                    treasury_ata_b: associated_token::get_associated_token_address(
+                        &TREASURY, &mint_b,
+                    ),
+                    referrer_ata_b: None,
+                    escrow: taken,
+                    maker_registry: Some(maker_registry_pda(maker)),
+                    maker_stats: Some(maker_stats),
+                    vault: taken_vault,
+                    proceeds: None,
+                    proceeds_vault: None,
+                    price_update: None,
+                    memo_program: None,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                    event_authority: event_authority_pda(),
+                    program: PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::Take {
+                    take_amount: 20,
+                    proof: vec![],
+                    max_pay: 20,
+                    min_receive: 0,
+                    memo: None,
+                    preimage: None,
+                }
+                .data(),
+            };
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            program.send_transaction(transaction).unwrap();
+            program.expire_blockhash();
+        }
+
+        let stats = read_stats(&program);
+        assert_eq!(stats.escrows_filled, 1);
+        assert_eq!(
+            stats.volume,
+            vec![
+                crate::state::MintVolume {
+                    mint: mint_a,
+                    amount: 10
+                },
+                crate::state::MintVolume {
+                    mint: mint_b,
+                    amount: 40
+                },
+            ]
+        );
+
+        let refund_ix = |maker_stats: Option<Pubkey>| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker,
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                escrow: refunded,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats,
+                vault: refunded_vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+
+        // An escrow reporting to the stats cannot skip them
+        let message = Message::new(&[refund_ix(None)], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::MakerStatsRequired
+            ),
+            "Refund should need the maker stats"
+        );
+
+        program.expire_blockhash();
+        let message = Message::new(&[refund_ix(Some(maker_stats))], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let stats = read_stats(&program);
+        assert_eq!(stats.escrows_created, 2);
+        assert_eq!(stats.escrows_filled, 1);
+        assert_eq!(stats.escrows_refunded, 1);
+    }
+
+    #[test]
+    fn test_take_many() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        let (first, first_vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            1u64,
+            10,
+            10,
+            0,
+        );
+        let (second, second_vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            2u64,
+            20,
+            40,
+            0,
+        );
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        // Fills the first escrow and half of the second
+        let take_many_ix = |min_receive: u64| {
+            take_many_instruction(
+                taker.pubkey(),
+                maker,
+                mint_a,
+                mint_b,
+                None,
+                &[(first, first_vault), (second, second_vault)],
+                vec![10, 20],
+                30,
+                min_receive,
+            )
+        };
+
+        // Either every escrow is taken or none is
+        let message = Message::new(&[take_many_ix(21)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::SlippageExceeded
+            ),
+            "TakeMany should fail when the total received is too low"
+        );
+        assert_eq!(token_amount(&program, &first_vault), 10);
+        assert_eq!(token_amount(&program, &second_vault), 20);
+
+        program.expire_blockhash();
+        let message = Message::new(&[take_many_ix(20)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(token_amount(&program, &taker_ata_a), 20);
+        assert!(
+            program.get_account(&first).is_none(),
+            "Filled escrow should be closed"
+        );
+        assert!(
+            program.get_account(&first_vault).is_none(),
+            "Filled escrow's vault should be closed"
+        );
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&second).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.remaining_deposit, 10);
+        assert_eq!(escrow_data.remaining_receive, 20);
+        assert_eq!(token_amount(&program, &second_vault), 10);
+
+        let maker_registry = crate::state::MakerRegistry::try_deserialize(
+            &mut program
+                .get_account(&maker_registry_pda(maker))
+                .unwrap()
+                .data
+                .as_ref(),
+        )
+        .unwrap();
+        assert_eq!(maker_registry.open_escrows, 1);
+        assert_eq!(maker_registry.recent[0].escrow, second);
+    }
+
+    #[test]
+    fn test_cross() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker_x = payer.pubkey();
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        // Maker Y sells mint_b for mint_a
+        let maker_y_keypair = Keypair::new();
+        let maker_y = maker_y_keypair.pubkey();
+        program.airdrop(&maker_y, 100 * LAMPORTS_PER_SOL).unwrap();
+        let maker_y_ata_b =
+            CreateAssociatedTokenAccount::new(&mut program, &maker_y_keypair, &mint_b)
+                .owner(&maker_y)
+                .send()
+                .unwrap();
+        MintTo::new(&mut program, &payer, &mint_b, &maker_y_ata_b, 22)
+            .send()
+            .unwrap();
+
+        // 10 mint_a for 10 mint_b, crossed with 12 mint_b for 8 mint_a
+        let (escrow_x, vault_x) = execute_make(
+            &mut program,
+            &payer,
+            maker_x,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            1u64,
+            10,
+            10,
+            0,
+        );
+        let (escrow_y, vault_y) = execute_make(
+            &mut program,
+            &maker_y_keypair,
+            maker_y,
+            mint_b,
+            mint_a,
+            maker_y_ata_b,
+            1u64,
+            12,
+            8,
+            0,
+        );
+        // 10 mint_b for 20 mint_a, dearer than escrow_x sells at
+        let (dear, dear_vault) = execute_make(
+            &mut program,
+            &maker_y_keypair,
+            maker_y,
+            mint_b,
+            mint_a,
+            maker_y_ata_b,
+            2u64,
+            10,
+            20,
+            0,
+        );
+
+        let cranker = Keypair::new();
+        program
+            .airdrop(&cranker.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+        let cross_ix = |escrow_y: Pubkey, vault_y: Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Cross {
+                cranker: cranker.pubkey(),
+                maker_x,
+                maker_y,
+                rent_payer_x: maker_x,
+                rent_payer_y: maker_y,
+                mint_a,
+                mint_b,
+                escrow_x,
+                escrow_y,
+                vault_x,
+                vault_y,
+                maker_x_ata_b: associated_token::get_associated_token_address(&maker_x, &mint_b),
+                maker_y_ata_a: associated_token::get_associated_token_address(&maker_y, &mint_a),
+                maker_x_ata_a: None,
+                maker_y_ata_b: None,
+                cranker_ata_a: associated_token::get_associated_token_address(
+                    &cranker.pubkey(),
+                    &mint_a,
+                ),
+                cranker_ata_b: associated_token::get_associated_token_address(
+                    &cranker.pubkey(),
+                    &mint_b,
+                ),
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_a: associated_token::get_associated_token_address(&TREASURY, &mint_a),
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                maker_registry_x: Some(maker_registry_pda(maker_x)),
+                maker_registry_y: Some(maker_registry_pda(maker_y)),
+                maker_stats_x: None,
+                maker_stats_y: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Cross {}.data(),
+        };
+
+        let message = Message::new(&[cross_ix(dear, dear_vault)], Some(&cranker.pubkey()));
+        let transaction = Transaction::new(&[&cranker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::PricesDoNotCross
+            ),
+            "Cross should fail when the prices do not cross"
+        );
+
+        // escrow_x is filled with 10 mint_b and pays 8 * 10 / 12 = 6.67, rounded up
+        // to 7 mint_a, for escrow_y to release 12 * 7 / 8 = 10.5, rounded down to 10
+        let message = Message::new(&[cross_ix(escrow_y, vault_y)], Some(&cranker.pubkey()));
+        let transaction = Transaction::new(&[&cranker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(
+            token_amount(
+                &program,
+                &associated_token::get_associated_token_address(&maker_x, &mint_b)
+            ),
+            10
+        );
+        assert_eq!(
+            token_amount(
+                &program,
+                &associated_token::get_associated_token_address(&maker_y, &mint_a)
+            ),
+            7
+        );
+        // The 3 mint_a escrow_x had left over is the cranker's spread
+        assert_eq!(
+            token_amount(
+                &program,
+                &associated_token::get_associated_token_address(&cranker.pubkey(), &mint_a)
+            ),
+            3
+        );
+        assert!(
+            program.get_account(&escrow_x).is_none(),
+            "Filled escrow should be closed"
+        );
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow_y).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.remaining_deposit, 2);
+        assert_eq!(escrow_data.remaining_receive, 1);
+        assert_eq!(token_amount(&program, &vault_y), 2);
+    }
+
+    #[test]
+    fn test_refund_timelock() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+
+        let make_data = |seed: u64, expiry: i64| crate::instruction::Make {
+            seed,
+            args: crate::MakeArgs {
+                deposit: 10,
+                receive: 10,
+                waiting_time: 0,
+                expiry,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: Some(crate::RefundTerms {
+                    no_refund_before: now + 1000,
+                    cancel_fee: 0,
+                    cancel_fee_window: 0,
+                }),
+                settlement_terms: None,
+            },
+        };
+
+        // The lock cannot outlast the expiry
+        let (mut make_ix, _escrow, _vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 124u64, 10, 10, 0);
+        make_ix.data = make_data(124u64, now + 500).data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::InvalidRefundLock
+        ));
+
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 10, 10, 0);
+        make_ix.data = make_data(123u64, 0).data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let refund_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker,
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+
+        let message = Message::new(&[refund_ix.clone()], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::RefundLocked
+        ));
+
+        warp_clock(&mut program, 1000);
+        let message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let escrow_account = program.get_account(&escrow);
+        assert!(
+            escrow_account.is_none() || escrow_account.unwrap().data.is_empty(),
+            "Escrow should be closed once the refund lock has passed"
+        );
+
+        msg!("\nAll refund timelock assertions passed!");
+    }
+
+    #[test]
+    fn test_take() {
+        // Use helper function to setup and execute Make instruction
+        // This creates the escrow with maker's tokens deposited
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 40, 0);
+
+        let maker = payer.pubkey();
+
+        // Setup TAKER (new user who will take the offer)
+        let taker = Keypair::new();
+        program
+            .airdrop(&taker.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        // Create taker's ATA for Mint A (to receive tokens from escrow)
+        let taker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_a)
+            .owner(&taker.pubkey())
+            .send()
+            .unwrap();
+        msg!("Taker ATA A: {}\n", taker_ata_a);
+
+        // Create taker's ATA for Mint B (to send tokens to maker)
+        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
+            .owner(&taker.pubkey())
+            .send()
+            .unwrap();
+        msg!("Taker ATA B: {}\n", taker_ata_b);
+
+        // Mint tokens to taker's ATA B (so taker has tokens to trade)
+        MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000)
+            .send()
+            .unwrap();
+
+
+        let maker_ata_b =
+            spl_associated_token_account::get_associated_token_address(&maker, &mint_b);
+        msg!("Maker ATA B : {}\n", maker_ata_b);
+
+        // Execute Take instruction
+        let take_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                payer: taker.pubkey(),
+                maker: maker,
+                rent_payer: maker,
+                mint_a: mint_a,
+                mint_b: mint_b,
+                taker_ata_a: taker_ata_a,
+                taker_ata_b: taker_ata_b,
+                maker_ata_b: maker_ata_b,
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                referrer_ata_b: None,
+                escrow: escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault: vault,
+                proceeds: None,
+                proceeds_vault: None,
+                price_update: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take {
+                take_amount: 40,
+                proof: vec![],
+                max_pay: 40,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data(),
+        };
+
+        let take_message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let take_blockhash = program.latest_blockhash();
+        let take_transaction = Transaction::new(&[&taker], take_message, take_blockhash);
+
+        let take_tx = program.send_transaction(take_transaction).unwrap();
+        msg!("\nTake transaction successful");
+        msg!("CUs Consumed: {}", take_tx.compute_units_consumed);
+        msg!("Tx Signature: {}", take_tx.signature);
+        assert!(
+            take_tx.compute_units_consumed <= TAKE_CU_BUDGET,
+            "Take should stay within its compute budget"
+        );
+
+        // Verify the swap completed correctly
+
+        // Check taker received 10 tokens of Mint A from vault
+        let taker_ata_a_account = program.get_account(&taker_ata_a).unwrap();
+        let taker_ata_a_data =
+            spl_token::state::Account::unpack(&taker_ata_a_account.data).unwrap();
+        assert_eq!(
+            taker_ata_a_data.amount, 10,
+            "Taker should receive 10 tokens of mint A"
+        );
+
+        // Check maker received 40 tokens of Mint B from taker
+        let maker_ata_b_account = program.get_account(&maker_ata_b).unwrap();
+        let maker_ata_b_data =
+            spl_token::state::Account::unpack(&maker_ata_b_account.data).unwrap();
+        assert_eq!(
+            maker_ata_b_data.amount, 40,
+            "Maker should receive 10 tokens of mint B"
+        );
+
+        msg!("\nAll Take assertions passed!");
+    }
+
+    #[test]
+    fn test_take_with_relayer() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        // The relayer pays the transaction fee and creates maker_ata_b
+        let relayer = Keypair::new();
+        program
+            .airdrop(&relayer.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+        let mut take_ix =
+            take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        take_ix.accounts[1] = solana_instruction::AccountMeta::new(relayer.pubkey(), true);
+
+        let taker_before = program.get_balance(&taker.pubkey()).unwrap();
+        let relayer_before = program.get_balance(&relayer.pubkey()).unwrap();
+        let message = Message::new(&[take_ix], Some(&relayer.pubkey()));
+        let transaction =
+            Transaction::new(&[&relayer, &taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(
+            program.get_balance(&taker.pubkey()).unwrap(),
+            taker_before,
+            "Taker should not pay for anything"
+        );
+        assert!(program.get_balance(&relayer.pubkey()).unwrap() < relayer_before);
+        let taker_ata_a_account = program.get_account(&taker_ata_a).unwrap();
+        let taker_ata_a_data =
+            spl_token::state::Account::unpack(&taker_ata_a_account.data).unwrap();
+        assert_eq!(taker_ata_a_data.amount, 10);
+    }
+
+    #[test]
+    fn test_take_with_signature() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 20, 20, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let taker_delegate = taker_delegate_pda(&taker.pubkey());
+
+        // One-off approval, after which the taker only signs orders
+        let approve_ix = spl_token_2022::instruction::approve(
+            &TOKEN_PROGRAM_ID,
+            &taker_ata_b,
+            &taker_delegate,
+            &taker.pubkey(),
+            &[],
+            20,
+        )
+        .unwrap();
+        let message = Message::new(&[approve_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let relayer = Keypair::new();
+        program
+            .airdrop(&relayer.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+        let clock: Clock = program.get_sysvar();
+        let order = crate::TakeOrder {
+            escrow,
+            take_amount: 10,
+            max_pay: 10,
+            min_receive: 10,
+            expiry: clock.unix_timestamp + 60,
+            nonce: 0,
+        };
+
+        let (ed25519_ix, take_ix) = take_with_signature_instructions(
+            relayer.pubkey(),
+            &taker,
+            maker,
+            mint_a,
+            mint_b,
+            None,
+            vault,
+            order,
+        );
+
+        // Only the relayer signs the transaction
+        let message = Message::new(
+            &[ed25519_ix.clone(), take_ix.clone()],
+            Some(&relayer.pubkey()),
+        );
+        let transaction = Transaction::new(&[&relayer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let taker_ata_a_account = program.get_account(&taker_ata_a).unwrap();
+        let taker_ata_a_data =
+            spl_token::state::Account::unpack(&taker_ata_a_account.data).unwrap();
+        assert_eq!(taker_ata_a_data.amount, 10);
+
+        // The same signed order cannot be landed twice
+        program.expire_blockhash();
+        let message = Message::new(&[ed25519_ix, take_ix.clone()], Some(&relayer.pubkey()));
+        let transaction = Transaction::new(&[&relayer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::NonceNotNext
+            ),
+            "A signed order should fill only once"
+        );
+
+        // Nor without the taker's signature in front of it
+        let message = Message::new(&[take_ix], Some(&relayer.pubkey()));
+        let transaction = Transaction::new(&[&relayer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::InvalidSignature
+            ),
+            "TakeWithSignature should need the ed25519 instruction"
+        );
+    }
+
+    #[test]
+    fn test_reveal_take() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let set_window_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::SetRevealWindow { maker, escrow }.to_account_metas(None),
+            data: crate::instruction::SetRevealWindow { reveal_window: 150 }.data(),
+        };
+        let message = Message::new(&[set_window_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_ix.clone()], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::CommitRequired
+            ),
+            "Take should need a commitment once a reveal window is set"
+        );
+
+        let salt = [9u8; 32];
+        let commitment = Pubkey::find_program_address(
+            &[b"take_commitment", escrow.as_ref(), taker.pubkey().as_ref()],
+            &PROGRAM_ID,
+        )
+        .0;
+        let commit_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::CommitTake {
+                taker: taker.pubkey(),
+                escrow,
+                commitment,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::CommitTake {
+                hash: crate::state::TakeCommitment::hash(&taker.pubkey(), &salt),
+            }
+            .data(),
+        };
+        // RevealTake's accounts are Take's followed by the commitment
+        let mut reveal_accounts = take_ix.accounts;
+        reveal_accounts.push(solana_instruction::AccountMeta::new(commitment, false));
+        let reveal_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: reveal_accounts,
+            data: crate::instruction::RevealTake {
+                salt,
+                take_amount: 10,
+                proof: vec![],
+                max_pay: 10,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data(),
+        };
+
+        // Committing and revealing in the same slot gives nothing away in advance
+        let message = Message::new(
+            &[commit_ix.clone(), reveal_ix.clone()],
+            Some(&taker.pubkey()),
+        );
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::RevealOutsideWindow
+            ),
+            "Reveal should need a later slot than the commitment"
+        );
+
+        let message = Message::new(&[commit_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        warp_clock(&mut program, 1);
+        let message = Message::new(&[reveal_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let taker_ata_a_account = program.get_account(&taker_ata_a).unwrap();
+        let taker_ata_a_data =
+            spl_token::state::Account::unpack(&taker_ata_a_account.data).unwrap();
+        assert_eq!(taker_ata_a_data.amount, 10);
+        assert!(
+            program.get_account(&commitment).is_none(),
+            "Commitment should be closed by the reveal"
+        );
+    }
+
+    #[test]
+    fn test_hashlock() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let secret = [7u8; 32];
+        let hashlock = anchor_lang::solana_program::hash::hashv(&[&secret]).to_bytes();
+        let make_data = |seed: u64, no_refund_before: i64| crate::instruction::Make {
+            seed,
+            args: crate::MakeArgs {
+                deposit: 10,
+                receive: 10,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: Some(crate::RefundTerms {
+                    no_refund_before,
+                    cancel_fee: 0,
+                    cancel_fee_window: 0,
+                }),
+                settlement_terms: Some(crate::SettlementTerms {
+                    hashlock: Some(hashlock),
+                    arbiter: None,
+                    review_period: None,
+                    vesting_duration: 0,
+                }),
+            },
+        };
+
+        // Without a refund lock the maker could refund before the taker claims
+        let (mut make_ix, _escrow, _vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 124u64, 10, 10, 0);
+        make_ix.data = make_data(124u64, 0).data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::InvalidHashlock
+        ));
+
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 10, 10, 0);
+        make_ix.data = make_data(123u64, now + 1000).data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let take_data = |preimage: Option<[u8; 32]>| crate::instruction::Take {
+            take_amount: 10,
+            proof: vec![],
+            max_pay: 10,
+            min_receive: 0,
+            memo: None,
+            preimage,
+        };
+
+        for preimage in [None, Some([8u8; 32])] {
+            let mut take_ix = take_ix.clone();
+            take_ix.data = take_data(preimage).data();
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            assert!(
+                failed_with(
+                    program.send_transaction(transaction),
+                    ErrorCode::InvalidPreimage
+                ),
+                "Take should need the hashlock's preimage"
+            );
+            program.expire_blockhash();
+        }
+
+        let mut take_ix = take_ix;
+        take_ix.data = take_data(Some(secret)).data();
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let taker_ata_a_account = program.get_account(&taker_ata_a).unwrap();
+        let taker_ata_a_data =
+            spl_token::state::Account::unpack(&taker_ata_a_account.data).unwrap();
+        assert_eq!(taker_ata_a_data.amount, 10);
+    }
+
+    #[test]
+    fn test_dispute() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let arbiter = Keypair::new();
+        program
+            .airdrop(&arbiter.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        let make_data = |seed: u64, taker: Option<Pubkey>| crate::instruction::Make {
+            seed,
+            args: crate::MakeArgs {
+                deposit: 10,
+                receive: 10,
+                waiting_time: 0,
+                expiry: 0,
+                taker,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: Some(crate::SettlementTerms {
+                    hashlock: None,
+                    arbiter: Some(arbiter.pubkey()),
+                    review_period: None,
+                    vesting_duration: 0,
+                }),
+            },
+        };
+
+        // An arbiter rules between the maker and a designated taker
+        let (mut make_ix, _escrow, _vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 124u64, 10, 10, 0);
+        make_ix.data = make_data(124u64, None).data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::InvalidArbiter
+        ));
+
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 10, 10, 0);
+        make_ix.data = make_data(123u64, Some(taker.pubkey())).data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let raise_ix = |disputant: Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::RaiseDispute {
+                disputant,
+                maker,
+                escrow,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::RaiseDispute {}.data(),
+        };
+
+        let stranger = Keypair::new();
+        program
+            .airdrop(&stranger.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+        let message = Message::new(&[raise_ix(stranger.pubkey())], Some(&stranger.pubkey()));
+        let transaction = Transaction::new(&[&stranger], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::NotDisputant
+        ));
+
+        let message = Message::new(&[raise_ix(taker.pubkey())], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let escrow_account = program.get_account(&escrow).unwrap();
+        let escrow_data =
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+        assert_eq!(escrow_data.status, crate::state::EscrowStatus::Disputed);
+
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::EscrowDisputed
+            ),
+            "A disputed escrow cannot be taken"
+        );
+
+        let refund_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker,
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+        let message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::EscrowDisputed
+            ),
+            "A disputed escrow cannot be refunded"
+        );
+
+        let resolve_ix = |signer: Pubkey, taker_amount: u64| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Resolve {
+                arbiter: signer,
+                maker,
+                taker: taker.pubkey(),
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                taker_ata_a,
+                escrow,
+                vault,
+                maker_registry: Some(maker_registry_pda(maker)),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Resolve { taker_amount }.data(),
+        };
+
+        let message = Message::new(&[resolve_ix(maker, 4)], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::NotArbiter
+        ));
+
+        let message = Message::new(&[resolve_ix(arbiter.pubkey(), 11)], Some(&arbiter.pubkey()));
+        let transaction = Transaction::new(&[&arbiter], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::InvalidRuling
+        ));
+
+        let maker_ata_a_before =
+            spl_token::state::Account::unpack(&program.get_account(&maker_ata_a).unwrap().data)
+                .unwrap()
+                .amount;
+        let message = Message::new(&[resolve_ix(arbiter.pubkey(), 4)], Some(&arbiter.pubkey()));
+        let transaction = Transaction::new(&[&arbiter], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let taker_ata_a_data =
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(taker_ata_a_data.amount, 4);
+        let maker_ata_a_data =
+            spl_token::state::Account::unpack(&program.get_account(&maker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(maker_ata_a_data.amount, maker_ata_a_before + 6);
+        assert!(
+            program.get_account(&escrow).is_none(),
+            "Escrow should be closed by the ruling"
+        );
+    }
+
+    #[test]
+    fn test_two_sided_escrow() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let make_two_sided = |program: &mut LiteSVM, seed: u64| {
+            let (mut make_ix, escrow, vault) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, seed, 10, 10, 0);
+            make_ix.data = crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 10,
+                    receive: 10,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: Some(crate::SettlementTerms {
+                        hashlock: None,
+                        arbiter: None,
+                        review_period: Some(100),
+                        vesting_duration: 0,
+                    }),
+                },
+            }
+            .data();
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            program.send_transaction(transaction).unwrap();
+            (escrow, vault)
+        };
+        let deposit_accounts = |escrow: Pubkey| {
+            let deposit =
+                Pubkey::find_program_address(&[b"taker_deposit", escrow.as_ref()], &PROGRAM_ID).0;
+            let deposit_vault = associated_token::get_associated_token_address(&deposit, &mint_b);
+            (deposit, deposit_vault)
+        };
+        let deposit_ix = |escrow: Pubkey| {
+            let (deposit, deposit_vault) = deposit_accounts(escrow);
+            Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::DepositPayment {
+                    taker: taker.pubkey(),
+                    maker,
+                    mint_a,
+                    mint_b,
+                    taker_ata_b,
+                    escrow,
+                    deposit,
+                    deposit_vault,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::DepositPayment {}.data(),
+            }
+        };
+
+        let (escrow, vault) = make_two_sided(&mut program, 123u64);
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::DepositRequired
+            ),
+            "Take cannot fill a two-sided escrow"
+        );
+
+        let message = Message::new(&[deposit_ix(escrow)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let (deposit, deposit_vault) = deposit_accounts(escrow);
+        let deposit_vault_data =
+            spl_token::state::Account::unpack(&program.get_account(&deposit_vault).unwrap().data)
+                .unwrap();
+        assert_eq!(deposit_vault_data.amount, 10);
+
+        let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+        let settle_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::SettleDeposit {
+                settler: payer.pubkey(),
+                maker,
+                taker: taker.pubkey(),
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_a,
+                maker_ata_b,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                escrow,
+                vault,
+                deposit,
+                deposit_vault,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SettleDeposit {}.data(),
+        };
+        let message = Message::new(&[settle_ix.clone()], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::ReviewNotOver
+            ),
+            "Settle should wait out the review period"
+        );
+
+        warp_clock(&mut program, 100);
+        let message = Message::new(&[settle_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let taker_ata_a_data =
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(taker_ata_a_data.amount, 10);
+        let maker_ata_b_data =
+            spl_token::state::Account::unpack(&program.get_account(&maker_ata_b).unwrap().data)
+                .unwrap();
+        assert_eq!(maker_ata_b_data.amount, 10);
+        assert!(program.get_account(&escrow).is_none());
+        assert!(program.get_account(&deposit).is_none());
+
+        // Both parties can call the trade off before it settles
+        let (escrow, _vault) = make_two_sided(&mut program, 124u64);
+        let message = Message::new(&[deposit_ix(escrow)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let taker_b_before =
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_b).unwrap().data)
+                .unwrap()
+                .amount;
+        let (deposit, deposit_vault) = deposit_accounts(escrow);
+        let cancel_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::CancelDeposit {
+                maker,
+                taker: taker.pubkey(),
+                mint_b,
+                taker_ata_b,
+                escrow,
+                deposit,
+                deposit_vault,
+                token_program: TOKEN_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::CancelDeposit {}.data(),
+        };
+        let message = Message::new(&[cancel_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer, &taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let taker_ata_b_data =
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_b).unwrap().data)
+                .unwrap();
+        assert_eq!(taker_ata_b_data.amount, taker_b_before + 10);
+        assert!(program.get_account(&deposit).is_none());
+        let escrow_account = program.get_account(&escrow).unwrap();
+        let escrow_data =
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+        assert_eq!(escrow_data.status, crate::state::EscrowStatus::Open);
+    }
+
+    #[test]
+    fn test_vesting() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 10, 10, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 123u64,
+            args: crate::MakeArgs {
+                deposit: 10,
+                receive: 10,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: Some(crate::SettlementTerms {
+                    hashlock: None,
+                    arbiter: None,
+                    review_period: None,
+                    vesting_duration: 100,
+                }),
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::VestingRequired
+            ),
+            "Take cannot fill a vesting escrow"
+        );
+
+        let vesting = vesting_pda(&escrow, &taker.pubkey());
+        let vesting_vault = associated_token::get_associated_token_address(&vesting, &mint_a);
+        let take_vested_ix =
+            take_vested_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_vested_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert!(
+            program.get_account(&escrow).is_none(),
+            "Escrow should close once its deposit is vesting"
+        );
+        let vesting_vault_data =
+            spl_token::state::Account::unpack(&program.get_account(&vesting_vault).unwrap().data)
+                .unwrap();
+        assert_eq!(vesting_vault_data.amount, 10);
+
+        let claim_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::ClaimVested {
+                taker: taker.pubkey(),
+                mint_a,
+                taker_ata_a,
+                vesting,
+                vesting_vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::ClaimVested {}.data(),
+        };
+        let taker_a_amount = |program: &LiteSVM| {
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_a).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        // Half the duration releases half the fill
+        warp_clock(&mut program, 50);
+        let message = Message::new(&[claim_ix.clone()], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        assert_eq!(taker_a_amount(&program), 5);
+
+        program.expire_blockhash();
+        let message = Message::new(&[claim_ix.clone()], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::NothingVested
+        ));
+
+        warp_clock(&mut program, 100);
+        let message = Message::new(&[claim_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        assert_eq!(taker_a_amount(&program), 10);
+        assert!(
+            program.get_account(&vesting).is_none(),
+            "Vesting should close with the last claim"
+        );
+    }
+
+    #[test]
+    fn test_standing_offer() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 10, 10, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 123u64,
+            args: crate::MakeArgs {
+                deposit: 10,
+                receive: 10,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: Some(crate::FillTerms {
+                    standing: true,
+                    min_fill: 0,
+                    fill_grace_period: 0,
+                }),
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let take = |program: &mut LiteSVM, take_amount: u64| {
+            let take_ix = take_instruction(
+                taker.pubkey(),
+                maker,
+                mint_a,
+                mint_b,
+                escrow,
+                vault,
+                take_amount,
+            );
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let top_up = |program: &mut LiteSVM, amount: u64, receive: Option<u64>| {
+            let top_up_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::TopUp {
+                    maker,
+                    mint_a,
+                    maker_ata_a: Some(maker_ata_a),
+                    escrow,
+                    vault,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::TopUp { amount, receive }.data(),
+            };
+            let message = Message::new(&[top_up_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let escrow_data = |program: &LiteSVM| {
+            crate::state::Escrow::try_deserialize(
+                &mut program.get_account(&escrow).unwrap().data.as_ref(),
+            )
+            .unwrap()
+        };
+
+        // Filling the whole deposit leaves the escrow and its vault in place
+        take(&mut program, 10).unwrap();
+        let data = escrow_data(&program);
+        assert_eq!(data.remaining_deposit, 0);
+        assert_eq!(data.total_filled, 10);
+        assert!(program.get_account(&vault).is_some());
+        assert!(failed_with(
+            take(&mut program, 1),
+            ErrorCode::InvalidTakeAmount
+        ));
+
+        // A drained offer has no price to scale, the refill names one
+        assert!(failed_with(
+            top_up(&mut program, 10, None),
+            ErrorCode::ReceiveRequired
+        ));
+        top_up(&mut program, 10, Some(20)).unwrap();
+        take(&mut program, 20).unwrap();
+
+        let data = escrow_data(&program);
+        assert_eq!(data.total_filled, 20);
+        assert_eq!(data.receive - data.remaining_receive, 30);
+        let taker_ata_a_data =
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(taker_ata_a_data.amount, 20);
+    }
+
+    #[test]
+    fn test_require_no_freeze_authority() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let frozen_mint_b = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&maker)
+            .freeze_authority(&maker)
+            .send()
+            .unwrap();
+
+        let make = |program: &mut LiteSVM, mint_b: Pubkey, seed: u64| {
+            let (mut make_ix, _escrow, _vault) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, seed, 10, 10, 0);
+            make_ix.data = crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 10,
+                    receive: 10,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: true,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data();
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            program.send_transaction(transaction)
+        };
+
+        assert!(failed_with(
+            make(&mut program, frozen_mint_b, 1),
+            ErrorCode::FreezeAuthoritySet
+        ));
+        make(&mut program, mint_b, 2).unwrap();
+    }
+
+    #[test]
+    fn test_rent_recipient() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let sponsor = Keypair::new().pubkey();
+        program.airdrop(&sponsor, LAMPORTS_PER_SOL).unwrap();
+
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 10, 10, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 123u64,
+            args: crate::MakeArgs {
+                deposit: 10,
+                receive: 10,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: Some(sponsor),
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let rent = program.get_account(&escrow).unwrap().lamports
+            + program.get_account(&vault).unwrap().lamports;
+
+        let refund = |program: &mut LiteSVM, rent_payer: Pubkey| {
+            let refund_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::Refund {
+                    maker,
+                    rent_payer,
+                    mint_a,
+                    maker_ata_a,
+                    escrow,
+                    maker_registry: Some(maker_registry_pda(maker)),
+                    maker_stats: None,
+                    vault,
+                    config: None,
+                    treasury: None,
+                    memo_program: None,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                    event_authority: event_authority_pda(),
+                    program: PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::Refund { memo: None }.data(),
+            };
+            let message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        // The rent goes to the named recipient, not back to the payer
+        assert!(refund(&mut program, maker).is_err());
+        refund(&mut program, sponsor).unwrap();
+        assert_eq!(
+            program.get_account(&sponsor).unwrap().lamports,
+            LAMPORTS_PER_SOL + rent
+        );
+    }
+
+    #[test]
+    fn test_min_fill() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let make = |program: &mut LiteSVM, min_fill: u64| {
+            let (mut make_ix, escrow, vault) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 30, 30, 0);
+            make_ix.data = crate::instruction::Make {
+                seed: 123u64,
+                args: crate::MakeArgs {
+                    deposit: 30,
+                    receive: 30,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: Some(crate::FillTerms {
+                        standing: false,
+                        min_fill,
+                        fill_grace_period: 0,
+                    }),
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data();
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result.map(|_| (escrow, vault))
+        };
+
+        assert!(failed_with(
+            make(&mut program, 31),
+            ErrorCode::InvalidMinFill
+        ));
+        let (escrow, vault) = make(&mut program, 10).unwrap();
+
+        let take = |program: &mut LiteSVM, take_amount: u64| {
+            let take_ix = take_instruction(
+                taker.pubkey(),
+                maker,
+                mint_a,
+                mint_b,
+                escrow,
+                vault,
+                take_amount,
+            );
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        assert!(failed_with(
+            take(&mut program, 9),
+            ErrorCode::FillBelowMinimum
+        ));
+        take(&mut program, 10).unwrap();
+        take(&mut program, 15).unwrap();
+
+        // The last 5 are under the minimum but empty the escrow
+        take(&mut program, 5).unwrap();
+        assert!(program.get_account(&escrow).is_none());
+    }
+
+    #[test]
+    fn test_fill_grace_period() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+
+        let make = |program: &mut LiteSVM, expiry: i64, fill_grace_period: i64| {
+            let (mut make_ix, escrow, vault) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 30, 30, 0);
+            make_ix.data = crate::instruction::Make {
+                seed: 123u64,
+                args: crate::MakeArgs {
+                    deposit: 30,
+                    receive: 30,
+                    waiting_time: 0,
+                    expiry,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: Some(crate::FillTerms {
+                        standing: false,
+                        min_fill: 0,
+                        fill_grace_period,
+                    }),
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data();
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result.map(|_| (escrow, vault))
+        };
+
+        // A grace period only makes sense for an offer that expires
+        assert!(failed_with(
+            make(&mut program, 0, 500),
+            ErrorCode::InvalidFillGracePeriod
+        ));
+        assert!(failed_with(
+            make(&mut program, now + 100, MAX_EXPIRY_HORIZON + 1),
+            ErrorCode::InvalidFillGracePeriod
+        ));
+        let (escrow, vault) = make(&mut program, now + 100, 500).unwrap();
+
+        let take = |program: &mut LiteSVM, take_amount: u64| {
+            let take_ix = take_instruction(
+                taker.pubkey(),
+                maker,
+                mint_a,
+                mint_b,
+                escrow,
+                vault,
+                take_amount,
+            );
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let expiry = |program: &LiteSVM| {
+            let escrow_account = program.get_account(&escrow).unwrap();
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref())
+                .unwrap()
+                .expiry
+        };
+
+        // Each partial fill keeps the offer open for the grace period
+        take(&mut program, 10).unwrap();
+        assert_eq!(expiry(&program), now + 500);
+
+        // Past the expiry set at Make, but within the grace period
+        warp_clock(&mut program, 200);
+        take(&mut program, 10).unwrap();
+        assert_eq!(expiry(&program), now + 700);
+
+        warp_clock(&mut program, 600);
+        assert!(failed_with(
+            take(&mut program, 10),
+            ErrorCode::EscrowExpired
+        ));
+
+        msg!("\nFill grace period assertions passed!");
+    }
+
+    #[test]
+    fn test_last_take_sweeps_residual_to_maker() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        // Tokens sent straight to the vault are not part of the offer
+        MintTo::new(&mut program, &payer, &mint_a, &vault, 3)
+            .send()
+            .unwrap();
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+        let maker_before = token_amount(&program, &maker_ata_a);
+
+        // The last fill cannot hand the leftover to the taker instead
+        let mut take_ix =
+            take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_ix.clone()], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::ResidualRecipientRequired
+        ));
+        program.expire_blockhash();
+
+        take_ix.accounts = crate::accounts::Take {
+            taker: taker.pubkey(),
+            payer: taker.pubkey(),
+            maker,
+            rent_payer: maker,
+            mint_a,
+            mint_b,
+            taker_ata_a,
+            taker_ata_b: associated_token::get_associated_token_address(&taker.pubkey(), &mint_b),
+            maker_ata_b: associated_token::get_associated_token_address(&maker, &mint_b),
+            maker_ata_a: Some(maker_ata_a),
+            config: config_pda(),
+            treasury: TREASURY,
+            treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+            referrer_ata_b: None,
+            escrow,
+            maker_registry: Some(maker_registry_pda(maker)),
+            maker_stats: None,
+            vault,
+            proceeds: None,
+            proceeds_vault: None,
+            price_update: None,
+            memo_program: None,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: TOKEN_PROGRAM_ID,
+            system_program: SYSTEM_PROGRAM_ID,
+            event_authority: event_authority_pda(),
+            program: PROGRAM_ID,
+        }
+        .to_account_metas(None);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        // The taker gets what they paid for, the rest goes back to the maker
+        assert_eq!(token_amount(&program, &taker_ata_a), 10);
+        assert_eq!(token_amount(&program, &maker_ata_a), maker_before + 3);
+        assert!(program.get_account(&vault).is_none());
+    }
+
+    #[test]
+    fn test_last_take_many_sweeps_residual_to_maker() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        MintTo::new(&mut program, &payer, &mint_a, &vault, 3)
+            .send()
+            .unwrap();
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+        let maker_before = token_amount(&program, &maker_ata_a);
+
+        let take_many_ix = |maker_ata_a: Option<Pubkey>| {
+            take_many_instruction(
+                taker.pubkey(),
+                maker,
+                mint_a,
+                mint_b,
+                maker_ata_a,
+                &[(escrow, vault)],
+                vec![10],
+                10,
+                10,
+            )
+        };
+        let message = Message::new(&[take_many_ix(None)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::ResidualRecipientRequired
+        ));
+        program.expire_blockhash();
+
+        let message = Message::new(&[take_many_ix(Some(maker_ata_a))], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(token_amount(&program, &taker_ata_a), 10);
+        assert_eq!(token_amount(&program, &maker_ata_a), maker_before + 3);
+        assert!(program.get_account(&vault).is_none());
+    }
+
+    #[test]
+    fn test_last_take_with_signature_sweeps_residual_to_maker() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let approve_ix = spl_token_2022::instruction::approve(
+            &TOKEN_PROGRAM_ID,
+            &taker_ata_b,
+            &taker_delegate_pda(&taker.pubkey()),
+            &taker.pubkey(),
+            &[],
+            10,
+        )
+        .unwrap();
+        let message = Message::new(&[approve_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        MintTo::new(&mut program, &payer, &mint_a, &vault, 3)
+            .send()
+            .unwrap();
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+        let maker_before = token_amount(&program, &maker_ata_a);
+
+        let clock: Clock = program.get_sysvar();
+        let order = crate::TakeOrder {
+            escrow,
+            take_amount: 10,
+            max_pay: 10,
+            min_receive: 10,
+            expiry: clock.unix_timestamp + 60,
+            nonce: 0,
+        };
+        let take_ixs = |maker_ata_a: Option<Pubkey>| {
+            let (ed25519_ix, take_ix) = take_with_signature_instructions(
+                payer.pubkey(),
+                &taker,
+                maker,
+                mint_a,
+                mint_b,
+                maker_ata_a,
+                vault,
+                order.clone(),
+            );
+            [ed25519_ix, take_ix]
+        };
+        let message = Message::new(&take_ixs(None), Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::ResidualRecipientRequired
+        ));
+        program.expire_blockhash();
+
+        let message = Message::new(&take_ixs(Some(maker_ata_a)), Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(token_amount(&program, &taker_ata_a), 10);
+        assert_eq!(token_amount(&program, &maker_ata_a), maker_before + 3);
+        assert!(program.get_account(&vault).is_none());
+    }
+
+    #[test]
+    fn test_whitelisted_takers() {
+        use crate::whitelist::{Whitelist, WHITELIST_DISCRIMINATOR, WHITELIST_PROGRAM_ID};
+
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 10, 10, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 123u64,
+            args: crate::MakeArgs {
+                deposit: 10,
+                receive: 10,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: true,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let entry = Whitelist::address(&taker.pubkey());
+        let set_entry = |program: &mut LiteSVM, expires_at: i64| {
+            let whitelist = Whitelist {
+                bump: 255,
+                expires_at,
+                reverification_requested: false,
+                version: 3,
+                added_at: 0,
+                tier: 0,
+                metadata: [0; 32],
+                removal_effective_at: 0,
+            };
+            let mut data = WHITELIST_DISCRIMINATOR.to_vec();
+            data.extend(anchor_lang::AnchorSerialize::try_to_vec(&whitelist).unwrap());
+            program
+                .set_account(
+                    entry,
+                    Account {
+                        lamports: program.minimum_balance_for_rent_exemption(data.len()),
+                        data,
+                        owner: WHITELIST_PROGRAM_ID,
+                        executable: false,
+                        rent_epoch: 0,
+                    },
+                )
+                .unwrap();
+        };
+        let take = |program: &mut LiteSVM| {
+            let mut take_ix =
+                take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 5);
+            take_ix
+                .accounts
+                .push(solana_instruction::AccountMeta::new_readonly(entry, false));
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        // No entry, then an expired one
+        assert!(failed_with(
+            take(&mut program),
+            ErrorCode::TakerNotWhitelisted
+        ));
+        let clock: Clock = program.get_sysvar();
+        set_entry(&mut program, clock.unix_timestamp - 1);
+        assert!(failed_with(
+            take(&mut program),
+            ErrorCode::TakerNotWhitelisted
+        ));
+
+        set_entry(&mut program, 0);
+        take(&mut program).unwrap();
+    }
+
+    #[test]
+    fn test_cancel_fee() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let cancel_fee = 1_000_000;
+        // Pays the rent, so the fee going back is told apart from it
+        let sponsor = Keypair::new().pubkey();
+        program.airdrop(&sponsor, LAMPORTS_PER_SOL).unwrap();
+
+        let make = |program: &mut LiteSVM, seed: u64| {
+            let (mut make_ix, escrow, vault) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, seed, 10, 10, 0);
+            make_ix.data = crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 10,
+                    receive: 10,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: Some(sponsor),
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: Some(crate::RefundTerms {
+                        no_refund_before: 0,
+                        cancel_fee,
+                        cancel_fee_window: 100,
+                    }),
+                    settlement_terms: None,
+                },
+            }
+            .data();
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            program.send_transaction(transaction).unwrap();
+            (escrow, vault)
+        };
+        let send = |program: &mut LiteSVM, ix: Instruction| {
+            let message = Message::new(&[ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let refund = |program: &mut LiteSVM, escrow: Pubkey, vault: Pubkey, treasury: bool| {
+            let refund_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::Refund {
+                    maker,
+                    rent_payer: sponsor,
+                    mint_a,
+                    maker_ata_a,
+                    escrow,
+                    maker_registry: Some(maker_registry_pda(maker)),
+                    maker_stats: None,
+                    vault,
+                    config: treasury.then(config_pda),
+                    treasury: treasury.then_some(TREASURY),
+                    memo_program: None,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                    event_authority: event_authority_pda(),
+                    program: PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::Refund { memo: None }.data(),
+            };
+            send(program, refund_ix)
+        };
+        let withdraw_partial = |program: &mut LiteSVM, escrow: Pubkey, vault: Pubkey| {
+            let withdraw_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::WithdrawPartial {
+                    maker,
+                    mint_a,
+                    maker_ata_a,
+                    escrow,
+                    vault,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::WithdrawPartial { amount: 5 }.data(),
+            };
+            send(program, withdraw_ix)
+        };
+        let update_expiry = |program: &mut LiteSVM, escrow: Pubkey, vault: Pubkey, expiry: i64| {
+            let update_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::UpdateEscrow {
+                    maker,
+                    config: config_pda(),
+                    mint_a,
+                    escrow,
+                    vault,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::UpdateEscrow {
+                    receive: 10,
+                    waiting_time: 0,
+                    expiry,
+                }
+                .data(),
+            };
+            send(program, update_ix)
+        };
+        // Escrow rent and vault rent, less the staked fee
+        let rent = |program: &LiteSVM, escrow: Pubkey, vault: Pubkey| {
+            program.get_balance(&escrow).unwrap() - cancel_fee
+                + program.get_balance(&vault).unwrap()
+        };
+
+        // Refunding inside the window forfeits the fee to the treasury
+        let (escrow, vault) = make(&mut program, 1);
+        assert!(failed_with(
+            refund(&mut program, escrow, vault, false),
+            ErrorCode::CancelFeeAccountsRequired
+        ));
+        let treasury_before = program.get_balance(&TREASURY).unwrap_or(0);
+        refund(&mut program, escrow, vault, true).unwrap();
+        assert_eq!(
+            program.get_balance(&TREASURY).unwrap_or(0),
+            treasury_before + cancel_fee
+        );
+
+        // Nor can the maker withdraw all but a unit inside it, or expire before it ends
+        let (refunded, refunded_vault) = make(&mut program, 2);
+        let (withdrawn, withdrawn_vault) = make(&mut program, 3);
+        let (expiring, expiring_vault) = make(&mut program, 4);
+        assert!(failed_with(
+            withdraw_partial(&mut program, withdrawn, withdrawn_vault),
+            ErrorCode::CancelFeeAtStake
+        ));
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+        assert!(failed_with(
+            update_expiry(&mut program, expiring, expiring_vault, now + 50),
+            ErrorCode::InvalidCancelFee
+        ));
+        update_expiry(&mut program, expiring, expiring_vault, now + 150).unwrap();
+
+        // After it, the fee goes back to the maker and only the rent to the sponsor
+        warp_clock(&mut program, 100);
+        let rent_refunded = rent(&program, refunded, refunded_vault);
+        let maker_before = program.get_balance(&maker).unwrap();
+        let sponsor_before = program.get_balance(&sponsor).unwrap();
+        refund(&mut program, refunded, refunded_vault, false).unwrap();
+        assert_eq!(
+            program.get_balance(&sponsor).unwrap(),
+            sponsor_before + rent_refunded
+        );
+        assert!(program.get_balance(&maker).unwrap() > maker_before + cancel_fee - 10_000);
+        assert_eq!(
+            program.get_balance(&TREASURY).unwrap_or(0),
+            treasury_before + cancel_fee
+        );
+        withdraw_partial(&mut program, withdrawn, withdrawn_vault).unwrap();
+
+        // So does closing the escrow once it expires
+        warp_clock(&mut program, 50);
+        let cranker = Keypair::new();
+        program
+            .airdrop(&cranker.pubkey(), LAMPORTS_PER_SOL)
+            .unwrap();
+        let rent_expiring = rent(&program, expiring, expiring_vault);
+        let maker_before = program.get_balance(&maker).unwrap();
+        let sponsor_before = program.get_balance(&sponsor).unwrap();
+        let close_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::CloseExpired {
+                cranker: cranker.pubkey(),
+                maker,
+                rent_payer: sponsor,
+                mint_a,
+                maker_ata_a,
+                escrow: expiring,
+                vault: expiring_vault,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::CloseExpired {}.data(),
+        };
+        let message = Message::new(&[close_ix], Some(&cranker.pubkey()));
+        let transaction = Transaction::new(&[&cranker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        assert_eq!(
+            program.get_balance(&maker).unwrap(),
+            maker_before + cancel_fee
+        );
+        assert_eq!(
+            program.get_balance(&sponsor).unwrap(),
+            sponsor_before + rent_expiring - crate::instructions::CLOSE_EXPIRED_BOUNTY
+        );
+    }
+
+    #[test]
+    fn test_quote() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 30, 100, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let quote_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Quote {
+                mint_a,
+                mint_b,
+                config: config_pda(),
+                escrow,
+                price_update: None,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Quote { take_amount: 24 }.data(),
+        };
+        let message = Message::new(&[quote_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        let simulated = program.simulate_transaction(transaction).unwrap();
+        let quote: crate::instructions::TakeQuote = anchor_lang::AnchorDeserialize::deserialize(
+            &mut simulated.meta.return_data.data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(quote.fill, 7);
+        assert_eq!(quote.payment, 24);
+        assert_eq!(quote.fee, 0);
+        assert_eq!(quote.remaining_deposit, 23);
+        assert_eq!(quote.remaining_receive, 76);
+
+        // Take gives exactly what was quoted
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 24);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let taker_ata_a_data =
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(taker_ata_a_data.amount, quote.fill);
+    }
+
+    #[test]
+    fn test_take_exact_out() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 30, 100, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let take_exact_out = |program: &mut LiteSVM, fill: u64, max_pay: u64| {
+            let mut take_ix = take_instruction(
+                taker.pubkey(),
+                maker,
+                mint_a,
+                mint_b,
+                escrow,
+                vault,
+                max_pay,
+            );
+            take_ix.data = crate::instruction::TakeExactOut {
+                fill,
+                proof: vec![],
+                max_pay,
+                memo: None,
+                preimage: None,
+            }
+            .data();
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let balance = |program: &LiteSVM, ata: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(ata).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        // 7 of 30 at 100 for 30 costs 23.33, rounded up to 24
+        assert!(failed_with(
+            take_exact_out(&mut program, 7, 23),
+            ErrorCode::SlippageExceeded
+        ));
+        assert!(failed_with(
+            take_exact_out(&mut program, 31, u64::MAX),
+            ErrorCode::InvalidFillAmount
+        ));
+        take_exact_out(&mut program, 7, 24).unwrap();
+        assert_eq!(balance(&program, &taker_ata_a), 7);
+        assert_eq!(balance(&program, &taker_ata_b), 1000000000 - 24);
+
+        let data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(data.remaining_deposit, 23);
+        assert_eq!(data.remaining_receive, 76);
+
+        // The rest costs exactly what is left to receive and closes the escrow
+        take_exact_out(&mut program, 23, 76).unwrap();
+        assert_eq!(balance(&program, &taker_ata_a), 30);
+        assert!(program.get_account(&escrow).is_none());
+    }
+
+    #[test]
+    fn test_take_with_waiting_time() {
+        let waiting_time = 300i64;
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 40, 90, waiting_time);
+
+        let maker = payer.pubkey();
+
+        // Taker setup
+        let taker = Keypair::new();
+        program
+            .airdrop(&taker.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        // Taker ATA A
+        let taker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_a)
+            .owner(&taker.pubkey())
+            .send()
+            .unwrap();
+        
+        // Taker ATA B
+        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
+            .owner(&taker.pubkey())
+            .send()
+            .unwrap();
+        
+        MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000)
+            .send()
+            .unwrap();
+
+
+        // Maker ATA B
+        let maker_ata_b =
+            spl_associated_token_account::get_associated_token_address(&maker, &mint_b);
+
+        let clock: Clock = program.get_sysvar();
+        let start_time = clock.unix_timestamp;
+
+        // The escrow stores the absolute unlock timestamp computed at Make
+        let escrow_account = program.get_account(&escrow).unwrap();
+        let escrow_data =
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+        assert_eq!(escrow_data.unlock_at, start_time + waiting_time);
+
+        // First attempt (should fail)
+        let take_ix_before_waiting = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                payer: taker.pubkey(),
+                maker,
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_a,
+                taker_ata_b,
+                maker_ata_b,
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                referrer_ata_b: None,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                proceeds: None,
+                proceeds_vault: None,
+                price_update: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take {
+                take_amount: 90,
+                proof: vec![],
+                max_pay: 90,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data(),
+        };
+
+        let msg_before_waiting = Message::new(&[take_ix_before_waiting], Some(&taker.pubkey()));
+        let bh_before_waiting = program.latest_blockhash();
+        let tx_before_waiting = Transaction::new(&[&taker], msg_before_waiting, bh_before_waiting);
+
+        let result_before_waiting = program.send_transaction(tx_before_waiting);
+        assert!(
+            failed_with(result_before_waiting, ErrorCode::WaitingTimeNotElapsed),
+            "Take should fail before waiting time"
+        );
+
+        msg!("✓ Take failed before waiting time");
+
+        let mut new_clock: Clock = program.get_sysvar();
+        let current_slot = new_clock.slot;
+
+        // 2. Update the timestamp for your program logic
+        new_clock.unix_timestamp += waiting_time;
+
+        // 3. Increment the slot relatively (ensures a new blockhash/signature)
+        // This fixes the "AlreadyProcessed" error
+        new_clock.slot = current_slot + 100;
+
+        // 4. Push the updated sysvar back to the VM
+        program.set_sysvar::<Clock>(&new_clock);
+
+        program.warp_to_slot(new_clock.slot);
+
+        msg!("New slot: {}", new_clock.slot);
+        msg!("New timestamp: {}", new_clock.unix_timestamp);
+
+        msg!("Old timestamp: {}", start_time,);
+        msg!(
+            "New timestamp: {} {}",
+            new_clock.unix_timestamp,
+            new_clock.slot
+        );
+
+        // Second take (should succeed)
+        let take_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                payer: taker.pubkey(),
+                maker,
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_a,
+                taker_ata_b,
+                maker_ata_b,
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                referrer_ata_b: None,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                proceeds: None,
+                proceeds_vault: None,
+                price_update: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take {
+                take_amount: 90,
+                proof: vec![],
+                max_pay: 90,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data(),
+        };
+
+        let msg = Message::new(&[take_ix], Some(&taker.pubkey()));
+        program.expire_blockhash();
+        let new_bh = program.latest_blockhash();
+        let tx = Transaction::new(&[&taker], msg, new_bh);
+
+        program.send_transaction(tx).unwrap();
+        msg!("✓ Take succeeded after waiting time");
+
+        // Final assertions
+        let taker_a_acc = program.get_account(&taker_ata_a).unwrap();
+        let taker_a_data = spl_token::state::Account::unpack(&taker_a_acc.data).unwrap();
+        assert_eq!(taker_a_data.amount, 40);
+
+        let maker_b_acc = program.get_account(&maker_ata_b).unwrap();
+        let maker_b_data = spl_token::state::Account::unpack(&maker_b_acc.data).unwrap();
+        assert_eq!(maker_b_data.amount, 90);
+
+        msg!("✓ All assertions passed");
+    }
+
+    #[test]
+    fn test_migrate_escrow_from_baseline() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        // An escrow as the originally deployed program wrote it: derived without the
+        // mints, its vault the escrow's ATA, unlocking at an absolute time
+        let seed = 123u64;
+        let (escrow, bump) = Pubkey::find_program_address(
+            &[b"escrow", maker.as_ref(), &seed.to_le_bytes()],
+            &PROGRAM_ID,
+        );
+        let vault = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+            .owner(&escrow)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &payer, &mint_a, &vault, 10)
+            .send()
+            .unwrap();
+        let clock: Clock = program.get_sysvar();
+        let unlock_at = clock.unix_timestamp + 100;
+
+        let mut data = Vec::new();
+        data.extend_from_slice(<crate::state::Escrow as anchor_lang::Discriminator>::DISCRIMINATOR);
+        data.extend_from_slice(&seed.to_le_bytes());
+        data.extend_from_slice(maker.as_ref());
+        data.extend_from_slice(mint_a.as_ref());
+        data.extend_from_slice(mint_b.as_ref());
+        data.extend_from_slice(&40u64.to_le_bytes());
+        data.push(bump);
+        data.extend_from_slice(&unlock_at.to_le_bytes());
+        assert_eq!(data.len(), crate::state::Escrow::BASELINE_LEN);
+        program
+            .set_account(
+                escrow,
+                Account {
+                    lamports: program.minimum_balance_for_rent_exemption(data.len()),
+                    data,
+                    owner: PROGRAM_ID,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+
+        let migrate_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::MigrateEscrow {
+                maker,
+                escrow,
+                vault,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::MigrateEscrow {}.data(),
+        };
+
+        let message = Message::new(&[migrate_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        let tx = program.send_transaction(transaction).unwrap();
+        msg!("MigrateEscrow transaction successful");
+        msg!("CUs Consumed: {}", tx.compute_units_consumed);
+
+        // The account grew to the current size and kept its baseline values
+        let escrow_account = program.get_account(&escrow).unwrap();
+        assert_eq!(
+            escrow_account.data.len(),
+            8 + crate::state::Escrow::INIT_SPACE
+        );
+        let escrow_data =
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+        assert_eq!(escrow_data.version, crate::state::Escrow::CURRENT_VERSION);
+        assert_eq!(escrow_data.maker, maker);
+        assert_eq!(escrow_data.mint_a, mint_a);
+        assert_eq!(escrow_data.mint_b, mint_b);
+        assert_eq!(escrow_data.status, crate::state::EscrowStatus::Open);
+        assert_eq!(escrow_data.seed, seed);
+        assert_eq!(escrow_data.receive, 40);
+        assert_eq!(escrow_data.unlock_at, unlock_at);
+        assert_eq!(escrow_data.bump, bump);
+        assert_eq!(escrow_data.expiry, 0);
+        assert_eq!(escrow_data.taker, None);
+        assert_eq!(escrow_data.rent_payer, maker);
+        assert_eq!(escrow_data.remaining_deposit, 10);
+        assert_eq!(escrow_data.remaining_receive, 40);
+        assert!(!escrow_data.mint_seeded);
+        assert!(!escrow_data.registered);
+        assert_eq!(escrow_data.total_filled, 0);
+        assert_eq!(escrow_data.min_fill, 0);
+
+        // The migrated escrow refunds under its original derivation
+        let refund_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker,
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                maker_registry: None,
+                maker_stats: None,
+                vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+        let message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let maker_a_acc = program.get_account(&maker_ata_a).unwrap();
+        let maker_a_data = spl_token::state::Account::unpack(&maker_a_acc.data).unwrap();
+        assert_eq!(maker_a_data.amount, 1000000000 + 10);
+        let escrow_account = program.get_account(&escrow);
+        assert!(escrow_account.is_none() || escrow_account.unwrap().data.is_empty());
+
+        msg!("\nAll baseline migration assertions passed!");
+    }
+
+    #[test]
+    fn test_migrate_escrow_tail() {
+        let (mut program, payer, _mint_a, _mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 40, 0);
+        let maker = payer.pubkey();
+
+        let migrate_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::MigrateEscrow {
+                maker,
+                escrow,
+                vault,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::MigrateEscrow {}.data(),
+        };
+
+        // A current escrow has nothing left to migrate
+        let message = Message::new(&[migrate_ix.clone()], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::EscrowUpToDate
+        ));
+
+        // Versions before the tail were never deployed, so a v26 account, which ends
+        // before `curve_bps`, is not a valid escrow
+        let mut escrow_account = program.get_account(&escrow).unwrap();
+        let mut escrow_data =
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+        escrow_data.version = crate::state::Escrow::TAIL_VERSION - 1;
+        let mut data = Vec::new();
+        escrow_data.try_serialize(&mut data).unwrap();
+        data.truncate(8 + crate::state::Escrow::INIT_SPACE - 2);
+        escrow_account.data = data;
+        program.set_account(escrow, escrow_account).unwrap();
+
+        program.expire_blockhash();
+        let message = Message::new(&[migrate_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::InvalidEscrowAccount
+        ));
+
+        msg!("\nAll tail migration assertions passed!");
+    }
+
+    #[test]
+    fn test_make_rejects_negative_waiting_time() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        let (make_ix, _, _) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 1u64, 10, 10, -1);
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::NegativeWaitingTime
+            ),
+            "Make should reject a negative waiting time"
+        );
+
+        msg!("\nNegative waiting time rejected!");
+    }
+
+    #[test]
+    fn test_make_rejects_unlock_timestamp_overflow() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        // Push the clock to the edge of i64 so now + waiting_time overflows
+        let clock: Clock = program.get_sysvar();
+        warp_clock(&mut program, i64::MAX - clock.unix_timestamp - 10);
+
+        let (make_ix, _, _) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 1u64, 10, 10, 100);
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(program.send_transaction(transaction), ErrorCode::Overflow),
+            "Make should fail instead of wrapping the unlock timestamp"
+        );
+
+        msg!("\nUnlock timestamp overflow rejected!");
+    }
+
+    #[test]
+    fn test_take_fails_when_clock_moves_backwards() {
+        let waiting_time = 300i64;
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 40, waiting_time);
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, _taker_ata_b) =
+            setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        // The clock jumps back past the Make timestamp
+        warp_clock(&mut program, -3600);
+
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 40);
+        let message = Message::new(&[take_ix.clone()], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::WaitingTimeNotElapsed
+            ),
+            "Take must not unlock early when the clock moves backwards"
+        );
+
+        // Only reaching the stored unlock timestamp releases the escrow
+        warp_clock(&mut program, 3600 + waiting_time);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        msg!("\nBackwards clock handled safely!");
+    }
+
+    #[test]
+    fn test_make_with_separate_rent_payer() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        // A sponsor funds the escrow and vault rent, the maker only authorizes the deposit
+        let sponsor = Keypair::new();
+        program
+            .airdrop(&sponsor.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        let seed = 123u64;
+        let escrow = escrow_pda(maker, seed, mint_a, mint_b);
+        let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker,
+                payer: sponsor.pubkey(),
+                config: config_pda(),
+                mint_a,
+                mint_b,
+                maker_ata_a: Some(maker_ata_a),
+                escrow,
+                maker_registry: maker_registry_pda(maker),
+                maker_stats: None,
+                vault,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 10,
+                    receive: 10,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data(),
+        };
+
+        let maker_lamports_before = program.get_account(&maker).unwrap().lamports;
+        let message = Message::new(&[make_ix], Some(&sponsor.pubkey()));
+        let transaction =
+            Transaction::new(&[&sponsor, &payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(
+            program.get_account(&maker).unwrap().lamports,
+            maker_lamports_before,
+            "Maker should not pay any rent or fees"
+        );
+        let escrow_account = program.get_account(&escrow).unwrap();
+        let escrow_data =
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+        assert_eq!(escrow_data.rent_payer, sponsor.pubkey());
+
+        // Refunding routes the reclaimed rent back to the sponsor
+        let rent = escrow_account.lamports + program.get_account(&vault).unwrap().lamports;
+        let sponsor_lamports_before = program.get_account(&sponsor.pubkey()).unwrap().lamports;
+
+        let refund_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker,
+                rent_payer: sponsor.pubkey(),
+                mint_a,
+                maker_ata_a,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+        let message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(
+            program.get_account(&sponsor.pubkey()).unwrap().lamports,
+            sponsor_lamports_before + rent,
+            "Sponsor should get the escrow and vault rent back"
+        );
+
+        msg!("\nAll rent payer assertions passed!");
+    }
+
+    #[test]
+    fn test_refund_to_non_canonical_token_account() {
+        let (mut program, payer, mint_a, _mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+        let maker = payer.pubkey();
+
+        // A rotated deposit address: a plain token account owned by the maker
+        let destination = CreateAccount::new(&mut program, &payer, &mint_a)
+            .owner(&maker)
+            .send()
+            .unwrap();
+        // A token account of the right mint owned by someone else
+        let stranger = Keypair::new();
+        let foreign = CreateAccount::new(&mut program, &payer, &mint_a)
+            .owner(&stranger.pubkey())
+            .send()
+            .unwrap();
+
+        let refund_ix = |maker_ata_a: Pubkey| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker,
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+
+        let message = Message::new(&[refund_ix(foreign)], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            program.send_transaction(transaction).is_err(),
+            "Refund must not pay out to an account the maker does not own"
+        );
+
+        let message = Message::new(&[refund_ix(destination)], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let destination_account = program.get_account(&destination).unwrap();
+        let destination_data =
+            spl_token::state::Account::unpack(&destination_account.data).unwrap();
+        assert_eq!(destination_data.amount, 10, "Refund should land in the chosen account");
+
+        msg!("\nAll refund destination assertions passed!");
+    }
+
+    /// Helper function to flip a token account's frozen state in place
+    fn set_frozen(program: &mut LiteSVM, token_account: Pubkey, frozen: bool) {
+        let mut account = program.get_account(&token_account).unwrap();
+        let mut data = spl_token::state::Account::unpack(&account.data).unwrap();
+        data.state = if frozen {
+            spl_token::state::AccountState::Frozen
+        } else {
+            spl_token::state::AccountState::Initialized
+        };
+        spl_token::state::Account::pack(data, &mut account.data).unwrap();
+        program.set_account(token_account, account).unwrap();
+    }
+
+    #[test]
+    fn test_take_fails_early_with_frozen_taker_account() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 40, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) =
+            setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        set_frozen(&mut program, taker_ata_a, true);
+
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 40);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        let result = program.send_transaction(transaction);
+        assert!(
+            failed_with(result, ErrorCode::TakerAccountFrozen),
+            "Take should fail with TakerAccountFrozen"
+        );
+
+        msg!("\nFrozen taker account rejected!");
+    }
+
+    #[test]
+    fn test_take_parks_proceeds_when_maker_account_frozen() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 40, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) =
+            setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let maker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_b)
+            .owner(&maker)
+            .send()
+            .unwrap();
+        set_frozen(&mut program, maker_ata_b, true);
+
+        // The plain Take path fails with a specific error
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 40);
+        let message = Message::new(&[take_ix.clone()], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::MakerAccountFrozen
+            ),
+            "Take without proceeds accounts should fail"
+        );
+
+        // Passing the proceeds accounts settles into a claimable PDA instead
+        let proceeds =
+            Pubkey::find_program_address(&[b"proceeds", escrow.as_ref()], &PROGRAM_ID).0;
+        let proceeds_vault = associated_token::get_associated_token_address(&proceeds, &mint_b);
+        let take_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                payer: taker.pubkey(),
+                maker,
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_a,
+                taker_ata_b: associated_token::get_associated_token_address(
+                    &taker.pubkey(),
+                    &mint_b,
+                ),
+                maker_ata_b,
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                referrer_ata_b: None,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                proceeds: Some(proceeds),
+                proceeds_vault: Some(proceeds_vault),
+                price_update: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take {
+                take_amount: 40,
+                proof: vec![],
+                max_pay: 40,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data(),
+        };
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let taker_a_data =
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(taker_a_data.amount, 10, "Taker should still be filled");
+        let proceeds_data =
+            spl_token::state::Account::unpack(&program.get_account(&proceeds_vault).unwrap().data)
+                .unwrap();
+        assert_eq!(proceeds_data.amount, 40, "Proceeds should be parked");
+
+        // Once thawed, the maker claims the proceeds and the taker gets the rent back
+        set_frozen(&mut program, maker_ata_b, false);
+        let claim_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::ClaimProceeds {
+                maker,
+                rent_payer: taker.pubkey(),
+                mint_b,
+                maker_ata_b,
+                proceeds,
+                proceeds_vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::ClaimProceeds {}.data(),
+        };
+        let message = Message::new(&[claim_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let maker_b_data =
+            spl_token::state::Account::unpack(&program.get_account(&maker_ata_b).unwrap().data)
+                .unwrap();
+        assert_eq!(maker_b_data.amount, 40, "Maker should receive the parked proceeds");
+        assert!(
+            program
+                .get_account(&proceeds)
+                .map_or(true, |account| account.lamports == 0),
+            "Proceeds account should be closed"
+        );
+
+        msg!("\nAll proceeds assertions passed!");
+    }
+
+    /// Helper function to overwrite a token account's delegate and close authority
+    fn set_authorities(
+        program: &mut LiteSVM,
+        token_account: Pubkey,
+        delegate: Option<Pubkey>,
+        close_authority: Option<Pubkey>,
+    ) {
+        let mut account = program.get_account(&token_account).unwrap();
+        let mut data = spl_token::state::Account::unpack(&account.data).unwrap();
+        data.delegate = delegate.into();
+        data.delegated_amount = if delegate.is_some() { u64::MAX } else { 0 };
+        data.close_authority = close_authority.into();
+        spl_token::state::Account::pack(data, &mut account.data).unwrap();
+        program.set_account(token_account, account).unwrap();
+    }
+
+    #[test]
+    fn test_take_rejects_recipient_accounts_with_third_party_authorities() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 40, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let maker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_b)
+            .owner(&maker)
+            .send()
+            .unwrap();
+        let interceptor = Pubkey::new_unique();
+
+        let take = |program: &mut LiteSVM| {
+            let take_ix =
+                take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 40);
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            program.send_transaction(transaction)
+        };
+
+        // A delegate on the account receiving mint_a could sweep the fill
+        set_authorities(&mut program, taker_ata_a, Some(interceptor), None);
+        assert!(
+            failed_with(take(&mut program), ErrorCode::UnexpectedDelegate),
+            "Take should reject a delegated taker_ata_a"
+        );
+        set_authorities(&mut program, taker_ata_a, None, None);
+
+        // A close authority on the account receiving mint_b could close it under the maker
+        set_authorities(&mut program, maker_ata_b, None, Some(interceptor));
+        assert!(
+            failed_with(take(&mut program), ErrorCode::UnexpectedCloseAuthority),
+            "Take should reject a closable maker_ata_b"
+        );
+        set_authorities(&mut program, maker_ata_b, None, None);
+
+        program.expire_blockhash();
+        take(&mut program).unwrap();
+        let maker_b_data =
+            spl_token::state::Account::unpack(&program.get_account(&maker_ata_b).unwrap().data)
+                .unwrap();
+        assert_eq!(
+            maker_b_data.amount, 40,
+            "Take should settle once the accounts are clean"
+        );
+
+        msg!("\nThird-party authorities on recipient accounts rejected!");
+    }
+
+    #[test]
+    fn test_partial_takes() {
+        // 100 of mint_a for 30 of mint_b does not divide evenly
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 100, 30, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let take = |program: &mut LiteSVM, take_amount: u64| {
+            let take_ix = take_instruction(
+                taker.pubkey(),
+                maker,
+                mint_a,
+                mint_b,
+                escrow,
+                vault,
+                take_amount,
+            );
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        // Zero and more than the remaining receive amount are rejected
+        assert!(failed_with(
+            take(&mut program, 0),
+            ErrorCode::InvalidTakeAmount
+        ));
+        assert!(failed_with(
+            take(&mut program, 31),
+            ErrorCode::InvalidTakeAmount
+        ));
+
+        // 10 of 30 pays 100 * 10 / 30 = 33.3, rounded down to 33
+        take(&mut program, 10).unwrap();
+        assert_eq!(token_amount(&program, &taker_ata_a), 33);
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(
+            escrow_data.status,
+            crate::state::EscrowStatus::PartiallyFilled
+        );
+        assert_eq!(escrow_data.remaining_deposit, 67);
+        assert_eq!(escrow_data.remaining_receive, 20);
+
+        // 7 of 20 pays 67 * 7 / 20 = 23.45, rounded down to 23
+        take(&mut program, 7).unwrap();
+        assert_eq!(token_amount(&program, &taker_ata_a), 56);
+
+        // The last fill takes whatever is left and closes the escrow
+        take(&mut program, 13).unwrap();
+        assert_eq!(token_amount(&program, &taker_ata_a), 100);
+        let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+        assert_eq!(token_amount(&program, &maker_ata_b), 30);
+
+        let escrow_account = program.get_account(&escrow);
+        assert!(
+            escrow_account.is_none() || escrow_account.unwrap().data.is_empty(),
+            "Escrow should be closed after the final fill"
+        );
+
+        msg!("\nAll partial take assertions passed!");
+    }
+
+    #[test]
+    fn test_take_slippage_limits() {
+        // 10 of 30 pays 100 * 10 / 30 = 33.3, rounded down to 33
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 100, 30, 0);
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let take = |program: &mut LiteSVM, max_pay: u64, min_receive: u64| {
+            let mut take_ix =
+                take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+            take_ix.data = crate::instruction::Take {
+                take_amount: 10,
+                proof: vec![],
+                max_pay,
+                min_receive,
+                memo: None,
+                preimage: None,
+            }
+            .data();
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        assert!(failed_with(
+            take(&mut program, 10, 34),
+            ErrorCode::SlippageExceeded
+        ));
+        assert!(failed_with(
+            take(&mut program, 9, 33),
+            ErrorCode::SlippageExceeded
+        ));
+        take(&mut program, 10, 33).unwrap();
+
+        let taker_a_data =
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(taker_a_data.amount, 33);
+
+        msg!("\nAll slippage assertions passed!");
+    }
+
+    #[test]
+    fn test_unit_priced_partial_takes() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        // 3 of mint_b per 7 of mint_a, so the 100 deposited cost 42.86, rounded up to 43
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 1, 100, 0, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 1,
+            args: crate::MakeArgs {
+                deposit: 100,
+                receive: 0,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: Some(crate::UnitPricing {
+                    unit_price: crate::state::UnitPrice {
+                        numerator: 3,
+                        denominator: 7,
+                    },
+                    curve_bps: 0,
+                }),
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let (taker, taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let take = |program: &mut LiteSVM, take_amount: u64| {
+            let take_ix = take_instruction(
+                taker.pubkey(),
+                maker,
+                mint_a,
+                mint_b,
+                escrow,
+                vault,
+                take_amount,
+            );
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        // A budget of 10 buys 23 units, which cost 9.86, rounded up to 10
+        take(&mut program, 10).unwrap();
+        assert_eq!(token_amount(&program, &taker_ata_a), 23);
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.remaining_deposit, 77);
+        assert_eq!(escrow_data.remaining_receive, 33);
+
+        // A budget of 9 buys 21 units at exactly 9
+        take(&mut program, 9).unwrap();
+        assert_eq!(token_amount(&program, &taker_ata_a), 44);
+
+        // An oversized budget only buys what is left and is charged its cost
+        take(&mut program, 100).unwrap();
+        assert_eq!(token_amount(&program, &taker_ata_a), 100);
+        assert_eq!(token_amount(&program, &taker_ata_b), 1000000000 - 43);
+        let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+        assert_eq!(token_amount(&program, &maker_ata_b), 43);
+
+        let escrow_account = program.get_account(&escrow);
+        assert!(
+            escrow_account.is_none() || escrow_account.unwrap().data.is_empty(),
+            "Escrow should be closed once the deposit is sold"
+        );
+
+        msg!("\nAll unit price assertions passed!");
+    }
+
+    #[test]
+    fn test_price_curve() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        // 1 of mint_b per unit with the vault full, rising to 2 for the last unit
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 1, 1000, 1000, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 1,
+            args: crate::MakeArgs {
+                deposit: 1000,
+                receive: 1000,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: Some(crate::UnitPricing {
+                    unit_price: crate::state::UnitPrice {
+                        numerator: 1,
+                        denominator: 1,
+                    },
+                    curve_bps: 10_000,
+                }),
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let (taker, taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let take = |program: &mut LiteSVM, take_amount: u64| {
+            let take_ix = take_instruction(
+                taker.pubkey(),
+                maker,
+                mint_a,
+                mint_b,
+                escrow,
+                vault,
+                take_amount,
+            );
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        // 140 units at a mean premium of 7%, 9.8 rounded up to 10
+        take(&mut program, 150).unwrap();
+        assert_eq!(token_amount(&program, &taker_ata_a), 140);
+        assert_eq!(token_amount(&program, &taker_ata_b), 1000000000 - 150);
+
+        // The same budget buys less from the drained vault, 124 units at a mean
+        // premium of 20.2%, 25.05 rounded up to 26
+        take(&mut program, 150).unwrap();
+        assert_eq!(token_amount(&program, &taker_ata_a), 264);
+        assert_eq!(token_amount(&program, &taker_ata_b), 1000000000 - 300);
+
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.curve_bps, 10_000);
+        assert_eq!(escrow_data.total_filled, 264);
+        assert_eq!(escrow_data.remaining_deposit, 736);
+
+        msg!("\nAll price curve assertions passed!");
+    }
+
+    #[test]
+    fn test_price_curve_full_fill_paths() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        // 10 units at 1 each rising to 2 for the last, 15 for the lot. The premium
+        // counts for more than the 10 left of the linear receive
+        let make = |program: &mut LiteSVM, seed: u64, vesting_duration: i64| {
+            let (mut make_ix, escrow, vault) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, seed, 10, 10, 0);
+            make_ix.data = crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 10,
+                    receive: 10,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: Some(crate::UnitPricing {
+                        unit_price: crate::state::UnitPrice {
+                            numerator: 1,
+                            denominator: 1,
+                        },
+                        curve_bps: 10_000,
+                    }),
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: Some(crate::SettlementTerms {
+                        hashlock: None,
+                        arbiter: None,
+                        review_period: None,
+                        vesting_duration,
+                    }),
+                },
+            }
+            .data();
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            program.send_transaction(transaction).unwrap();
+            (escrow, vault)
+        };
+        let send = |program: &mut LiteSVM, ixs: &[Instruction], signer: &Keypair| {
+            let message = Message::new(ixs, Some(&signer.pubkey()));
+            let transaction = Transaction::new(&[signer], message, program.latest_blockhash());
+            program.send_transaction(transaction).unwrap();
+        };
+        let taker_b_amount = |program: &LiteSVM| {
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_b).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        // TakeMany
+        let (escrow, vault) = make(&mut program, 1, 0);
+        let before = taker_b_amount(&program);
+        let take_many_ix = take_many_instruction(
+            taker.pubkey(),
+            maker,
+            mint_a,
+            mint_b,
+            None,
+            &[(escrow, vault)],
+            vec![20],
+            20,
+            10,
+        );
+        send(&mut program, &[take_many_ix], &taker);
+        assert_eq!(before - taker_b_amount(&program), 15);
+        assert!(program.get_account(&escrow).is_none());
+
+        // TakeVested
+        let (escrow, vault) = make(&mut program, 2, 100);
+        let before = taker_b_amount(&program);
+        let take_vested_ix =
+            take_vested_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 20);
+        send(&mut program, &[take_vested_ix], &taker);
+        assert_eq!(before - taker_b_amount(&program), 15);
+        assert!(program.get_account(&escrow).is_none());
+
+        // TakeWithSignature
+        let (escrow, vault) = make(&mut program, 3, 0);
+        let approve_ix = spl_token_2022::instruction::approve(
+            &TOKEN_PROGRAM_ID,
+            &taker_ata_b,
+            &taker_delegate_pda(&taker.pubkey()),
+            &taker.pubkey(),
+            &[],
+            20,
+        )
+        .unwrap();
+        send(&mut program, &[approve_ix], &taker);
+        let before = taker_b_amount(&program);
+        let order = crate::TakeOrder {
+            escrow,
+            take_amount: 20,
+            max_pay: 20,
+            min_receive: 10,
+            expiry: program.get_sysvar::<Clock>().unix_timestamp + 60,
+            nonce: 0,
+        };
+        let (ed25519_ix, take_ix) = take_with_signature_instructions(
+            maker, &taker, maker, mint_a, mint_b, None, vault, order,
+        );
+        send(&mut program, &[ed25519_ix, take_ix], &payer);
+        assert_eq!(before - taker_b_amount(&program), 15);
+        assert!(program.get_account(&escrow).is_none());
+    }
+
+    #[test]
+    fn test_top_up() {
+        // 100 of mint_a for 30 of mint_b, partially filled before the top-ups
+        let (mut program, payer, mint_a, mint_b, maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 100, 30, 0);
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let top_up = |program: &mut LiteSVM, amount: u64, receive: Option<u64>| {
+            let top_up_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::TopUp {
+                    maker,
+                    mint_a,
+                    maker_ata_a: Some(maker_ata_a),
+                    escrow,
+                    vault,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::TopUp { amount, receive }.data(),
+            };
+            let message = Message::new(&[top_up_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let escrow_data = |program: &LiteSVM| {
+            crate::state::Escrow::try_deserialize(
+                &mut program.get_account(&escrow).unwrap().data.as_ref(),
+            )
+            .unwrap()
+        };
+
+        // 67 left for 20, doubling the deposit to 134 doubles what is owed
+        top_up(&mut program, 67, None).unwrap();
+        let data = escrow_data(&program);
+        assert_eq!(data.remaining_deposit, 134);
+        assert_eq!(data.remaining_receive, 40);
+        assert_eq!(data.receive, 50);
+
+        // Or the maker names the new price for everything left
+        top_up(&mut program, 16, Some(45)).unwrap();
+        let data = escrow_data(&program);
+        assert_eq!(data.remaining_deposit, 150);
+        assert_eq!(data.remaining_receive, 45);
+        assert_eq!(data.receive, 55);
+
+        assert!(failed_with(
+            top_up(&mut program, 0, None),
+            ErrorCode::DepositTooSmall
+        ));
+
+        let vault_data =
+            spl_token::state::Account::unpack(&program.get_account(&vault).unwrap().data).unwrap();
+        assert_eq!(vault_data.amount, 150);
+
+        msg!("\nAll top-up assertions passed!");
+    }
+
+    #[test]
+    fn test_withdraw_partial() {
+        let (mut program, payer, mint_a, _mint_b, maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 100, 30, 0);
+        let maker = payer.pubkey();
+
+        let withdraw_partial = |program: &mut LiteSVM, amount: u64| {
+            let withdraw_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::WithdrawPartial {
+                    maker,
+                    mint_a,
+                    maker_ata_a,
+                    escrow,
+                    vault,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::WithdrawPartial { amount }.data(),
+            };
+            let message = Message::new(&[withdraw_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        // Withdrawing 40 of 100 leaves 60 for 18
+        withdraw_partial(&mut program, 40).unwrap();
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.remaining_deposit, 60);
+        assert_eq!(escrow_data.remaining_receive, 18);
+        assert_eq!(escrow_data.receive, 18);
+
+        let vault_data =
+            spl_token::state::Account::unpack(&program.get_account(&vault).unwrap().data).unwrap();
+        assert_eq!(vault_data.amount, 60);
+
+        // Nothing, or everything that is left, is not a partial withdrawal
+        assert!(failed_with(
+            withdraw_partial(&mut program, 0),
+            ErrorCode::InvalidWithdrawAmount
+        ));
+        assert!(failed_with(
+            withdraw_partial(&mut program, 60),
+            ErrorCode::InvalidWithdrawAmount
+        ));
+
+        msg!("\nAll partial withdrawal assertions passed!");
+    }
+
+    #[test]
+    fn test_oracle_escrow_resize_keeps_zero_receive() {
+        let (mut program, payer, mint_a, _mint_b, maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 100, 30, 0);
+        let maker = payer.pubkey();
+
+        // As an oracle-priced escrow stands after a partial fill: `receive` stays
+        // zero while `remaining_receive` holds the quote that fill was priced at
+        let mut escrow_account = program.get_account(&escrow).unwrap();
+        let mut escrow_data =
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+        escrow_data.price_feed = Some(Pubkey::new_unique());
+        escrow_data.receive = 0;
+        escrow_data.remaining_receive = 27;
+        let mut data = Vec::new();
+        escrow_data.try_serialize(&mut data).unwrap();
+        data.resize(escrow_account.data.len(), 0);
+        escrow_account.data = data;
+        program.set_account(escrow, escrow_account).unwrap();
+
+        let escrow_data = |program: &LiteSVM| {
+            crate::state::Escrow::try_deserialize(
+                &mut program.get_account(&escrow).unwrap().data.as_ref(),
+            )
+            .unwrap()
+        };
+        let send = |program: &mut LiteSVM, ix: Instruction| {
+            let message = Message::new(&[ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        let withdraw_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::WithdrawPartial {
+                maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::WithdrawPartial { amount: 40 }.data(),
+        };
+        send(&mut program, withdraw_ix).unwrap();
+        let data = escrow_data(&program);
+        assert_eq!(data.remaining_deposit, 60);
+        assert_eq!(data.remaining_receive, 17);
+        assert_eq!(data.receive, 0);
+
+        let top_up_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::TopUp {
+                maker,
+                mint_a,
+                maker_ata_a: Some(maker_ata_a),
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::TopUp {
+                amount: 40,
+                receive: None,
+            }
+            .data(),
+        };
+        send(&mut program, top_up_ix).unwrap();
+        let data = escrow_data(&program);
+        assert_eq!(data.remaining_deposit, 100);
+        assert_eq!(data.remaining_receive, 29);
+        assert_eq!(data.receive, 0);
+    }
+
+    #[test]
+    fn test_split() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 30, 60, 0);
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let (outsider, _outsider_ata_a, _outsider_ata_b) =
+            setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let child = escrow_pda(maker, 456u64, mint_a, mint_b);
+        let child_vault = associated_token::get_associated_token_address(&child, &mint_a);
+        let split = |program: &mut LiteSVM, amount: u64| {
+            let split_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::Split {
+                    maker,
+                    config: config_pda(),
+                    mint_a,
+                    mint_b,
+                    escrow,
+                    vault,
+                    child,
+                    child_vault,
+                    maker_registry: maker_registry_pda(maker),
+                    maker_stats: None,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::Split {
+                    seed: 456u64,
+                    amount,
+                    taker: Some(taker.pubkey()),
+                }
+                .data(),
+            };
+            let message = Message::new(&[split_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        // Splitting everything off is a Refund and a Make
+        assert!(failed_with(
+            split(&mut program, 30),
+            ErrorCode::InvalidSplitAmount
+        ));
+        split(&mut program, 10).unwrap();
+
+        let read = |program: &LiteSVM, escrow: Pubkey| {
+            let escrow_account = program.get_account(&escrow).unwrap();
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap()
+        };
+        let parent_data = read(&program, escrow);
+        assert_eq!(parent_data.remaining_deposit, 20);
+        assert_eq!(parent_data.remaining_receive, 40);
+        let child_data = read(&program, child);
+        assert_eq!(child_data.remaining_deposit, 10);
+        assert_eq!(child_data.remaining_receive, 20);
+        assert_eq!(child_data.taker, Some(taker.pubkey()));
+        assert_eq!(
+            child_data.vault_address(&child, &TOKEN_PROGRAM_ID),
+            child_vault
+        );
+        let child_vault_data =
+            spl_token::state::Account::unpack(&program.get_account(&child_vault).unwrap().data)
+                .unwrap();
+        assert_eq!(child_vault_data.amount, 10);
+
+        // The new escrow is reserved, the rest stays public
+        let take_ix = take_instruction(
+            outsider.pubkey(),
+            maker,
+            mint_a,
+            mint_b,
+            child,
+            child_vault,
+            20,
+        );
+        let message = Message::new(&[take_ix], Some(&outsider.pubkey()));
+        let transaction = Transaction::new(&[&outsider], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::UnauthorizedTaker
+        ));
+        let take_ix = take_instruction(outsider.pubkey(), maker, mint_a, mint_b, escrow, vault, 40);
+        let message = Message::new(&[take_ix], Some(&outsider.pubkey()));
+        let transaction = Transaction::new(&[&outsider], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let take_ix = take_instruction(
+            taker.pubkey(),
+            maker,
+            mint_a,
+            mint_b,
+            child,
+            child_vault,
+            20,
+        );
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        assert!(
+            program.get_account(&child).is_none(),
+            "Filled split escrow should be closed"
+        );
+
+        msg!("\nSplit assertions passed!");
+    }
+
+    #[test]
+    fn test_merge() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let (escrow, vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            1u64,
+            10,
+            20,
+            0,
+        );
+        let (other, other_vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            2u64,
+            20,
+            40,
+            0,
+        );
+        let (pricier, pricier_vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            3u64,
+            10,
+            30,
+            0,
+        );
+
+        let merge = |program: &mut LiteSVM, other: Pubkey, other_vault: Pubkey| {
+            let merge_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::Merge {
+                    maker,
+                    mint_a,
+                    escrow,
+                    vault,
+                    other,
+                    other_vault,
+                    rent_payer: maker,
+                    maker_registry: Some(maker_registry_pda(maker)),
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::Merge {}.data(),
+            };
+            let message = Message::new(&[merge_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        // A different price would change what takers get
+        assert!(failed_with(
+            merge(&mut program, pricier, pricier_vault),
+            ErrorCode::TermsMismatch
+        ));
+        merge(&mut program, other, other_vault).unwrap();
+
+        let escrow_account = program.get_account(&escrow).unwrap();
+        let escrow_data =
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+        assert_eq!(escrow_data.remaining_deposit, 30);
+        assert_eq!(escrow_data.remaining_receive, 60);
+        assert_eq!(escrow_data.receive, 60);
+        let vault_data =
+            spl_token::state::Account::unpack(&program.get_account(&vault).unwrap().data).unwrap();
+        assert_eq!(vault_data.amount, 30);
+        assert!(
+            program.get_account(&other).is_none(),
+            "Merged escrow should be closed"
+        );
+        assert!(
+            program.get_account(&other_vault).is_none(),
+            "Merged escrow's vault should be closed"
+        );
+        let registry_account = program.get_account(&maker_registry_pda(maker)).unwrap();
+        let maker_registry =
+            crate::state::MakerRegistry::try_deserialize(&mut registry_account.data.as_ref())
+                .unwrap();
+        assert_eq!(maker_registry.open_escrows, 2);
+
+        msg!("\nMerge assertions passed!");
+    }
+
+    #[test]
+    fn test_transfer_maker_authority() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+        let maker = payer.pubkey();
+        let custodian = Keypair::new();
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let transfer = |program: &mut LiteSVM, escrow: Pubkey, new_maker: Pubkey| {
+            let transfer_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::TransferMakerAuthority {
+                    maker,
+                    escrow,
+                    maker_registry: Some(maker_registry_pda(maker)),
+                    config: config_pda(),
+                    new_maker_registry: maker_registry_pda(new_maker),
+                    system_program: SYSTEM_PROGRAM_ID,
+                    event_authority: event_authority_pda(),
+                    program: PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::TransferMakerAuthority { new_maker }.data(),
+            };
+            let message = Message::new(&[transfer_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        assert!(failed_with(
+            transfer(&mut program, escrow, maker),
+            ErrorCode::InvalidNewMaker
+        ));
+        transfer(&mut program, escrow, custodian.pubkey()).unwrap();
+
+        let escrow_account = program.get_account(&escrow).unwrap();
+        let escrow_data =
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+        assert_eq!(escrow_data.maker, custodian.pubkey());
+        assert_eq!(escrow_data.made_by, Some(maker));
+        assert!(escrow_data.registered);
+        assert_eq!(read_maker_registry(&program, maker).open_escrows, 0);
+        let custodian_registry = read_maker_registry(&program, custodian.pubkey());
+        assert_eq!(custodian_registry.maker, custodian.pubkey());
+        assert_eq!(custodian_registry.open_escrows, 1);
+        assert_eq!(custodian_registry.recent[0].escrow, escrow);
+
+        // The escrow counts against the new maker's limit on open escrows
+        let set_limits_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::SetLimits {
+                admin: maker,
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetLimits {
+                min_deposit: 0,
+                max_open_escrows_per_maker: 1,
+            }
+            .data(),
+        };
+        let message = Message::new(&[set_limits_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let (other_escrow, _other_vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            124u64,
+            10,
+            10,
+            0,
+        );
+        assert!(failed_with(
+            transfer(&mut program, other_escrow, custodian.pubkey()),
+            ErrorCode::TooManyOpenEscrows
+        ));
+
+        // The previous maker can no longer refund
+        let refund_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker,
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                maker_registry: None,
+                maker_stats: None,
+                vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+        let message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        // Proceeds now go to the new maker, the rent back to the original payer
+        let custodian_ata_b =
+            associated_token::get_associated_token_address(&custodian.pubkey(), &mint_b);
+        let take_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                payer: taker.pubkey(),
+                maker: custodian.pubkey(),
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_a: associated_token::get_associated_token_address(
+                    &taker.pubkey(),
+                    &mint_a,
+                ),
+                taker_ata_b: associated_token::get_associated_token_address(
+                    &taker.pubkey(),
+                    &mint_b,
+                ),
+                maker_ata_b: custodian_ata_b,
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                referrer_ata_b: None,
+                escrow,
+                maker_registry: Some(maker_registry_pda(custodian.pubkey())),
+                maker_stats: None,
+                vault,
+                proceeds: None,
+                proceeds_vault: None,
+                price_update: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take {
+                take_amount: 10,
+                proof: vec![],
+                max_pay: 10,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data(),
+        };
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let custodian_b_data =
+            spl_token::state::Account::unpack(&program.get_account(&custodian_ata_b).unwrap().data)
+                .unwrap();
+        assert_eq!(custodian_b_data.amount, 10);
+        assert!(
+            program.get_account(&escrow).is_none(),
+            "Filled escrow should be closed"
+        );
+        assert_eq!(
+            read_maker_registry(&program, custodian.pubkey()).open_escrows,
+            0
+        );
+
+        msg!("\nMaker authority transfer assertions passed!");
+    }
+
+    #[test]
+    fn test_set_waiting_time() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a, open_escrow, _vault) =
+            setup_with_make(123u64, 10, 10, 1000);
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        // Same terms, reserved for a designated taker
+        let (mut make_ix, reserved_escrow, _reserved_vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 124u64, 10, 10, 1000);
+        make_ix.data = crate::instruction::Make {
+            seed: 124u64,
+            args: crate::MakeArgs {
+                deposit: 10,
+                receive: 10,
+                waiting_time: 1000,
+                expiry: 0,
+                taker: Some(taker.pubkey()),
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let set_waiting_time = |program: &mut LiteSVM, escrow: Pubkey, waiting_time: i64| {
+            let set_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::SetWaitingTime {
+                    maker,
+                    config: config_pda(),
+                    escrow,
+                    event_authority: event_authority_pda(),
+                    program: PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::SetWaitingTime { waiting_time }.data(),
+            };
+            let message = Message::new(&[set_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let unlock_at = |program: &LiteSVM, escrow: Pubkey| {
+            crate::state::Escrow::try_deserialize(
+                &mut program.get_account(&escrow).unwrap().data.as_ref(),
+            )
+            .unwrap()
+            .unlock_at
+        };
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+
+        // An open escrow can be unlocked right away
+        set_waiting_time(&mut program, open_escrow, 0).unwrap();
+        assert_eq!(unlock_at(&program, open_escrow), now);
+
+        // A reserved escrow can only be pushed back
+        assert!(failed_with(
+            set_waiting_time(&mut program, reserved_escrow, 0),
+            ErrorCode::WaitingTimeShortened
+        ));
+        set_waiting_time(&mut program, reserved_escrow, 2000).unwrap();
+        assert_eq!(unlock_at(&program, reserved_escrow), now + 2000);
+
+        assert!(failed_with(
+            set_waiting_time(&mut program, open_escrow, MAX_WAITING_TIME + 1),
+            ErrorCode::WaitingTimeTooLong
+        ));
+
+        msg!("\nAll set waiting time assertions passed!");
+    }
+
+    #[test]
+    fn test_set_escrow_paused() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let set_paused = |program: &mut LiteSVM, signer: &Keypair, paused: bool| {
+            let set_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::SetEscrowPaused {
+                    maker: signer.pubkey(),
+                    escrow,
+                    event_authority: event_authority_pda(),
+                    program: PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::SetEscrowPaused { paused }.data(),
+            };
+            let message = Message::new(&[set_ix], Some(&signer.pubkey()));
+            let transaction = Transaction::new(&[signer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let take = |program: &mut LiteSVM| {
+            let take_ix =
+                take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            program.send_transaction(transaction)
+        };
+
+        // Only the maker can pause their escrow
+        assert!(set_paused(&mut program, &taker, true).is_err());
+        set_paused(&mut program, &payer, true).unwrap();
+        assert!(failed_with(take(&mut program), ErrorCode::EscrowPaused));
+
+        set_paused(&mut program, &payer, false).unwrap();
+        take(&mut program).unwrap();
+        let escrow_account = program.get_account(&escrow);
+        assert!(escrow_account.is_none() || escrow_account.unwrap().data.is_empty());
+
+        msg!("\nAll escrow pause assertions passed!");
+    }
+
+    #[test]
+    fn test_payment_options() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let create_mint = |program: &mut LiteSVM| {
+            CreateMint::new(program, &payer)
+                .decimals(6)
+                .authority(&maker)
+                .send()
+                .unwrap()
+        };
+        let mint_c = create_mint(&mut program);
+        let unlisted_mint = create_mint(&mut program);
+
+        // 100 of mint_a for 30 of mint_b or 60 of mint_c
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 100, 30, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 123u64,
+            args: crate::MakeArgs {
+                deposit: 100,
+                receive: 30,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![crate::state::PaymentOption {
+                    mint: mint_c,
+                    receive: 60,
+                }],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let (taker, taker_ata_a, _taker_ata_c) = setup_taker(&mut program, &payer, mint_a, mint_c);
+        MintTo::new(
+            &mut program,
+            &payer,
+            &unlisted_mint,
+            &CreateAssociatedTokenAccount::new(&mut program, &taker, &unlisted_mint)
+                .owner(&taker.pubkey())
+                .send()
+                .unwrap(),
+            1000,
+        )
+        .send()
+        .unwrap();
+
+        let take_ix = take_instruction(
+            taker.pubkey(),
+            maker,
+            mint_a,
+            unlisted_mint,
+            escrow,
+            vault,
+            20,
+        );
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::InvalidMint
+        ));
+
+        // 20 of mint_c counts for 10 of mint_b, a third of the deposit
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_c, escrow, vault, 20);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let taker_ata_a_data =
+            spl_token::state::Account::unpack(&program.get_account(&taker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(taker_ata_a_data.amount, 33);
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.remaining_deposit, 67);
+        assert_eq!(escrow_data.remaining_receive, 20);
+
+        msg!("\nAll payment option assertions passed!");
+    }
+
+    #[test]
+    fn test_make_with_label() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        let mut make = |seed: u64, label: &str| {
+            let (mut make_ix, escrow, _vault) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, seed, 10, 10, 0);
+            make_ix.data = crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 10,
+                    receive: 10,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: Some(label.to_string()),
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data();
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            (program.send_transaction(transaction), escrow)
+        };
+
+        let (result, _) = make(1, &"x".repeat(crate::state::Escrow::MAX_LABEL_LEN + 1));
+        assert!(
+            failed_with(result, ErrorCode::LabelTooLong),
+            "Make should reject a label over the length limit"
+        );
+
+        let (result, escrow) = make(2, "10 A for 10 B, ipfs://offer");
+        result.unwrap();
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(
+            escrow_data.label.as_deref(),
+            Some("10 A for 10 B, ipfs://offer")
+        );
+
+        msg!("\nAll label assertions passed!");
+    }
+
+    #[test]
+    fn test_take_rejects_fill_too_small() {
+        // 10 of mint_a for 40 of mint_b: a take of 1 would pay 0.25, rounded down to nothing
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 40, 0);
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 1);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::FillTooSmall
+            ),
+            "Take should fail when the fill rounds down to zero"
+        );
+
+        // Four units of mint_b buy one unit of mint_a
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 4);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        msg!("\nAll fill size assertions passed!");
+    }
+
+    /// Helper function to read a Token-2022 account balance, which may carry extensions
+    fn token_2022_amount(program: &LiteSVM, token_account: &Pubkey) -> u64 {
+        use spl_token_2022::{extension::StateWithExtensions, state::Account as Token2022Account};
+
+        let account = program.get_account(token_account).unwrap();
+        StateWithExtensions::<Token2022Account>::unpack(&account.data)
+            .unwrap()
+            .base
+            .amount
+    }
+
+    #[test]
+    fn test_make_and_take_with_token_2022_mints() {
+        let (mut program, payer, _mint_a, _mint_b, _maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let token_program = spl_token_2022::ID;
+        let ata = |owner: &Pubkey, mint: &Pubkey| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                owner,
+                mint,
+                &token_program,
+            )
+        };
+
+        // Token-2022 on both the deposit and the receive side
+        let mint_a = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let mint_b = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+            .owner(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        let seed = 2022u64;
+        let escrow = escrow_pda(maker, seed, mint_a, mint_b);
+        let vault = ata(&escrow, &mint_a);
+
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker,
+                payer: maker,
+                config: config_pda(),
+                mint_a,
+                mint_b,
+                maker_ata_a: Some(maker_ata_a),
+                escrow,
+                maker_registry: maker_registry_pda(maker),
+                maker_stats: None,
+                vault,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 100,
+                    receive: 50,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data(),
+        };
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        assert_eq!(token_2022_amount(&program, &vault), 100);
+
+        // Taker pays in Token-2022 mint_b and receives Token-2022 mint_a
+        let taker = Keypair::new();
+        program
+            .airdrop(&taker.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
+            .owner(&taker.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        let take_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                payer: taker.pubkey(),
+                maker,
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_a: ata(&taker.pubkey(), &mint_a),
+                taker_ata_b,
+                maker_ata_b: ata(&maker, &mint_b),
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: ata(&TREASURY, &mint_b),
+                referrer_ata_b: None,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                proceeds: None,
+                proceeds_vault: None,
+                price_update: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take {
+                take_amount: 50,
+                proof: vec![],
+                max_pay: 50,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data(),
+        };
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(token_2022_amount(&program, &ata(&taker.pubkey(), &mint_a)), 100);
+        assert_eq!(token_2022_amount(&program, &ata(&maker, &mint_b)), 50);
+
+        // A Token-2022 mint cannot be paired with a legacy SPL Token mint
+        let legacy_mint = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&maker)
+            .send()
+            .unwrap();
+        let (make_ix, _, _) =
+            make_instruction(maker, mint_a, legacy_mint, maker_ata_a, 2023u64, 10, 10, 0);
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            program.send_transaction(transaction).is_err(),
+            "Make should fail when the mints belong to different token programs"
+        );
+
+        msg!("\nAll Token-2022 assertions passed!");
+    }
+
+    #[test]
+    fn test_make_and_take_with_transfer_fee_mint() {
+        use spl_token_2022::extension::{transfer_fee, ExtensionType};
+
+        let (mut program, payer, _mint_a, _mint_b, _maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let token_program = spl_token_2022::ID;
+        let ata = |owner: &Pubkey, mint: &Pubkey| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                owner,
+                mint,
+                &token_program,
+            )
+        };
+
+        // mint_a withholds 1% of every transfer
+        let mint_a = Keypair::new();
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        let create_ixs = [
+            solana_system_interface::instruction::create_account(
+                &maker,
+                &mint_a.pubkey(),
+                program.minimum_balance_for_rent_exemption(space),
+                space as u64,
+                &token_program,
+            ),
+            transfer_fee::instruction::initialize_transfer_fee_config(
+                &token_program,
+                &mint_a.pubkey(),
+                None,
+                None,
+                100,
+                u64::MAX,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &token_program,
+                &mint_a.pubkey(),
+                &maker,
+                None,
+                6,
+            )
+            .unwrap(),
+        ];
+        let message = Message::new(&create_ixs, Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer, &mint_a], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let mint_a = mint_a.pubkey();
+
+        let mint_b = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+            .owner(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 10_000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        let seed = 2022u64;
+        let escrow = escrow_pda(maker, seed, mint_a, mint_b);
+        let vault = ata(&escrow, &mint_a);
+
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker,
+                payer: maker,
+                config: config_pda(),
+                mint_a,
+                mint_b,
+                maker_ata_a: Some(maker_ata_a),
+                escrow,
+                maker_registry: maker_registry_pda(maker),
+                maker_stats: None,
+                vault,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 1000,
+                    receive: 50,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data(),
+        };
+
+        // Vesting would charge the fee again on every claim
+        let mut vesting_make_ix = make_ix.clone();
+        vesting_make_ix.data = crate::instruction::Make {
+            seed,
+            args: crate::MakeArgs {
+                deposit: 1000,
+                receive: 50,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: Some(crate::SettlementTerms {
+                    hashlock: None,
+                    arbiter: None,
+                    review_period: None,
+                    vesting_duration: 100,
+                }),
+            },
+        }
+        .data();
+        let message = Message::new(&[vesting_make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::VestingTransferFee
+        ));
+
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        // Only what reached the vault is on offer
+        let remaining_deposit = |program: &LiteSVM| {
+            crate::state::Escrow::try_deserialize(
+                &mut program.get_account(&escrow).unwrap().data.as_ref(),
+            )
+            .unwrap()
+            .remaining_deposit
+        };
+        assert_eq!(token_2022_amount(&program, &vault), 990);
+        assert_eq!(remaining_deposit(&program), 990);
+
+        let taker = Keypair::new();
+        program
+            .airdrop(&taker.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
+            .owner(&taker.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        let take_ix = |take_amount: u64| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                payer: taker.pubkey(),
+                maker,
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_a: ata(&taker.pubkey(), &mint_a),
+                taker_ata_b,
+                maker_ata_b: ata(&maker, &mint_b),
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: ata(&TREASURY, &mint_b),
+                referrer_ata_b: None,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                proceeds: None,
+                proceeds_vault: None,
+                price_update: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take {
+                take_amount,
+                proof: vec![],
+                max_pay: take_amount,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data(),
+        };
+
+        // Half the receive takes half the vault, and the escrow stays in step with it
+        let message = Message::new(&[take_ix(25)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        assert_eq!(token_2022_amount(&program, &vault), 495);
+        assert_eq!(remaining_deposit(&program), 495);
+        assert_eq!(
+            token_2022_amount(&program, &ata(&taker.pubkey(), &mint_a)),
+            490
+        );
+
+        let message = Message::new(&[take_ix(25)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let escrow_account = program.get_account(&escrow);
+        assert!(
+            escrow_account.is_none() || escrow_account.unwrap().data.is_empty(),
+            "Escrow should be closed once the vault is drained"
+        );
+
+        msg!("\nAll transfer fee assertions passed!");
+    }
+
+    #[test]
+    fn test_split_with_transfer_fee_mint() {
+        use spl_token_2022::extension::{transfer_fee, ExtensionType};
+
+        let (mut program, payer, _mint_a, _mint_b, _maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let token_program = spl_token_2022::ID;
+        let ata = |owner: &Pubkey, mint: &Pubkey| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                owner,
+                mint,
+                &token_program,
+            )
+        };
+
+        // mint_a withholds 1% of every transfer
+        let mint_a = Keypair::new();
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        let create_ixs = [
+            solana_system_interface::instruction::create_account(
+                &maker,
+                &mint_a.pubkey(),
+                program.minimum_balance_for_rent_exemption(space),
+                space as u64,
+                &token_program,
+            ),
+            transfer_fee::instruction::initialize_transfer_fee_config(
+                &token_program,
+                &mint_a.pubkey(),
+                None,
+                None,
+                100,
+                u64::MAX,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &token_program,
+                &mint_a.pubkey(),
+                &maker,
+                None,
+                6,
+            )
+            .unwrap(),
+        ];
+        let message = Message::new(&create_ixs, Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer, &mint_a], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let mint_a = mint_a.pubkey();
+
+        let mint_b = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+            .owner(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 10_000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        // 1 mint_b per unit, 990 of the 1000 deposited reach the vault
+        let seed = 2022u64;
+        let escrow = escrow_pda(maker, seed, mint_a, mint_b);
+        let vault = ata(&escrow, &mint_a);
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker,
+                payer: maker,
+                config: config_pda(),
+                mint_a,
+                mint_b,
+                maker_ata_a: Some(maker_ata_a),
+                escrow,
+                maker_registry: maker_registry_pda(maker),
+                maker_stats: None,
+                vault,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 1000,
+                    receive: 1000,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: Some(crate::UnitPricing {
+                        unit_price: crate::state::UnitPrice {
+                            numerator: 1,
+                            denominator: 1,
+                        },
+                        curve_bps: 0,
+                    }),
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data(),
+        };
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let child = escrow_pda(maker, 456u64, mint_a, mint_b);
+        let child_vault = ata(&child, &mint_a);
+        let split_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Split {
+                maker,
+                config: config_pda(),
+                mint_a,
+                mint_b,
+                escrow,
+                vault,
+                child,
+                child_vault,
+                maker_registry: maker_registry_pda(maker),
+                maker_stats: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Split {
+                seed: 456u64,
+                amount: 500,
+                taker: None,
+            }
+            .data(),
+        };
+        let message = Message::new(&[split_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let read = |program: &LiteSVM, escrow: Pubkey| {
+            let escrow_account = program.get_account(&escrow).unwrap();
+            crate::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap()
+        };
+        let parent_data = read(&program, escrow);
+        assert_eq!(parent_data.remaining_deposit, 490);
+        assert_eq!(parent_data.remaining_receive, 490);
+        assert_eq!(parent_data.receive, 490);
+
+        // The new escrow owes the unit cost of the 495 that reached its vault
+        assert_eq!(token_2022_amount(&program, &child_vault), 495);
+        let child_data = read(&program, child);
+        assert_eq!(child_data.remaining_deposit, 495);
+        assert_eq!(child_data.remaining_receive, 495);
+        assert_eq!(child_data.receive, 495);
+    }
+
+    #[test]
+    fn test_make_rejects_confidential_transfer_mint() {
+        use spl_token_2022::extension::{confidential_transfer, ExtensionType};
+
+        let (mut program, payer, _mint_a, _mint_b, _maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let token_program = spl_token_2022::ID;
+
+        let mint_a = Keypair::new();
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::ConfidentialTransferMint,
+        ])
+        .unwrap();
+        let create_ixs = [
+            solana_system_interface::instruction::create_account(
+                &maker,
+                &mint_a.pubkey(),
+                program.minimum_balance_for_rent_exemption(space),
+                space as u64,
+                &token_program,
+            ),
+            confidential_transfer::instruction::initialize_mint(
+                &token_program,
+                &mint_a.pubkey(),
+                None,
+                true,
+                None,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &token_program,
+                &mint_a.pubkey(),
+                &maker,
+                None,
+                6,
+            )
+            .unwrap(),
+        ];
+        let message = Message::new(&create_ixs, Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer, &mint_a], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let mint_a = mint_a.pubkey();
+
+        let mint_b = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+            .owner(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 10_000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        let seed = 2023u64;
+        let escrow = escrow_pda(maker, seed, mint_a, mint_b);
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker,
+                payer: maker,
+                config: config_pda(),
+                mint_a,
+                mint_b,
+                maker_ata_a: Some(maker_ata_a),
+                escrow,
+                maker_registry: maker_registry_pda(maker),
+                maker_stats: None,
+                vault: spl_associated_token_account::get_associated_token_address_with_program_id(
+                    &escrow,
+                    &mint_a,
+                    &token_program,
+                ),
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 1000,
+                    receive: 50,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data(),
+        };
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::ConfidentialTransferUnsupported
+            ),
+            "Make should reject a mint with confidential transfers"
+        );
+        assert!(program.get_account(&escrow).is_none());
+
+        msg!("\nAll confidential transfer assertions passed!");
+    }
+
+    #[test]
+    fn test_receive_quoted_in_ui_amount() {
+        use spl_token_2022::extension::{interest_bearing_mint, ExtensionType};
+
+        let (mut program, payer, _mint_a, _mint_b, _maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let token_program = spl_token_2022::ID;
+        let ata = |owner: &Pubkey, mint: &Pubkey| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                owner,
+                mint,
+                &token_program,
+            )
+        };
+
+        // mint_b accrues 100% a year, compounded continuously
+        let mint_b = Keypair::new();
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::InterestBearingConfig,
+        ])
+        .unwrap();
+        let create_ixs = [
+            solana_system_interface::instruction::create_account(
+                &maker,
+                &mint_b.pubkey(),
+                program.minimum_balance_for_rent_exemption(space),
+                space as u64,
+                &token_program,
+            ),
+            interest_bearing_mint::instruction::initialize(
+                &token_program,
+                &mint_b.pubkey(),
+                None,
+                10_000,
+            )
+            .unwrap(),
+            spl_token_2022::instruction::initialize_mint2(
+                &token_program,
+                &mint_b.pubkey(),
+                &maker,
+                None,
+                6,
+            )
+            .unwrap(),
+        ];
+        let message = Message::new(&create_ixs, Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer, &mint_b], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let mint_b = mint_b.pubkey();
+
+        let mint_a = CreateMint::new(&mut program, &payer)
+            .decimals(6)
+            .authority(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+            .owner(&maker)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 10_000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        let make_ix = |seed: u64, mint_b: Pubkey| {
+            let escrow = escrow_pda(maker, seed, mint_a, mint_b);
+            let instruction = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::Make {
+                    maker,
+                    payer: maker,
+                    config: config_pda(),
+                    mint_a,
+                    mint_b,
+                    maker_ata_a: Some(maker_ata_a),
+                    escrow,
+                    maker_registry: maker_registry_pda(maker),
+                    maker_stats: None,
+                    vault: ata(&escrow, &mint_a),
+                    memo_program: None,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program,
+                    system_program: SYSTEM_PROGRAM_ID,
+                    event_authority: event_authority_pda(),
+                    program: PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::Make {
+                    seed,
+                    args: crate::MakeArgs {
+                        deposit: 1000,
+                        receive: 1000,
+                        waiting_time: 0,
+                        expiry: 0,
+                        taker: None,
+                        taker_root: None,
+                        whitelisted_takers: false,
+                        require_no_freeze_authority: false,
+                        rent_recipient: None,
+                        payment_options: vec![],
+                        receive_in_ui_amount: true,
+                        label: None,
+                        memo: None,
+                        oracle: None,
+                        unit_pricing: None,
+                        fill_terms: None,
+                        refund_terms: None,
+                        settlement_terms: None,
+                    },
+                }
+                .data(),
+            };
+            (instruction, escrow)
+        };
+
+        // mint_a has no interest to quote in
+        let (instruction, _) = make_ix(1, mint_a);
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::InvalidUiAmountQuote
+            ),
+            "Make should only quote in UI amount for an interest-bearing mint_b"
+        );
+
+        let (instruction, escrow) = make_ix(2, mint_b);
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let vault = ata(&escrow, &mint_a);
+
+        let taker = Keypair::new();
+        program
+            .airdrop(&taker.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .unwrap();
+        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
+            .owner(&taker.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        let take_ix = |take_amount: u64| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Take {
+                taker: taker.pubkey(),
+                payer: taker.pubkey(),
+                maker,
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                taker_ata_a: ata(&taker.pubkey(), &mint_a),
+                taker_ata_b,
+                maker_ata_b: ata(&maker, &mint_b),
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: ata(&TREASURY, &mint_b),
+                referrer_ata_b: None,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                proceeds: None,
+                proceeds_vault: None,
+                price_update: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Take {
+                take_amount,
+                proof: vec![],
+                max_pay: take_amount,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data(),
+        };
+
+        // A year on, every raw unit of mint_b is worth e in UI amount:
+        // 184 pays for 500.16, which rounds to half the quote
+        warp_clock(&mut program, 31_556_736);
+        let message = Message::new(&[take_ix(184)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.remaining_receive, 500);
+        assert_eq!(token_2022_amount(&program, &vault), 500);
+
+        // Paying just over what is left still fills the escrow
+        let message = Message::new(&[take_ix(185)], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let escrow_account = program.get_account(&escrow);
+        assert!(
+            escrow_account.is_none() || escrow_account.unwrap().data.is_empty(),
+            "Escrow should be closed once the UI amount quote is paid"
+        );
+        assert_eq!(
+            token_2022_amount(&program, &ata(&taker.pubkey(), &mint_a)),
+            1000
+        );
+
+        msg!("\nAll UI amount quote assertions passed!");
+    }
+
+    #[test]
+    fn test_make_rejects_invalid_expiry() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+
+        // In the past, before the unlock time, and beyond the configured horizon
+        for (seed, waiting_time, expiry) in [
+            (1u64, 0, now - 1),
+            (2u64, 60, now + 30),
+            (3u64, 0, now + MAX_EXPIRY_HORIZON + 1),
+        ] {
+            let (make_ix, _, _) = make_instruction_with_expiry(
+                maker,
+                mint_a,
+                mint_b,
+                maker_ata_a,
+                seed,
+                10,
+                10,
+                waiting_time,
+                expiry,
+            );
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            assert!(
+                failed_with(
+                    program.send_transaction(transaction),
+                    ErrorCode::InvalidExpiry
+                ),
+                "Make should fail with expiry {} at time {}",
+                expiry,
+                now
+            );
+        }
+
+        // A deadline inside the horizon is stored on the escrow
+        let (make_ix, escrow, _) = make_instruction_with_expiry(
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            4u64,
+            10,
+            10,
+            0,
+            now + 3600,
+        );
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.expiry, now + 3600);
+
+        msg!("\nAll expiry bounds assertions passed!");
+    }
+
+    #[test]
+    fn test_take_fails_after_expiry_and_refund_is_always_allowed() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+
+        let (make_ix, escrow, vault) = make_instruction_with_expiry(
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            123u64,
+            10,
+            40,
+            0,
+            now + 3600,
+        );
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        // Lock the escrow so only an expired refund can get past require_active
+        let mut escrow_account = program.get_account(&escrow).unwrap();
+        escrow_account.data[crate::state::Escrow::STATUS_OFFSET] =
+            crate::state::EscrowStatus::Locked as u8;
+        program.set_account(escrow, escrow_account).unwrap();
+
+        let refund_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker,
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+        let message = Message::new(&[refund_ix.clone()], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::EscrowLocked
+            ),
+            "Refund of a locked escrow should fail before expiry"
+        );
+
+        warp_clock(&mut program, 3600);
+
+        // Unlock it again to show Take is stopped by the deadline itself
+        let mut escrow_account = program.get_account(&escrow).unwrap();
+        escrow_account.data[crate::state::Escrow::STATUS_OFFSET] =
+            crate::state::EscrowStatus::Open as u8;
+        program.set_account(escrow, escrow_account).unwrap();
+
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 40);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::EscrowExpired
+            ),
+            "Take should fail once the escrow has expired"
+        );
+
+        // Past expiry a refund goes through even while the escrow is locked
+        let mut escrow_account = program.get_account(&escrow).unwrap();
+        escrow_account.data[crate::state::Escrow::STATUS_OFFSET] =
+            crate::state::EscrowStatus::Locked as u8;
+        program.set_account(escrow, escrow_account).unwrap();
+
+        let message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let maker_ata_data =
+            spl_token::state::Account::unpack(&program.get_account(&maker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(
+            maker_ata_data.amount, 1000000000,
+            "Deposit should be returned"
+        );
+
+        msg!("\nAll expiry assertions passed!");
+    }
+
+    #[test]
+    fn test_close_expired_crank() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+
+        let (make_ix, escrow, vault) = make_instruction_with_expiry(
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a,
+            123u64,
+            10,
+            10,
+            0,
+            now + 3600,
+        );
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        // Anyone can crank, the maker does not sign
+        let cranker = Keypair::new();
+        program
+            .airdrop(&cranker.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        let close_ix = |maker_registry: Option<Pubkey>| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::CloseExpired {
+                cranker: cranker.pubkey(),
+                maker,
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                vault,
+                maker_registry,
+                maker_stats: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::CloseExpired {}.data(),
+        };
+
+        let message = Message::new(
+            &[close_ix(Some(maker_registry_pda(maker)))],
+            Some(&cranker.pubkey()),
+        );
+        let transaction = Transaction::new(&[&cranker], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::EscrowNotPastExpiry
+            ),
+            "CloseExpired should fail before the expiry"
+        );
+
+        warp_clock(&mut program, 3600);
+
+        // The escrow is counted in the maker's registry, which the crank updates
+        let message = Message::new(&[close_ix(None)], Some(&cranker.pubkey()));
+        let transaction = Transaction::new(&[&cranker], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::MakerRegistryRequired
+        ));
+
+        let cranker_before = program.get_balance(&cranker.pubkey()).unwrap();
+        let maker_before = program.get_balance(&maker).unwrap();
+        let escrow_rent = program.get_account(&escrow).unwrap().lamports;
+        let vault_rent = program.get_account(&vault).unwrap().lamports;
+
+        let message = Message::new(
+            &[close_ix(Some(maker_registry_pda(maker)))],
+            Some(&cranker.pubkey()),
+        );
+        let transaction = Transaction::new(&[&cranker], message, program.latest_blockhash());
+        let tx = program.send_transaction(transaction).unwrap();
+        msg!("CloseExpired transaction successful");
+        msg!("CUs Consumed: {}", tx.compute_units_consumed);
+
+        // Deposit is back with the maker, both accounts are gone
+        let maker_ata_data =
+            spl_token::state::Account::unpack(&program.get_account(&maker_ata_a).unwrap().data)
+                .unwrap();
+        assert_eq!(maker_ata_data.amount, 1000000000);
+        let escrow_account = program.get_account(&escrow);
+        assert!(
+            escrow_account.is_none() || escrow_account.unwrap().data.is_empty(),
+            "Escrow should be closed by the crank"
+        );
+        let vault_account = program.get_account(&vault);
+        assert!(
+            vault_account.is_none() || vault_account.unwrap().data.is_empty(),
+            "Vault should be closed by the crank"
+        );
+        let maker_registry = read_maker_registry(&program, maker);
+        assert_eq!(maker_registry.open_escrows, 0);
+        assert!(maker_registry.recent.is_empty());
+
+        // The cranker pays the fee and earns the bounty, the rest of the rent goes back
+        let cranker_after = program.get_balance(&cranker.pubkey()).unwrap();
+        assert_eq!(
+            cranker_after + 5000,
+            cranker_before + crate::instructions::CLOSE_EXPIRED_BOUNTY
+        );
+        let maker_after = program.get_balance(&maker).unwrap();
+        assert_eq!(
+            maker_after,
+            maker_before + escrow_rent + vault_rent - crate::instructions::CLOSE_EXPIRED_BOUNTY
+        );
+
+        msg!("\nAll CloseExpired assertions passed!");
+    }
+
+    #[test]
+    fn test_update_escrow() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 100, 40, 0);
+        let maker = payer.pubkey();
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+
+        let update = |program: &mut LiteSVM, receive: u64, waiting_time: i64, expiry: i64| {
+            let update_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::UpdateEscrow {
+                    maker,
+                    config: config_pda(),
+                    mint_a,
+                    escrow,
+                    vault,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::UpdateEscrow {
+                    receive,
+                    waiting_time,
+                    expiry,
+                }
+                .data(),
+            };
+            let message = Message::new(&[update_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let escrow_state = |program: &LiteSVM| {
+            crate::state::Escrow::try_deserialize(
+                &mut program.get_account(&escrow).unwrap().data.as_ref(),
+            )
+            .unwrap()
+        };
+
+        // Out of bounds terms are rejected like they are on Make
+        assert!(failed_with(
+            update(&mut program, 80, -1, 0),
+            ErrorCode::NegativeWaitingTime
+        ));
+        assert!(failed_with(
+            update(&mut program, 80, MAX_WAITING_TIME + 1, 0),
+            ErrorCode::WaitingTimeTooLong
+        ));
+        assert!(failed_with(
+            update(&mut program, 80, 60, now + 30),
+            ErrorCode::InvalidExpiry
+        ));
+
+        // Passing the wrong mint is reported as such rather than a generic constraint
+        let wrong_mint_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::UpdateEscrow {
+                maker,
+                config: config_pda(),
+                mint_a: mint_b,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::UpdateEscrow {
+                receive: 80,
+                waiting_time: 0,
+                expiry: 0,
+            }
+            .data(),
+        };
+        let message = Message::new(&[wrong_mint_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::InvalidMint
+        ));
+
+        // Without a designated taker the unlock can move either way
+        update(&mut program, 80, 60, now + 3600).unwrap();
+        assert_eq!(escrow_state(&program).unlock_at, now + 60);
+
+        update(&mut program, 80, 0, now + 3600).unwrap();
+        let escrow_data = escrow_state(&program);
+        assert_eq!(escrow_data.receive, 80);
+        assert_eq!(escrow_data.remaining_receive, 80);
+        assert_eq!(escrow_data.unlock_at, now);
+        assert_eq!(escrow_data.expiry, now + 3600);
+
+        // Once a taker has filled part of it the terms are fixed
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 20);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert!(
+            failed_with(
+                update(&mut program, 10, 0, 0),
+                ErrorCode::EscrowPartiallyFilled
+            ),
+            "UpdateEscrow should fail after a partial fill"
+        );
+        assert_eq!(escrow_state(&program).remaining_receive, 60);
+
+        msg!("\nAll UpdateEscrow assertions passed!");
+    }
+
+    #[test]
+    fn test_update_escrow_designated_taker() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let taker = Pubkey::new_unique();
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+
+        // Priced on a curve for a single taker
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 1, 1000, 1000, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 1,
+            args: crate::MakeArgs {
+                deposit: 1000,
+                receive: 1000,
+                waiting_time: 0,
+                expiry: 0,
+                taker: Some(taker),
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: Some(crate::UnitPricing {
+                    unit_price: crate::state::UnitPrice {
+                        numerator: 1,
+                        denominator: 1,
+                    },
+                    curve_bps: 10_000,
+                }),
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        // The designated taker agreed to the unlock time, so it can only move back
+        let mut update_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::UpdateEscrow {
+                maker,
+                config: config_pda(),
+                mint_a,
+                escrow,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::UpdateEscrow {
+                receive: 800,
+                waiting_time: 60,
+                expiry: 0,
+            }
+            .data(),
+        };
+        let message = Message::new(&[update_ix.clone()], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        program.expire_blockhash();
+
+        update_ix.data = crate::instruction::UpdateEscrow {
+            receive: 800,
+            waiting_time: 0,
+            expiry: 0,
+        }
+        .data();
+        let message = Message::new(&[update_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::WaitingTimeShortened
+        ));
+
+        // The fixed receive replaces the unit price along with its curve
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.unlock_at, now + 60);
+        assert_eq!(escrow_data.remaining_receive, 800);
+        assert_eq!(escrow_data.unit_price, None);
+        assert_eq!(escrow_data.curve_bps, 0);
+    }
+
+    #[test]
+    fn test_designated_taker() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let (outsider, _outsider_ata_a, _outsider_ata_b) =
+            setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 10, 10, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 123u64,
+            args: crate::MakeArgs {
+                deposit: 10,
+                receive: 10,
+                waiting_time: 0,
+                expiry: 0,
+                taker: Some(taker.pubkey()),
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.taker, Some(taker.pubkey()));
+
+        // Anyone other than the designated taker is turned away
+        let take_ix = take_instruction(outsider.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_ix], Some(&outsider.pubkey()));
+        let transaction = Transaction::new(&[&outsider], message, program.latest_blockhash());
+        assert!(
+            failed_with(
+                program.send_transaction(transaction),
+                ErrorCode::UnauthorizedTaker
+            ),
+            "Take should fail for a signer other than the designated taker"
+        );
+
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        msg!("\nAll designated taker assertions passed!");
+    }
+
+    #[test]
+    fn test_gift() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        let (taker, taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let (outsider, _outsider_ata_a, _outsider_ata_b) =
+            setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let make = |program: &mut LiteSVM, seed: u64, taker: Option<Pubkey>| {
+            let (mut make_ix, escrow, vault) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, seed, 10, 0, 0);
+            make_ix.data = crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: 10,
+                    receive: 0,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data();
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            (program.send_transaction(transaction), escrow, vault)
+        };
+
+        // A gift anyone could claim is rejected
+        let (result, _, _) = make(&mut program, 122u64, None);
+        assert!(
+            failed_with(result, ErrorCode::InvalidGift),
+            "Make should fail for a gift without a designated taker"
+        );
+
+        let (result, escrow, vault) = make(&mut program, 123u64, Some(taker.pubkey()));
+        result.unwrap();
+
+        let take_ix = take_instruction(outsider.pubkey(), maker, mint_a, mint_b, escrow, vault, 0);
+        let message = Message::new(&[take_ix], Some(&outsider.pubkey()));
+        let transaction = Transaction::new(&[&outsider], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::UnauthorizedTaker
+        ));
+
+        // Offering to pay for a gift is refused as well
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 1);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::InvalidTakeAmount
+        ));
+        program.expire_blockhash();
+
+        // The designated taker claims the whole deposit for nothing
+        let taker_b_before = token_amount(&program, &taker_ata_b);
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 0);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        assert_eq!(token_amount(&program, &taker_ata_a), 10);
+        assert_eq!(token_amount(&program, &taker_ata_b), taker_b_before);
+        assert!(program.get_account(&escrow).is_none());
+
+        msg!("\nAll gift assertions passed!");
+    }
+
+    /// Builds every level of a merkle tree over `leaves`, an odd node is carried up as is
+    fn merkle_levels(leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => crate::merkle::hash_pair(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        levels
+    }
+
+    fn merkle_proof(levels: &[Vec<[u8; 32]>], mut index: usize) -> Vec<[u8; 32]> {
+        let mut proof = vec![];
+        for level in &levels[..levels.len() - 1] {
+            if let Some(sibling) = level.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        proof
+    }
+
+    #[test]
+    fn test_merkle_proofs() {
+        // Odd and even sized trees, every member verifies and outsiders do not
+        for size in [1, 2, 3, 5, 8] {
+            let members: Vec<Pubkey> = (0..size).map(|_| Pubkey::new_unique()).collect();
+            let levels = merkle_levels(members.iter().map(crate::merkle::leaf).collect());
+            let root = levels.last().unwrap()[0];
+
+            for (index, member) in members.iter().enumerate() {
+                let proof = merkle_proof(&levels, index);
+                assert!(crate::merkle::verify(&proof, &root, member));
+                assert!(!crate::merkle::verify(&proof, &root, &Pubkey::new_unique()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_taker_allowlist() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        let takers: Vec<Keypair> = (0..3)
+            .map(|_| setup_taker(&mut program, &payer, mint_a, mint_b).0)
+            .collect();
+        let (outsider, _outsider_ata_a, _outsider_ata_b) =
+            setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        // Four leaves, the last one a key that never takes
+        let mut members: Vec<Pubkey> = takers.iter().map(|taker| taker.pubkey()).collect();
+        members.push(Pubkey::new_unique());
+        let levels = merkle_levels(members.iter().map(crate::merkle::leaf).collect());
+        let root = levels.last().unwrap()[0];
+
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 30, 30, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 123u64,
+            args: crate::MakeArgs {
+                deposit: 30,
+                receive: 30,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: Some(root),
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let take = |program: &mut LiteSVM, taker: &Keypair, proof: Vec<[u8; 32]>| {
+            let mut take_ix =
+                take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+            take_ix.data = crate::instruction::Take {
+                take_amount: 10,
+                proof,
+                max_pay: 10,
+                min_receive: 0,
+                memo: None,
+                preimage: None,
+            }
+            .data();
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        // No proof, someone else's proof, and an outsider are all rejected
+        assert!(failed_with(
+            take(&mut program, &takers[0], vec![]),
+            ErrorCode::TakerNotAllowlisted
+        ));
+        assert!(failed_with(
+            take(&mut program, &takers[0], merkle_proof(&levels, 1)),
+            ErrorCode::TakerNotAllowlisted
+        ));
+        assert!(failed_with(
+            take(&mut program, &outsider, merkle_proof(&levels, 0)),
+            ErrorCode::TakerNotAllowlisted
+        ));
+
+        // Each member fills a third with their own proof
+        for (index, taker) in takers.iter().enumerate() {
+            take(&mut program, taker, merkle_proof(&levels, index)).unwrap();
+        }
+        let escrow_account = program.get_account(&escrow);
+        assert!(
+            escrow_account.is_none() || escrow_account.unwrap().data.is_empty(),
+            "Escrow should be closed after the allowlisted takers filled it"
+        );
+
+        msg!("\nAll taker allowlist assertions passed!");
+    }
+
+    #[test]
+    fn test_protocol_fee() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 100, 1000, 0);
+        let maker = payer.pubkey();
+
+        let update_fee_ix = |admin: Pubkey, fee_bps: u16| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::UpdateFee {
+                admin,
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::UpdateFee {
+                fee_bps,
+                treasury: TREASURY,
+            }
+            .data(),
+        };
+
+        // Only the admin can change the fee, and only up to the cap
+        let stranger = Keypair::new();
+        program
+            .airdrop(&stranger.pubkey(), LAMPORTS_PER_SOL)
+            .unwrap();
+        let message = Message::new(
+            &[update_fee_ix(stranger.pubkey(), 250)],
+            Some(&stranger.pubkey()),
+        );
+        let transaction = Transaction::new(&[&stranger], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        let message = Message::new(
+            &[update_fee_ix(maker, crate::state::Config::MAX_FEE_BPS + 1)],
+            Some(&payer.pubkey()),
+        );
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::FeeTooHigh
+        ));
+
+        let message = Message::new(&[update_fee_ix(maker, 250)], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let (taker, _taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 1000);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let token_amount = |account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        // The taker pays the full 1000, 2.5% of it goes to the treasury
+        assert_eq!(token_amount(&taker_ata_b), 1000000000 - 1000);
+        let treasury_ata_b = associated_token::get_associated_token_address(&TREASURY, &mint_b);
+        assert_eq!(token_amount(&treasury_ata_b), 25);
+        let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+        assert_eq!(token_amount(&maker_ata_b), 975);
+
+        msg!("\nAll protocol fee assertions passed!");
+    }
+
+    #[test]
+    fn test_fee_tiers() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 100, 1000, 0);
+        let maker = payer.pubkey();
+
+        let update_fee_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::UpdateFee {
+                admin: maker,
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::UpdateFee {
+                fee_bps: 250,
+                treasury: TREASURY,
+            }
+            .data(),
+        };
+        let set_fee_tiers_ix = |fee_tiers: Vec<crate::state::FeeTier>| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::UpdateFee {
+                admin: maker,
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetFeeTiers { fee_tiers }.data(),
+        };
+        let tier = |min_size: u64, fee_bps: u16| crate::state::FeeTier { min_size, fee_bps };
+
+        // Thresholds must ascend
+        let message = Message::new(
+            &[set_fee_tiers_ix(vec![tier(500, 100), tier(500, 50)])],
+            Some(&payer.pubkey()),
+        );
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::InvalidFeeTiers
+        ));
+
+        let message = Message::new(
+            &[update_fee_ix, set_fee_tiers_ix(vec![tier(500, 100)])],
+            Some(&payer.pubkey()),
+        );
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let treasury_ata_b = associated_token::get_associated_token_address(&TREASURY, &mint_b);
+        let mut take = |take_amount: u64| {
+            let take_ix = take_instruction(
+                taker.pubkey(),
+                maker,
+                mint_a,
+                mint_b,
+                escrow,
+                vault,
+                take_amount,
+            );
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            program.send_transaction(transaction).unwrap();
+            spl_token::state::Account::unpack(&program.get_account(&treasury_ata_b).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        // 2.5% below the tier, 1% from it on
+        assert_eq!(take(400), 10);
+        assert_eq!(take(600), 16);
+    }
+
+    #[test]
+    fn test_referral_fee() {
         let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
-            setup_with_make(123u64, 10, 40, 0);
+            setup_with_make(123u64, 100, 1000, 0);
+        let maker = payer.pubkey();
+
+        let update_fee_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::UpdateFee {
+                admin: maker,
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::UpdateFee {
+                fee_bps: 250,
+                treasury: TREASURY,
+            }
+            .data(),
+        };
+        let set_referral_fee_ix = |referral_bps: u16| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::UpdateFee {
+                admin: maker,
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetReferralFee { referral_bps }.data(),
+        };
+        let message = Message::new(
+            &[update_fee_ix, set_referral_fee_ix(100)],
+            Some(&payer.pubkey()),
+        );
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let referrer = Pubkey::new_unique();
+        let referrer_ata_b = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_b)
+            .owner(&referrer)
+            .send()
+            .unwrap();
+
+        let mut take_ix =
+            take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 1000);
+        take_ix.accounts = crate::accounts::Take {
+            taker: taker.pubkey(),
+            payer: taker.pubkey(),
+            maker,
+            rent_payer: maker,
+            mint_a,
+            mint_b,
+            taker_ata_a: associated_token::get_associated_token_address(&taker.pubkey(), &mint_a),
+            taker_ata_b: associated_token::get_associated_token_address(&taker.pubkey(), &mint_b),
+            maker_ata_b: associated_token::get_associated_token_address(&maker, &mint_b),
+            maker_ata_a: None,
+            config: config_pda(),
+            treasury: TREASURY,
+            treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+            referrer_ata_b: Some(referrer_ata_b),
+            escrow,
+            maker_registry: Some(maker_registry_pda(maker)),
+            maker_stats: None,
+            vault,
+            proceeds: None,
+            proceeds_vault: None,
+            price_update: None,
+            memo_program: None,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program: TOKEN_PROGRAM_ID,
+            system_program: SYSTEM_PROGRAM_ID,
+            event_authority: event_authority_pda(),
+            program: PROGRAM_ID,
+        }
+        .to_account_metas(None);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let token_amount = |account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
 
+        // The referrer's 1% comes out of the 2.5% fee, the maker still gets 97.5%
+        assert_eq!(token_amount(&referrer_ata_b), 10);
+        let treasury_ata_b = associated_token::get_associated_token_address(&TREASURY, &mint_b);
+        assert_eq!(token_amount(&treasury_ata_b), 15);
+        let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+        assert_eq!(token_amount(&maker_ata_b), 975);
+    }
+
+    #[test]
+    fn test_config_limits() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
         let maker = payer.pubkey();
 
-        // Setup TAKER (new user who will take the offer)
+        let set_limits = |program: &mut LiteSVM, min_deposit: u64, max_open: u32| {
+            let set_limits_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::SetLimits {
+                    admin: maker,
+                    config: config_pda(),
+                }
+                .to_account_metas(None),
+                data: crate::instruction::SetLimits {
+                    min_deposit,
+                    max_open_escrows_per_maker: max_open,
+                }
+                .data(),
+            };
+            let message = Message::new(&[set_limits_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            program.send_transaction(transaction).unwrap();
+            program.expire_blockhash();
+        };
+        let make = |program: &mut LiteSVM, seed: u64, deposit: u64| {
+            let (make_ix, _, _) =
+                make_instruction(maker, mint_a, mint_b, maker_ata_a, seed, deposit, 10, 0);
+            let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            program.send_transaction(transaction)
+        };
+
+        set_limits(&mut program, 5, 1);
+        assert!(failed_with(
+            make(&mut program, 1, 4),
+            ErrorCode::DepositTooSmall
+        ));
+        make(&mut program, 1, 5).unwrap();
+        assert!(failed_with(
+            make(&mut program, 2, 5),
+            ErrorCode::TooManyOpenEscrows
+        ));
+
+        // 0 lifts the limit
+        set_limits(&mut program, 5, 0);
+        make(&mut program, 2, 5).unwrap();
+    }
+
+    #[test]
+    fn test_pause() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 10, 0);
+        let maker = payer.pubkey();
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let set_paused_ix = |admin: Pubkey, paused: bool| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::SetPaused {
+                admin,
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetPaused { paused }.data(),
+        };
+
+        // Only the admin can pause
+        let message = Message::new(
+            &[set_paused_ix(taker.pubkey(), true)],
+            Some(&taker.pubkey()),
+        );
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        let message = Message::new(&[set_paused_ix(maker, true)], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        // No new escrows and no fills while paused
+        let (make_ix, _, _) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 456u64, 10, 10, 0);
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::Paused
+        ));
+
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::Paused
+        ));
+
+        // Handing over the admin needs both keys, after which the old one is locked out
+        let new_admin = Keypair::new();
+        let set_admin_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::SetAdmin {
+                admin: maker,
+                new_admin: new_admin.pubkey(),
+                config: config_pda(),
+            }
+            .to_account_metas(None),
+            data: crate::instruction::SetAdmin {}.data(),
+        };
+        let message = Message::new(&[set_admin_ix], Some(&payer.pubkey()));
+        let transaction =
+            Transaction::new(&[&payer, &new_admin], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let message = Message::new(&[set_paused_ix(maker, false)], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(program.send_transaction(transaction).is_err());
+
+        // Refunds stay open while paused
+        let refund_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Refund {
+                maker,
+                rent_payer: maker,
+                mint_a,
+                maker_ata_a,
+                escrow,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                vault,
+                config: None,
+                treasury: None,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Refund { memo: None }.data(),
+        };
+        let message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        // The new admin can unpause
+        let message = Message::new(
+            &[set_paused_ix(new_admin.pubkey(), false)],
+            Some(&payer.pubkey()),
+        );
+        let transaction =
+            Transaction::new(&[&payer, &new_admin], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+        let config = crate::state::Config::try_deserialize(
+            &mut program.get_account(&config_pda()).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert!(!config.paused);
+        assert_eq!(config.admin, new_admin.pubkey());
+
+        msg!("\nAll pause assertions passed!");
+    }
+
+    /// Adds the wSOL mint to the test validator when it is not preloaded
+    fn ensure_native_mint(program: &mut LiteSVM) -> Pubkey {
+        let native_mint = spl_token::native_mint::ID;
+        if program.get_account(&native_mint).is_none() {
+            let mut data = vec![0; spl_token::state::Mint::LEN];
+            spl_token::state::Mint {
+                decimals: 9,
+                is_initialized: true,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            program
+                .set_account(
+                    native_mint,
+                    Account {
+                        lamports: program.minimum_balance_for_rent_exemption(data.len()),
+                        data,
+                        owner: TOKEN_PROGRAM_ID,
+                        executable: false,
+                        rent_epoch: 0,
+                    },
+                )
+                .unwrap();
+        }
+        native_mint
+    }
+
+    #[test]
+    fn test_native_sol_deposit_is_unwrapped_for_taker() {
+        let (mut program, payer, _mint_a, mint_b, _maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let native_mint = ensure_native_mint(&mut program);
+
+        // No wSOL account on the maker side, the deposit comes straight from lamports
+        let seed = 123u64;
+        let escrow = escrow_pda(maker, seed, native_mint, mint_b);
+        let vault = associated_token::get_associated_token_address(&escrow, &native_mint);
+        let make_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Make {
+                maker,
+                payer: maker,
+                config: config_pda(),
+                mint_a: native_mint,
+                mint_b,
+                maker_ata_a: None,
+                escrow,
+                maker_registry: maker_registry_pda(maker),
+                maker_stats: None,
+                vault,
+                memo_program: None,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+                event_authority: event_authority_pda(),
+                program: PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Make {
+                seed,
+                args: crate::MakeArgs {
+                    deposit: LAMPORTS_PER_SOL,
+                    receive: 10,
+                    waiting_time: 0,
+                    expiry: 0,
+                    taker: None,
+                    taker_root: None,
+                    whitelisted_takers: false,
+                    require_no_freeze_authority: false,
+                    rent_recipient: None,
+                    payment_options: vec![],
+                    receive_in_ui_amount: false,
+                    label: None,
+                    memo: None,
+                    oracle: None,
+                    unit_pricing: None,
+                    fill_terms: None,
+                    refund_terms: None,
+                    settlement_terms: None,
+                },
+            }
+            .data(),
+        };
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let vault_data =
+            spl_token::state::Account::unpack(&program.get_account(&vault).unwrap().data)
+                .unwrap();
+        assert_eq!(vault_data.amount, LAMPORTS_PER_SOL);
+
         let taker = Keypair::new();
         program
-            .airdrop(&taker.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL)
             .unwrap();
-
-        // Create taker's ATA for Mint A (to receive tokens from escrow)
-        let taker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_a)
+        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
             .owner(&taker.pubkey())
             .send()
             .unwrap();
-        msg!("Taker ATA A: {}\n", taker_ata_a);
-
-        // Create taker's ATA for Mint B (to send tokens to maker)
-        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
-            .owner(&taker.pubkey())
+        MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 10)
             .send()
             .unwrap();
-        msg!("Taker ATA B: {}\n", taker_ata_b);
+        let taker_lamports_before = program.get_balance(&taker.pubkey()).unwrap();
 
-        // Mint tokens to taker's ATA B (so taker has tokens to trade)
-        MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000)
+        let take_ix =
+            take_instruction(taker.pubkey(), maker, native_mint, mint_b, escrow, vault, 10);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        // The SOL lands as lamports, the temporary wSOL account is gone
+        let taker_ata_a =
+            associated_token::get_associated_token_address(&taker.pubkey(), &native_mint);
+        let taker_ata_a_account = program.get_account(&taker_ata_a);
+        assert!(taker_ata_a_account.is_none() || taker_ata_a_account.unwrap().data.is_empty());
+        // Less the fee and the rent of the maker and treasury mint_b accounts it created
+        let taker_lamports_after = program.get_balance(&taker.pubkey()).unwrap();
+        assert!(taker_lamports_after > taker_lamports_before + LAMPORTS_PER_SOL * 99 / 100);
+
+        msg!("\nAll native deposit assertions passed!");
+    }
+
+    #[test]
+    fn test_native_sol_payment_is_wrapped_for_taker() {
+        let (mut program, payer, mint_a, _mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let native_mint = ensure_native_mint(&mut program);
+
+        let (escrow, vault) = execute_make(
+            &mut program,
+            &payer,
+            maker,
+            mint_a,
+            native_mint,
+            maker_ata_a,
+            123u64,
+            10,
+            2 * LAMPORTS_PER_SOL,
+            0,
+        );
+
+        // The taker holds plain SOL and no wSOL account
+        let taker = Keypair::new();
+        program
+            .airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .unwrap();
+
+        let take_ix = take_instruction(
+            taker.pubkey(),
+            maker,
+            mint_a,
+            native_mint,
+            escrow,
+            vault,
+            2 * LAMPORTS_PER_SOL,
+        );
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let token_amount = |account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+        let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+        assert_eq!(token_amount(&taker_ata_a), 10);
+        let maker_ata_b = associated_token::get_associated_token_address(&maker, &native_mint);
+        assert_eq!(token_amount(&maker_ata_b), 2 * LAMPORTS_PER_SOL);
+
+        // The wrapper account used for the payment is closed again
+        let taker_ata_b =
+            associated_token::get_associated_token_address(&taker.pubkey(), &native_mint);
+        let taker_ata_b_account = program.get_account(&taker_ata_b);
+        assert!(taker_ata_b_account.is_none() || taker_ata_b_account.unwrap().data.is_empty());
+        assert!(program.get_balance(&taker.pubkey()).unwrap() < 8 * LAMPORTS_PER_SOL);
+
+        msg!("\nAll native payment assertions passed!");
+    }
+
+    /// Mints a single NFT to the maker and writes its Token Metadata account
+    /// Returns: (NFT mint, maker's NFT ATA)
+    fn mint_nft(
+        program: &mut LiteSVM,
+        payer: &Keypair,
+        collection: Option<crate::metadata::Collection>,
+    ) -> (Pubkey, Pubkey) {
+        let maker = payer.pubkey();
+        let nft = CreateMint::new(program, payer)
+            .decimals(0)
+            .authority(&maker)
+            .send()
+            .unwrap();
+        let maker_ata = CreateAssociatedTokenAccount::new(program, payer, &nft)
+            .owner(&maker)
+            .send()
+            .unwrap();
+        MintTo::new(program, payer, &nft, &maker_ata, 1)
             .send()
             .unwrap();
 
+        let metadata = crate::metadata::Metadata {
+            key: 4,
+            update_authority: maker,
+            mint: nft,
+            name: "Escrow Test".to_string(),
+            symbol: "ESC".to_string(),
+            uri: "https://example.com/nft.json".to_string(),
+            seller_fee_basis_points: 500,
+            creators: Some(vec![crate::metadata::Creator {
+                address: maker,
+                verified: true,
+                share: 100,
+            }]),
+            primary_sale_happened: false,
+            is_mutable: true,
+            edition_nonce: None,
+            token_standard: Some(0),
+            collection,
+        };
+        let data = anchor_lang::AnchorSerialize::try_to_vec(&metadata).unwrap();
+        program
+            .set_account(
+                crate::metadata::Metadata::address(&nft),
+                Account {
+                    lamports: program.minimum_balance_for_rent_exemption(data.len()),
+                    data,
+                    owner: crate::metadata::METADATA_PROGRAM_ID,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+
+        (nft, maker_ata)
+    }
+
+    fn make_nft_instruction(
+        maker: Pubkey,
+        nft: Pubkey,
+        mint_b: Pubkey,
+        maker_ata: Pubkey,
+        seed: u64,
+        collection: Option<Pubkey>,
+    ) -> (Instruction, Pubkey, Pubkey) {
+        make_nft_instruction_with_royalties(
+            maker, nft, mint_b, maker_ata, seed, 10, collection, false,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn make_nft_instruction_with_royalties(
+        maker: Pubkey,
+        nft: Pubkey,
+        mint_b: Pubkey,
+        maker_ata: Pubkey,
+        seed: u64,
+        receive: u64,
+        collection: Option<Pubkey>,
+        enforce_royalties: bool,
+    ) -> (Instruction, Pubkey, Pubkey) {
+        let (make_ix, escrow, vault) =
+            make_instruction(maker, nft, mint_b, maker_ata, seed, 1, receive, 0);
+        let metadata = crate::metadata::Metadata::address(&nft);
+        let mut accounts = make_ix.accounts;
+        accounts.push(solana_instruction::AccountMeta::new_readonly(metadata, false));
+
+        let make_nft_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: crate::instruction::MakeNft {
+                seed,
+                receive,
+                waiting_time: 0,
+                expiry: 0,
+                collection,
+                enforce_royalties,
+            }
+            .data(),
+        };
+        (make_nft_ix, escrow, vault)
+    }
+
+    #[test]
+    fn test_make_nft() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        let collection = Pubkey::new_unique();
+        let (nft, maker_nft_ata) = mint_nft(
+            &mut program,
+            &payer,
+            Some(crate::metadata::Collection {
+                verified: true,
+                key: collection,
+            }),
+        );
+
+        let send = |program: &mut LiteSVM, ix: Instruction| {
+            let message = Message::new(&[ix], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        // A fungible mint is not accepted
+        let (make_ix, _, _) = make_nft_instruction(maker, mint_a, mint_b, maker_ata_a, 1, None);
+        assert!(failed_with(
+            send(&mut program, make_ix),
+            ErrorCode::NotAnNft
+        ));
+
+        // The NFT must belong to the requested collection
+        let (make_ix, _, _) = make_nft_instruction(
+            maker,
+            nft,
+            mint_b,
+            maker_nft_ata,
+            2,
+            Some(Pubkey::new_unique()),
+        );
+        assert!(failed_with(
+            send(&mut program, make_ix),
+            ErrorCode::CollectionMismatch
+        ));
+
+        let (make_ix, escrow, vault) =
+            make_nft_instruction(maker, nft, mint_b, maker_nft_ata, 3, Some(collection));
+        send(&mut program, make_ix).unwrap();
+
+        let vault_data =
+            spl_token::state::Account::unpack(&program.get_account(&vault).unwrap().data).unwrap();
+        assert_eq!(vault_data.amount, 1);
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.mint_a, nft);
+        assert_eq!(escrow_data.remaining_deposit, 1);
+
+        msg!("\nAll NFT escrow assertions passed!");
+    }
+
+    #[test]
+    fn test_make_nft_rejects_unverified_collection() {
+        let (mut program, payer, _mint_a, mint_b, _maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        let collection = Pubkey::new_unique();
+        let (nft, maker_nft_ata) = mint_nft(
+            &mut program,
+            &payer,
+            Some(crate::metadata::Collection {
+                verified: false,
+                key: collection,
+            }),
+        );
+
+        let (make_ix, _, _) =
+            make_nft_instruction(maker, nft, mint_b, maker_nft_ata, 1, Some(collection));
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        assert!(failed_with(
+            program.send_transaction(transaction),
+            ErrorCode::CollectionMismatch
+        ));
+
+        // Without a collection requirement the same NFT is fine
+        let (make_ix, _, _) = make_nft_instruction(maker, nft, mint_b, maker_nft_ata, 2, None);
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        msg!("\nUnverified collection rejected!");
+    }
+
+    #[test]
+    fn test_take_nft_pays_royalties() {
+        let (mut program, payer, _mint_a, mint_b, _maker_ata_a) = setup();
+        let maker = payer.pubkey();
+
+        let (nft, maker_nft_ata) = mint_nft(&mut program, &payer, None);
+
+        // Split the 5% seller fee 60/40 between two creators, plus an unpaid one
+        let creators = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let metadata_address = crate::metadata::Metadata::address(&nft);
+        let mut metadata_account = program.get_account(&metadata_address).unwrap();
+        let mut metadata: crate::metadata::Metadata =
+            anchor_lang::AnchorDeserialize::deserialize(&mut metadata_account.data.as_ref())
+                .unwrap();
+        metadata.creators = Some(vec![
+            crate::metadata::Creator {
+                address: creators[0],
+                verified: true,
+                share: 60,
+            },
+            crate::metadata::Creator {
+                address: maker,
+                verified: true,
+                share: 0,
+            },
+            crate::metadata::Creator {
+                address: creators[1],
+                verified: false,
+                share: 40,
+            },
+        ]);
+        metadata_account.data = anchor_lang::AnchorSerialize::try_to_vec(&metadata).unwrap();
+        program
+            .set_account(metadata_address, metadata_account)
+            .unwrap();
+
+        let creator_atas = creators.map(|creator| {
+            CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_b)
+                .owner(&creator)
+                .send()
+                .unwrap()
+        });
+
+        let (make_ix, escrow, vault) = make_nft_instruction_with_royalties(
+            maker,
+            nft,
+            mint_b,
+            maker_nft_ata,
+            1,
+            10_000,
+            None,
+            true,
+        );
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let (taker, taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, nft, mint_b);
+        let send_take = |program: &mut LiteSVM, remaining: Vec<Pubkey>| {
+            let mut take_ix =
+                take_instruction(taker.pubkey(), maker, nft, mint_b, escrow, vault, 10_000);
+            take_ix.accounts.extend(
+                remaining
+                    .into_iter()
+                    .map(|key| solana_instruction::AccountMeta::new(key, false)),
+            );
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        // The metadata account is required
+        assert!(failed_with(
+            send_take(&mut program, vec![]),
+            ErrorCode::InvalidMetadata
+        ));
+
+        // Creator accounts must follow metadata order
+        assert!(failed_with(
+            send_take(
+                &mut program,
+                vec![metadata_address, creator_atas[1], creator_atas[0]]
+            ),
+            ErrorCode::InvalidCreatorAccount
+        ));
+
+        send_take(
+            &mut program,
+            vec![metadata_address, creator_atas[0], creator_atas[1]],
+        )
+        .unwrap();
+
+        let token_amount = |account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+
+        assert_eq!(token_amount(&taker_ata_a), 1);
+        assert_eq!(token_amount(&taker_ata_b), 1000000000 - 10_000);
+        assert_eq!(token_amount(&creator_atas[0]), 300);
+        assert_eq!(token_amount(&creator_atas[1]), 200);
+        let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+        assert_eq!(token_amount(&maker_ata_b), 9_500);
+
+        msg!("\nAll royalty assertions passed!");
+    }
+
+    fn auction_pda(escrow: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"auction", escrow.as_ref()], &PROGRAM_ID).0
+    }
+
+    fn bid_pda(auction: &Pubkey, bidder: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"bid", auction.as_ref(), bidder.as_ref()], &PROGRAM_ID).0
+    }
+
+    fn start_auction_instruction(
+        maker: Pubkey,
+        escrow: Pubkey,
+        end_at: i64,
+        reserve: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::StartAuction {
+                maker,
+                escrow,
+                auction: auction_pda(&escrow),
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::StartAuction { end_at, reserve }.data(),
+        }
+    }
+
+    fn bid_instruction(
+        bidder: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        escrow: Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        let auction = auction_pda(&escrow);
+        let bid = bid_pda(&auction, &bidder);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::PlaceBid {
+                bidder,
+                mint_a,
+                mint_b,
+                bidder_ata_a: associated_token::get_associated_token_address(&bidder, &mint_a),
+                bidder_ata_b: associated_token::get_associated_token_address(&bidder, &mint_b),
+                escrow,
+                auction,
+                bid,
+                bid_vault: associated_token::get_associated_token_address(&bid, &mint_b),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Bid { amount }.data(),
+        }
+    }
 
-        let maker_ata_b =
-            spl_associated_token_account::get_associated_token_address(&maker, &mint_b);
-        msg!("Maker ATA B : {}\n", maker_ata_b);
+    fn outbid_refund_instruction(bidder: Pubkey, mint_b: Pubkey, escrow: Pubkey) -> Instruction {
+        let auction = auction_pda(&escrow);
+        let bid = bid_pda(&auction, &bidder);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::OutbidRefund {
+                bidder,
+                mint_b,
+                bidder_ata_b: associated_token::get_associated_token_address(&bidder, &mint_b),
+                auction,
+                bid,
+                bid_vault: associated_token::get_associated_token_address(&bid, &mint_b),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::OutbidRefund {}.data(),
+        }
+    }
 
-        // Execute Take instruction
-        let take_ix = Instruction {
+    /// Settle instruction, with the winner's accounts when `winner` is given
+    fn settle_instruction(
+        settler: Pubkey,
+        maker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        escrow: Pubkey,
+        vault: Pubkey,
+        winner: Option<Pubkey>,
+    ) -> Instruction {
+        let auction = auction_pda(&escrow);
+        let bid = winner.map(|winner| bid_pda(&auction, &winner));
+        Instruction {
             program_id: PROGRAM_ID,
-            accounts: crate::accounts::Take {
-                taker: taker.pubkey(),
-                maker: maker,
-                mint_a: mint_a,
-                mint_b: mint_b,
-                taker_ata_a: taker_ata_a,
-                taker_ata_b: taker_ata_b,
-                maker_ata_b: maker_ata_b,
-                escrow: escrow,
-                vault: vault,
+            accounts: crate::accounts::Settle {
+                settler,
+                maker,
+                rent_payer: maker,
+                mint_a,
+                mint_b,
+                maker_ata_b: associated_token::get_associated_token_address(&maker, &mint_b),
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
+                escrow,
+                vault,
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                auction,
+                winner,
+                winner_ata_a: winner
+                    .map(|winner| associated_token::get_associated_token_address(&winner, &mint_a)),
+                bid,
+                bid_vault: bid
+                    .map(|bid| associated_token::get_associated_token_address(&bid, &mint_b)),
                 associated_token_program: spl_associated_token_account::ID,
                 token_program: TOKEN_PROGRAM_ID,
                 system_program: SYSTEM_PROGRAM_ID,
             }
             .to_account_metas(None),
-            data: crate::instruction::Take {}.data(),
+            data: crate::instruction::Settle {}.data(),
+        }
+    }
+
+    #[test]
+    fn test_english_auction() {
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
+            setup_with_make(123u64, 10, 100, 0);
+        let maker = payer.pubkey();
+
+        let send = |program: &mut LiteSVM, ix: Instruction, signer: &Keypair| {
+            let message = Message::new(&[ix], Some(&signer.pubkey()));
+            let transaction = Transaction::new(&[signer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
         };
 
-        let take_message = Message::new(&[take_ix], Some(&taker.pubkey()));
-        let take_blockhash = program.latest_blockhash();
-        let take_transaction = Transaction::new(&[&taker], take_message, take_blockhash);
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+        assert!(failed_with(
+            send(
+                &mut program,
+                start_auction_instruction(maker, escrow, now, 50),
+                &payer
+            ),
+            ErrorCode::InvalidAuctionEnd
+        ));
+        send(
+            &mut program,
+            start_auction_instruction(maker, escrow, now + 100, 50),
+            &payer,
+        )
+        .unwrap();
 
-        let take_tx = program.send_transaction(take_transaction).unwrap();
-        msg!("\nTake transaction successful");
-        msg!("CUs Consumed: {}", take_tx.compute_units_consumed);
-        msg!("Tx Signature: {}", take_tx.signature);
+        let (alice, _, alice_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let (bob, _, bob_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
 
-        // Verify the swap completed correctly
+        // The escrow can no longer be taken directly
+        let take_ix = take_instruction(alice.pubkey(), maker, mint_a, mint_b, escrow, vault, 100);
+        assert!(failed_with(
+            send(&mut program, take_ix, &alice),
+            ErrorCode::EscrowLocked
+        ));
 
-        // Check taker received 10 tokens of Mint A from vault
-        let taker_ata_a_account = program.get_account(&taker_ata_a).unwrap();
-        let taker_ata_a_data =
-            spl_token::state::Account::unpack(&taker_ata_a_account.data).unwrap();
-        assert_eq!(
-            taker_ata_a_data.amount, 10,
-            "Taker should receive 10 tokens of mint A"
+        // Bids must meet the reserve and beat the highest bid, top-ups count
+        let bid = |bidder: &Keypair, amount| {
+            bid_instruction(bidder.pubkey(), mint_a, mint_b, escrow, amount)
+        };
+        assert!(failed_with(
+            send(&mut program, bid(&alice, 40), &alice),
+            ErrorCode::BidTooLow
+        ));
+        send(&mut program, bid(&alice, 60), &alice).unwrap();
+        assert!(failed_with(
+            send(&mut program, bid(&bob, 60), &bob),
+            ErrorCode::BidTooLow
+        ));
+        send(&mut program, bid(&bob, 70), &bob).unwrap();
+        send(&mut program, bid(&alice, 20), &alice).unwrap();
+
+        // Only the losing bid can be withdrawn
+        assert!(failed_with(
+            send(
+                &mut program,
+                outbid_refund_instruction(alice.pubkey(), mint_b, escrow),
+                &alice
+            ),
+            ErrorCode::WinningBid
+        ));
+        send(
+            &mut program,
+            outbid_refund_instruction(bob.pubkey(), mint_b, escrow),
+            &bob,
+        )
+        .unwrap();
+
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+        assert_eq!(token_amount(&program, &bob_ata_b), 1000000000);
+
+        let settle_ix = settle_instruction(
+            maker,
+            maker,
+            mint_a,
+            mint_b,
+            escrow,
+            vault,
+            Some(alice.pubkey()),
         );
+        assert!(failed_with(
+            send(&mut program, settle_ix.clone(), &payer),
+            ErrorCode::AuctionNotEnded
+        ));
 
-        // Check maker received 40 tokens of Mint B from taker
-        let maker_ata_b_account = program.get_account(&maker_ata_b).unwrap();
-        let maker_ata_b_data =
-            spl_token::state::Account::unpack(&maker_ata_b_account.data).unwrap();
-        assert_eq!(
-            maker_ata_b_data.amount, 40,
-            "Maker should receive 10 tokens of mint B"
+        warp_clock(&mut program, 100);
+        assert!(failed_with(
+            send(&mut program, bid(&bob, 90), &bob),
+            ErrorCode::AuctionEnded
+        ));
+        send(&mut program, settle_ix, &payer).unwrap();
+
+        let alice_ata_a = associated_token::get_associated_token_address(&alice.pubkey(), &mint_a);
+        assert_eq!(token_amount(&program, &alice_ata_a), 10);
+        assert_eq!(token_amount(&program, &alice_ata_b), 1000000000 - 80);
+        let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+        assert_eq!(token_amount(&program, &maker_ata_b), 80);
+        let escrow_account = program.get_account(&escrow);
+        assert!(
+            escrow_account.is_none() || escrow_account.unwrap().data.is_empty(),
+            "Escrow should be closed by Settle"
+        );
+        let bid_account = program.get_account(&bid_pda(&auction_pda(&escrow), &alice.pubkey()));
+        assert!(
+            bid_account.is_none() || bid_account.unwrap().data.is_empty(),
+            "Winning bid should be closed by Settle"
         );
+        assert_eq!(read_maker_registry(&program, maker).open_escrows, 0);
 
-        msg!("\nAll Take assertions passed!");
+        msg!("\nAll auction assertions passed!");
     }
 
     #[test]
-    fn test_take_with_waiting_time() {
-        let waiting_time = 300i64;
+    fn test_settle_without_bids_reopens_escrow() {
         let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, vault) =
-            setup_with_make(123u64, 40, 90, waiting_time);
-
+            setup_with_make(123u64, 10, 100, 0);
         let maker = payer.pubkey();
 
-        // Taker setup
-        let taker = Keypair::new();
-        program
-            .airdrop(&taker.pubkey(), 100 * LAMPORTS_PER_SOL)
-            .unwrap();
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
+        let start_ix = start_auction_instruction(maker, escrow, now + 100, 50);
+        let message = Message::new(&[start_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
 
-        // Taker ATA A
-        let taker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_a)
-            .owner(&taker.pubkey())
-            .send()
-            .unwrap();
-        
-        // Taker ATA B
-        let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
-            .owner(&taker.pubkey())
-            .send()
-            .unwrap();
-        
-        MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000)
-            .send()
-            .unwrap();
+        warp_clock(&mut program, 100);
+        let settle_ix = settle_instruction(maker, maker, mint_a, mint_b, escrow, vault, None);
+        let message = Message::new(&[settle_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
 
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(escrow_data.status, crate::state::EscrowStatus::Open);
 
-        // Maker ATA B
-        let maker_ata_b =
-            spl_associated_token_account::get_associated_token_address(&maker, &mint_b);
+        // Back to a regular escrow that can be taken
+        let (taker, _taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let take_ix = take_instruction(taker.pubkey(), maker, mint_a, mint_b, escrow, vault, 100);
+        let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+        let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
 
-        let clock: Clock = program.get_sysvar();
-        let start_time = clock.unix_timestamp;
+        msg!("\nUnsold auction reopened the escrow!");
+    }
 
-        // First attempt (should fail)
-        let take_ix_before_waiting = Instruction {
+    fn counter_offer_pda(escrow: &Pubkey, taker: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"counter_offer", escrow.as_ref(), taker.as_ref()],
+            &PROGRAM_ID,
+        )
+        .0
+    }
+
+    fn propose_counter_offer_instruction(
+        taker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        escrow: Pubkey,
+        offer: u64,
+        request: u64,
+    ) -> Instruction {
+        let counter_offer = counter_offer_pda(&escrow, &taker);
+        Instruction {
             program_id: PROGRAM_ID,
-            accounts: crate::accounts::Take {
-                taker: taker.pubkey(),
+            accounts: crate::accounts::ProposeCounterOffer {
+                taker,
+                mint_a,
+                mint_b,
+                taker_ata_b: associated_token::get_associated_token_address(&taker, &mint_b),
+                escrow,
+                counter_offer,
+                counter_offer_vault: associated_token::get_associated_token_address(
+                    &counter_offer,
+                    &mint_b,
+                ),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::ProposeCounterOffer { offer, request }.data(),
+        }
+    }
+
+    fn accept_counter_offer_instruction(
+        maker: Pubkey,
+        taker: Pubkey,
+        mint_a: Pubkey,
+        mint_b: Pubkey,
+        taker_ata_a: Pubkey,
+        escrow: Pubkey,
+    ) -> Instruction {
+        let counter_offer = counter_offer_pda(&escrow, &taker);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::AcceptCounterOffer {
                 maker,
+                taker,
+                rent_payer: maker,
                 mint_a,
                 mint_b,
                 taker_ata_a,
-                taker_ata_b,
-                maker_ata_b,
+                maker_ata_b: associated_token::get_associated_token_address(&maker, &mint_b),
+                maker_ata_a: None,
+                config: config_pda(),
+                treasury: TREASURY,
+                treasury_ata_b: associated_token::get_associated_token_address(&TREASURY, &mint_b),
                 escrow,
-                vault,
+                vault: associated_token::get_associated_token_address(&escrow, &mint_a),
+                maker_registry: Some(maker_registry_pda(maker)),
+                maker_stats: None,
+                counter_offer,
+                counter_offer_vault: associated_token::get_associated_token_address(
+                    &counter_offer,
+                    &mint_b,
+                ),
                 associated_token_program: spl_associated_token_account::ID,
                 token_program: TOKEN_PROGRAM_ID,
                 system_program: SYSTEM_PROGRAM_ID,
             }
             .to_account_metas(None),
-            data: crate::instruction::Take {}.data(),
+            data: crate::instruction::AcceptCounterOffer {}.data(),
+        }
+    }
+
+    #[test]
+    fn test_counter_offers() {
+        // 100 of mint_a for 30 of mint_b
+        let (mut program, payer, mint_a, mint_b, _maker_ata_a, escrow, _vault) =
+            setup_with_make(123u64, 100, 30, 0);
+        let maker = payer.pubkey();
+        let (alice, alice_ata_a, alice_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let (bob, bob_ata_a, bob_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+
+        let send = |program: &mut LiteSVM, ix: Instruction, signer: &Keypair| {
+            let message = Message::new(&[ix], Some(&signer.pubkey()));
+            let transaction = Transaction::new(&[signer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
         };
 
-        let msg_before_waiting = Message::new(&[take_ix_before_waiting], Some(&taker.pubkey()));
-        let bh_before_waiting = program.latest_blockhash();
-        let tx_before_waiting = Transaction::new(&[&taker], msg_before_waiting, bh_before_waiting);
+        // Asking for more than the deposit is rejected
+        let propose_ix =
+            propose_counter_offer_instruction(alice.pubkey(), mint_a, mint_b, escrow, 10, 101);
+        assert!(failed_with(
+            send(&mut program, propose_ix, &alice),
+            ErrorCode::InvalidCounterOffer
+        ));
 
-        let result_before_waiting = program.send_transaction(tx_before_waiting);
+        // Alice offers 10 for half the deposit, below the asking price of 15
+        let propose_ix =
+            propose_counter_offer_instruction(alice.pubkey(), mint_a, mint_b, escrow, 10, 50);
+        send(&mut program, propose_ix, &alice).unwrap();
+        assert_eq!(token_amount(&program, &alice_ata_b), 1000000000 - 10);
+
+        // Bob changes his mind and gets his offer back
+        let propose_ix =
+            propose_counter_offer_instruction(bob.pubkey(), mint_a, mint_b, escrow, 5, 40);
+        send(&mut program, propose_ix, &bob).unwrap();
+        let bob_offer = counter_offer_pda(&escrow, &bob.pubkey());
+        let withdraw_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::WithdrawCounterOffer {
+                taker: bob.pubkey(),
+                mint_b,
+                taker_ata_b: bob_ata_b,
+                counter_offer: bob_offer,
+                counter_offer_vault: associated_token::get_associated_token_address(
+                    &bob_offer, &mint_b,
+                ),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::WithdrawCounterOffer {}.data(),
+        };
+        send(&mut program, withdraw_ix, &bob).unwrap();
+        assert_eq!(token_amount(&program, &bob_ata_b), 1000000000);
+        let bob_offer_account = program.get_account(&bob_offer);
         assert!(
-            result_before_waiting.is_err(),
-            "Take should fail before waiting time"
+            bob_offer_account.is_none() || bob_offer_account.unwrap().data.is_empty(),
+            "Withdrawn counter offer should be closed"
         );
 
-        msg!("✓ Take failed before waiting time");
+        // The maker settles at Alice's terms
+        let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+        let accept_ix = |taker: Pubkey, taker_ata_a: Pubkey| {
+            accept_counter_offer_instruction(maker, taker, mint_a, mint_b, taker_ata_a, escrow)
+        };
+        let alice_offer = counter_offer_pda(&escrow, &alice.pubkey());
+        send(&mut program, accept_ix(alice.pubkey(), alice_ata_a), &payer).unwrap();
 
-        let mut new_clock: Clock = program.get_sysvar();
-        let current_slot = new_clock.slot;
+        assert_eq!(token_amount(&program, &alice_ata_a), 50);
+        assert_eq!(token_amount(&program, &maker_ata_b), 10);
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(
+            escrow_data.status,
+            crate::state::EscrowStatus::PartiallyFilled
+        );
+        assert_eq!(escrow_data.remaining_deposit, 50);
+        assert_eq!(escrow_data.remaining_receive, 15);
+        assert_eq!(escrow_data.total_filled, 50);
+        let alice_offer_account = program.get_account(&alice_offer);
+        assert!(
+            alice_offer_account.is_none() || alice_offer_account.unwrap().data.is_empty(),
+            "Accepted counter offer should be closed"
+        );
+        assert_eq!(read_maker_registry(&program, maker).open_escrows, 1);
 
-        // 2. Update the timestamp for your program logic
-        new_clock.unix_timestamp += waiting_time;
+        // Bob comes back for the rest, which closes the escrow and drops it from the
+        // maker's registry
+        let propose_ix =
+            propose_counter_offer_instruction(bob.pubkey(), mint_a, mint_b, escrow, 20, 50);
+        send(&mut program, propose_ix, &bob).unwrap();
+        send(&mut program, accept_ix(bob.pubkey(), bob_ata_a), &payer).unwrap();
 
-        // 3. Increment the slot relatively (ensures a new blockhash/signature)
-        // This fixes the "AlreadyProcessed" error
-        new_clock.slot = current_slot + 100;
+        assert_eq!(token_amount(&program, &bob_ata_a), 50);
+        let escrow_account = program.get_account(&escrow);
+        assert!(
+            escrow_account.is_none() || escrow_account.unwrap().data.is_empty(),
+            "Escrow should be closed by the last counter offer"
+        );
+        let maker_registry = read_maker_registry(&program, maker);
+        assert_eq!(maker_registry.open_escrows, 0);
+        assert!(maker_registry.recent.is_empty());
 
-        // 4. Push the updated sysvar back to the VM
-        program.set_sysvar::<Clock>(&new_clock);
+        msg!("\nAll counter offer assertions passed!");
+    }
 
-        program.warp_to_slot(new_clock.slot);
+    #[test]
+    fn test_counter_offer_fill_terms() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let (taker, taker_ata_a, _taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let now = program.get_sysvar::<Clock>().unix_timestamp;
 
-        msg!("New slot: {}", new_clock.slot);
-        msg!("New timestamp: {}", new_clock.unix_timestamp);
+        // 100 of mint_a for 30 of mint_b, in fills of at least 20
+        let (mut make_ix, escrow, _vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 123u64, 100, 30, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 123u64,
+            args: crate::MakeArgs {
+                deposit: 100,
+                receive: 30,
+                waiting_time: 0,
+                expiry: now + 100,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: None,
+                unit_pricing: None,
+                fill_terms: Some(crate::FillTerms {
+                    standing: false,
+                    min_fill: 20,
+                    fill_grace_period: 500,
+                }),
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
 
-        msg!("Old timestamp: {}", start_time,);
-        msg!(
-            "New timestamp: {} {}",
-            new_clock.unix_timestamp,
-            new_clock.slot
+        let send = |program: &mut LiteSVM, ix: Instruction, signer: &Keypair| {
+            let message = Message::new(&[ix], Some(&signer.pubkey()));
+            let transaction = Transaction::new(&[signer], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+        let accept_ix = accept_counter_offer_instruction(
+            maker,
+            taker.pubkey(),
+            mint_a,
+            mint_b,
+            taker_ata_a,
+            escrow,
         );
 
-        // Second take (should succeed)
-        let take_ix = Instruction {
+        // An accepted counter offer is a fill like any other, so it respects the minimum
+        let propose_ix =
+            propose_counter_offer_instruction(taker.pubkey(), mint_a, mint_b, escrow, 3, 10);
+        send(&mut program, propose_ix, &taker).unwrap();
+        assert!(failed_with(
+            send(&mut program, accept_ix.clone(), &payer),
+            ErrorCode::FillBelowMinimum
+        ));
+
+        let withdraw_ix = Instruction {
             program_id: PROGRAM_ID,
-            accounts: crate::accounts::Take {
+            accounts: crate::accounts::WithdrawCounterOffer {
                 taker: taker.pubkey(),
-                maker,
-                mint_a,
                 mint_b,
-                taker_ata_a,
-                taker_ata_b,
-                maker_ata_b,
-                escrow,
-                vault,
+                taker_ata_b: associated_token::get_associated_token_address(
+                    &taker.pubkey(),
+                    &mint_b,
+                ),
+                counter_offer: counter_offer_pda(&escrow, &taker.pubkey()),
+                counter_offer_vault: associated_token::get_associated_token_address(
+                    &counter_offer_pda(&escrow, &taker.pubkey()),
+                    &mint_b,
+                ),
                 associated_token_program: spl_associated_token_account::ID,
                 token_program: TOKEN_PROGRAM_ID,
-                system_program: SYSTEM_PROGRAM_ID,
             }
             .to_account_metas(None),
-            data: crate::instruction::Take {}.data(),
+            data: crate::instruction::WithdrawCounterOffer {}.data(),
         };
+        send(&mut program, withdraw_ix, &taker).unwrap();
 
-        let msg = Message::new(&[take_ix], Some(&taker.pubkey()));
-        program.expire_blockhash();
-        let new_bh = program.latest_blockhash();
-        let tx = Transaction::new(&[&taker], msg, new_bh);
+        // A partial fill counts towards the total and keeps the offer open for the
+        // grace period
+        let propose_ix =
+            propose_counter_offer_instruction(taker.pubkey(), mint_a, mint_b, escrow, 6, 40);
+        send(&mut program, propose_ix, &taker).unwrap();
+        send(&mut program, accept_ix, &payer).unwrap();
 
-        program.send_transaction(tx).unwrap();
-        msg!("✓ Take succeeded after waiting time");
+        let escrow_data = crate::state::Escrow::try_deserialize(
+            &mut program.get_account(&escrow).unwrap().data.as_ref(),
+        )
+        .unwrap();
+        assert_eq!(
+            escrow_data.status,
+            crate::state::EscrowStatus::PartiallyFilled
+        );
+        assert_eq!(escrow_data.remaining_deposit, 60);
+        assert_eq!(escrow_data.total_filled, 40);
+        assert_eq!(escrow_data.expiry, now + 500);
 
-        // Final assertions
-        let taker_a_acc = program.get_account(&taker_ata_a).unwrap();
-        let taker_a_data = spl_token::state::Account::unpack(&taker_a_acc.data).unwrap();
-        assert_eq!(taker_a_data.amount, 40);
+        msg!("\nCounter offer fill term assertions passed!");
+    }
 
-        let maker_b_acc = program.get_account(&maker_ata_b).unwrap();
-        let maker_b_data = spl_token::state::Account::unpack(&maker_b_acc.data).unwrap();
-        assert_eq!(maker_b_data.amount, 90);
+    /// Writes a fully verified Pyth price update published at the current clock time
+    #[cfg(feature = "oracle")]
+    fn set_price(program: &mut LiteSVM, feed: Pubkey, price: i64, conf: u64, exponent: i32) {
+        use crate::oracle::{
+            PriceFeedMessage, PriceUpdateV2, VerificationLevel, PRICE_UPDATE_V2_DISCRIMINATOR,
+            PYTH_RECEIVER_PROGRAM_ID,
+        };
 
-        msg!("✓ All assertions passed");
+        let clock: Clock = program.get_sysvar();
+        let update = PriceUpdateV2 {
+            write_authority: Pubkey::new_unique(),
+            verification_level: VerificationLevel::Full,
+            price_message: PriceFeedMessage {
+                feed_id: [1; 32],
+                price,
+                conf,
+                exponent,
+                publish_time: clock.unix_timestamp,
+                prev_publish_time: clock.unix_timestamp - 1,
+                ema_price: price,
+                ema_conf: conf,
+            },
+            posted_slot: clock.slot,
+        };
+        let mut data = PRICE_UPDATE_V2_DISCRIMINATOR.to_vec();
+        data.extend(anchor_lang::AnchorSerialize::try_to_vec(&update).unwrap());
+        program
+            .set_account(
+                feed,
+                Account {
+                    lamports: program.minimum_balance_for_rent_exemption(data.len()),
+                    data,
+                    owner: PYTH_RECEIVER_PROGRAM_ID,
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            )
+            .unwrap();
+    }
+
+    #[cfg(feature = "oracle")]
+    #[test]
+    fn test_oracle_priced_escrow() {
+        let (mut program, payer, mint_a, mint_b, maker_ata_a) = setup();
+        let maker = payer.pubkey();
+        let feed = Pubkey::new_unique();
+
+        // 1000 mint_a at the oracle price plus a 1% spread. The fixed receive is
+        // ignored, so a zero one does not make the escrow a gift
+        let (mut make_ix, escrow, vault) =
+            make_instruction(maker, mint_a, mint_b, maker_ata_a, 1, 1000, 0, 0);
+        make_ix.data = crate::instruction::Make {
+            seed: 1,
+            args: crate::MakeArgs {
+                deposit: 1000,
+                receive: 0,
+                waiting_time: 0,
+                expiry: 0,
+                taker: None,
+                taker_root: None,
+                whitelisted_takers: false,
+                require_no_freeze_authority: false,
+                rent_recipient: None,
+                payment_options: vec![],
+                receive_in_ui_amount: false,
+                label: None,
+                memo: None,
+                oracle: Some(crate::OraclePricing {
+                    price_feed: feed,
+                    spread_bps: 100,
+                }),
+                unit_pricing: None,
+                fill_terms: None,
+                refund_terms: None,
+                settlement_terms: None,
+            },
+        }
+        .data();
+        let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        let (taker, taker_ata_a, taker_ata_b) = setup_taker(&mut program, &payer, mint_a, mint_b);
+        let send_take = |program: &mut LiteSVM, take_amount: u64, price_update: Option<Pubkey>| {
+            let take_ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: crate::accounts::Take {
+                    taker: taker.pubkey(),
+                    payer: taker.pubkey(),
+                    maker,
+                    rent_payer: maker,
+                    mint_a,
+                    mint_b,
+                    taker_ata_a,
+                    taker_ata_b,
+                    maker_ata_b: associated_token::get_associated_token_address(&maker, &mint_b),
+                    maker_ata_a: None,
+                    config: config_pda(),
+                    treasury: TREASURY,
+                    treasury_ata_b: associated_token::get_associated_token_address(
+                        &TREASURY, &mint_b,
+                    ),
+                    referrer_ata_b: None,
+                    escrow,
+                    maker_registry: Some(maker_registry_pda(maker)),
+                    maker_stats: None,
+                    vault,
+                    proceeds: None,
+                    proceeds_vault: None,
+                    price_update,
+                    memo_program: None,
+                    associated_token_program: spl_associated_token_account::ID,
+                    token_program: TOKEN_PROGRAM_ID,
+                    system_program: SYSTEM_PROGRAM_ID,
+                    event_authority: event_authority_pda(),
+                    program: PROGRAM_ID,
+                }
+                .to_account_metas(None),
+                data: crate::instruction::Take {
+                    take_amount,
+                    proof: vec![],
+                    max_pay: take_amount,
+                    min_receive: 0,
+                    memo: None,
+                    preimage: None,
+                }
+                .data(),
+            };
+            let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+            let transaction = Transaction::new(&[&taker], message, program.latest_blockhash());
+            let result = program.send_transaction(transaction);
+            program.expire_blockhash();
+            result
+        };
+
+        // 2.5 mint_b per mint_a values the deposit at 2500, 2525 with the spread
+        set_price(&mut program, feed, 2_500_000, 1_000, -6);
+        assert!(failed_with(
+            send_take(&mut program, 1263, None),
+            ErrorCode::InvalidPriceFeed
+        ));
+        assert!(failed_with(
+            send_take(&mut program, 1263, Some(Pubkey::new_unique())),
+            ErrorCode::InvalidPriceFeed
+        ));
+        assert!(failed_with(
+            send_take(&mut program, 2526, Some(feed)),
+            ErrorCode::InvalidTakeAmount
+        ));
+        // Paying nothing claims nothing
+        assert!(failed_with(
+            send_take(&mut program, 0, Some(feed)),
+            ErrorCode::InvalidTakeAmount
+        ));
+        send_take(&mut program, 1263, Some(feed)).unwrap();
+
+        let token_amount = |program: &LiteSVM, account: &Pubkey| {
+            spl_token::state::Account::unpack(&program.get_account(account).unwrap().data)
+                .unwrap()
+                .amount
+        };
+        assert_eq!(token_amount(&program, &taker_ata_a), 500);
+
+        // Prices older than a minute or with a wide confidence interval are refused
+        warp_clock(&mut program, crate::oracle::MAX_PRICE_AGE + 1);
+        assert!(failed_with(
+            send_take(&mut program, 1515, Some(feed)),
+            ErrorCode::StalePrice
+        ));
+        set_price(&mut program, feed, 3_000_000, 100_000, -6);
+        assert!(failed_with(
+            send_take(&mut program, 1515, Some(feed)),
+            ErrorCode::PriceTooUncertain
+        ));
+
+        // The rest is repriced at 3.0, so the remaining 500 now cost 1515
+        set_price(&mut program, feed, 3_000_000, 1_000, -6);
+        send_take(&mut program, 1515, Some(feed)).unwrap();
+
+        assert_eq!(token_amount(&program, &taker_ata_a), 1000);
+        let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+        assert_eq!(token_amount(&program, &maker_ata_b), 1263 + 1515);
+
+        msg!("\nAll oracle pricing assertions passed!");
     }
 }
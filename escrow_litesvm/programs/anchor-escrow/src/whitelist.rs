@@ -0,0 +1,76 @@
+//! Taker gating through the sibling whitelist-transfer-hook program.
+//!
+//! Its `Whitelist` entries are mirrored here, like Token Metadata in
+//! `crate::metadata`, so the two programs stay separate crates. An entry exists for
+//! every whitelisted user and older layouts are a prefix of the current one, so
+//! fields an entry predates read as zero.
+
+use anchor_lang::prelude::*;
+
+use crate::error::ErrorCode;
+
+pub const WHITELIST_PROGRAM_ID: Pubkey = pubkey!("2soGPuhbTmr52dHoRxhvJGA8ZNj5Q7iBCQwT4jdUNKqW");
+
+// Anchor discriminator of the whitelist program's Whitelist account
+pub const WHITELIST_DISCRIMINATOR: [u8; 8] = [204, 176, 52, 79, 146, 121, 54, 247];
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct Whitelist {
+    pub bump: u8,
+    pub expires_at: i64,
+    pub reverification_requested: bool,
+    pub version: u8,
+    pub added_at: i64,
+    pub tier: u8,
+    pub metadata: [u8; 32],
+    pub removal_effective_at: i64,
+}
+
+impl Whitelist {
+    // Serialized size of the layout above, without the discriminator
+    const LEN: usize = 1 + 8 + 1 + 1 + 8 + 1 + 32 + 8;
+
+    pub fn address(user: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[b"whitelist", user.as_ref()], &WHITELIST_PROGRAM_ID).0
+    }
+
+    /// Reads a whitelist entry, the caller checks the account address.
+    pub fn load(account: &AccountInfo) -> Result<Self> {
+        require_keys_eq!(
+            *account.owner,
+            WHITELIST_PROGRAM_ID,
+            ErrorCode::TakerNotWhitelisted
+        );
+        let data = account.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == WHITELIST_DISCRIMINATOR,
+            ErrorCode::TakerNotWhitelisted
+        );
+        let mut entry = [0u8; Self::LEN];
+        let len = (data.len() - 8).min(Self::LEN);
+        entry[..len].copy_from_slice(&data[8..8 + len]);
+        Self::deserialize(&mut &entry[..]).map_err(|_| error!(ErrorCode::TakerNotWhitelisted))
+    }
+
+    /// True while the entry would still let its user move the hooked mint.
+    pub fn is_active(&self, now: i64) -> bool {
+        let expired = self.expires_at != 0 && now > self.expires_at;
+        let removed = self.removal_effective_at != 0 && now >= self.removal_effective_at;
+        !expired && !removed
+    }
+}
+
+/// Fails unless `user`'s whitelist entry is among `accounts` and active.
+pub fn require_whitelisted(user: &Pubkey, accounts: &[AccountInfo]) -> Result<()> {
+    let address = Whitelist::address(user);
+    let account = accounts
+        .iter()
+        .find(|account| account.key() == address)
+        .ok_or(ErrorCode::TakerNotWhitelisted)?;
+    let entry = Whitelist::load(account)?;
+    require!(
+        entry.is_active(Clock::get()?.unix_timestamp),
+        ErrorCode::TakerNotWhitelisted
+    );
+    Ok(())
+}
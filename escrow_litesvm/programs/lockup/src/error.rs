@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("A lockup needs between one and MAX_TRANCHES tranches.")]
+    InvalidTrancheCount,
+    #[msg("Tranche amounts must be non-zero.")]
+    EmptyTranche,
+    #[msg("Clawback signers or threshold are inconsistent.")]
+    InvalidClawbackConfig,
+    #[msg("Veto window cannot be negative.")]
+    InvalidVetoWindow,
+    #[msg("Arithmetic overflow.")]
+    Overflow,
+    #[msg("No unlocked tranche left to claim.")]
+    NothingToClaim,
+    #[msg("Tranche index is out of range.")]
+    InvalidTranche,
+    #[msg("Tranche is already claimed.")]
+    TrancheClaimed,
+    #[msg("No early unlock is pending for this tranche.")]
+    NoPendingEarlyUnlock,
+    #[msg("Early unlock has already taken effect.")]
+    VetoWindowClosed,
+    #[msg("Lockup has no governance authority.")]
+    NoGovernance,
+    #[msg("Lockup was created without clawback signers.")]
+    ClawbackDisabled,
+    #[msg("Not enough clawback signers.")]
+    ClawbackThresholdNotMet,
+}
@@ -0,0 +1,102 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::ErrorCode, state::Lockup};
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut)]
+    pub beneficiary: Signer<'info>,
+    // Receives the lockup and vault rent once the last tranche is claimed
+    #[account(mut)]
+    pub grantor: SystemAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = beneficiary,
+        associated_token::mint = mint,
+        associated_token::authority = beneficiary,
+        associated_token::token_program = token_program,
+    )]
+    pub beneficiary_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = grantor,
+        has_one = beneficiary,
+        has_one = mint,
+        seeds = [b"lockup", grantor.key().as_ref(), lockup.seed.to_le_bytes().as_ref()],
+        bump = lockup.bump,
+    )]
+    pub lockup: Account<'info, Lockup>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = lockup,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Claim<'info> {
+    pub fn claim_unlocked(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        let mut amount = 0u64;
+        for tranche in self.lockup.tranches.iter_mut() {
+            if !tranche.claimed && tranche.is_unlocked(now) {
+                amount = amount
+                    .checked_add(tranche.amount)
+                    .ok_or(ErrorCode::Overflow)?;
+                tranche.claimed = true;
+            }
+        }
+        require!(amount > 0, ErrorCode::NothingToClaim);
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"lockup",
+            self.grantor.key.as_ref(),
+            &self.lockup.seed.to_le_bytes()[..],
+            &[self.lockup.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.beneficiary_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.lockup.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, amount, self.mint.decimals)?;
+
+        if !self.lockup.all_claimed() {
+            return Ok(());
+        }
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.grantor.to_account_info(),
+            authority: self.lockup.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)?;
+
+        self.lockup.close(self.grantor.to_account_info())
+    }
+}
@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
+};
+
+use crate::{error::ErrorCode, state::Lockup};
+
+/// Clawback signers are passed as remaining accounts, at least the threshold must sign.
+#[derive(Accounts)]
+pub struct Clawback<'info> {
+    #[account(mut)]
+    pub grantor: SystemAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = grantor,
+        token::token_program = token_program,
+    )]
+    pub grantor_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = grantor,
+        has_one = grantor,
+        has_one = mint,
+        seeds = [b"lockup", grantor.key().as_ref(), lockup.seed.to_le_bytes().as_ref()],
+        bump = lockup.bump,
+    )]
+    pub lockup: Account<'info, Lockup>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = lockup,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Clawback<'info> {
+    pub fn check_signers(&self, signers: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            self.lockup.clawback_threshold > 0,
+            ErrorCode::ClawbackDisabled
+        );
+
+        let mut approvals: Vec<Pubkey> = signers
+            .iter()
+            .filter(|account| {
+                account.is_signer && self.lockup.clawback_signers.contains(account.key)
+            })
+            .map(|account| account.key())
+            .collect();
+        approvals.sort();
+        approvals.dedup();
+
+        require!(
+            approvals.len() >= self.lockup.clawback_threshold as usize,
+            ErrorCode::ClawbackThresholdNotMet
+        );
+
+        Ok(())
+    }
+
+    pub fn clawback_and_close_vault(&mut self) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"lockup",
+            self.grantor.key.as_ref(),
+            &self.lockup.seed.to_le_bytes()[..],
+            &[self.lockup.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.grantor_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.lockup.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, self.vault.amount, self.mint.decimals)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: self.grantor.to_account_info(),
+            authority: self.lockup.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)
+    }
+}
@@ -0,0 +1,146 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    error::ErrorCode,
+    state::{Lockup, Tranche},
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct TrancheTerms {
+    pub amount: u64,
+    pub unlock_at: i64,
+}
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateLockup<'info> {
+    #[account(mut)]
+    pub grantor: Signer<'info>,
+    pub beneficiary: SystemAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = grantor,
+        associated_token::token_program = token_program,
+    )]
+    pub grantor_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = grantor,
+        seeds = [b"lockup", grantor.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Lockup::INIT_SPACE,
+    )]
+    pub lockup: Account<'info, Lockup>,
+    #[account(
+        init,
+        payer = grantor,
+        associated_token::mint = mint,
+        associated_token::authority = lockup,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateLockup<'info> {
+    pub fn init_lockup(
+        &mut self,
+        seed: u64,
+        tranches: Vec<TrancheTerms>,
+        governance: Option<Pubkey>,
+        veto_window: i64,
+        bumps: &CreateLockupBumps,
+    ) -> Result<()> {
+        require!(
+            !tranches.is_empty() && tranches.len() <= Lockup::MAX_TRANCHES,
+            ErrorCode::InvalidTrancheCount
+        );
+        require!(
+            tranches.iter().all(|tranche| tranche.amount > 0),
+            ErrorCode::EmptyTranche
+        );
+        require!(veto_window >= 0, ErrorCode::InvalidVetoWindow);
+
+        self.lockup.set_inner(Lockup {
+            grantor: self.grantor.key(),
+            beneficiary: self.beneficiary.key(),
+            mint: self.mint.key(),
+            seed,
+            tranches: tranches
+                .into_iter()
+                .map(|terms| Tranche {
+                    amount: terms.amount,
+                    unlock_at: terms.unlock_at,
+                    early_unlock_at: 0,
+                    claimed: false,
+                })
+                .collect(),
+            governance,
+            veto_window,
+            clawback_signers: Vec::new(),
+            clawback_threshold: 0,
+            bump: bumps.lockup,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_clawback(
+        &mut self,
+        clawback_signers: Vec<Pubkey>,
+        clawback_threshold: u8,
+    ) -> Result<()> {
+        let mut unique_signers = clawback_signers.clone();
+        unique_signers.sort();
+        unique_signers.dedup();
+        require!(
+            clawback_signers.len() <= Lockup::MAX_CLAWBACK_SIGNERS
+                && unique_signers.len() == clawback_signers.len(),
+            ErrorCode::InvalidClawbackConfig
+        );
+
+        // No signers disables clawback, otherwise the threshold must be reachable
+        let threshold = clawback_threshold as usize;
+        let valid_threshold = if clawback_signers.is_empty() {
+            threshold == 0
+        } else {
+            (1..=clawback_signers.len()).contains(&threshold)
+        };
+        require!(valid_threshold, ErrorCode::InvalidClawbackConfig);
+
+        self.lockup.clawback_signers = clawback_signers;
+        self.lockup.clawback_threshold = clawback_threshold;
+
+        Ok(())
+    }
+
+    pub fn deposit(&mut self) -> Result<()> {
+        let total = self
+            .lockup
+            .tranches
+            .iter()
+            .try_fold(0u64, |total, tranche| total.checked_add(tranche.amount))
+            .ok_or(ErrorCode::Overflow)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.grantor_ata.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.grantor.to_account_info(),
+            mint: self.mint.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+
+        transfer_checked(cpi_ctx, total, self.mint.decimals)
+    }
+}
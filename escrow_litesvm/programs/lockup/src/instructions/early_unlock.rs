@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+
+use crate::{error::ErrorCode, state::Lockup};
+
+#[derive(Accounts)]
+pub struct ProposeEarlyUnlock<'info> {
+    pub grantor: Signer<'info>,
+    #[account(
+        mut,
+        has_one = grantor,
+        seeds = [b"lockup", grantor.key().as_ref(), lockup.seed.to_le_bytes().as_ref()],
+        bump = lockup.bump,
+    )]
+    pub lockup: Account<'info, Lockup>,
+}
+
+impl<'info> ProposeEarlyUnlock<'info> {
+    pub fn propose_early_unlock(&mut self, tranche: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        // Without governance there is no one to veto, so it applies immediately
+        let delay = if self.lockup.governance.is_some() {
+            self.lockup.veto_window
+        } else {
+            0
+        };
+        let effective_at = now.checked_add(delay).ok_or(ErrorCode::Overflow)?;
+
+        let tranche = self
+            .lockup
+            .tranches
+            .get_mut(tranche as usize)
+            .ok_or(ErrorCode::InvalidTranche)?;
+        require!(!tranche.claimed, ErrorCode::TrancheClaimed);
+        tranche.early_unlock_at = effective_at;
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct VetoEarlyUnlock<'info> {
+    pub governance: Signer<'info>,
+    #[account(
+        mut,
+        constraint = lockup.governance == Some(governance.key()) @ ErrorCode::NoGovernance,
+        seeds = [b"lockup", lockup.grantor.as_ref(), lockup.seed.to_le_bytes().as_ref()],
+        bump = lockup.bump,
+    )]
+    pub lockup: Account<'info, Lockup>,
+}
+
+impl<'info> VetoEarlyUnlock<'info> {
+    pub fn veto_early_unlock(&mut self, tranche: u8) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        let tranche = self
+            .lockup
+            .tranches
+            .get_mut(tranche as usize)
+            .ok_or(ErrorCode::InvalidTranche)?;
+        require!(
+            tranche.early_unlock_at != 0,
+            ErrorCode::NoPendingEarlyUnlock
+        );
+        require!(now < tranche.early_unlock_at, ErrorCode::VetoWindowClosed);
+        tranche.early_unlock_at = 0;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+pub mod claim;
+pub mod clawback;
+pub mod create_lockup;
+pub mod early_unlock;
+
+pub use claim::*;
+pub use clawback::*;
+pub use create_lockup::*;
+pub use early_unlock::*;
@@ -0,0 +1,51 @@
+#![allow(unexpected_cfgs)]
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+
+mod error;
+mod instructions;
+pub mod state;
+mod tests;
+
+use instructions::*;
+
+declare_id!("FSmRZzxmH6EVHe2ZguR92WruUb7uRLCJCNY3Wt8NeYVB");
+
+#[program]
+pub mod lockup {
+    use super::*;
+
+    pub fn create_lockup(
+        ctx: Context<CreateLockup>,
+        seed: u64,
+        tranches: Vec<TrancheTerms>,
+        governance: Option<Pubkey>,
+        veto_window: i64,
+        clawback_signers: Vec<Pubkey>,
+        clawback_threshold: u8,
+    ) -> Result<()> {
+        ctx.accounts
+            .init_lockup(seed, tranches, governance, veto_window, &ctx.bumps)?;
+        ctx.accounts
+            .set_clawback(clawback_signers, clawback_threshold)?;
+        ctx.accounts.deposit()
+    }
+
+    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+        ctx.accounts.claim_unlocked()
+    }
+
+    pub fn propose_early_unlock(ctx: Context<ProposeEarlyUnlock>, tranche: u8) -> Result<()> {
+        ctx.accounts.propose_early_unlock(tranche)
+    }
+
+    pub fn veto_early_unlock(ctx: Context<VetoEarlyUnlock>, tranche: u8) -> Result<()> {
+        ctx.accounts.veto_early_unlock(tranche)
+    }
+
+    pub fn clawback<'info>(ctx: Context<'_, '_, 'info, 'info, Clawback<'info>>) -> Result<()> {
+        ctx.accounts.check_signers(ctx.remaining_accounts)?;
+        ctx.accounts.clawback_and_close_vault()
+    }
+}
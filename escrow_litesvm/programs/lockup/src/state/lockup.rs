@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace, Debug)]
+pub struct Tranche {
+    pub amount: u64,
+    pub unlock_at: i64,
+    // Set by the grantor, 0 when no early unlock is pending
+    pub early_unlock_at: i64,
+    pub claimed: bool,
+}
+
+impl Tranche {
+    pub fn is_unlocked(&self, now: i64) -> bool {
+        now >= self.unlock_at || (self.early_unlock_at != 0 && now >= self.early_unlock_at)
+    }
+}
+
+/// Team allocation held in a vault and released to the beneficiary tranche by tranche.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Lockup {
+    pub grantor: Pubkey,
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub seed: u64,
+    #[max_len(8)]
+    pub tranches: Vec<Tranche>,
+    // May veto early unlocks within veto_window, None leaves them unchecked
+    pub governance: Option<Pubkey>,
+    pub veto_window: i64,
+    #[max_len(5)]
+    pub clawback_signers: Vec<Pubkey>,
+    pub clawback_threshold: u8,
+    pub bump: u8,
+}
+
+impl Lockup {
+    pub const MAX_TRANCHES: usize = 8;
+    pub const MAX_CLAWBACK_SIGNERS: usize = 5;
+
+    pub fn all_claimed(&self) -> bool {
+        self.tranches.iter().all(|tranche| tranche.claimed)
+    }
+}
@@ -0,0 +1,3 @@
+pub mod lockup;
+
+pub use lockup::*;
@@ -0,0 +1,514 @@
+#[cfg(test)]
+mod tests {
+
+    use {
+        anchor_lang::{
+            prelude::{msg, Clock},
+            solana_program::program_pack::Pack,
+            AccountDeserialize, InstructionData, ToAccountMetas,
+        },
+        anchor_spl::{
+            associated_token::{self, spl_associated_token_account},
+            token::spl_token,
+            token_2022::spl_token_2022,
+        },
+        litesvm::LiteSVM,
+        litesvm_token::{
+            spl_token::ID as TOKEN_PROGRAM_ID, CreateAssociatedTokenAccount, CreateMint, MintTo,
+        },
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::Message,
+        solana_native_token::LAMPORTS_PER_SOL,
+        solana_pubkey::Pubkey,
+        solana_sdk_ids::system_program::ID as SYSTEM_PROGRAM_ID,
+        solana_signer::Signer,
+        solana_transaction::Transaction,
+        std::path::PathBuf,
+    };
+
+    use crate::{instructions::TrancheTerms, state::Lockup};
+
+    static PROGRAM_ID: Pubkey = crate::ID;
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    /// Setup function to initialize LiteSVM, load program, create the mint, and fund the grantor
+    /// Returns: (LiteSVM instance, grantor keypair, beneficiary keypair, mint, grantor_ata)
+    fn setup() -> (LiteSVM, Keypair, Keypair, Pubkey, Pubkey) {
+        let mut program = LiteSVM::new();
+        let grantor = Keypair::new();
+        let beneficiary = Keypair::new();
+
+        program
+            .airdrop(&grantor.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .expect("Failed to airdrop SOL to grantor");
+        program
+            .airdrop(&beneficiary.pubkey(), 10 * LAMPORTS_PER_SOL)
+            .expect("Failed to airdrop SOL to beneficiary");
+
+        // Load program SO file
+        let so_path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy/lockup.so");
+
+        let program_data = std::fs::read(so_path).expect("Failed to read program SO file");
+
+        program.add_program(PROGRAM_ID, &program_data);
+
+        let mint = CreateMint::new(&mut program, &grantor)
+            .decimals(6)
+            .authority(&grantor.pubkey())
+            .send()
+            .unwrap();
+
+        let grantor_ata = CreateAssociatedTokenAccount::new(&mut program, &grantor, &mint)
+            .owner(&grantor.pubkey())
+            .send()
+            .unwrap();
+
+        MintTo::new(&mut program, &grantor, &mint, &grantor_ata, 1000000000)
+            .send()
+            .unwrap();
+
+        (program, grantor, beneficiary, mint, grantor_ata)
+    }
+
+    fn lockup_pdas(grantor: Pubkey, mint: Pubkey, seed: u64) -> (Pubkey, Pubkey) {
+        let lockup = Pubkey::find_program_address(
+            &[b"lockup", grantor.as_ref(), &seed.to_le_bytes()],
+            &PROGRAM_ID,
+        )
+        .0;
+        let vault = associated_token::get_associated_token_address(&lockup, &mint);
+
+        (lockup, vault)
+    }
+
+    fn now(program: &LiteSVM) -> i64 {
+        program.get_sysvar::<Clock>().unix_timestamp
+    }
+
+    fn warp_clock(program: &mut LiteSVM, seconds: i64) {
+        let mut clock: Clock = program.get_sysvar();
+        clock.unix_timestamp += seconds;
+        clock.slot += 100;
+        program.set_sysvar::<Clock>(&clock);
+        program.warp_to_slot(clock.slot);
+        program.expire_blockhash();
+    }
+
+    fn token_amount(program: &LiteSVM, token_account: &Pubkey) -> u64 {
+        let account = program.get_account(token_account).unwrap();
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    /// Helper function to create a lockup with the given terms
+    /// Returns: (lockup PDA, vault)
+    fn execute_create_lockup(
+        program: &mut LiteSVM,
+        grantor: &Keypair,
+        beneficiary: Pubkey,
+        mint: Pubkey,
+        grantor_ata: Pubkey,
+        args: crate::instruction::CreateLockup,
+    ) -> (Pubkey, Pubkey) {
+        let (lockup, vault) = lockup_pdas(grantor.pubkey(), mint, args.seed);
+
+        let create_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::CreateLockup {
+                grantor: grantor.pubkey(),
+                beneficiary,
+                mint,
+                grantor_ata,
+                lockup,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: args.data(),
+        };
+
+        let message = Message::new(&[create_ix], Some(&grantor.pubkey()));
+        let transaction = Transaction::new(&[grantor], message, program.latest_blockhash());
+        program.send_transaction(transaction).unwrap();
+
+        (lockup, vault)
+    }
+
+    /// Two tranches of 100 and 200 tokens, unlocking after 30 and 60 days
+    fn staged_terms(
+        program: &LiteSVM,
+        governance: Option<Pubkey>,
+        clawback_signers: Vec<Pubkey>,
+        clawback_threshold: u8,
+    ) -> crate::instruction::CreateLockup {
+        let start = now(program);
+        crate::instruction::CreateLockup {
+            seed: 7,
+            tranches: vec![
+                TrancheTerms {
+                    amount: 100,
+                    unlock_at: start + 30 * DAY,
+                },
+                TrancheTerms {
+                    amount: 200,
+                    unlock_at: start + 60 * DAY,
+                },
+            ],
+            governance,
+            veto_window: DAY,
+            clawback_signers,
+            clawback_threshold,
+        }
+    }
+
+    fn claim_instruction(
+        beneficiary: Pubkey,
+        grantor: Pubkey,
+        mint: Pubkey,
+        lockup: Pubkey,
+        vault: Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Claim {
+                beneficiary,
+                grantor,
+                mint,
+                beneficiary_ata: associated_token::get_associated_token_address(
+                    &beneficiary,
+                    &mint,
+                ),
+                lockup,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Claim {}.data(),
+        }
+    }
+
+    fn send(
+        program: &mut LiteSVM,
+        ix: Instruction,
+        signers: &[&Keypair],
+    ) -> Result<(), litesvm::types::FailedTransactionMetadata> {
+        let message = Message::new(&[ix], Some(&signers[0].pubkey()));
+        let transaction = Transaction::new(signers, message, program.latest_blockhash());
+        program.send_transaction(transaction).map(|_| ())
+    }
+
+    #[test]
+    fn test_create_lockup() {
+        let (mut program, grantor, beneficiary, mint, grantor_ata) = setup();
+
+        let terms = staged_terms(&program, None, vec![], 0);
+        let (lockup, vault) = execute_create_lockup(
+            &mut program,
+            &grantor,
+            beneficiary.pubkey(),
+            mint,
+            grantor_ata,
+            terms,
+        );
+
+        assert_eq!(
+            token_amount(&program, &vault),
+            300,
+            "Vault holds every tranche"
+        );
+
+        let account = program.get_account(&lockup).unwrap();
+        let lockup_data = Lockup::try_deserialize(&mut account.data.as_ref()).unwrap();
+        assert_eq!(lockup_data.grantor, grantor.pubkey());
+        assert_eq!(lockup_data.beneficiary, beneficiary.pubkey());
+        assert_eq!(lockup_data.tranches.len(), 2);
+        assert!(lockup_data.tranches.iter().all(|tranche| !tranche.claimed));
+
+        msg!("\nAll create lockup assertions passed!");
+    }
+
+    #[test]
+    fn test_lockup_with_token_2022_mint() {
+        use spl_token_2022::{extension::StateWithExtensions, state::Account as Token2022Account};
+
+        let (mut program, grantor, beneficiary, _mint, _grantor_ata) = setup();
+        let token_program = spl_token_2022::ID;
+        let ata = |owner: &Pubkey, mint: &Pubkey| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                owner,
+                mint,
+                &token_program,
+            )
+        };
+        let token_2022_amount = |program: &LiteSVM, token_account: &Pubkey| {
+            let account = program.get_account(token_account).unwrap();
+            StateWithExtensions::<Token2022Account>::unpack(&account.data)
+                .unwrap()
+                .base
+                .amount
+        };
+
+        let mint = CreateMint::new(&mut program, &grantor)
+            .decimals(6)
+            .authority(&grantor.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let grantor_ata = CreateAssociatedTokenAccount::new(&mut program, &grantor, &mint)
+            .owner(&grantor.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &grantor, &mint, &grantor_ata, 1000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        // The vault is the Token-2022 associated account of the lockup
+        let terms = staged_terms(&program, None, vec![], 0);
+        let (lockup, _) = lockup_pdas(grantor.pubkey(), mint, terms.seed);
+        let vault = ata(&lockup, &mint);
+        let create_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::CreateLockup {
+                grantor: grantor.pubkey(),
+                beneficiary: beneficiary.pubkey(),
+                mint,
+                grantor_ata,
+                lockup,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: terms.data(),
+        };
+        send(&mut program, create_ix, &[&grantor]).unwrap();
+        assert_eq!(token_2022_amount(&program, &vault), 300);
+        assert_eq!(token_2022_amount(&program, &grantor_ata), 700);
+        assert_eq!(program.get_account(&vault).unwrap().owner, token_program);
+
+        // Tranches are claimed through the same token program
+        let beneficiary_ata = ata(&beneficiary.pubkey(), &mint);
+        let claim_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Claim {
+                beneficiary: beneficiary.pubkey(),
+                grantor: grantor.pubkey(),
+                mint,
+                beneficiary_ata,
+                lockup,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Claim {}.data(),
+        };
+        warp_clock(&mut program, 30 * DAY);
+        send(&mut program, claim_ix, &[&beneficiary]).unwrap();
+        assert_eq!(token_2022_amount(&program, &beneficiary_ata), 100);
+        assert_eq!(token_2022_amount(&program, &vault), 200);
+
+        msg!("\nAll Token-2022 lockup assertions passed!");
+    }
+
+    #[test]
+    fn test_claim_releases_tranches_in_stages() {
+        let (mut program, grantor, beneficiary, mint, grantor_ata) = setup();
+
+        let terms = staged_terms(&program, None, vec![], 0);
+        let (lockup, vault) = execute_create_lockup(
+            &mut program,
+            &grantor,
+            beneficiary.pubkey(),
+            mint,
+            grantor_ata,
+            terms,
+        );
+        let claim_ix =
+            claim_instruction(beneficiary.pubkey(), grantor.pubkey(), mint, lockup, vault);
+        let beneficiary_ata =
+            associated_token::get_associated_token_address(&beneficiary.pubkey(), &mint);
+
+        // Nothing is unlocked yet
+        assert!(
+            send(&mut program, claim_ix.clone(), &[&beneficiary]).is_err(),
+            "Claim should fail before the first milestone"
+        );
+
+        // First milestone releases only the first tranche
+        warp_clock(&mut program, 30 * DAY);
+        send(&mut program, claim_ix.clone(), &[&beneficiary]).unwrap();
+        assert_eq!(token_amount(&program, &beneficiary_ata), 100);
+        assert_eq!(token_amount(&program, &vault), 200);
+
+        // Claiming again before the next milestone has nothing to pay
+        assert!(
+            send(&mut program, claim_ix.clone(), &[&beneficiary]).is_err(),
+            "Claim should fail once the unlocked tranche is paid out"
+        );
+
+        // Last milestone pays the rest and closes the lockup
+        warp_clock(&mut program, 30 * DAY);
+        send(&mut program, claim_ix, &[&beneficiary]).unwrap();
+        assert_eq!(token_amount(&program, &beneficiary_ata), 300);
+
+        let lockup_account = program.get_account(&lockup);
+        assert!(
+            lockup_account.is_none() || lockup_account.unwrap().data.is_empty(),
+            "Lockup should be closed after the last claim"
+        );
+        let vault_account = program.get_account(&vault);
+        assert!(
+            vault_account.is_none() || vault_account.unwrap().data.is_empty(),
+            "Vault should be closed after the last claim"
+        );
+
+        msg!("\nAll staged claim assertions passed!");
+    }
+
+    #[test]
+    fn test_governance_can_veto_early_unlock() {
+        let (mut program, grantor, beneficiary, mint, grantor_ata) = setup();
+        let governance = Keypair::new();
+        program
+            .airdrop(&governance.pubkey(), LAMPORTS_PER_SOL)
+            .unwrap();
+
+        let terms = staged_terms(&program, Some(governance.pubkey()), vec![], 0);
+        let (lockup, vault) = execute_create_lockup(
+            &mut program,
+            &grantor,
+            beneficiary.pubkey(),
+            mint,
+            grantor_ata,
+            terms,
+        );
+
+        let propose_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::ProposeEarlyUnlock {
+                grantor: grantor.pubkey(),
+                lockup,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::ProposeEarlyUnlock { tranche: 0 }.data(),
+        };
+        let veto_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::VetoEarlyUnlock {
+                governance: governance.pubkey(),
+                lockup,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::VetoEarlyUnlock { tranche: 0 }.data(),
+        };
+        let claim_ix =
+            claim_instruction(beneficiary.pubkey(), grantor.pubkey(), mint, lockup, vault);
+
+        // Vetoed inside the window, the tranche stays locked
+        send(&mut program, propose_ix.clone(), &[&grantor]).unwrap();
+        send(&mut program, veto_ix.clone(), &[&governance]).unwrap();
+        warp_clock(&mut program, 2 * DAY);
+        assert!(
+            send(&mut program, claim_ix.clone(), &[&beneficiary]).is_err(),
+            "Claim should fail after the early unlock was vetoed"
+        );
+
+        // Not vetoed, the tranche unlocks once the window passes
+        send(&mut program, propose_ix, &[&grantor]).unwrap();
+        warp_clock(&mut program, 2 * DAY);
+        assert!(
+            send(&mut program, veto_ix, &[&governance]).is_err(),
+            "Veto should fail after the window closed"
+        );
+        send(&mut program, claim_ix, &[&beneficiary]).unwrap();
+        assert_eq!(token_amount(&program, &vault), 200);
+
+        msg!("\nAll early unlock veto assertions passed!");
+    }
+
+    #[test]
+    fn test_clawback_requires_threshold() {
+        let (mut program, grantor, beneficiary, mint, grantor_ata) = setup();
+        let signers: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+        for signer in &signers {
+            program.airdrop(&signer.pubkey(), LAMPORTS_PER_SOL).unwrap();
+        }
+
+        let terms = staged_terms(
+            &program,
+            None,
+            signers.iter().map(|signer| signer.pubkey()).collect(),
+            2,
+        );
+        let (lockup, vault) = execute_create_lockup(
+            &mut program,
+            &grantor,
+            beneficiary.pubkey(),
+            mint,
+            grantor_ata,
+            terms,
+        );
+
+        let clawback_ix = |approvers: &[&Keypair]| {
+            let mut accounts = crate::accounts::Clawback {
+                grantor: grantor.pubkey(),
+                mint,
+                grantor_ata,
+                lockup,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None);
+            accounts.extend(
+                approvers
+                    .iter()
+                    .map(|approver| AccountMeta::new_readonly(approver.pubkey(), true)),
+            );
+            Instruction {
+                program_id: PROGRAM_ID,
+                accounts,
+                data: crate::instruction::Clawback {}.data(),
+            }
+        };
+
+        let before = token_amount(&program, &grantor_ata);
+
+        // One approval is below the 2-of-3 threshold
+        assert!(
+            send(&mut program, clawback_ix(&[&signers[0]]), &[&signers[0]]).is_err(),
+            "Clawback should fail with a single approval"
+        );
+
+        // Two approvals return the locked tokens and close the lockup
+        send(
+            &mut program,
+            clawback_ix(&[&signers[0], &signers[2]]),
+            &[&signers[0], &signers[2]],
+        )
+        .unwrap();
+        assert_eq!(token_amount(&program, &grantor_ata), before + 300);
+
+        let lockup_account = program.get_account(&lockup);
+        assert!(
+            lockup_account.is_none() || lockup_account.unwrap().data.is_empty(),
+            "Lockup should be closed after clawback"
+        );
+
+        msg!("\nAll clawback assertions passed!");
+    }
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Shares must be non-zero, unique and add up to 10,000 bps.")]
+    InvalidShares,
+    #[msg("Arithmetic overflow.")]
+    Overflow,
+    #[msg("Signer is not a recipient of this splitter.")]
+    NotARecipient,
+    #[msg("Nothing to claim.")]
+    NothingToClaim,
+    #[msg("Every recipient must claim before shares can change.")]
+    UnsettledShares,
+}
@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{error::ErrorCode, state::Splitter};
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = recipient,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub recipient_ata: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        has_one = mint,
+        seeds = [b"splitter", splitter.authority.as_ref(), splitter.seed.to_le_bytes().as_ref()],
+        bump = splitter.bump,
+    )]
+    pub splitter: Account<'info, Splitter>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = splitter,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Claim<'info> {
+    pub fn claim(&mut self) -> Result<()> {
+        let index = self
+            .splitter
+            .shares
+            .iter()
+            .position(|share| share.recipient == self.recipient.key())
+            .ok_or(ErrorCode::NotARecipient)?;
+
+        let share = &self.splitter.shares[index];
+        let amount = self
+            .splitter
+            .entitled(self.vault.amount, share.bps)?
+            .saturating_sub(share.claimed);
+        require!(amount > 0, ErrorCode::NothingToClaim);
+
+        self.splitter.shares[index].claimed = self.splitter.shares[index]
+            .claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        self.splitter.total_claimed = self
+            .splitter
+            .total_claimed
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"splitter",
+            self.splitter.authority.as_ref(),
+            &self.splitter.seed.to_le_bytes()[..],
+            &[self.splitter.bump],
+        ]];
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            to: self.recipient_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            authority: self.splitter.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        transfer_checked(cpi_context, amount, self.mint.decimals)
+    }
+}
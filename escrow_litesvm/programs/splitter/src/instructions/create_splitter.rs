@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    error::ErrorCode,
+    state::{Share, Splitter},
+};
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ShareTerms {
+    pub recipient: Pubkey,
+    pub bps: u16,
+}
+
+/// Checks the terms and turns them into shares with nothing claimed yet.
+pub fn build_shares(terms: Vec<ShareTerms>) -> Result<Vec<Share>> {
+    require!(
+        !terms.is_empty() && terms.len() <= Splitter::MAX_RECIPIENTS,
+        ErrorCode::InvalidShares
+    );
+
+    let mut recipients: Vec<Pubkey> = terms.iter().map(|share| share.recipient).collect();
+    recipients.sort();
+    recipients.dedup();
+    require!(recipients.len() == terms.len(), ErrorCode::InvalidShares);
+
+    let total_bps = terms
+        .iter()
+        .try_fold(0u16, |total, share| {
+            (share.bps > 0).then_some(())?;
+            total.checked_add(share.bps)
+        })
+        .ok_or(ErrorCode::InvalidShares)?;
+    require!(total_bps == Splitter::TOTAL_BPS, ErrorCode::InvalidShares);
+
+    Ok(terms
+        .into_iter()
+        .map(|share| Share {
+            recipient: share.recipient,
+            bps: share.bps,
+            claimed: 0,
+        })
+        .collect())
+}
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateSplitter<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"splitter", authority.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Splitter::INIT_SPACE,
+    )]
+    pub splitter: Account<'info, Splitter>,
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = mint,
+        associated_token::authority = splitter,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateSplitter<'info> {
+    pub fn init_splitter(
+        &mut self,
+        seed: u64,
+        shares: Vec<ShareTerms>,
+        bumps: &CreateSplitterBumps,
+    ) -> Result<()> {
+        self.splitter.set_inner(Splitter {
+            authority: self.authority.key(),
+            mint: self.mint.key(),
+            seed,
+            shares: build_shares(shares)?,
+            total_claimed: 0,
+            bump: bumps.splitter,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,9 @@
+pub mod claim;
+pub mod create_splitter;
+pub mod pay;
+pub mod update_shares;
+
+pub use claim::*;
+pub use create_splitter::*;
+pub use pay::*;
+pub use update_shares::*;
@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    error::ErrorCode,
+    instructions::{build_shares, ShareTerms},
+    state::Splitter,
+};
+
+#[derive(Accounts)]
+pub struct UpdateShares<'info> {
+    pub authority: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        has_one = authority,
+        has_one = mint,
+        seeds = [b"splitter", authority.key().as_ref(), splitter.seed.to_le_bytes().as_ref()],
+        bump = splitter.bump,
+    )]
+    pub splitter: Account<'info, Splitter>,
+    #[account(
+        associated_token::mint = mint,
+        associated_token::authority = splitter,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> UpdateShares<'info> {
+    pub fn update_shares(&mut self, shares: Vec<ShareTerms>) -> Result<()> {
+        // Changing bps would rewrite what recipients already earned, so they must be paid first
+        for share in self.splitter.shares.iter() {
+            require!(
+                self.splitter.entitled(self.vault.amount, share.bps)? == share.claimed,
+                ErrorCode::UnsettledShares
+            );
+        }
+
+        // Whatever is left is rounding dust, split again under the new shares
+        self.splitter.shares = build_shares(shares)?;
+        self.splitter.total_claimed = 0;
+
+        Ok(())
+    }
+}
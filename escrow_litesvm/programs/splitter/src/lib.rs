@@ -0,0 +1,38 @@
+#![allow(unexpected_cfgs)]
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+
+mod error;
+mod instructions;
+pub mod state;
+mod tests;
+
+use instructions::*;
+
+declare_id!("6wdFwYr6iZ6fBGpNfhFwVUAtcxiczpozEKN461C2WfAB");
+
+#[program]
+pub mod splitter {
+    use super::*;
+
+    pub fn create_splitter(
+        ctx: Context<CreateSplitter>,
+        seed: u64,
+        shares: Vec<ShareTerms>,
+    ) -> Result<()> {
+        ctx.accounts.init_splitter(seed, shares, &ctx.bumps)
+    }
+
+    pub fn pay(ctx: Context<Pay>, amount: u64) -> Result<()> {
+        ctx.accounts.pay(amount)
+    }
+
+    pub fn claim(ctx: Context<Claim>) -> Result<()> {
+        ctx.accounts.claim()
+    }
+
+    pub fn update_shares(ctx: Context<UpdateShares>, shares: Vec<ShareTerms>) -> Result<()> {
+        ctx.accounts.update_shares(shares)
+    }
+}
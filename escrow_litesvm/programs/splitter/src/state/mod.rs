@@ -0,0 +1,3 @@
+pub mod splitter;
+
+pub use splitter::*;
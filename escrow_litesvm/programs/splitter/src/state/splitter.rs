@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use math::Rounding;
+
+use crate::error::ErrorCode;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace, Debug)]
+pub struct Share {
+    pub recipient: Pubkey,
+    pub bps: u16,
+    // Paid out to this recipient since the shares were last set
+    pub claimed: u64,
+}
+
+/// Splits everything paid into its vault between recipients by basis-point shares.
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Splitter {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub seed: u64,
+    #[max_len(10)]
+    pub shares: Vec<Share>,
+    // Sum of every share's claimed amount
+    pub total_claimed: u64,
+    pub bump: u8,
+}
+
+impl Splitter {
+    pub const MAX_RECIPIENTS: usize = 10;
+    pub const TOTAL_BPS: u16 = 10_000;
+
+    /// What a share has earned in total, rounded down. The rounding dust stays in the vault
+    /// and is split again once the shares are reset.
+    pub fn entitled(&self, vault_amount: u64, bps: u16) -> Result<u64> {
+        let received = vault_amount
+            .checked_add(self.total_claimed)
+            .ok_or(ErrorCode::Overflow)?;
+
+        math::apply_bps(received, bps, Rounding::Down).ok_or(ErrorCode::Overflow.into())
+    }
+}
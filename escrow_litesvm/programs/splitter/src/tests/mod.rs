@@ -0,0 +1,437 @@
+#[cfg(test)]
+mod tests {
+
+    use {
+        anchor_lang::{
+            prelude::msg, solana_program::program_pack::Pack, AccountDeserialize, InstructionData,
+            ToAccountMetas,
+        },
+        anchor_spl::{
+            associated_token::{self, spl_associated_token_account},
+            token::spl_token,
+            token_2022::spl_token_2022,
+        },
+        litesvm::LiteSVM,
+        litesvm_token::{
+            spl_token::ID as TOKEN_PROGRAM_ID, CreateAssociatedTokenAccount, CreateMint, MintTo,
+        },
+        solana_instruction::Instruction,
+        solana_keypair::Keypair,
+        solana_message::Message,
+        solana_native_token::LAMPORTS_PER_SOL,
+        solana_pubkey::Pubkey,
+        solana_sdk_ids::system_program::ID as SYSTEM_PROGRAM_ID,
+        solana_signer::Signer,
+        solana_transaction::Transaction,
+        std::path::PathBuf,
+    };
+
+    use crate::{instructions::ShareTerms, state::Splitter};
+
+    static PROGRAM_ID: Pubkey = crate::ID;
+
+    const SEED: u64 = 42;
+
+    /// Setup function to initialize LiteSVM, load program, create the mint and three recipients
+    /// Returns: (LiteSVM instance, authority keypair, recipients, mint, authority_ata)
+    fn setup() -> (LiteSVM, Keypair, Vec<Keypair>, Pubkey, Pubkey) {
+        let mut program = LiteSVM::new();
+        let authority = Keypair::new();
+        let recipients: Vec<Keypair> = (0..3).map(|_| Keypair::new()).collect();
+
+        program
+            .airdrop(&authority.pubkey(), 100 * LAMPORTS_PER_SOL)
+            .expect("Failed to airdrop SOL to authority");
+        for recipient in &recipients {
+            program
+                .airdrop(&recipient.pubkey(), LAMPORTS_PER_SOL)
+                .expect("Failed to airdrop SOL to recipient");
+        }
+
+        // Load program SO file
+        let so_path =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/deploy/splitter.so");
+
+        let program_data = std::fs::read(so_path).expect("Failed to read program SO file");
+
+        program.add_program(PROGRAM_ID, &program_data);
+
+        let mint = CreateMint::new(&mut program, &authority)
+            .decimals(6)
+            .authority(&authority.pubkey())
+            .send()
+            .unwrap();
+
+        // The authority doubles as the payer in these tests
+        let authority_ata = CreateAssociatedTokenAccount::new(&mut program, &authority, &mint)
+            .owner(&authority.pubkey())
+            .send()
+            .unwrap();
+
+        MintTo::new(&mut program, &authority, &mint, &authority_ata, 1000000000)
+            .send()
+            .unwrap();
+
+        (program, authority, recipients, mint, authority_ata)
+    }
+
+    fn splitter_pdas(authority: Pubkey, mint: Pubkey) -> (Pubkey, Pubkey) {
+        let splitter = Pubkey::find_program_address(
+            &[b"splitter", authority.as_ref(), &SEED.to_le_bytes()],
+            &PROGRAM_ID,
+        )
+        .0;
+        let vault = associated_token::get_associated_token_address(&splitter, &mint);
+
+        (splitter, vault)
+    }
+
+    fn token_amount(program: &LiteSVM, token_account: &Pubkey) -> u64 {
+        let account = program.get_account(token_account).unwrap();
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    fn send(
+        program: &mut LiteSVM,
+        ix: Instruction,
+        signer: &Keypair,
+    ) -> Result<(), litesvm::types::FailedTransactionMetadata> {
+        let message = Message::new(&[ix], Some(&signer.pubkey()));
+        let transaction = Transaction::new(&[signer], message, program.latest_blockhash());
+        program.send_transaction(transaction).map(|_| ())
+    }
+
+    /// 50% / 30% / 20% split between the three recipients
+    fn default_shares(recipients: &[Keypair]) -> Vec<ShareTerms> {
+        recipients
+            .iter()
+            .zip([5_000, 3_000, 2_000])
+            .map(|(recipient, bps)| ShareTerms {
+                recipient: recipient.pubkey(),
+                bps,
+            })
+            .collect()
+    }
+
+    fn create_splitter_instruction(
+        authority: Pubkey,
+        mint: Pubkey,
+        shares: Vec<ShareTerms>,
+    ) -> Instruction {
+        let (splitter, vault) = splitter_pdas(authority, mint);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::CreateSplitter {
+                authority,
+                mint,
+                splitter,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::CreateSplitter { seed: SEED, shares }.data(),
+        }
+    }
+
+    fn pay_instruction(payer: Pubkey, payer_ata: Pubkey, mint: Pubkey, amount: u64) -> Instruction {
+        let (splitter, vault) = splitter_pdas(payer, mint);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Pay {
+                payer,
+                mint,
+                payer_ata,
+                splitter,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Pay { amount }.data(),
+        }
+    }
+
+    fn claim_instruction(recipient: Pubkey, authority: Pubkey, mint: Pubkey) -> Instruction {
+        let (splitter, vault) = splitter_pdas(authority, mint);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Claim {
+                recipient,
+                mint,
+                recipient_ata: associated_token::get_associated_token_address(&recipient, &mint),
+                splitter,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Claim {}.data(),
+        }
+    }
+
+    fn update_shares_instruction(
+        authority: Pubkey,
+        splitter_authority: Pubkey,
+        mint: Pubkey,
+        shares: Vec<ShareTerms>,
+    ) -> Instruction {
+        let (splitter, vault) = splitter_pdas(splitter_authority, mint);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::UpdateShares {
+                authority,
+                mint,
+                splitter,
+                vault,
+                token_program: TOKEN_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::UpdateShares { shares }.data(),
+        }
+    }
+
+    fn recipient_balance(program: &LiteSVM, recipient: &Keypair, mint: &Pubkey) -> u64 {
+        token_amount(
+            program,
+            &associated_token::get_associated_token_address(&recipient.pubkey(), mint),
+        )
+    }
+
+    #[test]
+    fn test_create_splitter_rejects_bad_shares() {
+        let (mut program, authority, recipients, mint, _authority_ata) = setup();
+
+        // Shares must add up to exactly 10,000 bps
+        let mut short = default_shares(&recipients);
+        short[2].bps = 1_000;
+        let ix = create_splitter_instruction(authority.pubkey(), mint, short);
+        assert!(send(&mut program, ix, &authority).is_err());
+
+        // The same recipient cannot appear twice
+        let mut duplicate = default_shares(&recipients);
+        duplicate[1].recipient = recipients[0].pubkey();
+        let ix = create_splitter_instruction(authority.pubkey(), mint, duplicate);
+        assert!(send(&mut program, ix, &authority).is_err());
+
+        let ix = create_splitter_instruction(authority.pubkey(), mint, default_shares(&recipients));
+        send(&mut program, ix, &authority).unwrap();
+
+        let (splitter, _vault) = splitter_pdas(authority.pubkey(), mint);
+        let account = program.get_account(&splitter).unwrap();
+        let splitter_data = Splitter::try_deserialize(&mut account.data.as_ref()).unwrap();
+        assert_eq!(splitter_data.shares.len(), 3);
+        assert_eq!(splitter_data.total_claimed, 0);
+
+        msg!("\nAll share validation assertions passed!");
+    }
+
+    #[test]
+    fn test_claims_follow_shares_and_carry_dust() {
+        let (mut program, authority, recipients, mint, authority_ata) = setup();
+        let ix = create_splitter_instruction(authority.pubkey(), mint, default_shares(&recipients));
+        send(&mut program, ix, &authority).unwrap();
+        let (_splitter, vault) = splitter_pdas(authority.pubkey(), mint);
+
+        // 1001 does not split evenly, 1 token of dust stays behind
+        let ix = pay_instruction(authority.pubkey(), authority_ata, mint, 1001);
+        send(&mut program, ix, &authority).unwrap();
+        for recipient in &recipients {
+            let ix = claim_instruction(recipient.pubkey(), authority.pubkey(), mint);
+            send(&mut program, ix, recipient).unwrap();
+        }
+        assert_eq!(recipient_balance(&program, &recipients[0], &mint), 500);
+        assert_eq!(recipient_balance(&program, &recipients[1], &mint), 300);
+        assert_eq!(recipient_balance(&program, &recipients[2], &mint), 200);
+        assert_eq!(token_amount(&program, &vault), 1);
+
+        // A second claim with nothing new paid in has nothing to pay
+        let ix = claim_instruction(recipients[0].pubkey(), authority.pubkey(), mint);
+        assert!(send(&mut program, ix, &recipients[0]).is_err());
+
+        // The dust counts towards the next payment
+        let ix = pay_instruction(authority.pubkey(), authority_ata, mint, 999);
+        send(&mut program, ix, &authority).unwrap();
+        for recipient in &recipients {
+            let ix = claim_instruction(recipient.pubkey(), authority.pubkey(), mint);
+            send(&mut program, ix, recipient).unwrap();
+        }
+        assert_eq!(recipient_balance(&program, &recipients[0], &mint), 1000);
+        assert_eq!(recipient_balance(&program, &recipients[1], &mint), 600);
+        assert_eq!(recipient_balance(&program, &recipients[2], &mint), 400);
+        assert_eq!(token_amount(&program, &vault), 0);
+
+        // Someone outside the split cannot claim
+        let outsider = Keypair::new();
+        program
+            .airdrop(&outsider.pubkey(), LAMPORTS_PER_SOL)
+            .unwrap();
+        let ix = claim_instruction(outsider.pubkey(), authority.pubkey(), mint);
+        assert!(send(&mut program, ix, &outsider).is_err());
+
+        msg!("\nAll claim assertions passed!");
+    }
+
+    #[test]
+    fn test_splitter_with_token_2022_mint() {
+        use spl_token_2022::{extension::StateWithExtensions, state::Account as Token2022Account};
+
+        let (mut program, authority, recipients, _mint, _authority_ata) = setup();
+        let token_program = spl_token_2022::ID;
+        let ata = |owner: &Pubkey, mint: &Pubkey| {
+            spl_associated_token_account::get_associated_token_address_with_program_id(
+                owner,
+                mint,
+                &token_program,
+            )
+        };
+        let token_2022_amount = |program: &LiteSVM, token_account: &Pubkey| {
+            let account = program.get_account(token_account).unwrap();
+            StateWithExtensions::<Token2022Account>::unpack(&account.data)
+                .unwrap()
+                .base
+                .amount
+        };
+
+        let mint = CreateMint::new(&mut program, &authority)
+            .decimals(6)
+            .authority(&authority.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        let authority_ata = CreateAssociatedTokenAccount::new(&mut program, &authority, &mint)
+            .owner(&authority.pubkey())
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+        MintTo::new(&mut program, &authority, &mint, &authority_ata, 1000)
+            .token_program_id(&token_program)
+            .send()
+            .unwrap();
+
+        // The vault is the Token-2022 associated account of the splitter
+        let (splitter, _) = splitter_pdas(authority.pubkey(), mint);
+        let vault = ata(&splitter, &mint);
+        let create_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::CreateSplitter {
+                authority: authority.pubkey(),
+                mint,
+                splitter,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::CreateSplitter {
+                seed: SEED,
+                shares: default_shares(&recipients),
+            }
+            .data(),
+        };
+        send(&mut program, create_ix, &authority).unwrap();
+        assert_eq!(program.get_account(&vault).unwrap().owner, token_program);
+
+        // Payments and claims go through the same token program
+        let pay_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Pay {
+                payer: authority.pubkey(),
+                mint,
+                payer_ata: authority_ata,
+                splitter,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Pay { amount: 1000 }.data(),
+        };
+        send(&mut program, pay_ix, &authority).unwrap();
+        assert_eq!(token_2022_amount(&program, &vault), 1000);
+
+        let recipient_ata = ata(&recipients[0].pubkey(), &mint);
+        let claim_ix = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: crate::accounts::Claim {
+                recipient: recipients[0].pubkey(),
+                mint,
+                recipient_ata,
+                splitter,
+                vault,
+                associated_token_program: spl_associated_token_account::ID,
+                token_program,
+                system_program: SYSTEM_PROGRAM_ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::Claim {}.data(),
+        };
+        send(&mut program, claim_ix, &recipients[0]).unwrap();
+        assert_eq!(token_2022_amount(&program, &recipient_ata), 500);
+        assert_eq!(token_2022_amount(&program, &vault), 500);
+
+        msg!("\nAll Token-2022 splitter assertions passed!");
+    }
+
+    #[test]
+    fn test_update_shares_requires_authority_and_settlement() {
+        let (mut program, authority, recipients, mint, authority_ata) = setup();
+        let ix = create_splitter_instruction(authority.pubkey(), mint, default_shares(&recipients));
+        send(&mut program, ix, &authority).unwrap();
+
+        let ix = pay_instruction(authority.pubkey(), authority_ata, mint, 1000);
+        send(&mut program, ix, &authority).unwrap();
+
+        let even: Vec<ShareTerms> = recipients[..2]
+            .iter()
+            .map(|recipient| ShareTerms {
+                recipient: recipient.pubkey(),
+                bps: 5_000,
+            })
+            .collect();
+
+        // Only the authority may change the split
+        let ix = update_shares_instruction(
+            recipients[0].pubkey(),
+            authority.pubkey(),
+            mint,
+            even.clone(),
+        );
+        assert!(send(&mut program, ix, &recipients[0]).is_err());
+
+        // Not everyone has claimed yet
+        let ix =
+            update_shares_instruction(authority.pubkey(), authority.pubkey(), mint, even.clone());
+        assert!(send(&mut program, ix, &authority).is_err());
+
+        for recipient in &recipients {
+            let ix = claim_instruction(recipient.pubkey(), authority.pubkey(), mint);
+            send(&mut program, ix, recipient).unwrap();
+        }
+        let ix = update_shares_instruction(authority.pubkey(), authority.pubkey(), mint, even);
+        send(&mut program, ix, &authority).unwrap();
+
+        // New payments follow the new split
+        let ix = pay_instruction(authority.pubkey(), authority_ata, mint, 1000);
+        send(&mut program, ix, &authority).unwrap();
+        let ix = claim_instruction(recipients[1].pubkey(), authority.pubkey(), mint);
+        send(&mut program, ix, &recipients[1]).unwrap();
+        assert_eq!(
+            recipient_balance(&program, &recipients[1], &mint),
+            300 + 500
+        );
+
+        let ix = claim_instruction(recipients[2].pubkey(), authority.pubkey(), mint);
+        assert!(
+            send(&mut program, ix, &recipients[2]).is_err(),
+            "A removed recipient should no longer be able to claim"
+        );
+
+        msg!("\nAll update shares assertions passed!");
+    }
+}
@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Whitelist entry has expired and needs re-verification.")]
+    WhitelistExpired,
+    #[msg("No re-verification was requested for this entry.")]
+    ReverificationNotRequested,
+    #[msg("Validity period must be positive.")]
+    InvalidValidityPeriod,
+    #[msg("Account is not a whitelist entry of this program.")]
+    InvalidWhitelistAccount,
+    #[msg("Signer is not the whitelist admin for this mint.")]
+    Unauthorized,
+    #[msg("Whitelist entry has been removed.")]
+    WhitelistRemoved,
+    #[msg("Grace period cannot be negative.")]
+    InvalidGracePeriod,
+    #[msg("No removal is pending for this entry.")]
+    NoPendingRemoval,
+}
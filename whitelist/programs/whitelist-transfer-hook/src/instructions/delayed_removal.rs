@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    error::ErrorCode,
+    state::{MintConfig, Whitelist},
+};
+
+// Schedule a removal that only takes effect after a grace period
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct RemoveWithDelay<'info> {
+    pub admin: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"mint-config", mint.key().as_ref()],
+        bump = mint_config.bump,
+        has_one = mint,
+        constraint = mint_config.is_admin(&admin.key(), &mint) @ ErrorCode::Unauthorized,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    #[account(
+        mut,
+        seeds = [b"whitelist", user.as_ref()],
+        bump = whitelist.bump
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+}
+
+impl<'info> RemoveWithDelay<'info> {
+    pub fn remove_with_delay(&mut self, _user: Pubkey, grace_period: i64) -> Result<()> {
+        require!(grace_period >= 0, ErrorCode::InvalidGracePeriod);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.whitelist.removal_effective_at = now.saturating_add(grace_period);
+        msg!("User removal scheduled for {}", self.whitelist.removal_effective_at);
+        Ok(())
+    }
+}
+
+// Cancel a scheduled removal, allowed until the grace period ends
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct CancelRemoval<'info> {
+    pub admin: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"mint-config", mint.key().as_ref()],
+        bump = mint_config.bump,
+        has_one = mint,
+        constraint = mint_config.is_admin(&admin.key(), &mint) @ ErrorCode::Unauthorized,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    #[account(
+        mut,
+        seeds = [b"whitelist", user.as_ref()],
+        bump = whitelist.bump
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+}
+
+impl<'info> CancelRemoval<'info> {
+    pub fn cancel_removal(&mut self, _user: Pubkey) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            self.whitelist.removal_effective_at != 0 && !self.whitelist.is_removed(now),
+            ErrorCode::NoPendingRemoval
+        );
+
+        self.whitelist.removal_effective_at = 0;
+        msg!("User removal cancelled");
+        Ok(())
+    }
+}
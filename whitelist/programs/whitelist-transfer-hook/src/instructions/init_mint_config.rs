@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{error::ErrorCode, state::MintConfig};
+
+// Mint authority registers the whitelist admin for its mint
+#[derive(Accounts)]
+pub struct InitMintConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(
+        constraint = mint.mint_authority.contains(&authority.key()) @ ErrorCode::Unauthorized,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + MintConfig::INIT_SPACE,
+        seeds = [b"mint-config", mint.key().as_ref()],
+        bump
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitMintConfig<'info> {
+    pub fn init_mint_config(
+        &mut self,
+        bumps: &InitMintConfigBumps,
+        admin: Pubkey,
+        mint_authority_is_admin: bool,
+    ) -> Result<()> {
+        self.mint_config.set_inner(MintConfig {
+            mint: self.mint.key(),
+            admin,
+            mint_authority_is_admin,
+            bump: bumps.mint_config,
+        });
+        msg!("Mint config initialized");
+        Ok(())
+    }
+}
@@ -3,10 +3,18 @@ pub mod transfer_hook;
 pub mod initialize_whitelist;
 pub mod whitelist_operations;
 pub mod mint_token;
+pub mod init_mint_config;
+pub mod reverification;
+pub mod delayed_removal;
+pub mod upgrade_whitelist_entry;
 
 
 pub use init_extra_account_meta::*;
 pub use transfer_hook::*;
 pub use initialize_whitelist::*;
 pub use whitelist_operations::*;
-pub use mint_token::*;
\ No newline at end of file
+pub use mint_token::*;
+pub use init_mint_config::*;
+pub use reverification::*;
+pub use delayed_removal::*;
+pub use upgrade_whitelist_entry::*;
\ No newline at end of file
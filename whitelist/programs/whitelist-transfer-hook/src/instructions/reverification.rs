@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    error::ErrorCode,
+    state::{MintConfig, Whitelist},
+};
+
+// User flags their own entry for renewal
+#[derive(Accounts)]
+pub struct RequestReverification<'info> {
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"whitelist", user.key().as_ref()],
+        bump = whitelist.bump
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+}
+
+impl<'info> RequestReverification<'info> {
+    pub fn request_reverification(&mut self) -> Result<()> {
+        self.whitelist.reverification_requested = true;
+        msg!("Re-verification requested");
+        Ok(())
+    }
+}
+
+// Admin renews a flagged entry for another validity period
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct ApproveReverification<'info> {
+    pub admin: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"mint-config", mint.key().as_ref()],
+        bump = mint_config.bump,
+        has_one = mint,
+        constraint = mint_config.is_admin(&admin.key(), &mint) @ ErrorCode::Unauthorized,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    #[account(
+        mut,
+        seeds = [b"whitelist", user.as_ref()],
+        bump = whitelist.bump
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+}
+
+impl<'info> ApproveReverification<'info> {
+    pub fn approve_reverification(&mut self, _user: Pubkey, validity_period: i64) -> Result<()> {
+        require!(
+            self.whitelist.reverification_requested,
+            ErrorCode::ReverificationNotRequested
+        );
+        require!(validity_period > 0, ErrorCode::InvalidValidityPeriod);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.whitelist.expires_at = now.saturating_add(validity_period);
+        self.whitelist.reverification_requested = false;
+        msg!("Re-verification approved until {}", self.whitelist.expires_at);
+        Ok(())
+    }
+}
@@ -16,7 +16,7 @@ use anchor_spl::{
     }
 };
 
-use crate::state::Whitelist;
+use crate::{error::ErrorCode, state::Whitelist};
 
 #[derive(Accounts)]
 pub struct TransferHook<'info> {
@@ -56,7 +56,12 @@ impl<'info> TransferHook<'info> {
         msg!("Destination token owner: {}", self.destination_token.owner);
 
         // If we reached here, the whitelist PDA exists and was validated
-        // This means the user IS whitelisted
+        // This means the user IS whitelisted, as long as the entry is still current
+        let now = Clock::get()?.unix_timestamp;
+        require!(!self.whitelist.is_expired(now), ErrorCode::WhitelistExpired);
+        // A delayed removal keeps the entry usable until its grace period ends
+        require!(!self.whitelist.is_removed(now), ErrorCode::WhitelistRemoved);
+
         msg!("Transfer allowed: The address is whitelisted");
         Ok(())
     }
@@ -0,0 +1,82 @@
+use anchor_lang::{prelude::*, system_program, Discriminator};
+
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    error::ErrorCode,
+    state::{MintConfig, Whitelist},
+};
+
+// Upgrade an existing entry to the current layout in place
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct UpgradeWhitelistEntry<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"mint-config", mint.key().as_ref()],
+        bump = mint_config.bump,
+        has_one = mint,
+        constraint = mint_config.is_admin(&admin.key(), &mint) @ ErrorCode::Unauthorized,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+    /// CHECK: may hold an older layout that Account<Whitelist> cannot deserialize,
+    /// owner and discriminator are checked in the handler
+    #[account(
+        mut,
+        seeds = [b"whitelist", user.as_ref()],
+        bump
+    )]
+    pub whitelist: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpgradeWhitelistEntry<'info> {
+    pub fn upgrade_whitelist_entry(&mut self, _user: Pubkey) -> Result<()> {
+        let account_info = self.whitelist.to_account_info();
+        require_keys_eq!(*account_info.owner, crate::ID, ErrorCode::InvalidWhitelistAccount);
+
+        let mut entry = {
+            let data = account_info.try_borrow_data()?;
+            require!(
+                data.len() >= Whitelist::V0_LEN && data[..8] == *Whitelist::DISCRIMINATOR,
+                ErrorCode::InvalidWhitelistAccount
+            );
+
+            // Older layouts are a prefix of the current one, fields appended since then
+            // (including the version of pre-v2 entries) read as zero
+            let mut padded = data.to_vec();
+            padded.resize(padded.len().max(8 + Whitelist::INIT_SPACE), 0);
+            Whitelist::try_deserialize(&mut &padded[..])?
+        };
+
+        if entry.version >= Whitelist::CURRENT_VERSION {
+            msg!("Whitelist entry already at version {}", entry.version);
+            return Ok(());
+        }
+        entry.version = Whitelist::CURRENT_VERSION;
+
+        let new_account_size = 8 + Whitelist::INIT_SPACE;
+        if new_account_size > account_info.data_len() {
+            // Admin pays the rent for the larger layout
+            let lamports_required = (Rent::get()?).minimum_balance(new_account_size);
+            let rent_diff = lamports_required.saturating_sub(account_info.lamports());
+            if rent_diff > 0 {
+                let cpi_program = self.system_program.to_account_info();
+                let cpi_accounts = system_program::Transfer {
+                    from: self.admin.to_account_info(),
+                    to: account_info.clone(),
+                };
+                let cpi_context = CpiContext::new(cpi_program, cpi_accounts);
+                system_program::transfer(cpi_context, rent_diff)?;
+            }
+
+            account_info.resize(new_account_size)?;
+        }
+
+        entry.try_serialize(&mut &mut account_info.try_borrow_mut_data()?[..])?;
+        msg!("Whitelist entry upgraded to version {}", Whitelist::CURRENT_VERSION);
+        Ok(())
+    }
+}
@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
 
-use crate::state::whitelist::Whitelist;
+use crate::{
+    error::ErrorCode,
+    state::{MintConfig, Whitelist},
+};
 
 // Add user to whitelist (creates their PDA)
 #[derive(Accounts)]
@@ -8,6 +12,14 @@ use crate::state::whitelist::Whitelist;
 pub struct AddToWhitelist<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"mint-config", mint.key().as_ref()],
+        bump = mint_config.bump,
+        has_one = mint,
+        constraint = mint_config.is_admin(&admin.key(), &mint) @ ErrorCode::Unauthorized,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
     #[account(
         init,
         payer = admin,
@@ -21,7 +33,16 @@ pub struct AddToWhitelist<'info> {
 
 impl<'info> AddToWhitelist<'info> {
     pub fn add_to_whitelist(&mut self, bumps: &AddToWhitelistBumps, _user: Pubkey) -> Result<()> {
-        self.whitelist.bump = bumps.whitelist;
+        self.whitelist.set_inner(Whitelist {
+            bump: bumps.whitelist,
+            expires_at: 0,
+            reverification_requested: false,
+            version: Whitelist::CURRENT_VERSION,
+            added_at: Clock::get()?.unix_timestamp,
+            tier: 0,
+            metadata: [0; 32],
+            removal_effective_at: 0,
+        });
         msg!("User added to whitelist");
         Ok(())
     }
@@ -33,6 +54,14 @@ impl<'info> AddToWhitelist<'info> {
 pub struct RemoveFromWhitelist<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        seeds = [b"mint-config", mint.key().as_ref()],
+        bump = mint_config.bump,
+        has_one = mint,
+        constraint = mint_config.is_admin(&admin.key(), &mint) @ ErrorCode::Unauthorized,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
     #[account(
         mut,
         close = admin,
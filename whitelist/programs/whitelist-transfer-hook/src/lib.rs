@@ -3,6 +3,7 @@
 
 use anchor_lang::prelude::*;
 
+mod error;
 mod instructions;
 mod state;
 
@@ -24,6 +25,15 @@ pub mod whitelist_transfer_hook {
     //     ctx.accounts.initialize_whitelist(ctx.bumps)
     // }
 
+    pub fn init_mint_config(
+        ctx: Context<InitMintConfig>,
+        admin: Pubkey,
+        mint_authority_is_admin: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .init_mint_config(&ctx.bumps, admin, mint_authority_is_admin)
+    }
+
     pub fn add_to_whitelist(ctx: Context<AddToWhitelist>, user: Pubkey) -> Result<()> {
         ctx.accounts.add_to_whitelist(&ctx.bumps, user)
     }
@@ -32,6 +42,34 @@ pub mod whitelist_transfer_hook {
         ctx.accounts.remove_from_whitelist(user)
     }
 
+    pub fn remove_with_delay(
+        ctx: Context<RemoveWithDelay>,
+        user: Pubkey,
+        grace_period: i64,
+    ) -> Result<()> {
+        ctx.accounts.remove_with_delay(user, grace_period)
+    }
+
+    pub fn cancel_removal(ctx: Context<CancelRemoval>, user: Pubkey) -> Result<()> {
+        ctx.accounts.cancel_removal(user)
+    }
+
+    pub fn request_reverification(ctx: Context<RequestReverification>) -> Result<()> {
+        ctx.accounts.request_reverification()
+    }
+
+    pub fn approve_reverification(
+        ctx: Context<ApproveReverification>,
+        user: Pubkey,
+        validity_period: i64,
+    ) -> Result<()> {
+        ctx.accounts.approve_reverification(user, validity_period)
+    }
+
+    pub fn upgrade_whitelist_entry(ctx: Context<UpgradeWhitelistEntry>, user: Pubkey) -> Result<()> {
+        ctx.accounts.upgrade_whitelist_entry(user)
+    }
+
     pub fn init_mint(ctx: Context<TokenFactory>) -> Result<()> {
         ctx.accounts.init_mint()
     }
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+// Who may manage the whitelist on behalf of a hooked mint
+#[account]
+#[derive(InitSpace)]
+pub struct MintConfig {
+    pub mint: Pubkey,
+    pub admin: Pubkey,
+    // When set, the mint's current mint authority is also accepted as admin
+    pub mint_authority_is_admin: bool,
+    pub bump: u8,
+}
+
+impl MintConfig {
+    pub fn is_admin(&self, key: &Pubkey, mint: &Mint) -> bool {
+        *key == self.admin
+            || (self.mint_authority_is_admin && mint.mint_authority.contains(key))
+    }
+}
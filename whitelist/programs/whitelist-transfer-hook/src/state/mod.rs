@@ -1,3 +1,5 @@
+pub mod mint_config;
 pub mod whitelist;
 
+pub use mint_config::*;
 pub use whitelist::*;
\ No newline at end of file
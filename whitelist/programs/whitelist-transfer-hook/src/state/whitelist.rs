@@ -1,8 +1,37 @@
 use anchor_lang::prelude::*;
 
 // The account's existence itself means the user is whitelisted
+//
+// New fields are only ever appended so older entries stay a prefix of the
+// current layout and can be upgraded in place with `upgrade_whitelist_entry`.
 #[account]
 #[derive(InitSpace)]
 pub struct Whitelist {
     pub bump: u8,
-}
\ No newline at end of file
+    // Unix timestamp after which transfers are rejected, 0 means no expiry
+    pub expires_at: i64,
+    // Set by the user to ask the admin to renew their entry
+    pub reverification_requested: bool,
+    pub version: u8,
+    // Unix timestamp the user was first whitelisted, 0 if unknown (pre-v2 entries)
+    pub added_at: i64,
+    pub tier: u8,
+    pub metadata: [u8; 32],
+    // Unix timestamp a delayed removal takes effect, 0 means none pending
+    pub removal_effective_at: i64,
+}
+
+impl Whitelist {
+    pub const CURRENT_VERSION: u8 = 3;
+
+    // Account size (with discriminator) of the original bump-only layout
+    pub const V0_LEN: usize = 8 + 1;
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now > self.expires_at
+    }
+
+    pub fn is_removed(&self, now: i64) -> bool {
+        self.removal_effective_at != 0 && now >= self.removal_effective_at
+    }
+}
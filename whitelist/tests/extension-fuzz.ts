@@ -0,0 +1,163 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
+  createAssociatedTokenAccountInstruction,
+  createMintToInstruction,
+  createTransferCheckedWithTransferHookInstruction,
+  getAccount,
+  getAssociatedTokenAddressSync,
+} from "@solana/spl-token";
+import { Transaction, sendAndConfirmTransaction } from "@solana/web3.js";
+import { expect } from "chai";
+import { WhitelistTransferHook } from "../target/types/whitelist_transfer_hook";
+import {
+  createHookMintInstructions,
+  describeExtensions,
+  mulberry32,
+  randomExtensionSet,
+} from "./utils/mint-extensions";
+
+// FUZZ_SEED replays a run, FUZZ_RUNS sets how many combinations are tried
+const seed = Number(process.env.FUZZ_SEED ?? Math.floor(Math.random() * 2 ** 32));
+const runs = Number(process.env.FUZZ_RUNS ?? 8);
+
+describe(`transfer hook extension fuzz (FUZZ_SEED=${seed})`, () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const wallet = provider.wallet as anchor.Wallet;
+
+  const program = anchor.workspace.whitelistTransferHook as Program<WhitelistTransferHook>;
+
+  // Sender gets whitelisted once, the receiver never is
+  const sender = anchor.web3.Keypair.generate();
+  const receiver = anchor.web3.Keypair.generate();
+
+  const pda = (label: string, key: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync([Buffer.from(label), key.toBuffer()], program.programId)[0];
+
+  const ata = (mint: anchor.web3.PublicKey, owner: anchor.web3.PublicKey) =>
+    getAssociatedTokenAddressSync(mint, owner, false, TOKEN_2022_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+
+  // Creates the mint, its MintConfig and ExtraAccountMetaList, and funds the sender
+  async function setUpMint(extensions: ReturnType<typeof randomExtensionSet>) {
+    const mint = anchor.web3.Keypair.generate();
+
+    const transaction = new Transaction().add(
+      ...(await createHookMintInstructions(
+        provider.connection,
+        wallet.publicKey,
+        mint.publicKey,
+        wallet.publicKey,
+        program.programId,
+        extensions,
+      )),
+    );
+    await sendAndConfirmTransaction(provider.connection, transaction, [wallet.payer, mint]);
+
+    await program.methods.initMintConfig(wallet.publicKey, true)
+      .accountsPartial({
+        authority: wallet.publicKey,
+        mint: mint.publicKey,
+        mintConfig: pda("mint-config", mint.publicKey),
+      })
+      .rpc();
+
+    await program.methods.initializeTransferHook()
+      .accountsPartial({
+        payer: wallet.publicKey,
+        mint: mint.publicKey,
+        extraAccountMetaList: pda("extra-account-metas", mint.publicKey),
+      })
+      .rpc();
+
+    const funding = new Transaction().add(
+      createAssociatedTokenAccountInstruction(
+        wallet.publicKey,
+        ata(mint.publicKey, sender.publicKey),
+        sender.publicKey,
+        mint.publicKey,
+        TOKEN_2022_PROGRAM_ID,
+        ASSOCIATED_TOKEN_PROGRAM_ID,
+      ),
+      createAssociatedTokenAccountInstruction(
+        wallet.publicKey,
+        ata(mint.publicKey, receiver.publicKey),
+        receiver.publicKey,
+        mint.publicKey,
+        TOKEN_2022_PROGRAM_ID,
+        ASSOCIATED_TOKEN_PROGRAM_ID,
+      ),
+      createMintToInstruction(
+        mint.publicKey,
+        ata(mint.publicKey, sender.publicKey),
+        wallet.publicKey,
+        100 * 10 ** 9,
+        [],
+        TOKEN_2022_PROGRAM_ID,
+      ),
+    );
+    await sendAndConfirmTransaction(provider.connection, funding, [wallet.payer]);
+
+    return mint.publicKey;
+  }
+
+  async function transfer(mint: anchor.web3.PublicKey, from: anchor.web3.Keypair, to: anchor.web3.PublicKey) {
+    const instruction = await createTransferCheckedWithTransferHookInstruction(
+      provider.connection,
+      ata(mint, from.publicKey),
+      mint,
+      ata(mint, to),
+      from.publicKey,
+      BigInt(10 ** 9),
+      9,
+      [],
+      "confirmed",
+      TOKEN_2022_PROGRAM_ID,
+    );
+    const transaction = new Transaction().add(instruction);
+    return sendAndConfirmTransaction(provider.connection, transaction, [wallet.payer, from]);
+  }
+
+  before(async () => {
+    // Any hook mint works to whitelist the sender, entries are per user
+    const mint = await setUpMint([]);
+    await program.methods.addToWhitelist(sender.publicKey)
+      .accountsPartial({
+        admin: wallet.publicKey,
+        mint,
+        mintConfig: pda("mint-config", mint),
+        whitelist: pda("whitelist", sender.publicKey),
+      })
+      .rpc();
+  });
+
+  const rng = mulberry32(seed);
+  for (let run = 0; run < runs; run++) {
+    const extensions = randomExtensionSet(rng);
+
+    it(`run ${run}: ${describeExtensions(extensions)}`, async () => {
+      const mint = await setUpMint(extensions);
+
+      // Whitelisted sender goes through the hook
+      await transfer(mint, sender, receiver.publicKey);
+      const received = await getAccount(
+        provider.connection,
+        ata(mint, receiver.publicKey),
+        "confirmed",
+        TOKEN_2022_PROGRAM_ID,
+      );
+      expect(Number(received.amount)).to.be.greaterThan(0);
+
+      // Receiver is not whitelisted, so the hook must reject sending it back
+      try {
+        await transfer(mint, receiver, sender.publicKey);
+        expect.fail("transfer from a non-whitelisted owner should fail");
+      } catch (error) {
+        expect(error.message).to.not.include("transfer from a non-whitelisted owner should fail");
+      }
+    });
+  }
+});
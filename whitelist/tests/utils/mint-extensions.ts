@@ -0,0 +1,125 @@
+import {
+  AccountState,
+  ExtensionType,
+  TOKEN_2022_PROGRAM_ID,
+  createInitializeDefaultAccountStateInstruction,
+  createInitializeGroupMemberPointerInstruction,
+  createInitializeGroupPointerInstruction,
+  createInitializeInterestBearingMintInstruction,
+  createInitializeMetadataPointerInstruction,
+  createInitializeMintCloseAuthorityInstruction,
+  createInitializeMintInstruction,
+  createInitializePermanentDelegateInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createInitializeTransferHookInstruction,
+  getMintLen,
+} from "@solana/spl-token";
+import {
+  Connection,
+  PublicKey,
+  SystemProgram,
+  TransactionInstruction,
+} from "@solana/web3.js";
+
+// Mint extensions that can sit next to TransferHook without changing who may
+// transfer. NonTransferable, confidential transfers and a frozen
+// DefaultAccountState are left out on purpose.
+export const HOOK_COMPATIBLE_EXTENSIONS = [
+  ExtensionType.MintCloseAuthority,
+  ExtensionType.TransferFeeConfig,
+  ExtensionType.InterestBearingConfig,
+  ExtensionType.DefaultAccountState,
+  ExtensionType.PermanentDelegate,
+  ExtensionType.MetadataPointer,
+  ExtensionType.GroupPointer,
+  ExtensionType.GroupMemberPointer,
+];
+
+// Small seeded PRNG so a failing combination can be replayed with FUZZ_SEED
+export function mulberry32(seed: number): () => number {
+  let state = seed >>> 0;
+  return () => {
+    state = (state + 0x6d2b79f5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  };
+}
+
+export function randomExtensionSet(rng: () => number): ExtensionType[] {
+  return HOOK_COMPATIBLE_EXTENSIONS.filter(() => rng() < 0.5);
+}
+
+function initExtensionInstruction(
+  extension: ExtensionType,
+  mint: PublicKey,
+  authority: PublicKey,
+): TransactionInstruction {
+  switch (extension) {
+    case ExtensionType.MintCloseAuthority:
+      return createInitializeMintCloseAuthorityInstruction(mint, authority, TOKEN_2022_PROGRAM_ID);
+    case ExtensionType.TransferFeeConfig:
+      // 1% fee capped at 1 token, so transfers still deliver most of the amount
+      return createInitializeTransferFeeConfigInstruction(
+        mint,
+        authority,
+        authority,
+        100,
+        BigInt(10 ** 9),
+        TOKEN_2022_PROGRAM_ID,
+      );
+    case ExtensionType.InterestBearingConfig:
+      return createInitializeInterestBearingMintInstruction(mint, authority, 500, TOKEN_2022_PROGRAM_ID);
+    case ExtensionType.DefaultAccountState:
+      return createInitializeDefaultAccountStateInstruction(
+        mint,
+        AccountState.Initialized,
+        TOKEN_2022_PROGRAM_ID,
+      );
+    case ExtensionType.PermanentDelegate:
+      return createInitializePermanentDelegateInstruction(mint, authority, TOKEN_2022_PROGRAM_ID);
+    case ExtensionType.MetadataPointer:
+      return createInitializeMetadataPointerInstruction(mint, authority, mint, TOKEN_2022_PROGRAM_ID);
+    case ExtensionType.GroupPointer:
+      return createInitializeGroupPointerInstruction(mint, authority, mint, TOKEN_2022_PROGRAM_ID);
+    case ExtensionType.GroupMemberPointer:
+      return createInitializeGroupMemberPointerInstruction(mint, authority, mint, TOKEN_2022_PROGRAM_ID);
+    default:
+      throw new Error(`No initializer for extension ${ExtensionType[extension]}`);
+  }
+}
+
+/**
+ * Instructions creating a Token-2022 mint with the TransferHook extension
+ * pointing at `hookProgram` plus the given extra extensions.
+ */
+export async function createHookMintInstructions(
+  connection: Connection,
+  payer: PublicKey,
+  mint: PublicKey,
+  authority: PublicKey,
+  hookProgram: PublicKey,
+  extensions: ExtensionType[],
+  decimals = 9,
+): Promise<TransactionInstruction[]> {
+  const mintLen = getMintLen([ExtensionType.TransferHook, ...extensions]);
+  const lamports = await connection.getMinimumBalanceForRentExemption(mintLen);
+
+  return [
+    SystemProgram.createAccount({
+      fromPubkey: payer,
+      newAccountPubkey: mint,
+      space: mintLen,
+      lamports,
+      programId: TOKEN_2022_PROGRAM_ID,
+    }),
+    createInitializeTransferHookInstruction(mint, authority, hookProgram, TOKEN_2022_PROGRAM_ID),
+    ...extensions.map((extension) => initExtensionInstruction(extension, mint, authority)),
+    createInitializeMintInstruction(mint, decimals, authority, null, TOKEN_2022_PROGRAM_ID),
+  ];
+}
+
+export function describeExtensions(extensions: ExtensionType[]): string {
+  return ["TransferHook", ...extensions.map((extension) => ExtensionType[extension])].join(" + ");
+}
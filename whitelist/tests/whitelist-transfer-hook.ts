@@ -19,6 +19,7 @@ import {
   Transaction, 
   sendAndConfirmTransaction 
 } from '@solana/web3.js';
+import { expect } from "chai";
 import { WhitelistTransferHook } from "../target/types/whitelist_transfer_hook";
 
 describe("whitelist-transfer-hook", () => {
@@ -58,6 +59,12 @@ describe("whitelist-transfer-hook", () => {
     program.programId,
   );
 
+  // MintConfig address, records who may manage the whitelist for this mint
+  const [mintConfig] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from('mint-config'), mint2022.publicKey.toBuffer()],
+    program.programId,
+  );
+
   // const whitelist = anchor.web3.PublicKey.findProgramAddressSync(
   //   [
   //     Buffer.from("whitelist"),
@@ -78,6 +85,66 @@ describe("whitelist-transfer-hook", () => {
   //   console.log("Transaction signature:", tx);
   // });
 
+  it('Create Mint Account with Transfer Hook Extension (Program)', async () => {
+  const tx = await program.methods
+    .initMint()
+     .accountsPartial({
+        user: wallet.publicKey,
+        mint: mint2022.publicKey,
+        extraAccountMetaList: extraAccountMetaListPDA,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+    .signers([mint2022])
+    .rpc();
+
+  console.log("\nMint created via program with transfer hook");
+  console.log("Mint address:", mint2022.publicKey.toBase58());
+  console.log("Transaction Signature:", tx);
+});
+
+  it("Initialize mint config (mint authority acts as admin)", async () => {
+    // The separate admin key is never used: the mint authority is recognised instead
+    const separateAdmin = anchor.web3.Keypair.generate();
+    const tx = await program.methods.initMintConfig(separateAdmin.publicKey, true)
+      .accountsPartial({
+        authority: wallet.publicKey,
+        mint: mint2022.publicKey,
+        mintConfig,
+      })
+      .rpc();
+
+    const config = await program.account.mintConfig.fetch(mintConfig);
+    expect(config.mintAuthorityIsAdmin).to.equal(true);
+
+    console.log("\nMint config initialized:", mintConfig.toBase58());
+    console.log("Transaction signature:", tx);
+  });
+
+  it("Non-admin cannot add to whitelist", async () => {
+    const outsider = anchor.web3.Keypair.generate();
+    const [whitelist]=anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("whitelist"),
+        outsider.publicKey.toBuffer()
+      ],
+      program.programId
+    )
+    try {
+      await program.methods.addToWhitelist(outsider.publicKey)
+        .accountsPartial({
+          admin: outsider.publicKey,
+          mint: mint2022.publicKey,
+          mintConfig,
+          whitelist,
+        })
+        .signers([outsider])
+        .rpc();
+      expect.fail("outsider should not be able to manage the whitelist");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("Unauthorized");
+    }
+  });
+
   it("Add user to whitelist", async () => {
     // Derive the whitelist PDA for this specific user
     const [whitelist]=anchor.web3.PublicKey.findProgramAddressSync(
@@ -90,6 +157,8 @@ describe("whitelist-transfer-hook", () => {
     const tx = await program.methods.addToWhitelist(provider.publicKey)
       .accountsPartial({
         admin: provider.publicKey,
+        mint: mint2022.publicKey,
+        mintConfig,
         whitelist,
       })
       .rpc();
@@ -98,6 +167,155 @@ describe("whitelist-transfer-hook", () => {
     console.log("Transaction signature:", tx);
   });
 
+  it("Request re-verification", async () => {
+    const [whitelist]=anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("whitelist"),
+        provider.publicKey.toBuffer()
+      ],
+      program.programId
+    )
+    const tx = await program.methods.requestReverification()
+      .accountsPartial({
+        user: provider.publicKey,
+        whitelist,
+      })
+      .rpc();
+
+    const entry = await program.account.whitelist.fetch(whitelist);
+    expect(entry.reverificationRequested).to.equal(true);
+
+    console.log("\nRe-verification requested:", provider.publicKey.toBase58());
+    console.log("Transaction signature:", tx);
+  });
+
+  it("Approve re-verification", async () => {
+    const [whitelist]=anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("whitelist"),
+        provider.publicKey.toBuffer()
+      ],
+      program.programId
+    )
+    // Renew the entry for 30 days
+    const validityPeriod = new anchor.BN(30 * 24 * 60 * 60);
+    const tx = await program.methods.approveReverification(provider.publicKey, validityPeriod)
+      .accountsPartial({
+        admin: provider.publicKey,
+        mint: mint2022.publicKey,
+        mintConfig,
+        whitelist,
+      })
+      .rpc();
+
+    const entry = await program.account.whitelist.fetch(whitelist);
+    expect(entry.reverificationRequested).to.equal(false);
+    expect(entry.expiresAt.toNumber()).to.be.greaterThan(Math.floor(Date.now() / 1000));
+
+    console.log("\nRe-verification approved:", provider.publicKey.toBase58());
+    console.log("Transaction signature:", tx);
+  });
+
+  it("Upgrade whitelist entry is a no-op for current entries", async () => {
+    const [whitelist]=anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("whitelist"),
+        provider.publicKey.toBuffer()
+      ],
+      program.programId
+    )
+    const before = await program.account.whitelist.fetch(whitelist);
+
+    const tx = await program.methods.upgradeWhitelistEntry(provider.publicKey)
+      .accountsPartial({
+        admin: provider.publicKey,
+        mint: mint2022.publicKey,
+        mintConfig,
+        whitelist,
+      })
+      .rpc();
+
+    const after = await program.account.whitelist.fetch(whitelist);
+    expect(after.version).to.equal(3);
+    expect(after.addedAt.toNumber()).to.equal(before.addedAt.toNumber());
+    expect(after.expiresAt.toNumber()).to.equal(before.expiresAt.toNumber());
+
+    console.log("\nWhitelist entry upgrade checked:", provider.publicKey.toBase58());
+    console.log("Transaction signature:", tx);
+  });
+
+  it("Approve re-verification fails without a request", async () => {
+    const [whitelist]=anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("whitelist"),
+        provider.publicKey.toBuffer()
+      ],
+      program.programId
+    )
+    try {
+      await program.methods.approveReverification(provider.publicKey, new anchor.BN(60))
+        .accountsPartial({
+          admin: provider.publicKey,
+          mint: mint2022.publicKey,
+          mintConfig,
+          whitelist,
+        })
+        .rpc();
+      expect.fail("approve should fail when no re-verification is pending");
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal("ReverificationNotRequested");
+    }
+  });
+
+  it("Remove with delay keeps the entry during the grace period", async () => {
+    const [whitelist]=anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("whitelist"),
+        provider.publicKey.toBuffer()
+      ],
+      program.programId
+    )
+    // One hour grace period
+    const tx = await program.methods.removeWithDelay(provider.publicKey, new anchor.BN(60 * 60))
+      .accountsPartial({
+        admin: provider.publicKey,
+        mint: mint2022.publicKey,
+        mintConfig,
+        whitelist,
+      })
+      .rpc();
+
+    const entry = await program.account.whitelist.fetch(whitelist);
+    expect(entry.removalEffectiveAt.toNumber()).to.be.greaterThan(Math.floor(Date.now() / 1000));
+
+    console.log("\nUser removal scheduled:", provider.publicKey.toBase58());
+    console.log("Transaction signature:", tx);
+  });
+
+  it("Cancel a pending removal", async () => {
+    const [whitelist]=anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("whitelist"),
+        provider.publicKey.toBuffer()
+      ],
+      program.programId
+    )
+    const tx = await program.methods.cancelRemoval(provider.publicKey)
+      .accountsPartial({
+        admin: provider.publicKey,
+        mint: mint2022.publicKey,
+        mintConfig,
+        whitelist,
+      })
+      .rpc();
+
+    const entry = await program.account.whitelist.fetch(whitelist);
+    expect(entry.removalEffectiveAt.toNumber()).to.equal(0);
+
+    console.log("\nUser removal cancelled:", provider.publicKey.toBase58());
+    console.log("Transaction signature:", tx);
+  });
+
   it("Remove user to whitelist", async () => {
     // Derive the whitelist PDA for this specific user
     const [whitelist]=anchor.web3.PublicKey.findProgramAddressSync(
@@ -111,6 +329,8 @@ describe("whitelist-transfer-hook", () => {
     const tx = await program.methods.removeFromWhitelist(provider.publicKey)
       .accountsPartial({
         admin: provider.publicKey,
+        mint: mint2022.publicKey,
+        mintConfig,
         whitelist,
       })
       .rpc();
@@ -156,23 +376,6 @@ describe("whitelist-transfer-hook", () => {
   //   console.log("\nTransaction Signature: ", txSig);
   // });
 
-  it('Create Mint Account with Transfer Hook Extension (Program)', async () => {
-  const tx = await program.methods
-    .initMint()
-     .accountsPartial({
-        user: wallet.publicKey,
-        mint: mint2022.publicKey,
-        extraAccountMetaList: extraAccountMetaListPDA,
-        tokenProgram: TOKEN_2022_PROGRAM_ID,
-      })
-    .signers([mint2022])
-    .rpc();
-
-  console.log("\nMint created via program with transfer hook");
-  console.log("Mint address:", mint2022.publicKey.toBase58());
-  console.log("Transaction Signature:", tx);
-});
-
   it('Create Token Accounts and Mint Tokens', async () => {
     // 100 tokens
     const amount = 100 * 10 ** 9;